@@ -0,0 +1,1027 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BufferWriter;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::bytes::EndOfBuffer;
+use crate::varint::VarInt;
+use std::borrow::Cow;
+
+#[cfg(feature = "async")]
+use crate::bytes::AsyncRead;
+
+#[cfg(feature = "async")]
+use crate::bytes::AsyncWrite;
+
+#[cfg(feature = "async")]
+use crate::bytes;
+
+/// Error capsule parsing.
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    /// Payload required too big.
+    #[error("cannot parse capsule as payload limit is reached")]
+    PayloadTooBig,
+}
+
+/// An error during capsule I/O read operation.
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[derive(Debug, thiserror::Error)]
+pub enum IoReadError {
+    /// Error during parsing a capsule.
+    #[error(transparent)]
+    Parse(ParseError),
+
+    /// Error due to I/O operation.
+    #[error(transparent)]
+    IO(bytes::IoReadError),
+}
+
+#[cfg(feature = "async")]
+impl From<bytes::IoReadError> for IoReadError {
+    #[inline(always)]
+    fn from(io_error: bytes::IoReadError) -> Self {
+        IoReadError::IO(io_error)
+    }
+}
+
+/// An error during capsule I/O write operation.
+#[cfg(feature = "async")]
+pub type IoWriteError = bytes::IoWriteError;
+
+/// Alias for [`Capsule<'static>`](Capsule);
+pub type CapsuleOwned = Capsule<'static>;
+
+/// A [`Capsule`] type.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CapsuleKind {
+    /// DATAGRAM capsule type.
+    Datagram,
+
+    /// `CLOSE_WEBTRANSPORT_SESSION` capsule type.
+    CloseWebTransportSession,
+
+    /// `DRAIN_WEBTRANSPORT_SESSION` capsule type.
+    DrainWebTransportSession,
+
+    /// `WT_MAX_DATA` capsule type.
+    WtMaxData,
+
+    /// `WT_MAX_STREAMS` capsule type (bidirectional streams).
+    WtMaxStreamsBidi,
+
+    /// `WT_MAX_STREAMS` capsule type (unidirectional streams).
+    WtMaxStreamsUni,
+
+    /// `WT_DATA_BLOCKED` capsule type.
+    WtDataBlocked,
+
+    /// `WT_STREAMS_BLOCKED` capsule type (bidirectional streams).
+    WtStreamsBlockedBidi,
+
+    /// `WT_STREAMS_BLOCKED` capsule type (unidirectional streams).
+    WtStreamsBlockedUni,
+}
+
+impl CapsuleKind {
+    const fn parse(id: VarInt) -> Option<Self> {
+        match id {
+            capsule_kind_ids::DATAGRAM => Some(CapsuleKind::Datagram),
+            capsule_kind_ids::CLOSE_WEBTRANSPORT_SESSION => {
+                Some(CapsuleKind::CloseWebTransportSession)
+            }
+            capsule_kind_ids::DRAIN_WEBTRANSPORT_SESSION => {
+                Some(CapsuleKind::DrainWebTransportSession)
+            }
+            capsule_kind_ids::WT_MAX_DATA => Some(CapsuleKind::WtMaxData),
+            capsule_kind_ids::WT_MAX_STREAMS_BIDI => Some(CapsuleKind::WtMaxStreamsBidi),
+            capsule_kind_ids::WT_MAX_STREAMS_UNI => Some(CapsuleKind::WtMaxStreamsUni),
+            capsule_kind_ids::WT_DATA_BLOCKED => Some(CapsuleKind::WtDataBlocked),
+            capsule_kind_ids::WT_STREAMS_BLOCKED_BIDI => Some(CapsuleKind::WtStreamsBlockedBidi),
+            capsule_kind_ids::WT_STREAMS_BLOCKED_UNI => Some(CapsuleKind::WtStreamsBlockedUni),
+            _ => None,
+        }
+    }
+
+    const fn id(self) -> VarInt {
+        match self {
+            CapsuleKind::Datagram => capsule_kind_ids::DATAGRAM,
+            CapsuleKind::CloseWebTransportSession => capsule_kind_ids::CLOSE_WEBTRANSPORT_SESSION,
+            CapsuleKind::DrainWebTransportSession => capsule_kind_ids::DRAIN_WEBTRANSPORT_SESSION,
+            CapsuleKind::WtMaxData => capsule_kind_ids::WT_MAX_DATA,
+            CapsuleKind::WtMaxStreamsBidi => capsule_kind_ids::WT_MAX_STREAMS_BIDI,
+            CapsuleKind::WtMaxStreamsUni => capsule_kind_ids::WT_MAX_STREAMS_UNI,
+            CapsuleKind::WtDataBlocked => capsule_kind_ids::WT_DATA_BLOCKED,
+            CapsuleKind::WtStreamsBlockedBidi => capsule_kind_ids::WT_STREAMS_BLOCKED_BIDI,
+            CapsuleKind::WtStreamsBlockedUni => capsule_kind_ids::WT_STREAMS_BLOCKED_UNI,
+        }
+    }
+}
+
+/// A capsule as defined in RFC 9297.
+///
+/// Capsules are exchanged on the session stream, inside the payload of HTTP3 DATA frames.
+///
+/// Reading functions silently skip capsules of unknown type, as required by the
+/// capsule protocol.
+#[derive(Debug)]
+pub struct Capsule<'a> {
+    kind: CapsuleKind,
+    payload: Cow<'a, [u8]>,
+}
+
+impl<'a> Capsule<'a> {
+    const MAX_PARSE_PAYLOAD_ALLOWED: usize = 65535;
+
+    /// Creates a new capsule of type [`CapsuleKind::Datagram`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_datagram(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(CapsuleKind::Datagram, payload)
+    }
+
+    /// Reads a [`Capsule`] from a [`BytesReader`].
+    ///
+    /// Capsules of unknown type are skipped.
+    ///
+    /// It returns [`None`] if the `bytes_reader` does not contain enough bytes
+    /// to parse an entire capsule.
+    ///
+    /// In case [`None`] or [`Err`], `bytes_reader` might be partially read.
+    pub fn read<R>(bytes_reader: &mut R) -> Result<Option<Self>, ParseError>
+    where
+        R: BytesReader<'a>,
+    {
+        loop {
+            let kind_id = match bytes_reader.get_varint() {
+                Some(kind_id) => kind_id,
+                None => return Ok(None),
+            };
+
+            let payload_len = match bytes_reader.get_varint() {
+                Some(payload_len) => payload_len.into_inner() as usize,
+                None => return Ok(None),
+            };
+
+            let kind = match CapsuleKind::parse(kind_id) {
+                Some(kind) => kind,
+                None => match bytes_reader.get_bytes(payload_len) {
+                    Some(_unknown_payload) => continue,
+                    None => return Ok(None),
+                },
+            };
+
+            if payload_len > Self::MAX_PARSE_PAYLOAD_ALLOWED {
+                return Err(ParseError::PayloadTooBig);
+            }
+
+            let payload = match bytes_reader.get_bytes(payload_len) {
+                Some(payload) => payload,
+                None => return Ok(None),
+            };
+
+            return Ok(Some(Self::new(kind, Cow::Borrowed(payload))));
+        }
+    }
+
+    /// Reads a [`Capsule`] from a `reader`.
+    ///
+    /// Capsules of unknown type are skipped.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async<R>(reader: &mut R) -> Result<Capsule<'a>, IoReadError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        use crate::bytes::BytesReaderAsync;
+
+        fn map_fin(error: bytes::IoReadError) -> bytes::IoReadError {
+            match error {
+                bytes::IoReadError::ImmediateFin => bytes::IoReadError::UnexpectedFin,
+                _ => error,
+            }
+        }
+
+        loop {
+            let kind_id = reader.get_varint().await?;
+
+            let payload_len = reader.get_varint().await.map_err(map_fin)?.into_inner() as usize;
+
+            let kind = match CapsuleKind::parse(kind_id) {
+                Some(kind) => kind,
+                None => {
+                    let mut discard = [0; 512];
+                    let mut remaining = payload_len;
+
+                    while remaining > 0 {
+                        let chunk_len = remaining.min(discard.len());
+                        reader
+                            .get_buffer(&mut discard[..chunk_len])
+                            .await
+                            .map_err(map_fin)?;
+                        remaining -= chunk_len;
+                    }
+
+                    continue;
+                }
+            };
+
+            if payload_len > Self::MAX_PARSE_PAYLOAD_ALLOWED {
+                return Err(IoReadError::Parse(ParseError::PayloadTooBig));
+            }
+
+            let mut payload = vec![0; payload_len];
+
+            reader.get_buffer(&mut payload).await.map_err(map_fin)?;
+
+            payload.shrink_to_fit();
+
+            return Ok(Self::new(kind, Cow::Owned(payload)));
+        }
+    }
+
+    /// Reads a [`Capsule`] from a [`BufferReader`].
+    ///
+    /// Capsules of unknown type are skipped.
+    ///
+    /// It returns [`None`] if the `buffer_reader` does not contain enough bytes
+    /// to parse an entire capsule.
+    ///
+    /// In case [`None`] or [`Err`], `buffer_reader` offset if not advanced.
+    pub fn read_from_buffer(
+        buffer_reader: &mut BufferReader<'a>,
+    ) -> Result<Option<Self>, ParseError> {
+        let mut buffer_reader_child = buffer_reader.child();
+
+        match Self::read(&mut *buffer_reader_child)? {
+            Some(capsule) => {
+                buffer_reader_child.commit();
+                Ok(Some(capsule))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the header of a capsule of unknown type from a [`BufferReader`].
+    ///
+    /// On success, the header is consumed and the length of the payload to be skipped
+    /// is returned: unlike [`Self::read_from_buffer`], the payload is not required to be
+    /// in the buffer, so that it can be discarded as it is received.
+    ///
+    /// It returns [`None`] if the next capsule is of known type or its header is not
+    /// entirely in the buffer. In that case, `buffer_reader` offset is not advanced.
+    pub fn read_unknown_header(buffer_reader: &mut BufferReader<'a>) -> Option<u64> {
+        let mut buffer_reader_child = buffer_reader.child();
+
+        let kind_id = buffer_reader_child.get_varint()?;
+        let payload_len = buffer_reader_child.get_varint()?;
+
+        if CapsuleKind::parse(kind_id).is_some() {
+            return None;
+        }
+
+        buffer_reader_child.commit();
+        Some(payload_len.into_inner())
+    }
+
+    /// Writes a [`Capsule`] into a [`BytesWriter`].
+    ///
+    /// It returns [`Err`] if the `bytes_writer` does not have enough capacity
+    /// to write the entire capsule.
+    /// See [`Self::write_size`] to retrieve the exact amount of required capacity.
+    ///
+    /// In case [`Err`], `bytes_writer` might be partially written.
+    pub fn write<W>(&self, bytes_writer: &mut W) -> Result<(), EndOfBuffer>
+    where
+        W: BytesWriter,
+    {
+        bytes_writer.put_varint(self.kind.id())?;
+        bytes_writer.put_varint(self.payload_len())?;
+        bytes_writer.put_bytes(&self.payload)?;
+
+        Ok(())
+    }
+
+    /// Writes a [`Capsule`] into a `writer`.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn write_async<W>(&self, writer: &mut W) -> Result<(), IoWriteError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        use crate::bytes::BytesWriterAsync;
+
+        writer.put_varint(self.kind.id()).await?;
+        writer.put_varint(self.payload_len()).await?;
+        writer.put_buffer(&self.payload).await?;
+
+        Ok(())
+    }
+
+    /// Writes this [`Capsule`] into a buffer via [`BufferWriter`].
+    ///
+    /// In case [`Err`], `buffer_writer` is not advanced.
+    pub fn write_to_buffer(&self, buffer_writer: &mut BufferWriter) -> Result<(), EndOfBuffer> {
+        if buffer_writer.capacity() < self.write_size() {
+            return Err(EndOfBuffer);
+        }
+
+        self.write(buffer_writer)
+            .expect("Enough capacity for capsule");
+
+        Ok(())
+    }
+
+    /// Returns the needed capacity to write this capsule into a buffer.
+    pub fn write_size(&self) -> usize {
+        self.kind.id().size() + self.payload_len().size() + self.payload.len()
+    }
+
+    /// Returns the [`CapsuleKind`] of this [`Capsule`].
+    #[inline(always)]
+    pub const fn kind(&self) -> CapsuleKind {
+        self.kind
+    }
+
+    /// Returns the payload of this [`Capsule`].
+    #[inline(always)]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    fn new(kind: CapsuleKind, payload: Cow<'a, [u8]>) -> Self {
+        assert!(payload.len() <= VarInt::MAX.into_inner() as usize);
+
+        Self { kind, payload }
+    }
+
+    #[inline(always)]
+    fn payload_len(&self) -> VarInt {
+        VarInt::try_from(self.payload.len() as u64)
+            .expect("Payload cannot be larger than varint max")
+    }
+
+    #[cfg(test)]
+    pub(crate) fn into_owned<'b>(self) -> Capsule<'b> {
+        Capsule {
+            kind: self.kind,
+            payload: Cow::Owned(self.payload.into_owned()),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn serialize_any(kind: VarInt, payload: &[u8]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        buffer.put_varint(kind).unwrap();
+        buffer
+            .put_varint(VarInt::try_from(payload.len() as u64).unwrap())
+            .unwrap();
+        buffer.put_bytes(payload).unwrap();
+
+        buffer
+    }
+}
+
+/// Typed payloads of WebTransport capsules.
+pub mod capsules {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    /// Direction of the streams a flow-control capsule refers to.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum StreamDirection {
+        /// Bidirectional streams.
+        Bi,
+
+        /// Unidirectional streams.
+        Uni,
+    }
+
+    /// `CLOSE_WEBTRANSPORT_SESSION` capsule.
+    ///
+    /// Sent on the session stream to terminate the session with an application
+    /// error code and a message.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct CloseWebTransportSession {
+        error_code: u32,
+        reason: String,
+    }
+
+    impl CloseWebTransportSession {
+        /// Maximum length (in bytes) of the close reason.
+        pub const MAX_REASON_LEN: usize = 1024;
+
+        /// Creates a new capsule.
+        ///
+        /// `reason` is truncated (on a character boundary) to [`Self::MAX_REASON_LEN`] bytes.
+        pub fn new<S>(error_code: u32, reason: S) -> Self
+        where
+            S: Into<String>,
+        {
+            let mut reason = reason.into();
+
+            if reason.len() > Self::MAX_REASON_LEN {
+                let mut len = Self::MAX_REASON_LEN;
+                while !reason.is_char_boundary(len) {
+                    len -= 1;
+                }
+                reason.truncate(len);
+            }
+
+            Self { error_code, reason }
+        }
+
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of malformed payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::CloseWebTransportSession`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            assert!(matches!(
+                capsule.kind(),
+                CapsuleKind::CloseWebTransportSession
+            ));
+
+            let payload = capsule.payload();
+
+            if payload.len() < 4 || payload.len() - 4 > Self::MAX_REASON_LEN {
+                return Err(ErrorCode::Message);
+            }
+
+            let error_code = u32::from_be_bytes(payload[..4].try_into().expect("4 bytes"));
+            let reason = std::str::from_utf8(&payload[4..])
+                .map_err(|_| ErrorCode::Message)?
+                .to_string();
+
+            Ok(Self { error_code, reason })
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            let mut payload = Vec::with_capacity(4 + self.reason.len());
+            payload.extend_from_slice(&self.error_code.to_be_bytes());
+            payload.extend_from_slice(self.reason.as_bytes());
+
+            Capsule::new(CapsuleKind::CloseWebTransportSession, Cow::Owned(payload))
+        }
+
+        /// Returns the application error code.
+        #[inline(always)]
+        pub fn error_code(&self) -> u32 {
+            self.error_code
+        }
+
+        /// Returns the close reason.
+        #[inline(always)]
+        pub fn reason(&self) -> &str {
+            &self.reason
+        }
+    }
+
+    /// `DRAIN_WEBTRANSPORT_SESSION` capsule.
+    ///
+    /// Signals the peer the session is going to be closed soon.
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+    pub struct DrainWebTransportSession;
+
+    impl DrainWebTransportSession {
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of not empty payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::DrainWebTransportSession`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            assert!(matches!(
+                capsule.kind(),
+                CapsuleKind::DrainWebTransportSession
+            ));
+
+            if !capsule.payload().is_empty() {
+                return Err(ErrorCode::Message);
+            }
+
+            Ok(Self)
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            Capsule::new(
+                CapsuleKind::DrainWebTransportSession,
+                Cow::Owned(Vec::new()),
+            )
+        }
+    }
+
+    /// `WT_MAX_DATA` capsule.
+    ///
+    /// Maximum amount of data that can be sent on all the streams of a session.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct WtMaxData {
+        max_data: VarInt,
+    }
+
+    impl WtMaxData {
+        /// Creates a new capsule.
+        pub fn new(max_data: VarInt) -> Self {
+            Self { max_data }
+        }
+
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of malformed payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::WtMaxData`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            assert!(matches!(capsule.kind(), CapsuleKind::WtMaxData));
+
+            Ok(Self {
+                max_data: read_single_varint(capsule.payload())?,
+            })
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            Capsule::new(CapsuleKind::WtMaxData, single_varint(self.max_data))
+        }
+
+        /// Returns the maximum amount of data.
+        #[inline(always)]
+        pub fn max_data(&self) -> VarInt {
+            self.max_data
+        }
+    }
+
+    /// `WT_DATA_BLOCKED` capsule.
+    ///
+    /// Signals the sender would like to send data but it is blocked by session flow control.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct WtDataBlocked {
+        max_data: VarInt,
+    }
+
+    impl WtDataBlocked {
+        /// Creates a new capsule.
+        pub fn new(max_data: VarInt) -> Self {
+            Self { max_data }
+        }
+
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of malformed payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::WtDataBlocked`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            assert!(matches!(capsule.kind(), CapsuleKind::WtDataBlocked));
+
+            Ok(Self {
+                max_data: read_single_varint(capsule.payload())?,
+            })
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            Capsule::new(CapsuleKind::WtDataBlocked, single_varint(self.max_data))
+        }
+
+        /// Returns the limit at which blocking occurred.
+        #[inline(always)]
+        pub fn max_data(&self) -> VarInt {
+            self.max_data
+        }
+    }
+
+    /// `WT_MAX_STREAMS` capsule.
+    ///
+    /// Cumulative number of streams of a given direction the peer can open in a session.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct WtMaxStreams {
+        direction: StreamDirection,
+        max_streams: VarInt,
+    }
+
+    impl WtMaxStreams {
+        /// Creates a new capsule.
+        pub fn new(direction: StreamDirection, max_streams: VarInt) -> Self {
+            Self {
+                direction,
+                max_streams,
+            }
+        }
+
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of malformed payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::WtMaxStreamsBidi`]
+        /// or [`CapsuleKind::WtMaxStreamsUni`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            let direction = match capsule.kind() {
+                CapsuleKind::WtMaxStreamsBidi => StreamDirection::Bi,
+                CapsuleKind::WtMaxStreamsUni => StreamDirection::Uni,
+                _ => panic!("Capsule is not WT_MAX_STREAMS"),
+            };
+
+            Ok(Self {
+                direction,
+                max_streams: read_single_varint(capsule.payload())?,
+            })
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            let kind = match self.direction {
+                StreamDirection::Bi => CapsuleKind::WtMaxStreamsBidi,
+                StreamDirection::Uni => CapsuleKind::WtMaxStreamsUni,
+            };
+
+            Capsule::new(kind, single_varint(self.max_streams))
+        }
+
+        /// Returns the direction of the streams.
+        #[inline(always)]
+        pub fn direction(&self) -> StreamDirection {
+            self.direction
+        }
+
+        /// Returns the maximum cumulative number of streams.
+        #[inline(always)]
+        pub fn max_streams(&self) -> VarInt {
+            self.max_streams
+        }
+    }
+
+    /// `WT_STREAMS_BLOCKED` capsule.
+    ///
+    /// Signals the sender would like to open a stream but it is blocked by the session limit.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct WtStreamsBlocked {
+        direction: StreamDirection,
+        max_streams: VarInt,
+    }
+
+    impl WtStreamsBlocked {
+        /// Creates a new capsule.
+        pub fn new(direction: StreamDirection, max_streams: VarInt) -> Self {
+            Self {
+                direction,
+                max_streams,
+            }
+        }
+
+        /// Constructs the capsule parsing payload of a [`Capsule`].
+        ///
+        /// Returns an [`Err`] in case of malformed payload.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `capsule` is not type [`CapsuleKind::WtStreamsBlockedBidi`]
+        /// or [`CapsuleKind::WtStreamsBlockedUni`].
+        pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+            let direction = match capsule.kind() {
+                CapsuleKind::WtStreamsBlockedBidi => StreamDirection::Bi,
+                CapsuleKind::WtStreamsBlockedUni => StreamDirection::Uni,
+                _ => panic!("Capsule is not WT_STREAMS_BLOCKED"),
+            };
+
+            Ok(Self {
+                direction,
+                max_streams: read_single_varint(capsule.payload())?,
+            })
+        }
+
+        /// Generates a [`Capsule`] with this payload.
+        pub fn generate_capsule(&self) -> CapsuleOwned {
+            let kind = match self.direction {
+                StreamDirection::Bi => CapsuleKind::WtStreamsBlockedBidi,
+                StreamDirection::Uni => CapsuleKind::WtStreamsBlockedUni,
+            };
+
+            Capsule::new(kind, single_varint(self.max_streams))
+        }
+
+        /// Returns the direction of the streams.
+        #[inline(always)]
+        pub fn direction(&self) -> StreamDirection {
+            self.direction
+        }
+
+        /// Returns the limit at which blocking occurred.
+        #[inline(always)]
+        pub fn max_streams(&self) -> VarInt {
+            self.max_streams
+        }
+    }
+
+    fn read_single_varint(payload: &[u8]) -> Result<VarInt, ErrorCode> {
+        let mut buffer_reader = BufferReader::new(payload);
+        let value = buffer_reader.get_varint().ok_or(ErrorCode::Message)?;
+
+        if buffer_reader.capacity() > 0 {
+            return Err(ErrorCode::Message);
+        }
+
+        Ok(value)
+    }
+
+    fn single_varint(value: VarInt) -> Cow<'static, [u8]> {
+        let mut payload = Vec::with_capacity(value.size());
+        payload.put_varint(value).expect("Vec does not have EOF");
+        Cow::Owned(payload)
+    }
+}
+
+mod capsule_kind_ids {
+    use crate::varint::VarInt;
+
+    pub const DATAGRAM: VarInt = VarInt::from_u32(0x00);
+    pub const CLOSE_WEBTRANSPORT_SESSION: VarInt = VarInt::from_u32(0x2843);
+    pub const DRAIN_WEBTRANSPORT_SESSION: VarInt = VarInt::from_u32(0x78ae);
+    pub const WT_MAX_DATA: VarInt = VarInt::from_u32(0x190b_4d3d);
+    pub const WT_MAX_STREAMS_BIDI: VarInt = VarInt::from_u32(0x190b_4d3f);
+    pub const WT_MAX_STREAMS_UNI: VarInt = VarInt::from_u32(0x190b_4d40);
+    pub const WT_DATA_BLOCKED: VarInt = VarInt::from_u32(0x190b_4d41);
+    pub const WT_STREAMS_BLOCKED_BIDI: VarInt = VarInt::from_u32(0x190b_4d43);
+    pub const WT_STREAMS_BLOCKED_UNI: VarInt = VarInt::from_u32(0x190b_4d44);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::capsules::*;
+    use super::*;
+
+    #[test]
+    fn close_session() {
+        let close = CloseWebTransportSession::new(42, "bye");
+
+        let capsule = close.generate_capsule();
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::CloseWebTransportSession
+        ));
+
+        let capsule = utils::assert_serde(capsule);
+        assert_eq!(
+            CloseWebTransportSession::with_capsule(&capsule).unwrap(),
+            close
+        );
+    }
+
+    #[tokio::test]
+    async fn close_session_async() {
+        let close = CloseWebTransportSession::new(u32::MAX, "");
+
+        let capsule = utils::assert_serde_async(close.generate_capsule()).await;
+        assert_eq!(
+            CloseWebTransportSession::with_capsule(&capsule).unwrap(),
+            close
+        );
+    }
+
+    #[test]
+    fn close_session_reason_truncated() {
+        let reason = "è".repeat(CloseWebTransportSession::MAX_REASON_LEN);
+        let close = CloseWebTransportSession::new(0, reason);
+
+        assert!(close.reason().len() <= CloseWebTransportSession::MAX_REASON_LEN);
+        assert!(close.reason().len() > CloseWebTransportSession::MAX_REASON_LEN - 2);
+    }
+
+    #[test]
+    fn close_session_malformed() {
+        let buffer = Capsule::serialize_any(CapsuleKind::CloseWebTransportSession.id(), b"\0\0");
+        let capsule = Capsule::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(CloseWebTransportSession::with_capsule(&capsule).is_err());
+
+        let buffer = Capsule::serialize_any(
+            CapsuleKind::CloseWebTransportSession.id(),
+            b"\0\0\0\0\xff\xfe",
+        );
+        let capsule = Capsule::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(CloseWebTransportSession::with_capsule(&capsule).is_err());
+    }
+
+    #[test]
+    fn drain_session() {
+        let capsule = utils::assert_serde(DrainWebTransportSession.generate_capsule());
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::DrainWebTransportSession
+        ));
+        DrainWebTransportSession::with_capsule(&capsule).unwrap();
+
+        let buffer = Capsule::serialize_any(CapsuleKind::DrainWebTransportSession.id(), b"x");
+        let capsule = Capsule::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(DrainWebTransportSession::with_capsule(&capsule).is_err());
+    }
+
+    #[test]
+    fn flow_control() {
+        let max_data = WtMaxData::new(VarInt::from_u32(1_000_000));
+        let capsule = utils::assert_serde(max_data.generate_capsule());
+        assert_eq!(WtMaxData::with_capsule(&capsule).unwrap(), max_data);
+
+        let data_blocked = WtDataBlocked::new(VarInt::from_u32(1));
+        let capsule = utils::assert_serde(data_blocked.generate_capsule());
+        assert_eq!(WtDataBlocked::with_capsule(&capsule).unwrap(), data_blocked);
+
+        for direction in [StreamDirection::Bi, StreamDirection::Uni] {
+            let max_streams = WtMaxStreams::new(direction, VarInt::from_u32(100));
+            let capsule = utils::assert_serde(max_streams.generate_capsule());
+            assert_eq!(WtMaxStreams::with_capsule(&capsule).unwrap(), max_streams);
+
+            let streams_blocked = WtStreamsBlocked::new(direction, VarInt::from_u32(100));
+            let capsule = utils::assert_serde(streams_blocked.generate_capsule());
+            assert_eq!(
+                WtStreamsBlocked::with_capsule(&capsule).unwrap(),
+                streams_blocked
+            );
+        }
+    }
+
+    #[test]
+    fn flow_control_trailing_bytes() {
+        let buffer = Capsule::serialize_any(CapsuleKind::WtMaxData.id(), &[0x01, 0x02]);
+        let capsule = Capsule::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(WtMaxData::with_capsule(&capsule).is_err());
+    }
+
+    #[test]
+    fn read_eof() {
+        let buffer = Capsule::serialize_any(CapsuleKind::Datagram.id(), b"This is a test payload");
+
+        for len in 0..buffer.len() {
+            assert!(Capsule::read(&mut &buffer[..len]).unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn read_eof_async() {
+        let buffer = Capsule::serialize_any(CapsuleKind::Datagram.id(), b"This is a test payload");
+
+        for len in 0..buffer.len() {
+            let result = Capsule::read_async(&mut &buffer[..len]).await;
+
+            match len {
+                0 => assert!(matches!(
+                    result,
+                    Err(IoReadError::IO(bytes::IoReadError::ImmediateFin))
+                )),
+                _ => assert!(matches!(
+                    result,
+                    Err(IoReadError::IO(bytes::IoReadError::UnexpectedFin))
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn read_from_buffer_incomplete() {
+        let buffer = Capsule::serialize_any(CapsuleKind::Datagram.id(), b"This is a test payload");
+        let mut buffer_reader = BufferReader::new(&buffer[..buffer.len() - 1]);
+
+        assert!(Capsule::read_from_buffer(&mut buffer_reader)
+            .unwrap()
+            .is_none());
+        assert_eq!(buffer_reader.offset(), 0);
+    }
+
+    #[test]
+    fn unknown_capsule() {
+        let mut buffer = Capsule::serialize_any(VarInt::from_u32(0x0042_4242), &[0; 1024]);
+        buffer.extend(Capsule::serialize_any(
+            CapsuleKind::Datagram.id(),
+            b"payload",
+        ));
+
+        let capsule = Capsule::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(matches!(capsule.kind(), CapsuleKind::Datagram));
+        assert_eq!(capsule.payload(), b"payload");
+    }
+
+    #[tokio::test]
+    async fn unknown_capsule_async() {
+        let mut buffer = Capsule::serialize_any(VarInt::from_u32(0x0042_4242), &[0; 1024]);
+        buffer.extend(Capsule::serialize_any(
+            CapsuleKind::Datagram.id(),
+            b"payload",
+        ));
+
+        let capsule = Capsule::read_async(&mut buffer.as_slice()).await.unwrap();
+        assert!(matches!(capsule.kind(), CapsuleKind::Datagram));
+        assert_eq!(capsule.payload(), b"payload");
+    }
+
+    #[test]
+    fn unknown_capsule_only() {
+        let buffer = Capsule::serialize_any(VarInt::from_u32(0x0042_4242), b"payload");
+        let mut buffer_reader = BufferReader::new(&buffer);
+
+        assert!(Capsule::read_from_buffer(&mut buffer_reader)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn unknown_capsule_header() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(VarInt::from_u32(0x0042_4242)).unwrap();
+        buffer.put_varint(VarInt::from_u32(1 << 30)).unwrap();
+        buffer.extend_from_slice(b"partial payload");
+
+        let mut buffer_reader = BufferReader::new(&buffer);
+        assert_eq!(
+            Capsule::read_unknown_header(&mut buffer_reader),
+            Some(1 << 30)
+        );
+        assert_eq!(buffer_reader.buffer_remaining(), b"partial payload");
+
+        let buffer = Capsule::serialize_any(CapsuleKind::Datagram.id(), b"payload");
+        let mut buffer_reader = BufferReader::new(&buffer);
+        assert!(Capsule::read_unknown_header(&mut buffer_reader).is_none());
+        assert_eq!(buffer_reader.offset(), 0);
+
+        let mut buffer_reader = BufferReader::new(&buffer[..1]);
+        assert!(Capsule::read_unknown_header(&mut buffer_reader).is_none());
+        assert_eq!(buffer_reader.offset(), 0);
+    }
+
+    #[test]
+    fn payload_too_big() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(CapsuleKind::Datagram.id()).unwrap();
+        buffer
+            .put_varint(VarInt::from_u32(
+                Capsule::MAX_PARSE_PAYLOAD_ALLOWED as u32 + 1,
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            Capsule::read_from_buffer(&mut BufferReader::new(&buffer)),
+            Err(ParseError::PayloadTooBig)
+        ));
+    }
+
+    #[tokio::test]
+    async fn payload_too_big_async() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(CapsuleKind::Datagram.id()).unwrap();
+        buffer
+            .put_varint(VarInt::from_u32(
+                Capsule::MAX_PARSE_PAYLOAD_ALLOWED as u32 + 1,
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            Capsule::read_async(&mut &*buffer).await,
+            Err(IoReadError::Parse(ParseError::PayloadTooBig)),
+        ));
+    }
+
+    mod utils {
+        use super::*;
+
+        pub fn assert_serde(capsule: Capsule) -> CapsuleOwned {
+            let mut buffer = Vec::new();
+
+            capsule.write(&mut buffer).unwrap();
+            assert_eq!(buffer.len(), capsule.write_size());
+
+            let mut buffer = buffer.as_slice();
+            let capsule = Capsule::read(&mut buffer).unwrap().unwrap();
+            assert!(buffer.is_empty());
+
+            capsule.into_owned()
+        }
+
+        #[cfg(feature = "async")]
+        pub async fn assert_serde_async(capsule: Capsule<'_>) -> CapsuleOwned {
+            let mut buffer = Vec::new();
+
+            capsule.write_async(&mut buffer).await.unwrap();
+            assert_eq!(buffer.len(), capsule.write_size());
+
+            let mut buffer = buffer.as_slice();
+            let capsule = Capsule::read_async(&mut buffer).await.unwrap();
+            assert!(buffer.is_empty());
+
+            capsule.into_owned()
+        }
+    }
+}
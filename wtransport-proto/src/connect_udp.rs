@@ -0,0 +1,549 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BufferWriter;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::bytes::EndOfBuffer;
+use crate::error::ErrorCode;
+use crate::headers::Headers;
+use crate::varint::VarInt;
+use std::fmt::Display;
+use std::fmt::Write;
+use url::Url;
+
+/// The `:protocol` value of CONNECT-UDP requests.
+pub const PROTOCOL: &str = "connect-udp";
+
+/// The context ID of HTTP datagrams carrying UDP payloads.
+pub const CONTEXT_ID_UDP: VarInt = VarInt::from_u32(0);
+
+/// Error returned when a URI template is not valid.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid CONNECT-UDP URI template")]
+pub struct InvalidTemplate;
+
+/// A template variable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Variable {
+    TargetHost,
+    TargetPort,
+}
+
+impl Variable {
+    fn parse(name: &str) -> Result<Self, InvalidTemplate> {
+        match name {
+            "target_host" => Ok(Variable::TargetHost),
+            "target_port" => Ok(Variable::TargetPort),
+            _ => Err(InvalidTemplate),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Variable::TargetHost => "target_host",
+            Variable::TargetPort => "target_port",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    /// Literal text.
+    Literal(String),
+
+    /// Simple string expansion (`{var}`).
+    Simple(Variable),
+
+    /// Form-style query expansion (`{?var,...}`) or continuation (`{&var,...}`).
+    Query(char, Vec<Variable>),
+}
+
+/// A URI template (RFC 6570) locating a CONNECT-UDP proxy.
+///
+/// Only the expressions needed by CONNECT-UDP are supported: simple string expansion
+/// (`{var}`) and form-style query (`{?var,...}` and `{&var,...}`). Variables must be
+/// `target_host` and `target_port`, and both must appear.
+///
+/// # Examples
+///
+/// ```
+/// use wtransport_proto::connect_udp::UriTemplate;
+///
+/// let template =
+///     UriTemplate::new("https://proxy.example.org/.well-known/masque/udp/{target_host}/{target_port}/")
+///         .unwrap();
+///
+/// assert_eq!(
+///     template.expand("192.0.2.6", 443),
+///     "https://proxy.example.org/.well-known/masque/udp/192.0.2.6/443/"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+impl UriTemplate {
+    /// The default template path, defined by RFC 9298.
+    pub const DEFAULT_PATH: &'static str = "/.well-known/masque/udp/{target_host}/{target_port}/";
+
+    /// Parses a URI `template`.
+    ///
+    /// It must be an absolute `https` URI.
+    pub fn new<S>(template: S) -> Result<Self, InvalidTemplate>
+    where
+        S: ToString,
+    {
+        let template = template.to_string();
+        let parts = Self::parse_parts(&template)?;
+
+        let variables = parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Literal(_) => Vec::new(),
+                Part::Simple(variable) => vec![*variable],
+                Part::Query(_, variables) => variables.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        if !variables.contains(&Variable::TargetHost) || !variables.contains(&Variable::TargetPort)
+        {
+            return Err(InvalidTemplate);
+        }
+
+        let this = Self { template, parts };
+
+        let url = Url::parse(&this.expand("example.org", 443)).map_err(|_| InvalidTemplate)?;
+        if url.scheme() != "https" || this.path_parts().is_none() {
+            return Err(InvalidTemplate);
+        }
+
+        Ok(this)
+    }
+
+    /// Creates the template with [`DEFAULT_PATH`](Self::DEFAULT_PATH) for a proxy at `authority`.
+    pub fn with_default_path(authority: &str) -> Result<Self, InvalidTemplate> {
+        Self::new(format!("https://{authority}{}", Self::DEFAULT_PATH))
+    }
+
+    /// Returns the URI for proxying UDP to `target_host` and `target_port`.
+    pub fn expand(&self, target_host: &str, target_port: u16) -> String {
+        let mut uri = String::with_capacity(self.template.len());
+        let target_port = target_port.to_string();
+
+        let value = |variable| match variable {
+            Variable::TargetHost => target_host,
+            Variable::TargetPort => target_port.as_str(),
+        };
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => uri.push_str(literal),
+                Part::Simple(variable) => percent_encode(&mut uri, value(*variable)),
+                Part::Query(operator, variables) => {
+                    for (index, variable) in variables.iter().enumerate() {
+                        uri.push(if index == 0 { *operator } else { '&' });
+                        uri.push_str(variable.name());
+                        uri.push('=');
+                        percent_encode(&mut uri, value(*variable));
+                    }
+                }
+            }
+        }
+
+        uri
+    }
+
+    /// Matches the `:path` of a request against the template.
+    ///
+    /// Returns target host and port on success.
+    pub fn match_path(&self, path: &str) -> Option<(String, u16)> {
+        let mut target_host = None;
+        let mut target_port = None;
+        let mut rest = path;
+
+        let mut assign = |variable, value: String| {
+            let slot = match variable {
+                Variable::TargetHost => &mut target_host,
+                Variable::TargetPort => &mut target_port,
+            };
+
+            match slot {
+                Some(current) => *current == value,
+                None => {
+                    *slot = Some(value);
+                    true
+                }
+            }
+        };
+
+        for part in self.path_parts()? {
+            match part {
+                Part::Literal(literal) => {
+                    rest = rest.strip_prefix(literal.as_str())?;
+                }
+                Part::Simple(variable) => {
+                    let end = rest
+                        .find(|c: char| !is_unreserved(c) && c != '%')
+                        .unwrap_or(rest.len());
+
+                    if !assign(variable, percent_decode(&rest[..end])?) {
+                        return None;
+                    }
+
+                    rest = &rest[end..];
+                }
+                Part::Query(operator, variables) => {
+                    let query = rest.strip_prefix(operator)?;
+                    let end = query.find('#').unwrap_or(query.len());
+
+                    for (name, value) in query[..end].split('&').filter_map(|p| p.split_once('=')) {
+                        if let Some(variable) = variables.iter().find(|v| v.name() == name) {
+                            if !assign(*variable, percent_decode(value)?) {
+                                return None;
+                            }
+                        }
+                    }
+
+                    rest = &query[end..];
+                }
+            }
+        }
+
+        if !rest.is_empty() {
+            return None;
+        }
+
+        let target_host = target_host.filter(|host| !host.is_empty())?;
+        let target_port = target_port?.parse().ok().filter(|port| *port != 0)?;
+
+        Some((target_host, target_port))
+    }
+
+    /// Returns the template as string.
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the parts following the authority (i.e., path and query).
+    fn path_parts(&self) -> Option<Vec<Part>> {
+        let mut parts = self.parts.iter();
+
+        let Some(Part::Literal(first)) = parts.next() else {
+            return None;
+        };
+
+        let after_scheme = first.find("://")? + 3;
+        let path_start = first[after_scheme..].find('/')? + after_scheme;
+
+        let mut path_parts = vec![Part::Literal(first[path_start..].to_string())];
+        path_parts.extend(parts.cloned());
+
+        Some(path_parts)
+    }
+
+    fn parse_parts(template: &str) -> Result<Vec<Part>, InvalidTemplate> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            match rest.find('{') {
+                Some(0) => {
+                    let end = rest.find('}').ok_or(InvalidTemplate)?;
+                    let expression = &rest[1..end];
+
+                    let part = match expression.chars().next() {
+                        Some(operator @ ('?' | '&')) => Part::Query(
+                            operator,
+                            expression[1..]
+                                .split(',')
+                                .map(Variable::parse)
+                                .collect::<Result<_, _>>()?,
+                        ),
+                        _ => Part::Simple(Variable::parse(expression)?),
+                    };
+
+                    parts.push(part);
+                    rest = &rest[end + 1..];
+                }
+                Some(start) => {
+                    parts.push(Part::Literal(rest[..start].to_string()));
+                    rest = &rest[start..];
+                }
+                None => {
+                    parts.push(Part::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+
+        if parts
+            .iter()
+            .any(|part| matches!(part, Part::Literal(literal) if literal.contains('}')))
+        {
+            return Err(InvalidTemplate);
+        }
+
+        Ok(parts)
+    }
+}
+
+impl Display for UriTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn percent_encode(output: &mut String, value: &str) {
+    for byte in value.bytes() {
+        if is_unreserved(byte as char) {
+            output.push(byte as char);
+        } else {
+            write!(output, "%{byte:02X}").expect("Writing to string cannot fail");
+        }
+    }
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let high = (input.next()? as char).to_digit(16)?;
+            let low = (input.next()? as char).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// A CONNECT-UDP request.
+#[derive(Debug)]
+pub struct ConnectUdpRequest(Headers);
+
+impl ConnectUdpRequest {
+    /// Creates a request for proxying UDP to `target_host` and `target_port`.
+    pub fn new(template: &UriTemplate, target_host: &str, target_port: u16) -> Self {
+        let url = Url::parse(&template.expand(target_host, target_port))
+            .expect("Template has been validated");
+
+        let path = format!(
+            "{}{}",
+            url.path(),
+            url.query().map(|s| format!("?{}", s)).unwrap_or_default()
+        );
+
+        let headers = [
+            (":method", "CONNECT"),
+            (":scheme", "https"),
+            (":protocol", PROTOCOL),
+            (":authority", url.authority()),
+            (":path", &path),
+            ("capsule-protocol", "?1"),
+        ]
+        .into_iter()
+        .collect();
+
+        Self(headers)
+    }
+
+    /// Returns the `:authority` field of the request.
+    pub fn authority(&self) -> &str {
+        self.0
+            .get(":authority")
+            .expect("CONNECT-UDP request must contain ':authority' field")
+    }
+
+    /// Returns the `:path` field of the request.
+    pub fn path(&self) -> &str {
+        self.0
+            .get(":path")
+            .expect("CONNECT-UDP request must contain ':path' field")
+    }
+
+    /// Returns the whole headers associated with the request.
+    pub fn headers(&self) -> &Headers {
+        &self.0
+    }
+}
+
+/// The payload of an HTTP datagram associated with a CONNECT-UDP request.
+///
+/// It is made of a context ID followed by the payload. UDP packets are carried
+/// with [`CONTEXT_ID_UDP`].
+pub struct UdpPayload<'a> {
+    context_id: VarInt,
+    payload: &'a [u8],
+}
+
+impl<'a> UdpPayload<'a> {
+    /// Creates a new [`UdpPayload`] carrying a UDP packet.
+    #[inline(always)]
+    pub fn new(payload: &'a [u8]) -> Self {
+        Self::with_context_id(CONTEXT_ID_UDP, payload)
+    }
+
+    /// Creates a new [`UdpPayload`] with a given context ID.
+    #[inline(always)]
+    pub fn with_context_id(context_id: VarInt, payload: &'a [u8]) -> Self {
+        Self {
+            context_id,
+            payload,
+        }
+    }
+
+    /// Reads [`UdpPayload`] from the payload of an HTTP datagram.
+    pub fn read(http_payload: &'a [u8]) -> Result<Self, ErrorCode> {
+        let mut buffer_reader = BufferReader::new(http_payload);
+
+        let context_id = buffer_reader.get_varint().ok_or(ErrorCode::Datagram)?;
+        let payload = buffer_reader.buffer_remaining();
+
+        Ok(Self {
+            context_id,
+            payload,
+        })
+    }
+
+    /// Writes [`UdpPayload`] as HTTP datagram payload into `buffer`.
+    ///
+    /// It returns the number of bytes written.
+    /// It returns [`Err`] if the `buffer` does not have enough capacity.
+    /// See [`Self::write_size`].
+    ///
+    /// In case of [`Err`], `buffer` is not written.
+    pub fn write(&self, buffer: &mut [u8]) -> Result<usize, EndOfBuffer> {
+        if buffer.len() < self.write_size() {
+            return Err(EndOfBuffer);
+        }
+
+        let mut buffer_writer = BufferWriter::new(buffer);
+
+        buffer_writer
+            .put_varint(self.context_id)
+            .expect("Buffer has capacity");
+
+        buffer_writer
+            .put_bytes(self.payload)
+            .expect("Buffer has capacity");
+
+        Ok(buffer_writer.offset())
+    }
+
+    /// Returns the needed capacity to write this payload into a buffer.
+    #[inline(always)]
+    pub fn write_size(&self) -> usize {
+        self.context_id.size() + self.payload.len()
+    }
+
+    /// Returns the context ID.
+    #[inline(always)]
+    pub fn context_id(&self) -> VarInt {
+        self.context_id
+    }
+
+    /// Returns the payload.
+    #[inline(always)]
+    pub fn payload(&self) -> &[u8] {
+        self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_default() {
+        let template = UriTemplate::with_default_path("proxy.example.org:4443").unwrap();
+
+        let uri = template.expand("2001:db8::42", 443);
+        assert_eq!(
+            uri,
+            "https://proxy.example.org:4443/.well-known/masque/udp/2001%3Adb8%3A%3A42/443/"
+        );
+
+        assert_eq!(
+            template.match_path("/.well-known/masque/udp/2001%3Adb8%3A%3A42/443/"),
+            Some(("2001:db8::42".to_string(), 443))
+        );
+    }
+
+    #[test]
+    fn template_query() {
+        let template =
+            UriTemplate::new("https://example.org/proxy{?target_host,target_port}").unwrap();
+
+        let uri = template.expand("192.0.2.6", 53);
+        assert_eq!(
+            uri,
+            "https://example.org/proxy?target_host=192.0.2.6&target_port=53"
+        );
+
+        assert_eq!(
+            template.match_path("/proxy?target_port=53&target_host=192.0.2.6"),
+            Some(("192.0.2.6".to_string(), 53))
+        );
+    }
+
+    #[test]
+    fn template_mismatch() {
+        let template = UriTemplate::with_default_path("example.org").unwrap();
+
+        assert!(template.match_path("/").is_none());
+        assert!(template
+            .match_path("/.well-known/masque/udp/example.com/0/")
+            .is_none());
+        assert!(template
+            .match_path("/.well-known/masque/udp/example.com/port/")
+            .is_none());
+        assert!(template
+            .match_path("/.well-known/masque/udp//443/")
+            .is_none());
+        assert!(template
+            .match_path("/.well-known/masque/udp/example.com/443/extra")
+            .is_none());
+    }
+
+    #[test]
+    fn template_invalid() {
+        assert!(UriTemplate::new("https://example.org/{target_host}").is_err());
+        assert!(UriTemplate::new("https://example.org/{target_host}/{port}").is_err());
+        assert!(UriTemplate::new("http://example.org/{target_host}/{target_port}").is_err());
+        assert!(UriTemplate::new("https://example.org/{target_host/{target_port}").is_err());
+        assert!(UriTemplate::new("{target_host}/{target_port}").is_err());
+    }
+
+    #[test]
+    fn request() {
+        let template = UriTemplate::with_default_path("example.org").unwrap();
+        let request = ConnectUdpRequest::new(&template, "192.0.2.6", 443);
+
+        assert_eq!(request.authority(), "example.org");
+        assert_eq!(request.path(), "/.well-known/masque/udp/192.0.2.6/443/");
+        assert_eq!(request.headers().get(":protocol"), Some(PROTOCOL));
+    }
+
+    #[test]
+    fn payload() {
+        let payload = UdpPayload::new(b"ping");
+
+        let mut buffer = vec![0; payload.write_size()];
+        payload.write(&mut buffer).unwrap();
+        assert_eq!(buffer, b"\x00ping");
+
+        let payload = UdpPayload::read(&buffer).unwrap();
+        assert_eq!(payload.context_id(), CONTEXT_ID_UDP);
+        assert_eq!(payload.payload(), b"ping");
+
+        assert!(UdpPayload::read(&[]).is_err());
+    }
+}
@@ -39,17 +39,29 @@ pub enum ErrorCode {
     /// H3_REQUEST_REJECTED.
     RequestRejected,
 
+    /// H3_REQUEST_CANCELLED.
+    RequestCancelled,
+
     /// H3_MESSAGE_ERROR.
     Message,
 
     /// QPACK_DECOMPRESSION_FAILED.
     Decompression,
 
+    /// QPACK_ENCODER_STREAM_ERROR.
+    EncoderStream,
+
+    /// QPACK_DECODER_STREAM_ERROR.
+    DecoderStream,
+
     /// WEBTRANSPORT_BUFFERED_STREAM_REJECTED.
     BufferedStreamRejected,
 
     /// WEBTRANSPORT_SESSION_GONE.
     SessionGone,
+
+    /// WEBTRANSPORT_FLOW_CONTROL_ERROR.
+    FlowControl,
 }
 
 impl ErrorCode {
@@ -67,12 +79,16 @@ impl ErrorCode {
             ErrorCode::Settings => h3_error_codes::H3_SETTINGS_ERROR,
             ErrorCode::MissingSettings => h3_error_codes::H3_MISSING_SETTINGS,
             ErrorCode::RequestRejected => h3_error_codes::H3_REQUEST_REJECTED,
+            ErrorCode::RequestCancelled => h3_error_codes::H3_REQUEST_CANCELLED,
             ErrorCode::Message => h3_error_codes::H3_MESSAGE_ERROR,
             ErrorCode::Decompression => qpack_error_codes::QPACK_DECOMPRESSION_FAILED,
+            ErrorCode::EncoderStream => qpack_error_codes::QPACK_ENCODER_STREAM_ERROR,
+            ErrorCode::DecoderStream => qpack_error_codes::QPACK_DECODER_STREAM_ERROR,
             ErrorCode::BufferedStreamRejected => {
                 wt_error_codes::WEBTRANSPORT_BUFFERED_STREAM_REJECTED
             }
             ErrorCode::SessionGone => wt_error_codes::WEBTRANSPORT_SESSION_GONE,
+            ErrorCode::FlowControl => wt_error_codes::WEBTRANSPORT_FLOW_CONTROL_ERROR,
         }
     }
 }
@@ -97,16 +113,50 @@ impl Display for ErrorCode {
             ErrorCode::Settings => write!(f, "SettingsError"),
             ErrorCode::MissingSettings => write!(f, "MissingSettingsError"),
             ErrorCode::RequestRejected => write!(f, "RequestRejectedError"),
+            ErrorCode::RequestCancelled => write!(f, "RequestCancelledError"),
             ErrorCode::Message => write!(f, "MessageError"),
             ErrorCode::Decompression => write!(f, "DecompressionError"),
+            ErrorCode::EncoderStream => write!(f, "EncoderStreamError"),
+            ErrorCode::DecoderStream => write!(f, "DecoderStreamError"),
             ErrorCode::BufferedStreamRejected => write!(f, "BufferedStreamRejected"),
             ErrorCode::SessionGone => write!(f, "SessionGone"),
+            ErrorCode::FlowControl => write!(f, "FlowControlError"),
         }
     }
 }
 
 impl std::error::Error for ErrorCode {}
 
+/// Maps a WebTransport application error code into the HTTP3 error space.
+///
+/// Application codes used for resetting or stopping WebTransport streams are
+/// carried in the range starting at `0x52e4a40fa8db`, skipping the codepoints
+/// reserved for exercising HTTP3 error codes (`0x1f * N + 0x21`).
+pub fn webtransport_to_http3_code(code: u32) -> VarInt {
+    let code = u64::from(code);
+    let http3_code =
+        wt_error_codes::WEBTRANSPORT_APPLICATION_FIRST.into_inner() + code + code / 0x1e;
+
+    VarInt::try_from_u64(http3_code).expect("Mapped code is in varint range")
+}
+
+/// Maps an HTTP3 error code back into a WebTransport application error code.
+///
+/// Returns [`None`] if `code` is outside the range reserved for WebTransport
+/// application error codes, or if it is a reserved codepoint.
+pub fn http3_to_webtransport_code(code: VarInt) -> Option<u32> {
+    if code < wt_error_codes::WEBTRANSPORT_APPLICATION_FIRST
+        || code > wt_error_codes::WEBTRANSPORT_APPLICATION_LAST
+        || (code.into_inner() - 0x21) % 0x1f == 0
+    {
+        return None;
+    }
+
+    let shifted = code.into_inner() - wt_error_codes::WEBTRANSPORT_APPLICATION_FIRST.into_inner();
+
+    u32::try_from(shifted - shifted / 0x1f).ok()
+}
+
 mod h3_error_codes {
     use crate::varint::VarInt;
 
@@ -121,6 +171,7 @@ mod h3_error_codes {
     pub const H3_SETTINGS_ERROR: VarInt = VarInt::from_u32(0x0109);
     pub const H3_MISSING_SETTINGS: VarInt = VarInt::from_u32(0x010a);
     pub const H3_REQUEST_REJECTED: VarInt = VarInt::from_u32(0x010b);
+    pub const H3_REQUEST_CANCELLED: VarInt = VarInt::from_u32(0x010c);
     pub const H3_MESSAGE_ERROR: VarInt = VarInt::from_u32(0x010e);
 }
 
@@ -128,6 +179,8 @@ mod qpack_error_codes {
     use crate::varint::VarInt;
 
     pub const QPACK_DECOMPRESSION_FAILED: VarInt = VarInt::from_u32(0x0200);
+    pub const QPACK_ENCODER_STREAM_ERROR: VarInt = VarInt::from_u32(0x0201);
+    pub const QPACK_DECODER_STREAM_ERROR: VarInt = VarInt::from_u32(0x0202);
 }
 
 mod wt_error_codes {
@@ -135,4 +188,48 @@ mod wt_error_codes {
 
     pub const WEBTRANSPORT_BUFFERED_STREAM_REJECTED: VarInt = VarInt::from_u32(0x3994_bd84);
     pub const WEBTRANSPORT_SESSION_GONE: VarInt = VarInt::from_u32(0x170d_7b68);
+    pub const WEBTRANSPORT_FLOW_CONTROL_ERROR: VarInt = VarInt::from_u32(0x045d_4487);
+
+    pub const WEBTRANSPORT_APPLICATION_FIRST: VarInt =
+        unsafe { VarInt::from_u64_unchecked(0x52e4_a40f_a8db) };
+    pub const WEBTRANSPORT_APPLICATION_LAST: VarInt =
+        unsafe { VarInt::from_u64_unchecked(0x52e5_ac98_3162) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webtransport_code_mapping() {
+        assert_eq!(
+            webtransport_to_http3_code(0),
+            wt_error_codes::WEBTRANSPORT_APPLICATION_FIRST
+        );
+        assert_eq!(
+            webtransport_to_http3_code(u32::MAX),
+            wt_error_codes::WEBTRANSPORT_APPLICATION_LAST
+        );
+
+        for code in (0..1000).chain([0x1d, 0x1e, 0x1f, u32::MAX - 1, u32::MAX]) {
+            let http3_code = webtransport_to_http3_code(code);
+            assert_ne!((http3_code.into_inner() - 0x21) % 0x1f, 0);
+            assert_eq!(http3_to_webtransport_code(http3_code), Some(code));
+        }
+    }
+
+    #[test]
+    fn webtransport_code_out_of_range() {
+        let first = wt_error_codes::WEBTRANSPORT_APPLICATION_FIRST.into_inner();
+        let last = wt_error_codes::WEBTRANSPORT_APPLICATION_LAST.into_inner();
+
+        assert!(http3_to_webtransport_code(VarInt::from_u32(0)).is_none());
+        assert!(http3_to_webtransport_code(h3_error_codes::H3_NO_ERROR).is_none());
+        assert!(http3_to_webtransport_code(wt_error_codes::WEBTRANSPORT_SESSION_GONE).is_none());
+        assert!(http3_to_webtransport_code(VarInt::try_from_u64(first - 1).unwrap()).is_none());
+        assert!(http3_to_webtransport_code(VarInt::try_from_u64(last + 1).unwrap()).is_none());
+
+        let reserved = (first..).find(|code| (code - 0x21) % 0x1f == 0).unwrap();
+        assert!(http3_to_webtransport_code(VarInt::try_from_u64(reserved).unwrap()).is_none());
+    }
 }
@@ -20,9 +20,9 @@ use crate::bytes;
 /// Error frame parsing.
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
-    /// Error for unknown frame ID.
-    #[error("cannot parse HTTP3 frame as ID is unknown")]
-    UnknownFrame,
+    /// Error for frame ID reserved to HTTP/2 frame types.
+    #[error("cannot parse HTTP3 frame as ID is reserved")]
+    ReservedFrame,
 
     /// Error for invalid session ID.
     #[error("cannot parse HTTP3 frame as session ID is invalid")]
@@ -74,11 +74,31 @@ pub enum FrameKind {
     /// SETTINGS frame type.
     Settings,
 
+    /// `CANCEL_PUSH` frame type.
+    CancelPush,
+
+    /// GOAWAY frame type.
+    GoAway,
+
+    /// `MAX_PUSH_ID` frame type.
+    MaxPushId,
+
+    /// `PRIORITY_UPDATE` frame type, for a request stream (RFC 9218).
+    PriorityUpdateRequest,
+
+    /// `PRIORITY_UPDATE` frame type, for a push stream (RFC 9218).
+    PriorityUpdatePush,
+
     /// WebTransport frame type.
     WebTransport,
 
     /// Exercise frame.
     Exercise(VarInt),
+
+    /// Frame of unknown type, to be ignored.
+    ///
+    /// Its payload is skipped while reading.
+    Unknown(VarInt),
 }
 
 impl FrameKind {
@@ -88,14 +108,33 @@ impl FrameKind {
         id.into_inner() >= 0x21 && ((id.into_inner() - 0x21) % 0x1f == 0)
     }
 
-    const fn parse(id: VarInt) -> Option<Self> {
+    /// Checks whether an `id` is reserved as it was used by HTTP/2 frame types
+    /// not existing in HTTP3.
+    ///
+    /// Receiving such a frame is a connection error of type `H3_FRAME_UNEXPECTED`.
+    #[inline(always)]
+    pub const fn is_id_reserved(id: VarInt) -> bool {
+        matches!(id.into_inner(), 0x02 | 0x06 | 0x08 | 0x09)
+    }
+
+    /// Parses a frame type `id`.
+    ///
+    /// Returns [`None`] if the frame type is reserved (see [`Self::is_id_reserved`]).
+    /// Types not known are parsed as [`FrameKind::Unknown`].
+    pub const fn parse(id: VarInt) -> Option<Self> {
         match id {
             frame_kind_ids::DATA => Some(FrameKind::Data),
             frame_kind_ids::HEADERS => Some(FrameKind::Headers),
+            frame_kind_ids::CANCEL_PUSH => Some(FrameKind::CancelPush),
             frame_kind_ids::SETTINGS => Some(FrameKind::Settings),
+            frame_kind_ids::GOAWAY => Some(FrameKind::GoAway),
+            frame_kind_ids::MAX_PUSH_ID => Some(FrameKind::MaxPushId),
+            frame_kind_ids::PRIORITY_UPDATE_REQUEST => Some(FrameKind::PriorityUpdateRequest),
+            frame_kind_ids::PRIORITY_UPDATE_PUSH => Some(FrameKind::PriorityUpdatePush),
             frame_kind_ids::WEBTRANSPORT_STREAM => Some(FrameKind::WebTransport),
             id if FrameKind::is_id_exercise(id) => Some(FrameKind::Exercise(id)),
-            _ => None,
+            id if FrameKind::is_id_reserved(id) => None,
+            id => Some(FrameKind::Unknown(id)),
         }
     }
 
@@ -103,9 +142,14 @@ impl FrameKind {
         match self {
             FrameKind::Data => frame_kind_ids::DATA,
             FrameKind::Headers => frame_kind_ids::HEADERS,
+            FrameKind::CancelPush => frame_kind_ids::CANCEL_PUSH,
             FrameKind::Settings => frame_kind_ids::SETTINGS,
+            FrameKind::GoAway => frame_kind_ids::GOAWAY,
+            FrameKind::MaxPushId => frame_kind_ids::MAX_PUSH_ID,
+            FrameKind::PriorityUpdateRequest => frame_kind_ids::PRIORITY_UPDATE_REQUEST,
+            FrameKind::PriorityUpdatePush => frame_kind_ids::PRIORITY_UPDATE_PUSH,
             FrameKind::WebTransport => frame_kind_ids::WEBTRANSPORT_STREAM,
-            FrameKind::Exercise(id) => id,
+            FrameKind::Exercise(id) | FrameKind::Unknown(id) => id,
         }
     }
 }
@@ -120,6 +164,16 @@ pub struct Frame<'a> {
 impl<'a> Frame<'a> {
     const MAX_PARSE_PAYLOAD_ALLOWED: usize = 4096;
 
+    /// Creates a new frame of type [`FrameKind::Data`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_data(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(FrameKind::Data, payload, None)
+    }
+
     /// Creates a new frame of type [`FrameKind::Headers`].
     ///
     /// # Panics
@@ -140,6 +194,36 @@ impl<'a> Frame<'a> {
         Self::new(FrameKind::Settings, payload, None)
     }
 
+    /// Creates a new frame of type [`FrameKind::GoAway`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_goaway(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(FrameKind::GoAway, payload, None)
+    }
+
+    /// Creates a new frame of type [`FrameKind::CancelPush`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_cancel_push(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(FrameKind::CancelPush, payload, None)
+    }
+
+    /// Creates a new frame of type [`FrameKind::MaxPushId`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_max_push_id(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(FrameKind::MaxPushId, payload, None)
+    }
+
     /// Creates a new frame of type [`FrameKind::WebTransport`].
     #[inline(always)]
     pub fn new_webtransport(session_id: SessionId) -> Self {
@@ -168,12 +252,15 @@ impl<'a> Frame<'a> {
     /// to parse an entire frame.
     ///
     /// In case [`None`] or [`Err`], `bytes_reader` might be partially read.
+    ///
+    /// Frames of unknown type are returned as [`FrameKind::Unknown`] once
+    /// their whole payload has been skipped; the returned frame has an empty payload.
     pub fn read<R>(bytes_reader: &mut R) -> Result<Option<Self>, ParseError>
     where
         R: BytesReader<'a>,
     {
         let kind = match bytes_reader.get_varint() {
-            Some(kind_id) => FrameKind::parse(kind_id).ok_or(ParseError::UnknownFrame)?,
+            Some(kind_id) => FrameKind::parse(kind_id).ok_or(ParseError::ReservedFrame)?,
             None => return Ok(None),
         };
 
@@ -191,6 +278,13 @@ impl<'a> Frame<'a> {
                 None => return Ok(None),
             };
 
+            if matches!(kind, FrameKind::Unknown(_)) {
+                return match bytes_reader.get_bytes(payload_len) {
+                    Some(_) => Ok(Some(Self::new(kind, Cow::Owned(Vec::new()), None))),
+                    None => Ok(None),
+                };
+            }
+
             if payload_len > Self::MAX_PARSE_PAYLOAD_ALLOWED {
                 return Err(ParseError::PayloadTooBig);
             }
@@ -205,6 +299,9 @@ impl<'a> Frame<'a> {
     }
 
     /// Reads a [`Frame`] from a `reader`.
+    ///
+    /// Frames of unknown type are returned as [`FrameKind::Unknown`] once
+    /// their whole payload has been discarded; the returned frame has an empty payload.
     #[cfg(feature = "async")]
     #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
     pub async fn read_async<R>(reader: &mut R) -> Result<Frame<'a>, IoReadError>
@@ -214,7 +311,8 @@ impl<'a> Frame<'a> {
         use crate::bytes::BytesReaderAsync;
 
         let kind_id = reader.get_varint().await?;
-        let kind = FrameKind::parse(kind_id).ok_or(IoReadError::Parse(ParseError::UnknownFrame))?;
+        let kind =
+            FrameKind::parse(kind_id).ok_or(IoReadError::Parse(ParseError::ReservedFrame))?;
 
         if matches!(kind, FrameKind::WebTransport) {
             let session_id =
@@ -235,6 +333,25 @@ impl<'a> Frame<'a> {
                 })?
                 .into_inner() as usize;
 
+            if matches!(kind, FrameKind::Unknown(_)) {
+                let mut discard = [0; 512];
+                let mut remaining = payload_len;
+
+                while remaining > 0 {
+                    let chunk_len = remaining.min(discard.len());
+                    reader
+                        .get_buffer(&mut discard[..chunk_len])
+                        .await
+                        .map_err(|e| match e {
+                            bytes::IoReadError::ImmediateFin => bytes::IoReadError::UnexpectedFin,
+                            _ => e,
+                        })?;
+                    remaining -= chunk_len;
+                }
+
+                return Ok(Self::new(kind, Cow::Owned(Vec::new()), None));
+            }
+
             if payload_len > Self::MAX_PARSE_PAYLOAD_ALLOWED {
                 return Err(IoReadError::Parse(ParseError::PayloadTooBig));
             }
@@ -451,6 +568,11 @@ mod frame_kind_ids {
     pub const DATA: VarInt = VarInt::from_u32(0x00);
     pub const HEADERS: VarInt = VarInt::from_u32(0x01);
     pub const SETTINGS: VarInt = VarInt::from_u32(0x04);
+    pub const CANCEL_PUSH: VarInt = VarInt::from_u32(0x03);
+    pub const GOAWAY: VarInt = VarInt::from_u32(0x07);
+    pub const MAX_PUSH_ID: VarInt = VarInt::from_u32(0x0d);
+    pub const PRIORITY_UPDATE_REQUEST: VarInt = VarInt::from_u32(0x000f_0700);
+    pub const PRIORITY_UPDATE_PUSH: VarInt = VarInt::from_u32(0x000f_0701);
     pub const WEBTRANSPORT_STREAM: VarInt = VarInt::from_u32(0x41);
 }
 
@@ -603,21 +725,51 @@ mod tests {
 
     #[test]
     fn unknown_frame() {
-        let buffer = Frame::serialize_any(VarInt::from_u32(0x0042_4242), b"This is a test payload");
+        let mut buffer = Frame::serialize_any(VarInt::from_u32(0x0042_4242), &[0; 8192]);
+        buffer.extend(Frame::serialize_any(frame_kind_ids::DATA, b"after"));
+        let mut buffer = buffer.as_slice();
+
+        let frame = Frame::read(&mut buffer).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Unknown(id) if id.into_inner() == 0x0042_4242));
+        assert!(frame.payload().is_empty());
+
+        let frame = Frame::read(&mut buffer).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Data));
+        assert_eq!(frame.payload(), b"after");
+    }
+
+    #[tokio::test]
+    async fn unknown_frame_async() {
+        let mut buffer = Frame::serialize_any(VarInt::from_u32(0x0042_4242), &[0; 8192]);
+        buffer.extend(Frame::serialize_any(frame_kind_ids::DATA, b"after"));
+        let mut buffer = buffer.as_slice();
+
+        let frame = Frame::read_async(&mut buffer).await.unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Unknown(id) if id.into_inner() == 0x0042_4242));
+        assert!(frame.payload().is_empty());
+
+        let frame = Frame::read_async(&mut buffer).await.unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Data));
+        assert_eq!(frame.payload(), b"after");
+    }
+
+    #[test]
+    fn reserved_frame() {
+        let buffer = Frame::serialize_any(VarInt::from_u32(0x06), b"ping");
 
         assert!(matches!(
             Frame::read(&mut buffer.as_slice()),
-            Err(ParseError::UnknownFrame)
+            Err(ParseError::ReservedFrame)
         ));
     }
 
     #[tokio::test]
-    async fn unknown_frame_async() {
-        let buffer = Frame::serialize_any(VarInt::from_u32(0x0042_4242), b"This is a test payload");
+    async fn reserved_frame_async() {
+        let buffer = Frame::serialize_any(VarInt::from_u32(0x06), b"ping");
 
         assert!(matches!(
             Frame::read_async(&mut buffer.as_slice()).await,
-            Err(IoReadError::Parse(ParseError::UnknownFrame))
+            Err(IoReadError::Parse(ParseError::ReservedFrame))
         ));
     }
 
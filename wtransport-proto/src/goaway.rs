@@ -0,0 +1,86 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::error::ErrorCode;
+use crate::frame::Frame;
+use crate::frame::FrameKind;
+use crate::varint::VarInt;
+use std::borrow::Cow;
+
+/// An HTTP3 GOAWAY frame payload.
+///
+/// It carries the identifier (a client-initiated bidirectional stream ID when sent
+/// by a server, a push ID when sent by a client) from which requests will not
+/// be processed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GoAway {
+    id: VarInt,
+}
+
+impl GoAway {
+    /// Creates a new GOAWAY payload.
+    #[inline(always)]
+    pub fn new(id: VarInt) -> Self {
+        Self { id }
+    }
+
+    /// Constructs [`GoAway`] parsing payload of a [`Frame`].
+    ///
+    /// Returns an [`Err`] in case of malformed payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::GoAway`].
+    pub fn with_frame(frame: &Frame) -> Result<Self, ErrorCode> {
+        assert!(matches!(frame.kind(), FrameKind::GoAway));
+
+        let mut buffer_reader = BufferReader::new(frame.payload());
+        let id = buffer_reader.get_varint().ok_or(ErrorCode::Frame)?;
+
+        if buffer_reader.capacity() > 0 {
+            return Err(ErrorCode::Frame);
+        }
+
+        Ok(Self { id })
+    }
+
+    /// Generates a [`Frame`] with this payload.
+    pub fn generate_frame(&self) -> Frame<'static> {
+        let mut payload = Vec::with_capacity(self.id.size());
+        payload.put_varint(self.id).expect("Vec does not have EOF");
+
+        Frame::new_goaway(Cow::Owned(payload))
+    }
+
+    /// Returns the identifier carried by the frame.
+    #[inline(always)]
+    pub fn id(&self) -> VarInt {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let goaway = GoAway::new(VarInt::from_u32(1024));
+
+        let mut buffer = Vec::new();
+        goaway.generate_frame().write(&mut buffer).unwrap();
+
+        let frame = Frame::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::GoAway));
+        assert_eq!(GoAway::with_frame(&frame).unwrap(), goaway);
+    }
+
+    #[test]
+    fn malformed() {
+        let frame = Frame::new_goaway(Cow::Borrowed(&[]));
+        assert!(matches!(GoAway::with_frame(&frame), Err(ErrorCode::Frame)));
+
+        let frame = Frame::new_goaway(Cow::Borrowed(&[0x01, 0x02]));
+        assert!(matches!(GoAway::with_frame(&frame), Err(ErrorCode::Frame)));
+    }
+}
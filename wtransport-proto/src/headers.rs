@@ -1,54 +1,195 @@
 use crate::error::ErrorCode;
 use crate::frame::Frame;
 use crate::frame::FrameKind;
+use crate::ids::StreamId;
+use crate::qpack::DecodeOutcome;
 use crate::qpack::Decoder;
+use crate::qpack::DynamicDecoder;
+use crate::qpack::DynamicEncoder;
 use crate::qpack::Encoder;
 use std::borrow::Cow;
-use std::collections::HashMap;
 
 /// HTTP3 headers from the request or response.
-#[derive(Debug)]
-pub struct Headers(HashMap<String, String>);
+///
+/// Header fields are kept in the order they have been inserted (or received), and
+/// the same field name can appear multiple times (see [`get_all`](Self::get_all)).
+/// Lookups by name are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
 
 impl Headers {
     /// Constructs the headers from a HTTP3 [`Frame`].
     ///
+    /// Multiple `cookie` fields are joined into a single one, as
+    /// specified in RFC 9114 (section 4.2.1).
+    ///
     /// # Panics
     ///
     /// Panics if `frame` is not type [`FrameKind::Headers`].
     pub fn with_frame(frame: &Frame) -> Result<Self, ErrorCode> {
         assert!(matches!(frame.kind(), FrameKind::Headers));
 
-        let headers = Decoder::decode(frame.payload()).map_err(|_| ErrorCode::Decompression)?;
+        let fields = Decoder::decode(frame.payload()).map_err(|_| ErrorCode::Decompression)?;
+
+        Ok(Self::with_decoded_fields(fields))
+    }
+
+    /// Constructs the headers from a HTTP3 [`Frame`] received on stream `stream_id`,
+    /// resolving dynamic table references with `decoder`.
+    ///
+    /// Returns [`None`] if the frame references dynamic table entries not yet received:
+    /// the stream is blocked and decoding must be retried later.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::Headers`].
+    pub fn with_frame_dynamic(
+        frame: &Frame,
+        stream_id: StreamId,
+        decoder: &mut DynamicDecoder,
+    ) -> Result<Option<Self>, ErrorCode> {
+        assert!(matches!(frame.kind(), FrameKind::Headers));
 
-        Ok(Self(headers))
+        match decoder
+            .decode(stream_id, frame.payload())
+            .map_err(|_| ErrorCode::Decompression)?
+        {
+            DecodeOutcome::Decoded(fields) => Ok(Some(Self::with_decoded_fields(fields))),
+            DecodeOutcome::Blocked => Ok(None),
+        }
     }
 
     /// Generates a [`Frame`] with these headers.
     pub fn generate_frame(&self) -> Frame<'static> {
-        let payload = Encoder::encode(&self.0);
+        let payload = Encoder::encode(self.iter());
+        Frame::new_headers(Cow::Owned(payload.to_vec()))
+    }
+
+    /// Generates a [`Frame`] with these headers to be sent on stream `stream_id`,
+    /// using the dynamic table of `encoder`.
+    pub fn generate_frame_dynamic(
+        &self,
+        stream_id: StreamId,
+        encoder: &mut DynamicEncoder,
+    ) -> Frame<'static> {
+        let payload = encoder.encode(stream_id, self.iter());
         Frame::new_headers(Cow::Owned(payload.to_vec()))
     }
 
     /// Returns a reference to the value associated with the key.
+    ///
+    /// If the field appears multiple times, the first value is returned.
     #[inline(always)]
     pub fn get<K>(&self, key: K) -> Option<&str>
     where
         K: AsRef<str>,
     {
-        self.0.get(key.as_ref()).map(|s| s.as_str())
+        self.get_all(key).next()
+    }
+
+    /// Returns all the values associated with the key, in order.
+    pub fn get_all<K>(&self, key: K) -> impl Iterator<Item = &str>
+    where
+        K: AsRef<str>,
+    {
+        self.0
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key.as_ref()))
+            .map(|(_, v)| v.as_str())
     }
 
     /// Inserts a field (key, value) in the headers.
     ///
-    /// If the headers did have this key present, the value is updated.
-    #[inline(always)]
+    /// If the headers did have this key present, the first field is updated
+    /// and any other field with the same key is removed.
     pub fn insert<K, V>(&mut self, key: K, value: V)
     where
         K: ToString,
         V: ToString,
     {
-        self.0.insert(key.to_string(), value.to_string());
+        let field = (key.to_string(), value.to_string());
+
+        match self.position(&field.0) {
+            Some(index) => {
+                let mut position = 0;
+                self.0.retain(|(k, _)| {
+                    let retain = position <= index || !k.eq_ignore_ascii_case(&field.0);
+                    position += 1;
+                    retain
+                });
+
+                self.0[index] = field;
+            }
+            None => self.0.push(field),
+        }
+    }
+
+    /// Removes all the fields with the given key.
+    pub fn remove<K>(&mut self, key: K)
+    where
+        K: AsRef<str>,
+    {
+        self.0
+            .retain(|(k, _)| !k.eq_ignore_ascii_case(key.as_ref()));
+    }
+
+    /// Appends a field (key, value) at the end of the headers.
+    ///
+    /// Fields already present with the same key are left untouched.
+    #[inline(always)]
+    pub fn append<K, V>(&mut self, key: K, value: V)
+    where
+        K: ToString,
+        V: ToString,
+    {
+        self.0.push((key.to_string(), value.to_string()));
+    }
+
+    /// Returns an iterator over all fields (key, value), in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the number of fields.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no fields.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.0.iter().position(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+
+    fn with_decoded_fields(fields: Vec<(String, String)>) -> Self {
+        let mut headers = Self(Vec::with_capacity(fields.len()));
+        let mut cookie: Option<usize> = None;
+
+        for (key, value) in fields {
+            if !key.eq_ignore_ascii_case("cookie") {
+                headers.0.push((key, value));
+                continue;
+            }
+
+            match cookie {
+                Some(index) => {
+                    let crumbs = &mut headers.0[index].1;
+                    crumbs.push_str("; ");
+                    crumbs.push_str(&value);
+                }
+                None => {
+                    cookie = Some(headers.0.len());
+                    headers.0.push((key, value));
+                }
+            }
+        }
+
+        headers
     }
 }
 
@@ -66,8 +207,8 @@ where
     }
 }
 
-impl AsRef<HashMap<String, String>> for Headers {
-    fn as_ref(&self) -> &HashMap<String, String> {
+impl AsRef<[(String, String)]> for Headers {
+    fn as_ref(&self) -> &[(String, String)] {
         &self.0
     }
 }
@@ -112,6 +253,82 @@ mod tests {
         assert_eq!(headers.get("key3"), Some("value3"));
     }
 
+    #[test]
+    fn insert_duplicates() {
+        let mut headers = [("key1", "value1"), ("key2", "value2"), ("Key1", "value3")]
+            .into_iter()
+            .collect::<Headers>();
+
+        headers.insert("KEY1", "value1bis");
+
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            [("KEY1", "value1bis"), ("key2", "value2")]
+        );
+    }
+
+    #[test]
+    fn get_all() {
+        let mut headers = [("key1", "value1"), ("key2", "value2")]
+            .into_iter()
+            .collect::<Headers>();
+
+        headers.append("key1", "value3");
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("key1"), Some("value1"));
+        assert_eq!(
+            headers.get_all("key1").collect::<Vec<_>>(),
+            ["value1", "value3"]
+        );
+        assert_eq!(headers.get_all("key3").count(), 0);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let headers = [("Key1", "value1")].into_iter().collect::<Headers>();
+
+        assert_eq!(headers.get("key1"), Some("value1"));
+        assert_eq!(headers.get("KEY1"), Some("value1"));
+    }
+
+    #[test]
+    fn order_and_duplicates() {
+        let headers = [
+            ("key2", "value2"),
+            ("key1", "value1"),
+            ("sec-webtransport-http3-draft", "draft02"),
+            ("key3", "value3"),
+            ("sec-webtransport-http3-draft", "draft07"),
+        ]
+        .into_iter()
+        .collect::<Headers>();
+
+        let frame = headers.generate_frame();
+
+        assert_eq!(headers, Headers::with_frame(&frame).unwrap());
+    }
+
+    #[test]
+    fn cookie_crumbs() {
+        let headers = [
+            ("cookie", "a=b"),
+            ("key1", "value1"),
+            ("cookie", "c=d"),
+            ("cookie", "e=f"),
+        ]
+        .into_iter()
+        .collect::<Headers>();
+
+        let frame = headers.generate_frame();
+        let headers = Headers::with_frame(&frame).unwrap();
+
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            [("cookie", "a=b; c=d; e=f"), ("key1", "value1")]
+        );
+    }
+
     #[test]
     fn idempotence() {
         let headers = [("key1", "value1"), ("key2", "value2")]
@@ -265,6 +265,9 @@ impl StatusCode {
     /// HTTP 404 Not Found status code.
     pub const NOT_FOUND: Self = Self(404);
 
+    /// HTTP 421 Misdirected Request status code.
+    pub const MISDIRECTED_REQUEST: Self = Self(421);
+
     /// Tries to construct from `u32`.
     #[inline(always)]
     pub fn try_from_u32(value: u32) -> Result<Self, InvalidStatusCode> {
@@ -5,6 +5,12 @@
 /// I/O and buffer operations.
 pub mod bytes;
 
+/// HTTP3 capsules.
+pub mod capsule;
+
+/// CONNECT-UDP (proxying UDP in HTTP) utilities.
+pub mod connect_udp;
+
 /// HTTP3 datagrams.
 pub mod datagram;
 
@@ -14,12 +20,18 @@ pub mod error;
 /// HTTP3 frame.
 pub mod frame;
 
+/// HTTP3 GOAWAY frame payload.
+pub mod goaway;
+
 /// HTTP3 HEADERS frame payload.
 pub mod headers;
 
 /// Types for identifiers.
 pub mod ids;
 
+/// HTTP3 `MAX_PUSH_ID` and `CANCEL_PUSH` frame payloads.
+pub mod push;
+
 /// Basic QPACK implementation.
 pub mod qpack;
 
@@ -38,5 +50,8 @@ pub mod stream_header;
 /// QUIC variable-length integer.
 pub mod varint;
 
+/// WebSocket over HTTP/3 (RFC 9220) utilities.
+pub mod websocket;
+
 /// Application Layer Protocol Negotiation for WebTransport connections.
 pub const WEBTRANSPORT_ALPN: &[u8; 2] = b"h3";
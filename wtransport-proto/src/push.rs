@@ -0,0 +1,150 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::error::ErrorCode;
+use crate::frame::Frame;
+use crate::frame::FrameKind;
+use crate::varint::VarInt;
+use std::borrow::Cow;
+
+/// An HTTP3 `MAX_PUSH_ID` frame payload.
+///
+/// Sent by a client, it carries the maximum push ID the server can use.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MaxPushId {
+    push_id: VarInt,
+}
+
+impl MaxPushId {
+    /// Creates a new `MAX_PUSH_ID` payload.
+    #[inline(always)]
+    pub fn new(push_id: VarInt) -> Self {
+        Self { push_id }
+    }
+
+    /// Constructs [`MaxPushId`] parsing payload of a [`Frame`].
+    ///
+    /// Returns an [`Err`] in case of malformed payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::MaxPushId`].
+    pub fn with_frame(frame: &Frame) -> Result<Self, ErrorCode> {
+        assert!(matches!(frame.kind(), FrameKind::MaxPushId));
+
+        Ok(Self {
+            push_id: parse_push_id(frame)?,
+        })
+    }
+
+    /// Generates a [`Frame`] with this payload.
+    pub fn generate_frame(&self) -> Frame<'static> {
+        Frame::new_max_push_id(Cow::Owned(serialize_push_id(self.push_id)))
+    }
+
+    /// Returns the maximum push ID carried by the frame.
+    #[inline(always)]
+    pub fn push_id(&self) -> VarInt {
+        self.push_id
+    }
+}
+
+/// An HTTP3 `CANCEL_PUSH` frame payload.
+///
+/// It carries the push ID of the server push being cancelled.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CancelPush {
+    push_id: VarInt,
+}
+
+impl CancelPush {
+    /// Creates a new `CANCEL_PUSH` payload.
+    #[inline(always)]
+    pub fn new(push_id: VarInt) -> Self {
+        Self { push_id }
+    }
+
+    /// Constructs [`CancelPush`] parsing payload of a [`Frame`].
+    ///
+    /// Returns an [`Err`] in case of malformed payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::CancelPush`].
+    pub fn with_frame(frame: &Frame) -> Result<Self, ErrorCode> {
+        assert!(matches!(frame.kind(), FrameKind::CancelPush));
+
+        Ok(Self {
+            push_id: parse_push_id(frame)?,
+        })
+    }
+
+    /// Generates a [`Frame`] with this payload.
+    pub fn generate_frame(&self) -> Frame<'static> {
+        Frame::new_cancel_push(Cow::Owned(serialize_push_id(self.push_id)))
+    }
+
+    /// Returns the push ID carried by the frame.
+    #[inline(always)]
+    pub fn push_id(&self) -> VarInt {
+        self.push_id
+    }
+}
+
+fn parse_push_id(frame: &Frame) -> Result<VarInt, ErrorCode> {
+    let mut buffer_reader = BufferReader::new(frame.payload());
+    let push_id = buffer_reader.get_varint().ok_or(ErrorCode::Frame)?;
+
+    if buffer_reader.capacity() > 0 {
+        return Err(ErrorCode::Frame);
+    }
+
+    Ok(push_id)
+}
+
+fn serialize_push_id(push_id: VarInt) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(push_id.size());
+    payload.put_varint(push_id).expect("Vec does not have EOF");
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde() {
+        let max_push_id = MaxPushId::new(VarInt::from_u32(1024));
+
+        let mut buffer = Vec::new();
+        max_push_id.generate_frame().write(&mut buffer).unwrap();
+
+        let frame = Frame::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::MaxPushId));
+        assert_eq!(MaxPushId::with_frame(&frame).unwrap(), max_push_id);
+
+        let cancel_push = CancelPush::new(VarInt::from_u32(42));
+
+        let mut buffer = Vec::new();
+        cancel_push.generate_frame().write(&mut buffer).unwrap();
+
+        let frame = Frame::read(&mut buffer.as_slice()).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::CancelPush));
+        assert_eq!(CancelPush::with_frame(&frame).unwrap(), cancel_push);
+    }
+
+    #[test]
+    fn malformed() {
+        let frame = Frame::new_max_push_id(Cow::Borrowed(&[]));
+        assert!(matches!(
+            MaxPushId::with_frame(&frame),
+            Err(ErrorCode::Frame)
+        ));
+
+        let frame = Frame::new_cancel_push(Cow::Borrowed(&[0x01, 0x02]));
+        assert!(matches!(
+            CancelPush::with_frame(&frame),
+            Err(ErrorCode::Frame)
+        ));
+    }
+}
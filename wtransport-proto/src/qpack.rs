@@ -2,7 +2,10 @@ use crate::bytes::BufferReader;
 use crate::bytes::BytesReader;
 use crate::bytes::BytesWriter;
 use crate::bytes::EndOfBuffer;
+use crate::ids::StreamId;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 
 /// Usage: `const_assert!(Var1: Ty, Var2: Ty, ... => expression)`
 macro_rules! const_assert {
@@ -42,6 +45,22 @@ pub enum DecodingError {
     /// Index is out-of-bound in the static table.
     #[error("index not found in the static table")]
     IndexNotfound,
+
+    /// Reference to an entry not present (or not allowed) in the dynamic table.
+    #[error("invalid dynamic table reference")]
+    InvalidReference,
+
+    /// Dynamic table capacity exceeds the maximum allowed.
+    #[error("dynamic table capacity exceeded")]
+    CapacityExceeded,
+
+    /// Too many streams are blocked waiting for dynamic table updates.
+    #[error("too many blocked streams")]
+    BlockedStreamsExceeded,
+
+    /// Encoder or decoder stream instruction is not valid in the current state.
+    #[error("invalid instruction")]
+    InvalidInstruction,
 }
 
 enum FieldLineType {
@@ -56,14 +75,14 @@ enum FieldLineType {
 ///
 /// It only supports stateless decoding, so any data requiring
 /// dynamic table will end up in a [`DecodingError::DynamicNotSupported`]
-/// error.
+/// error. See [`DynamicDecoder`] for decoding with the dynamic table.
 pub struct Decoder;
 
 impl Decoder {
     /// Decodes data stream.
     ///
-    /// Result is an hash-map of headers.
-    pub fn decode<D>(data: D) -> Result<HashMap<String, String>, DecodingError>
+    /// Result is the list of header fields, in the order they have been encoded.
+    pub fn decode<D>(data: D) -> Result<Vec<(String, String)>, DecodingError>
     where
         D: AsRef<[u8]>,
     {
@@ -72,7 +91,17 @@ impl Decoder {
         Self::decode_integer::<8, _>(&mut buffer_reader)?;
         Self::decode_integer::<7, _>(&mut buffer_reader)?;
 
-        let mut headers = HashMap::new();
+        Self::decode_field_lines(&mut buffer_reader, None)
+    }
+
+    /// Decodes all field lines of a section.
+    ///
+    /// Dynamic table references are resolved against `dynamic` (if any).
+    fn decode_field_lines(
+        buffer_reader: &mut BufferReader,
+        dynamic: Option<&SectionContext>,
+    ) -> Result<Vec<(String, String)>, DecodingError> {
+        let mut headers = Vec::new();
 
         while buffer_reader.capacity() > 0 {
             let field = buffer_reader.buffer_remaining()[0];
@@ -80,40 +109,50 @@ impl Decoder {
             match Self::decode_field_line_type(field) {
                 FieldLineType::Indexed => {
                     let is_dynamic = field & 0b0100_0000 == 0;
-                    if is_dynamic {
-                        return Err(DecodingError::DynamicNotSupported);
-                    }
+                    let index = Self::decode_integer::<6, _>(&mut *buffer_reader)?.1;
+
+                    let (key, value) = if is_dynamic {
+                        SectionContext::lookup_relative(dynamic, index)?
+                    } else {
+                        StaticTable::lookup_field(index).ok_or(DecodingError::IndexNotfound)?
+                    };
 
-                    let index = Self::decode_integer::<6, _>(&mut buffer_reader)?.1;
-                    let (key, value) =
-                        StaticTable::lookup_field(index).ok_or(DecodingError::IndexNotfound)?;
-                    headers.insert(key.to_string(), value.to_string());
+                    headers.push((key.to_string(), value.to_string()));
                 }
                 FieldLineType::IndexedPost => {
-                    return Err(DecodingError::DynamicNotSupported);
+                    let index = Self::decode_integer::<4, _>(&mut *buffer_reader)?.1;
+                    let (key, value) = SectionContext::lookup_post_base(dynamic, index)?;
+
+                    headers.push((key.to_string(), value.to_string()));
                 }
                 FieldLineType::LiteralRefName => {
                     let is_dynamic = field & 0b0001_0000 == 0;
-                    if is_dynamic {
-                        return Err(DecodingError::DynamicNotSupported);
-                    }
+                    let index = Self::decode_integer::<4, _>(&mut *buffer_reader)?.1;
 
-                    let index = Self::decode_integer::<4, _>(&mut buffer_reader)?.1;
-                    let key = StaticTable::lookup_field(index)
-                        .ok_or(DecodingError::IndexNotfound)?
-                        .0;
-                    let value = Self::decode_string::<7, _>(&mut buffer_reader)?;
+                    let key = if is_dynamic {
+                        SectionContext::lookup_relative(dynamic, index)?.0
+                    } else {
+                        StaticTable::lookup_field(index)
+                            .ok_or(DecodingError::IndexNotfound)?
+                            .0
+                    };
 
-                    headers.insert(key.to_string(), value);
+                    let value = Self::decode_string::<7, _>(&mut *buffer_reader)?;
+
+                    headers.push((key.to_string(), value));
                 }
                 FieldLineType::LiteralPostRefName => {
-                    return Err(DecodingError::DynamicNotSupported);
+                    let index = Self::decode_integer::<3, _>(&mut *buffer_reader)?.1;
+                    let key = SectionContext::lookup_post_base(dynamic, index)?.0;
+                    let value = Self::decode_string::<7, _>(&mut *buffer_reader)?;
+
+                    headers.push((key.to_string(), value));
                 }
                 FieldLineType::LiteralLitName => {
-                    let key = Self::decode_string::<3, _>(&mut buffer_reader)?;
-                    let value = Self::decode_string::<7, _>(&mut buffer_reader)?;
+                    let key = Self::decode_string::<3, _>(&mut *buffer_reader)?;
+                    let value = Self::decode_string::<7, _>(&mut *buffer_reader)?;
 
-                    headers.insert(key, value);
+                    headers.push((key, value));
                 }
             }
         }
@@ -218,6 +257,7 @@ impl Decoder {
 ///
 /// It only supports stateless decoding, so all encoding
 /// will be performed by means of the static table.
+/// See [`DynamicEncoder`] for encoding with the dynamic table.
 pub struct Encoder;
 
 impl Encoder {
@@ -320,6 +360,792 @@ impl Encoder {
     }
 }
 
+/// Result of [`DynamicDecoder::decode`].
+#[derive(Debug)]
+pub enum DecodeOutcome {
+    /// The field section has been decoded into the list of header fields.
+    Decoded(Vec<(String, String)>),
+
+    /// The field section references dynamic table entries not received yet.
+    ///
+    /// Decoding has to be retried once the encoder stream delivers more instructions.
+    Blocked,
+}
+
+/// QPACK decoder with dynamic table support.
+///
+/// The dynamic table is updated by means of the instructions received on the peer's encoder
+/// stream (see [`feed_encoder_stream`](Self::feed_encoder_stream)).
+/// Instructions to be sent on the local decoder stream (section acknowledgements, stream
+/// cancellations and insert count increments) are buffered and can be retrieved with
+/// [`take_instructions`](Self::take_instructions).
+#[derive(Debug)]
+pub struct DynamicDecoder {
+    table: DynamicTable,
+    max_table_capacity: usize,
+    max_blocked_streams: usize,
+    blocked_streams: HashSet<StreamId>,
+    acknowledged_inserts: usize,
+    encoder_stream: Vec<u8>,
+    instructions: Vec<u8>,
+}
+
+impl DynamicDecoder {
+    /// Creates a new decoder.
+    ///
+    /// `max_table_capacity` and `max_blocked_streams` are the values advertised to the peer
+    /// with `SETTINGS_QPACK_MAX_TABLE_CAPACITY` and `SETTINGS_QPACK_BLOCKED_STREAMS`.
+    pub fn new(max_table_capacity: usize, max_blocked_streams: usize) -> Self {
+        Self {
+            table: DynamicTable::default(),
+            max_table_capacity,
+            max_blocked_streams,
+            blocked_streams: HashSet::new(),
+            acknowledged_inserts: 0,
+            encoder_stream: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Returns the total number of insertions into the dynamic table.
+    #[inline(always)]
+    pub fn insert_count(&self) -> usize {
+        self.table.inserted
+    }
+
+    /// Returns the number of streams currently blocked.
+    #[inline(always)]
+    pub fn blocked_streams(&self) -> usize {
+        self.blocked_streams.len()
+    }
+
+    /// Processes data received on the peer's encoder stream.
+    ///
+    /// Incomplete instructions are buffered until more data is fed.
+    /// Returns an [`Err`] in case of an encoder stream error.
+    pub fn feed_encoder_stream(&mut self, data: &[u8]) -> Result<(), DecodingError> {
+        self.encoder_stream.extend_from_slice(data);
+
+        let mut offset = 0;
+
+        loop {
+            let mut buffer_reader = BufferReader::new(&self.encoder_stream[offset..]);
+
+            if buffer_reader.capacity() == 0 {
+                break;
+            }
+
+            match Self::read_encoder_instruction(&mut buffer_reader) {
+                Ok(instruction) => {
+                    offset += buffer_reader.offset();
+                    self.apply_encoder_instruction(instruction)?;
+                }
+                Err(DecodingError::UnexpectedFin) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        self.encoder_stream.drain(..offset);
+
+        let increment = self.table.inserted - self.acknowledged_inserts;
+        if increment > 0 {
+            Encoder::encode_integer::<6, _>(0b00, increment, &mut self.instructions)
+                .expect("vec does not eof");
+            self.acknowledged_inserts = self.table.inserted;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the field section received on stream `stream_id`.
+    ///
+    /// Returns [`DecodeOutcome::Blocked`] if the section references entries not yet inserted
+    /// in the dynamic table. In that case, the stream is accounted as blocked until
+    /// decoding succeeds or it is cancelled (see [`cancel_stream`](Self::cancel_stream)).
+    pub fn decode<D>(
+        &mut self,
+        stream_id: StreamId,
+        data: D,
+    ) -> Result<DecodeOutcome, DecodingError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let mut buffer_reader = BufferReader::new(data.as_ref());
+
+        let encoded_insert_count = Decoder::decode_integer::<8, _>(&mut buffer_reader)?.1;
+        let required_insert_count = self.required_insert_count(encoded_insert_count)?;
+
+        let (sign, delta_base) = Decoder::decode_integer::<7, _>(&mut buffer_reader)?;
+        let base = if sign & 0x1 == 0 {
+            required_insert_count.checked_add(delta_base)
+        } else {
+            required_insert_count.checked_sub(delta_base + 1)
+        }
+        .ok_or(DecodingError::InvalidReference)?;
+
+        if required_insert_count > self.table.inserted {
+            if !self.blocked_streams.contains(&stream_id)
+                && self.blocked_streams.len() >= self.max_blocked_streams
+            {
+                return Err(DecodingError::BlockedStreamsExceeded);
+            }
+
+            self.blocked_streams.insert(stream_id);
+            return Ok(DecodeOutcome::Blocked);
+        }
+
+        self.blocked_streams.remove(&stream_id);
+
+        let context = SectionContext {
+            table: &self.table,
+            base,
+            required_insert_count,
+        };
+
+        let headers = Decoder::decode_field_lines(&mut buffer_reader, Some(&context))?;
+
+        if required_insert_count > 0 {
+            Encoder::encode_integer::<7, _>(
+                0b1,
+                stream_id.into_u64() as usize,
+                &mut self.instructions,
+            )
+            .expect("vec does not eof");
+
+            self.acknowledged_inserts = self.acknowledged_inserts.max(required_insert_count);
+        }
+
+        Ok(DecodeOutcome::Decoded(headers))
+    }
+
+    /// Abandons decoding of stream `stream_id`.
+    ///
+    /// If the stream is blocked, a stream cancellation instruction is emitted.
+    pub fn cancel_stream(&mut self, stream_id: StreamId) {
+        if self.blocked_streams.remove(&stream_id) {
+            Encoder::encode_integer::<6, _>(
+                0b01,
+                stream_id.into_u64() as usize,
+                &mut self.instructions,
+            )
+            .expect("vec does not eof");
+        }
+    }
+
+    /// Takes the instructions to be sent on the local decoder stream.
+    #[inline(always)]
+    pub fn take_instructions(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.instructions)
+    }
+
+    fn max_entries(&self) -> usize {
+        self.max_table_capacity / DynamicTable::ENTRY_OVERHEAD
+    }
+
+    fn required_insert_count(&self, encoded_insert_count: usize) -> Result<usize, DecodingError> {
+        if encoded_insert_count == 0 {
+            return Ok(0);
+        }
+
+        let full_range = 2 * self.max_entries();
+        if encoded_insert_count > full_range {
+            return Err(DecodingError::InvalidReference);
+        }
+
+        let max_value = self.table.inserted + self.max_entries();
+        let max_wrapped = (max_value / full_range) * full_range;
+        let mut required_insert_count = max_wrapped + encoded_insert_count - 1;
+
+        if required_insert_count > max_value {
+            if required_insert_count <= full_range {
+                return Err(DecodingError::InvalidReference);
+            }
+
+            required_insert_count -= full_range;
+        }
+
+        if required_insert_count == 0 {
+            return Err(DecodingError::InvalidReference);
+        }
+
+        Ok(required_insert_count)
+    }
+
+    fn read_encoder_instruction(
+        buffer_reader: &mut BufferReader,
+    ) -> Result<EncoderInstruction, DecodingError> {
+        let byte = buffer_reader.buffer_remaining()[0];
+
+        if byte & 0b1000_0000 != 0 {
+            let (flags, index) = Decoder::decode_integer::<6, _>(&mut *buffer_reader)?;
+            let value = Decoder::decode_string::<7, _>(&mut *buffer_reader)?;
+
+            Ok(EncoderInstruction::InsertNameRef {
+                is_static: flags & 0b01 != 0,
+                index,
+                value,
+            })
+        } else if byte & 0b0100_0000 != 0 {
+            let name = Decoder::decode_string::<5, _>(&mut *buffer_reader)?;
+            let value = Decoder::decode_string::<7, _>(&mut *buffer_reader)?;
+
+            Ok(EncoderInstruction::InsertLiteralName { name, value })
+        } else if byte & 0b0010_0000 != 0 {
+            let capacity = Decoder::decode_integer::<5, _>(&mut *buffer_reader)?.1;
+            Ok(EncoderInstruction::SetCapacity(capacity))
+        } else {
+            let index = Decoder::decode_integer::<5, _>(&mut *buffer_reader)?.1;
+            Ok(EncoderInstruction::Duplicate(index))
+        }
+    }
+
+    fn apply_encoder_instruction(
+        &mut self,
+        instruction: EncoderInstruction,
+    ) -> Result<(), DecodingError> {
+        match instruction {
+            EncoderInstruction::InsertNameRef {
+                is_static,
+                index,
+                value,
+            } => {
+                let name = if is_static {
+                    StaticTable::lookup_field(index)
+                        .ok_or(DecodingError::IndexNotfound)?
+                        .0
+                        .to_string()
+                } else {
+                    self.lookup_encoder_relative(index)?.0.clone()
+                };
+
+                self.table.insert(name, value)
+            }
+            EncoderInstruction::InsertLiteralName { name, value } => self.table.insert(name, value),
+            EncoderInstruction::SetCapacity(capacity) => {
+                if capacity > self.max_table_capacity {
+                    return Err(DecodingError::CapacityExceeded);
+                }
+
+                self.table.set_capacity(capacity);
+                Ok(())
+            }
+            EncoderInstruction::Duplicate(index) => {
+                let (name, value) = self.lookup_encoder_relative(index)?.clone();
+                self.table.insert(name, value)
+            }
+        }
+    }
+
+    fn lookup_encoder_relative(&self, index: usize) -> Result<&(String, String), DecodingError> {
+        self.table
+            .inserted
+            .checked_sub(index + 1)
+            .and_then(|absolute| self.table.get(absolute))
+            .ok_or(DecodingError::InvalidReference)
+    }
+}
+
+/// QPACK encoder with dynamic table support.
+///
+/// Field lines are inserted into the dynamic table as they are encoded, and they are
+/// referenced in later sections once the peer has acknowledged their insertion.
+/// Therefore, the encoder never causes the peer's decoder to block.
+///
+/// Instructions to be sent on the local encoder stream can be retrieved with
+/// [`take_instructions`](Self::take_instructions), while instructions received on the peer's
+/// decoder stream are processed with [`feed_decoder_stream`](Self::feed_decoder_stream).
+#[derive(Debug)]
+pub struct DynamicEncoder {
+    table: DynamicTable,
+    max_table_capacity: usize,
+    peer_max_table_capacity: usize,
+    known_received_count: usize,
+    outstanding_sections: HashMap<StreamId, VecDeque<OutstandingSection>>,
+    decoder_stream: Vec<u8>,
+    instructions: Vec<u8>,
+}
+
+impl DynamicEncoder {
+    /// Field names never inserted into the dynamic table.
+    const NEVER_INDEXED: &'static [&'static str] = &[":path", "authorization", "cookie"];
+
+    /// Creates a new encoder.
+    ///
+    /// The dynamic table capacity will not exceed `max_table_capacity`.
+    /// Until [`set_peer_max_table_capacity`](Self::set_peer_max_table_capacity) is called,
+    /// only the static table is used.
+    pub fn new(max_table_capacity: usize) -> Self {
+        Self {
+            table: DynamicTable::default(),
+            max_table_capacity,
+            peer_max_table_capacity: 0,
+            known_received_count: 0,
+            outstanding_sections: HashMap::new(),
+            decoder_stream: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Sets the `SETTINGS_QPACK_MAX_TABLE_CAPACITY` advertised by the peer.
+    ///
+    /// It enables the dynamic table. This method must be called at most once.
+    pub fn set_peer_max_table_capacity(&mut self, peer_max_table_capacity: usize) {
+        self.peer_max_table_capacity = peer_max_table_capacity;
+
+        let capacity = self.max_table_capacity.min(peer_max_table_capacity);
+        if capacity > 0 {
+            self.table.set_capacity(capacity);
+            Encoder::encode_integer::<5, _>(0b001, capacity, &mut self.instructions)
+                .expect("vec does not eof");
+        }
+    }
+
+    /// Encodes headers of a field section to be sent on stream `stream_id`.
+    pub fn encode<H, K, V>(&mut self, stream_id: StreamId, headers: H) -> Box<[u8]>
+    where
+        H: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut lines = Vec::new();
+        let mut insertions = Vec::new();
+
+        for (key, value) in headers.into_iter() {
+            let (key, value) = (key.as_ref(), value.as_ref());
+
+            let static_index = StaticTable::lookup_index(key, value);
+            if let Some(LookupIndexFound::KeyValue(index)) = static_index {
+                lines.push(FieldLine::Static(index));
+                continue;
+            }
+
+            let dynamic_index = self.lookup_acknowledged(key, value);
+            if let Some(LookupIndexFound::KeyValue(absolute)) = dynamic_index {
+                lines.push(FieldLine::Dynamic(absolute));
+                continue;
+            }
+
+            if self.is_indexable(key, value) {
+                insertions.push((key.to_string(), value.to_string()));
+            }
+
+            lines.push(match (static_index, dynamic_index) {
+                (Some(LookupIndexFound::KeyOnly(index)), _) => {
+                    FieldLine::StaticName(index, value.to_string())
+                }
+                (_, Some(LookupIndexFound::KeyOnly(absolute))) => {
+                    FieldLine::DynamicName(absolute, value.to_string())
+                }
+                _ => FieldLine::Literal(key.to_string(), value.to_string()),
+            });
+        }
+
+        let referenced = lines.iter().filter_map(FieldLine::absolute_index);
+        let required_insert_count = referenced.clone().max().map_or(0, |max| max + 1);
+        let min_reference = referenced.min();
+
+        for (key, value) in insertions {
+            self.insert(key, value, min_reference);
+        }
+
+        let section = self.encode_section(required_insert_count, &lines);
+
+        if let Some(min_reference) = min_reference {
+            self.outstanding_sections
+                .entry(stream_id)
+                .or_default()
+                .push_back(OutstandingSection {
+                    required_insert_count,
+                    min_reference,
+                });
+        }
+
+        section
+    }
+
+    /// Processes data received on the peer's decoder stream.
+    ///
+    /// Incomplete instructions are buffered until more data is fed.
+    /// Returns an [`Err`] in case of a decoder stream error.
+    pub fn feed_decoder_stream(&mut self, data: &[u8]) -> Result<(), DecodingError> {
+        self.decoder_stream.extend_from_slice(data);
+
+        let mut offset = 0;
+
+        let result = loop {
+            let mut buffer_reader = BufferReader::new(&self.decoder_stream[offset..]);
+
+            if buffer_reader.capacity() == 0 {
+                break Ok(());
+            }
+
+            let byte = buffer_reader.buffer_remaining()[0];
+
+            let instruction = if byte & 0b1000_0000 != 0 {
+                Decoder::decode_integer::<7, _>(&mut buffer_reader)
+                    .map(|(_, stream_id)| DecoderInstruction::SectionAck(stream_id))
+            } else if byte & 0b0100_0000 != 0 {
+                Decoder::decode_integer::<6, _>(&mut buffer_reader)
+                    .map(|(_, stream_id)| DecoderInstruction::StreamCancellation(stream_id))
+            } else {
+                Decoder::decode_integer::<6, _>(&mut buffer_reader)
+                    .map(|(_, increment)| DecoderInstruction::InsertCountIncrement(increment))
+            };
+
+            match instruction {
+                Ok(instruction) => {
+                    offset += buffer_reader.offset();
+
+                    if let Err(error) = self.apply_decoder_instruction(instruction) {
+                        break Err(error);
+                    }
+                }
+                Err(DecodingError::UnexpectedFin) => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+
+        self.decoder_stream.drain(..offset);
+
+        result
+    }
+
+    /// Takes the instructions to be sent on the local encoder stream.
+    #[inline(always)]
+    pub fn take_instructions(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.instructions)
+    }
+
+    fn apply_decoder_instruction(
+        &mut self,
+        instruction: DecoderInstruction,
+    ) -> Result<(), DecodingError> {
+        match instruction {
+            DecoderInstruction::SectionAck(stream_id) => {
+                let stream_id = Self::stream_id(stream_id)?;
+
+                let sections = self
+                    .outstanding_sections
+                    .get_mut(&stream_id)
+                    .ok_or(DecodingError::InvalidInstruction)?;
+
+                let section = sections
+                    .pop_front()
+                    .ok_or(DecodingError::InvalidInstruction)?;
+
+                if sections.is_empty() {
+                    self.outstanding_sections.remove(&stream_id);
+                }
+
+                self.known_received_count =
+                    self.known_received_count.max(section.required_insert_count);
+            }
+            DecoderInstruction::StreamCancellation(stream_id) => {
+                self.outstanding_sections
+                    .remove(&Self::stream_id(stream_id)?);
+            }
+            DecoderInstruction::InsertCountIncrement(increment) => {
+                let known_received_count = self
+                    .known_received_count
+                    .checked_add(increment)
+                    .filter(|count| increment > 0 && *count <= self.table.inserted)
+                    .ok_or(DecodingError::InvalidInstruction)?;
+
+                self.known_received_count = known_received_count;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stream_id(stream_id: usize) -> Result<StreamId, DecodingError> {
+        crate::varint::VarInt::try_from_u64(stream_id as u64)
+            .map(StreamId::new)
+            .map_err(|_| DecodingError::InvalidInstruction)
+    }
+
+    fn is_indexable(&self, key: &str, value: &str) -> bool {
+        !Self::NEVER_INDEXED.contains(&key)
+            && DynamicTable::entry_size(key, value) <= self.table.capacity / 2
+            && !self
+                .table
+                .entries()
+                .any(|(_, entry)| entry.0 == key && entry.1 == value)
+    }
+
+    /// Looks up an entry whose insertion has been acknowledged by the peer.
+    fn lookup_acknowledged(&self, key: &str, value: &str) -> Option<LookupIndexFound> {
+        let mut found = None;
+
+        for (absolute, entry) in self.table.entries().rev() {
+            if absolute >= self.known_received_count || entry.0 != key {
+                continue;
+            }
+
+            if entry.1 == value {
+                return Some(LookupIndexFound::KeyValue(absolute));
+            }
+
+            found.get_or_insert(LookupIndexFound::KeyOnly(absolute));
+        }
+
+        found
+    }
+
+    /// Inserts an entry if it does not require evicting entries that might still be referenced.
+    fn insert(&mut self, key: String, value: String, min_reference: Option<usize>) {
+        let evictable_below = self
+            .outstanding_sections
+            .values()
+            .flatten()
+            .map(|section| section.min_reference)
+            .chain(min_reference)
+            .fold(self.known_received_count, usize::min);
+
+        let size = DynamicTable::entry_size(&key, &value);
+        if !self.table.can_insert(size, evictable_below) {
+            return;
+        }
+
+        match StaticTable::lookup_index(&key, &value) {
+            Some(LookupIndexFound::KeyOnly(index)) | Some(LookupIndexFound::KeyValue(index)) => {
+                Encoder::encode_integer::<6, _>(0b11, index, &mut self.instructions)
+                    .expect("vec does not eof");
+            }
+            None => {
+                Encoder::encode_string::<5, _, _>(0b01, &key, &mut self.instructions)
+                    .expect("vec does not eof");
+            }
+        }
+
+        Encoder::encode_string::<7, _, _>(0, &value, &mut self.instructions)
+            .expect("vec does not eof");
+
+        self.table
+            .insert(key, value)
+            .expect("Entry size checked against capacity");
+    }
+
+    fn encode_section(&self, required_insert_count: usize, lines: &[FieldLine]) -> Box<[u8]> {
+        let mut buffer = Vec::new();
+
+        let encoded_insert_count = if required_insert_count == 0 {
+            0
+        } else {
+            let max_entries = self.peer_max_table_capacity / DynamicTable::ENTRY_OVERHEAD;
+            required_insert_count % (2 * max_entries) + 1
+        };
+
+        // Base is equal to the required insert count: all references are relative
+        Encoder::encode_integer::<8, _>(0, encoded_insert_count, &mut buffer)
+            .expect("vec does not eof");
+        Encoder::encode_integer::<7, _>(0, 0, &mut buffer).expect("vec does not eof");
+
+        let base = required_insert_count;
+
+        for line in lines {
+            match line {
+                FieldLine::Static(index) => {
+                    Encoder::encode_integer::<6, _>(0b11, *index, &mut buffer)
+                        .expect("vec does not eof");
+                }
+                FieldLine::Dynamic(absolute) => {
+                    Encoder::encode_integer::<6, _>(0b10, base - 1 - absolute, &mut buffer)
+                        .expect("vec does not eof");
+                }
+                FieldLine::StaticName(index, value) => {
+                    Encoder::encode_integer::<4, _>(0b0101, *index, &mut buffer)
+                        .expect("vec does not eof");
+                    Encoder::encode_string::<7, _, _>(0, value, &mut buffer)
+                        .expect("vec does not eof");
+                }
+                FieldLine::DynamicName(absolute, value) => {
+                    Encoder::encode_integer::<4, _>(0b0100, base - 1 - absolute, &mut buffer)
+                        .expect("vec does not eof");
+                    Encoder::encode_string::<7, _, _>(0, value, &mut buffer)
+                        .expect("vec does not eof");
+                }
+                FieldLine::Literal(key, value) => {
+                    Encoder::encode_string::<3, _, _>(0b10, key, &mut buffer)
+                        .expect("vec does not eof");
+                    Encoder::encode_string::<7, _, _>(0, value, &mut buffer)
+                        .expect("vec does not eof");
+                }
+            }
+        }
+
+        buffer.into_boxed_slice()
+    }
+}
+
+enum EncoderInstruction {
+    InsertNameRef {
+        is_static: bool,
+        index: usize,
+        value: String,
+    },
+    InsertLiteralName {
+        name: String,
+        value: String,
+    },
+    SetCapacity(usize),
+    Duplicate(usize),
+}
+
+enum DecoderInstruction {
+    SectionAck(usize),
+    StreamCancellation(usize),
+    InsertCountIncrement(usize),
+}
+
+enum FieldLine {
+    Static(usize),
+    Dynamic(usize),
+    StaticName(usize, String),
+    DynamicName(usize, String),
+    Literal(String, String),
+}
+
+impl FieldLine {
+    fn absolute_index(&self) -> Option<usize> {
+        match self {
+            FieldLine::Dynamic(absolute) | FieldLine::DynamicName(absolute, _) => Some(*absolute),
+            _ => None,
+        }
+    }
+}
+
+/// A field section not yet acknowledged by the peer.
+#[derive(Debug)]
+struct OutstandingSection {
+    required_insert_count: usize,
+    min_reference: usize,
+}
+
+/// Dynamic table state while decoding a field section.
+struct SectionContext<'a> {
+    table: &'a DynamicTable,
+    base: usize,
+    required_insert_count: usize,
+}
+
+impl<'a> SectionContext<'a> {
+    fn lookup_relative(
+        context: Option<&Self>,
+        index: usize,
+    ) -> Result<(&'a str, &'a str), DecodingError> {
+        let context = context.ok_or(DecodingError::DynamicNotSupported)?;
+        let absolute = context
+            .base
+            .checked_sub(index + 1)
+            .ok_or(DecodingError::InvalidReference)?;
+
+        context.lookup(absolute)
+    }
+
+    fn lookup_post_base(
+        context: Option<&Self>,
+        index: usize,
+    ) -> Result<(&'a str, &'a str), DecodingError> {
+        let context = context.ok_or(DecodingError::DynamicNotSupported)?;
+        let absolute = context
+            .base
+            .checked_add(index)
+            .ok_or(DecodingError::InvalidReference)?;
+
+        context.lookup(absolute)
+    }
+
+    fn lookup(&self, absolute: usize) -> Result<(&'a str, &'a str), DecodingError> {
+        if absolute >= self.required_insert_count {
+            return Err(DecodingError::InvalidReference);
+        }
+
+        self.table
+            .get(absolute)
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .ok_or(DecodingError::InvalidReference)
+    }
+}
+
+/// QPACK dynamic table (RFC 9204, Section 3.2).
+#[derive(Debug, Default)]
+struct DynamicTable {
+    entries: VecDeque<(String, String)>,
+    size: usize,
+    capacity: usize,
+    inserted: usize,
+}
+
+impl DynamicTable {
+    const ENTRY_OVERHEAD: usize = 32;
+
+    fn entry_size(key: &str, value: &str) -> usize {
+        key.len() + value.len() + Self::ENTRY_OVERHEAD
+    }
+
+    /// Absolute index of the oldest entry in the table.
+    fn first_index(&self) -> usize {
+        self.inserted - self.entries.len()
+    }
+
+    fn get(&self, absolute: usize) -> Option<&(String, String)> {
+        absolute
+            .checked_sub(self.first_index())
+            .and_then(|index| self.entries.get(index))
+    }
+
+    /// Iterates over entries along with their absolute index, from the oldest.
+    fn entries(&self) -> impl DoubleEndedIterator<Item = (usize, &(String, String))> {
+        let first_index = self.first_index();
+        self.entries
+            .iter()
+            .enumerate()
+            .map(move |(index, entry)| (first_index + index, entry))
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.evict(capacity);
+        self.capacity = capacity;
+    }
+
+    fn insert(&mut self, key: String, value: String) -> Result<(), DecodingError> {
+        let size = Self::entry_size(&key, &value);
+        if size > self.capacity {
+            return Err(DecodingError::CapacityExceeded);
+        }
+
+        self.evict(self.capacity - size);
+        self.entries.push_back((key, value));
+        self.size += size;
+        self.inserted += 1;
+
+        Ok(())
+    }
+
+    /// Checks whether an entry of `size` fits evicting only entries below `evictable_below`.
+    fn can_insert(&self, size: usize, evictable_below: usize) -> bool {
+        let mut available = self.capacity.saturating_sub(self.size);
+
+        for (absolute, (key, value)) in self.entries() {
+            if available >= size || absolute >= evictable_below {
+                break;
+            }
+
+            available += Self::entry_size(key, value);
+        }
+
+        available >= size
+    }
+
+    fn evict(&mut self, max_size: usize) {
+        while self.size > max_size {
+            let (key, value) = self.entries.pop_front().expect("Size accounts for entries");
+            self.size -= Self::entry_size(&key, &value);
+        }
+    }
+}
+
 enum LookupIndexFound {
     KeyValue(usize),
     KeyOnly(usize),
@@ -478,21 +1304,21 @@ mod tests {
 
     #[test]
     fn encode_decode() {
-        let headers = HashMap::from([
+        let headers = [
             ("key1", "value1"),
             (":status", "200"),
             ("key2", "value2"),
             (":status", "not_found"),
-        ]);
+        ];
 
-        let enc_data = Encoder::encode(&headers);
+        let enc_data = Encoder::encode(headers);
         let headers_dec = Decoder::decode(enc_data).unwrap();
         let headers_dec = headers_dec
             .iter()
             .map(|(k, v)| (k.as_str(), v.as_str()))
-            .collect();
+            .collect::<Vec<_>>();
 
-        assert_eq!(headers, headers_dec);
+        assert_eq!(headers.as_slice(), headers_dec);
     }
 
     #[test]
@@ -580,4 +1406,250 @@ mod tests {
             assert_eq!(value, value_dec);
         }
     }
+
+    fn stream_id(id: u32) -> StreamId {
+        StreamId::new(crate::varint::VarInt::from_u32(id))
+    }
+
+    fn decoded(outcome: DecodeOutcome) -> HashMap<String, String> {
+        match outcome {
+            DecodeOutcome::Decoded(headers) => headers.into_iter().collect(),
+            DecodeOutcome::Blocked => panic!("Unexpected blocked stream"),
+        }
+    }
+
+    /// Section prefix with `encoded_insert_count` and base equal to required insert count.
+    fn section_prefix(encoded_insert_count: usize) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        Encoder::encode_integer::<8, _>(0, encoded_insert_count, &mut buffer).unwrap();
+        Encoder::encode_integer::<7, _>(0, 0, &mut buffer).unwrap();
+        buffer
+    }
+
+    fn insert_literal(name: &str, value: &str) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        Encoder::encode_string::<5, _, _>(0b01, name, &mut buffer).unwrap();
+        Encoder::encode_string::<7, _, _>(0, value, &mut buffer).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn dynamic_encode_decode() {
+        let headers = HashMap::from([
+            (":method", "CONNECT"),
+            (":authority", "example.com"),
+            (":path", "/chat"),
+            ("key1", "value1"),
+            ("origin", "https://example.com"),
+        ]);
+
+        let mut encoder = DynamicEncoder::new(4096);
+        let mut decoder = DynamicDecoder::new(4096, 16);
+
+        encoder.set_peer_max_table_capacity(4096);
+
+        let section_first = encoder.encode(stream_id(0), &headers);
+        decoder
+            .feed_encoder_stream(&encoder.take_instructions())
+            .unwrap();
+        let headers_dec = decoded(decoder.decode(stream_id(0), &section_first).unwrap());
+        encoder
+            .feed_decoder_stream(&decoder.take_instructions())
+            .unwrap();
+
+        assert_eq!(decoder.insert_count(), 3);
+
+        let section_second = encoder.encode(stream_id(4), &headers);
+        assert!(section_second.len() < section_first.len());
+        assert!(encoder.take_instructions().is_empty());
+
+        let headers_dec_second = decoded(decoder.decode(stream_id(4), &section_second).unwrap());
+        encoder
+            .feed_decoder_stream(&decoder.take_instructions())
+            .unwrap();
+
+        for headers_dec in [headers_dec, headers_dec_second] {
+            let headers_dec = headers_dec
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            assert_eq!(headers, headers_dec);
+        }
+    }
+
+    #[test]
+    fn dynamic_static_only() {
+        let headers = [("key1", "value1"), (":status", "200")];
+
+        let mut encoder = DynamicEncoder::new(4096);
+        let section = encoder.encode(stream_id(0), headers);
+        assert!(encoder.take_instructions().is_empty());
+
+        let headers_dec = Decoder::decode(section).unwrap();
+        assert_eq!(headers_dec[0], ("key1".to_string(), "value1".to_string()));
+    }
+
+    #[test]
+    fn dynamic_blocked() {
+        let mut decoder = DynamicDecoder::new(4096, 1);
+
+        let mut section = section_prefix(2);
+        Encoder::encode_integer::<6, _>(0b10, 0, &mut section).unwrap();
+
+        assert!(matches!(
+            decoder.decode(stream_id(0), &section),
+            Ok(DecodeOutcome::Blocked)
+        ));
+        assert_eq!(decoder.blocked_streams(), 1);
+
+        assert!(matches!(
+            decoder.decode(stream_id(4), &section),
+            Err(DecodingError::BlockedStreamsExceeded)
+        ));
+
+        let mut instructions = Vec::new();
+        Encoder::encode_integer::<5, _>(0b001, 4096, &mut instructions).unwrap();
+        instructions.extend(insert_literal("key1", "value1"));
+
+        // Partial instruction is buffered
+        let (first, second) = instructions.split_at(instructions.len() - 2);
+        decoder.feed_encoder_stream(first).unwrap();
+        assert_eq!(decoder.insert_count(), 0);
+        decoder.feed_encoder_stream(second).unwrap();
+        assert_eq!(decoder.insert_count(), 1);
+
+        let headers = decoded(decoder.decode(stream_id(0), &section).unwrap());
+        assert_eq!(headers.get("key1").map(String::as_str), Some("value1"));
+        assert_eq!(decoder.blocked_streams(), 0);
+
+        // Insert Count Increment (1) and Section Acknowledgment (stream 0)
+        assert_eq!(decoder.take_instructions(), [0b0000_0001, 0b1000_0000]);
+    }
+
+    #[test]
+    fn dynamic_stream_cancellation() {
+        let mut decoder = DynamicDecoder::new(4096, 16);
+
+        let mut section = section_prefix(2);
+        Encoder::encode_integer::<6, _>(0b10, 0, &mut section).unwrap();
+
+        assert!(matches!(
+            decoder.decode(stream_id(8), &section),
+            Ok(DecodeOutcome::Blocked)
+        ));
+
+        decoder.cancel_stream(stream_id(8));
+        decoder.cancel_stream(stream_id(12));
+
+        assert_eq!(decoder.blocked_streams(), 0);
+        assert_eq!(decoder.take_instructions(), [0b0100_1000]);
+    }
+
+    #[test]
+    fn dynamic_encoder_instructions() {
+        let mut decoder = DynamicDecoder::new(128, 16);
+
+        let mut instructions = Vec::new();
+        Encoder::encode_integer::<5, _>(0b001, 128, &mut instructions).unwrap();
+        instructions.extend(insert_literal("key1", "value1"));
+        // Insert With Name Reference (dynamic, relative index 0)
+        Encoder::encode_integer::<6, _>(0b10, 0, &mut instructions).unwrap();
+        Encoder::encode_string::<7, _, _>(0, "value2", &mut instructions).unwrap();
+        // Insert With Name Reference (static `:path`)
+        Encoder::encode_integer::<6, _>(0b11, 1, &mut instructions).unwrap();
+        Encoder::encode_string::<7, _, _>(0, "/chat", &mut instructions).unwrap();
+        // Duplicate (relative index 2, i.e., `key1: value1`), evicting the first entry
+        Encoder::encode_integer::<5, _>(0b000, 2, &mut instructions).unwrap();
+
+        decoder.feed_encoder_stream(&instructions).unwrap();
+        assert_eq!(decoder.insert_count(), 4);
+
+        // Required insert count 4 is encoded as 4 % (2 * max entries) + 1
+        let mut section = section_prefix(5);
+        Encoder::encode_integer::<6, _>(0b10, 2, &mut section).unwrap();
+        let headers = decoded(decoder.decode(stream_id(0), &section).unwrap());
+        assert_eq!(headers.get("key1").map(String::as_str), Some("value2"));
+
+        let mut section = section_prefix(5);
+        Encoder::encode_integer::<6, _>(0b10, 0, &mut section).unwrap();
+        Encoder::encode_integer::<6, _>(0b10, 1, &mut section).unwrap();
+        let headers = decoded(decoder.decode(stream_id(0), &section).unwrap());
+        assert_eq!(headers.get("key1").map(String::as_str), Some("value1"));
+        assert_eq!(headers.get(":path").map(String::as_str), Some("/chat"));
+
+        // The first entry has been evicted
+        Encoder::encode_integer::<6, _>(0b10, 3, &mut section).unwrap();
+        assert!(matches!(
+            decoder.decode(stream_id(0), &section),
+            Err(DecodingError::InvalidReference)
+        ));
+
+        // Post-base reference beyond required insert count
+        let mut section = section_prefix(5);
+        Encoder::encode_integer::<4, _>(0b0001, 0, &mut section).unwrap();
+        assert!(matches!(
+            decoder.decode(stream_id(0), &section),
+            Err(DecodingError::InvalidReference)
+        ));
+    }
+
+    #[test]
+    fn dynamic_capacity_exceeded() {
+        let mut decoder = DynamicDecoder::new(64, 16);
+
+        let mut instructions = Vec::new();
+        Encoder::encode_integer::<5, _>(0b001, 65, &mut instructions).unwrap();
+
+        assert!(matches!(
+            decoder.feed_encoder_stream(&instructions),
+            Err(DecodingError::CapacityExceeded)
+        ));
+
+        let mut decoder = DynamicDecoder::new(64, 16);
+        assert!(matches!(
+            decoder.feed_encoder_stream(&insert_literal("key1", "value1")),
+            Err(DecodingError::CapacityExceeded)
+        ));
+    }
+
+    #[test]
+    fn dynamic_decoder_stream_errors() {
+        let mut encoder = DynamicEncoder::new(4096);
+        encoder.set_peer_max_table_capacity(4096);
+
+        // Insert Count Increment beyond the number of insertions
+        assert!(matches!(
+            encoder.feed_decoder_stream(&[0b0000_0001]),
+            Err(DecodingError::InvalidInstruction)
+        ));
+
+        // Section Acknowledgment without outstanding sections
+        let mut encoder = DynamicEncoder::new(4096);
+        assert!(matches!(
+            encoder.feed_decoder_stream(&[0b1000_0000]),
+            Err(DecodingError::InvalidInstruction)
+        ));
+    }
+
+    #[test]
+    fn dynamic_eviction() {
+        let mut encoder = DynamicEncoder::new(128);
+        let mut decoder = DynamicDecoder::new(128, 16);
+        encoder.set_peer_max_table_capacity(4096);
+
+        for index in 0..32 {
+            let headers = [(format!("key{}", index % 5), format!("value{}", index % 3))];
+
+            let section = encoder.encode(stream_id(index * 4), headers.clone());
+            decoder
+                .feed_encoder_stream(&encoder.take_instructions())
+                .unwrap();
+            let headers_dec = decoded(decoder.decode(stream_id(index * 4), &section).unwrap());
+            encoder
+                .feed_decoder_stream(&decoder.take_instructions())
+                .unwrap();
+
+            assert_eq!(headers_dec, HashMap::from(headers));
+        }
+    }
 }
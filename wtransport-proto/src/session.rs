@@ -1,6 +1,9 @@
 use crate::headers::Headers;
 use crate::ids::InvalidStatusCode;
 use crate::ids::StatusCode;
+use crate::settings::SettingId;
+use crate::settings::Settings;
+use std::fmt;
 use url::Url;
 
 /// Error when parsing URL.
@@ -108,6 +111,14 @@ pub enum HeadersParseError {
 #[error("used reserved header")]
 pub struct ReservedHeader;
 
+/// An error when a WebTransport protocol cannot be carried in a header field.
+///
+/// Protocols are sent as structured field strings, hence they can only contain
+/// printable ASCII characters.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid protocol")]
+pub struct InvalidProtocol;
+
 /// A CONNECT WebTransport request.
 #[derive(Debug)]
 pub struct SessionRequest(Headers);
@@ -127,6 +138,9 @@ impl SessionRequest {
     pub const RESERVED_HEADERS: &'static [&'static str] =
         &[":method", ":scheme", ":protocol", ":authority", ":path"];
 
+    /// Header field listing the application protocols offered by the client.
+    pub const AVAILABLE_PROTOCOLS_HEADER: &'static str = "wt-available-protocols";
+
     /// Parses an URL to build a Session request.
     pub fn new<S>(url: S) -> Result<Self, UrlParseError>
     where
@@ -181,6 +195,48 @@ impl SessionRequest {
         self.0.get("user-agent")
     }
 
+    /// Returns the application protocols offered by the client, in order of preference.
+    ///
+    /// They are parsed from the [`AVAILABLE_PROTOCOLS_HEADER`](Self::AVAILABLE_PROTOCOLS_HEADER)
+    /// field. If the field is absent or it is not a valid list of strings, no protocol is offered.
+    pub fn offered_protocols(&self) -> Vec<String> {
+        self.0
+            .get(Self::AVAILABLE_PROTOCOLS_HEADER)
+            .and_then(sf::parse_string_list)
+            .unwrap_or_default()
+    }
+
+    /// Sets the application protocols offered to the server, in order of preference.
+    ///
+    /// An empty list removes the offer.
+    pub fn set_offered_protocols<I, S>(&mut self, protocols: I) -> Result<(), InvalidProtocol>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let protocols = protocols
+            .into_iter()
+            .map(|protocol| sf::serialize_string(protocol.as_ref()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(InvalidProtocol)?;
+
+        if protocols.is_empty() {
+            self.0.remove(Self::AVAILABLE_PROTOCOLS_HEADER);
+        } else {
+            self.0
+                .insert(Self::AVAILABLE_PROTOCOLS_HEADER, protocols.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the draft the client declares in the request, if any.
+    ///
+    /// See [`WebTransportDraft::with_headers`].
+    pub fn draft(&self) -> Option<WebTransportDraft> {
+        WebTransportDraft::with_headers(&self.0)
+    }
+
     /// Gets a field from the request (if present).
     pub fn get<K>(&self, key: K) -> Option<&str>
     where
@@ -205,7 +261,10 @@ impl SessionRequest {
     {
         let key = key.to_string();
 
-        if Self::RESERVED_HEADERS.iter().any(|rh| rh == &key) {
+        if Self::RESERVED_HEADERS
+            .iter()
+            .any(|rh| rh.eq_ignore_ascii_case(&key))
+        {
             return Err(ReservedHeader);
         }
 
@@ -278,9 +337,13 @@ impl UrlParseError {
 }
 
 /// A WebTransport CONNECT response.
+#[derive(Debug)]
 pub struct SessionResponse(Headers);
 
 impl SessionResponse {
+    /// Header field carrying the application protocol selected by the server.
+    pub const PROTOCOL_HEADER: &'static str = "wt-protocol";
+
     /// Constructs from [`StatusCode`].
     pub fn with_status_code(status_code: StatusCode) -> Self {
         let headers = [(":status", status_code.to_string())].into_iter().collect();
@@ -302,6 +365,11 @@ impl SessionResponse {
         Self::with_status_code(StatusCode::NOT_FOUND)
     }
 
+    /// Constructs with [`StatusCode::MISDIRECTED_REQUEST`].
+    pub fn misdirected_request() -> Self {
+        Self::with_status_code(StatusCode::MISDIRECTED_REQUEST)
+    }
+
     /// Returns the status code.
     pub fn code(&self) -> StatusCode {
         self.0
@@ -322,6 +390,39 @@ impl SessionResponse {
         self.0.insert(key, value);
     }
 
+    /// Appends a header field to the response.
+    ///
+    /// Fields already present with the same key are left untouched.
+    pub fn append<K, V>(&mut self, key: K, value: V)
+    where
+        K: ToString,
+        V: ToString,
+    {
+        self.0.append(key, value);
+    }
+
+    /// Returns the application protocol selected by the server, if any.
+    ///
+    /// It is parsed from the [`PROTOCOL_HEADER`](Self::PROTOCOL_HEADER) field.
+    /// If the field is not a valid string, no protocol is selected.
+    pub fn protocol(&self) -> Option<String> {
+        self.0.get(Self::PROTOCOL_HEADER).and_then(sf::parse_string)
+    }
+
+    /// Sets the application protocol selected by the server.
+    pub fn set_protocol(&mut self, protocol: &str) -> Result<(), InvalidProtocol> {
+        let protocol = sf::serialize_string(protocol).ok_or(InvalidProtocol)?;
+        self.0.insert(Self::PROTOCOL_HEADER, protocol);
+        Ok(())
+    }
+
+    /// Returns the draft the server declares in the response, if any.
+    ///
+    /// See [`WebTransportDraft::with_headers`].
+    pub fn draft(&self) -> Option<WebTransportDraft> {
+        WebTransportDraft::with_headers(&self.0)
+    }
+
     /// Returns the whole headers associated with the request.
     pub fn headers(&self) -> &Headers {
         &self.0
@@ -332,13 +433,264 @@ impl TryFrom<Headers> for SessionResponse {
     type Error = HeadersParseError;
 
     fn try_from(headers: Headers) -> Result<Self, Self::Error> {
-        let status_code = headers
+        headers
             .get(":status")
             .ok_or(HeadersParseError::MissingStatusCode)?
-            .parse()
+            .parse::<StatusCode>()
             .map_err(|InvalidStatusCode| HeadersParseError::InvalidStatusCode)?;
 
-        Ok(Self::with_status_code(status_code))
+        Ok(Self(headers))
+    }
+}
+
+/// A draft version of the WebTransport over HTTP/3 protocol.
+///
+/// Drafts differ in the settings used to advertise support for WebTransport.
+/// Variants are ordered from the oldest to the newest draft.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum WebTransportDraft {
+    /// Draft 02: support is advertised with `SETTINGS_ENABLE_WEBTRANSPORT`.
+    Draft02,
+
+    /// Drafts 03 to 07: support is advertised with `SETTINGS_WEBTRANSPORT_MAX_SESSIONS`.
+    Draft07,
+
+    /// Drafts 08 and later: support is advertised with `SETTINGS_WT_MAX_SESSIONS`.
+    Draft09,
+}
+
+impl WebTransportDraft {
+    /// All known drafts, from the oldest to the newest.
+    pub const ALL: [Self; 3] = [Self::Draft02, Self::Draft07, Self::Draft09];
+
+    /// Header field carrying the draft in requests and responses.
+    pub const HEADER: &'static str = "sec-webtransport-http3-draft";
+
+    /// Header field sent by draft 02 clients along with the request.
+    pub const HEADER_DRAFT02: &'static str = "sec-webtransport-http3-draft02";
+
+    /// Returns the value of [`HEADER`](Self::HEADER) for this draft.
+    pub fn header_value(self) -> &'static str {
+        match self {
+            Self::Draft02 => "draft02",
+            Self::Draft07 => "draft07",
+            Self::Draft09 => "draft09",
+        }
+    }
+
+    /// Parses the value of [`HEADER`](Self::HEADER).
+    pub fn with_header_value(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|draft| draft.header_value().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// Returns the draft declared in `headers`, if any.
+    ///
+    /// [`HEADER`](Self::HEADER) takes precedence over [`HEADER_DRAFT02`](Self::HEADER_DRAFT02).
+    pub fn with_headers(headers: &Headers) -> Option<Self> {
+        match headers.get(Self::HEADER) {
+            Some(value) => Self::with_header_value(value),
+            None => headers.get(Self::HEADER_DRAFT02).map(|_| Self::Draft02),
+        }
+    }
+
+    /// Returns `true` if the peer advertised support for this draft in its `settings`.
+    pub fn is_supported_by(self, settings: &Settings) -> bool {
+        match self {
+            Self::Draft02 => settings
+                .get(SettingId::EnableWebTransport)
+                .is_some_and(|value| value.into_inner() == 1),
+            Self::Draft07 => settings
+                .get(SettingId::WebTransportMaxSessions)
+                .is_some_and(|value| value.into_inner() > 0),
+            Self::Draft09 => settings
+                .get(SettingId::WtMaxSessions)
+                .is_some_and(|value| value.into_inner() > 0),
+        }
+    }
+
+    /// Returns the newest draft among `drafts` the peer supports according to its `settings`.
+    pub fn negotiate(drafts: &[Self], settings: &Settings) -> Option<Self> {
+        drafts
+            .iter()
+            .copied()
+            .filter(|draft| draft.is_supported_by(settings))
+            .max()
+    }
+}
+
+impl fmt::Display for WebTransportDraft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.header_value())
+    }
+}
+
+/// Minimal support for structured field values (RFC 8941) made of strings.
+mod sf {
+    /// Serializes a string item.
+    ///
+    /// Returns [`None`] if `value` contains non-printable or non-ASCII characters.
+    pub fn serialize_string(value: &str) -> Option<String> {
+        if !value.bytes().all(|byte| (0x20..=0x7e).contains(&byte)) {
+            return None;
+        }
+
+        let mut serialized = String::with_capacity(value.len() + 2);
+        serialized.push('"');
+
+        for c in value.chars() {
+            if c == '"' || c == '\\' {
+                serialized.push('\\');
+            }
+            serialized.push(c);
+        }
+
+        serialized.push('"');
+        Some(serialized)
+    }
+
+    /// Parses a string item. Parameters are ignored.
+    pub fn parse_string(input: &str) -> Option<String> {
+        let mut parser = Parser::new(input);
+
+        parser.skip_sp();
+        let value = parser.string()?;
+        parser.parameters()?;
+        parser.skip_sp();
+
+        parser.is_empty().then_some(value)
+    }
+
+    /// Parses a list whose members are string items. Parameters are ignored.
+    pub fn parse_string_list(input: &str) -> Option<Vec<String>> {
+        let mut parser = Parser::new(input);
+        let mut values = Vec::new();
+
+        parser.skip_sp();
+
+        while !parser.is_empty() {
+            values.push(parser.string()?);
+            parser.parameters()?;
+            parser.skip_ows();
+
+            if parser.is_empty() {
+                break;
+            }
+
+            parser.expect(b',')?;
+            parser.skip_ows();
+
+            if parser.is_empty() {
+                return None;
+            }
+        }
+
+        Some(values)
+    }
+
+    struct Parser<'a> {
+        input: &'a [u8],
+        offset: usize,
+    }
+
+    impl<'a> Parser<'a> {
+        fn new(input: &'a str) -> Self {
+            Self {
+                input: input.as_bytes(),
+                offset: 0,
+            }
+        }
+
+        fn is_empty(&self) -> bool {
+            self.offset >= self.input.len()
+        }
+
+        fn peek(&self) -> Option<u8> {
+            self.input.get(self.offset).copied()
+        }
+
+        fn next(&mut self) -> Option<u8> {
+            let byte = self.peek()?;
+            self.offset += 1;
+            Some(byte)
+        }
+
+        fn expect(&mut self, byte: u8) -> Option<()> {
+            (self.next()? == byte).then_some(())
+        }
+
+        fn skip_sp(&mut self) {
+            while self.peek() == Some(b' ') {
+                self.offset += 1;
+            }
+        }
+
+        fn skip_ows(&mut self) {
+            while matches!(self.peek(), Some(b' ' | b'\t')) {
+                self.offset += 1;
+            }
+        }
+
+        fn string(&mut self) -> Option<String> {
+            self.expect(b'"')?;
+            let mut value = String::new();
+
+            loop {
+                match self.next()? {
+                    b'"' => return Some(value),
+                    b'\\' => match self.next()? {
+                        escaped @ (b'"' | b'\\') => value.push(escaped as char),
+                        _ => return None,
+                    },
+                    byte @ 0x20..=0x7e => value.push(byte as char),
+                    _ => return None,
+                }
+            }
+        }
+
+        /// Skips parameters of an item.
+        fn parameters(&mut self) -> Option<()> {
+            while self.peek() == Some(b';') {
+                self.offset += 1;
+                self.skip_sp();
+
+                match self.next()? {
+                    b'a'..=b'z' | b'*' => {}
+                    _ => return None,
+                }
+
+                while matches!(
+                    self.peek(),
+                    Some(b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' | b'*')
+                ) {
+                    self.offset += 1;
+                }
+
+                if self.peek() == Some(b'=') {
+                    self.offset += 1;
+                    self.bare_item()?;
+                }
+            }
+
+            Some(())
+        }
+
+        /// Skips a bare item used as parameter value.
+        fn bare_item(&mut self) -> Option<()> {
+            if self.peek() == Some(b'"') {
+                return self.string().map(|_| ());
+            }
+
+            let start = self.offset;
+
+            while matches!(self.peek(), Some(byte) if byte > 0x20 && byte < 0x7f && !matches!(byte, b',' | b';' | b'"'))
+            {
+                self.offset += 1;
+            }
+
+            (self.offset > start).then_some(())
+        }
     }
 }
 
@@ -476,5 +828,138 @@ mod tests {
             request.insert(":path", "example"),
             Err(ReservedHeader)
         ));
+
+        assert!(matches!(
+            request.insert(":PATH", "example"),
+            Err(ReservedHeader)
+        ));
+    }
+
+    #[test]
+    fn parse_response_headers() {
+        let response = SessionResponse::try_from(
+            [
+                (":status", "200"),
+                ("sec-webtransport-http3-draft", "draft02"),
+                ("key1", "value1"),
+            ]
+            .into_iter()
+            .collect::<Headers>(),
+        )
+        .unwrap();
+
+        assert_eq!(response.code(), StatusCode::OK);
+        assert_eq!(response.headers().get("key1"), Some("value1"));
+        assert_eq!(response.headers().len(), 3);
+    }
+
+    #[test]
+    fn draft_headers() {
+        let headers = [("sec-webtransport-http3-draft02", "1")]
+            .into_iter()
+            .collect::<Headers>();
+        assert_eq!(
+            WebTransportDraft::with_headers(&headers),
+            Some(WebTransportDraft::Draft02)
+        );
+
+        let headers = [
+            ("sec-webtransport-http3-draft02", "1"),
+            ("sec-webtransport-http3-draft", "draft07"),
+        ]
+        .into_iter()
+        .collect::<Headers>();
+        assert_eq!(
+            WebTransportDraft::with_headers(&headers),
+            Some(WebTransportDraft::Draft07)
+        );
+
+        let headers = [("sec-webtransport-http3-draft", "draft99")]
+            .into_iter()
+            .collect::<Headers>();
+        assert_eq!(WebTransportDraft::with_headers(&headers), None);
+    }
+
+    #[test]
+    fn draft_negotiation() {
+        use crate::varint::VarInt;
+
+        let settings = Settings::builder()
+            .enable_webtransport()
+            .webtransport_max_sessions(VarInt::from_u32(0))
+            .wt_max_sessions(VarInt::from_u32(4))
+            .build();
+
+        assert_eq!(
+            WebTransportDraft::negotiate(&WebTransportDraft::ALL, &settings),
+            Some(WebTransportDraft::Draft09)
+        );
+
+        assert_eq!(
+            WebTransportDraft::negotiate(
+                &[WebTransportDraft::Draft02, WebTransportDraft::Draft07],
+                &settings
+            ),
+            Some(WebTransportDraft::Draft02)
+        );
+
+        assert_eq!(
+            WebTransportDraft::negotiate(&[WebTransportDraft::Draft07], &settings),
+            None
+        );
+    }
+
+    #[test]
+    fn offered_protocols() {
+        let mut request = SessionRequest::new("https://localhost:4433").unwrap();
+        assert!(request.offered_protocols().is_empty());
+
+        request
+            .set_offered_protocols(["chat", "chat \"v2\""])
+            .unwrap();
+        assert_eq!(
+            request.get(SessionRequest::AVAILABLE_PROTOCOLS_HEADER),
+            Some(r#""chat", "chat \"v2\"""#)
+        );
+        assert_eq!(request.offered_protocols(), ["chat", "chat \"v2\""]);
+
+        assert!(matches!(
+            request.set_offered_protocols(["caf\u{e9}"]),
+            Err(InvalidProtocol)
+        ));
+
+        request.set_offered_protocols::<_, &str>([]).unwrap();
+        assert!(request
+            .get(SessionRequest::AVAILABLE_PROTOCOLS_HEADER)
+            .is_none());
+    }
+
+    #[test]
+    fn parse_protocols() {
+        assert_eq!(
+            sf::parse_string_list(r#""a";q=1,  "b";x="y";z, "c""#).unwrap(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(sf::parse_string_list("").unwrap(), Vec::<String>::new());
+        assert!(sf::parse_string_list(r#""a","#).is_none());
+        assert!(sf::parse_string_list(r#""a", b"#).is_none());
+        assert!(sf::parse_string_list(r#""a\n""#).is_none());
+        assert!(sf::parse_string_list(r#""a"#).is_none());
+
+        assert_eq!(sf::parse_string(r#""chat""#).unwrap(), "chat");
+        assert!(sf::parse_string(r#""chat", "other""#).is_none());
+    }
+
+    #[test]
+    fn selected_protocol() {
+        let mut response = SessionResponse::ok();
+        assert!(response.protocol().is_none());
+
+        response.set_protocol("chat").unwrap();
+        assert_eq!(
+            response.headers().get(SessionResponse::PROTOCOL_HEADER),
+            Some(r#""chat""#)
+        );
+        assert_eq!(response.protocol().as_deref(), Some("chat"));
     }
 }
@@ -40,6 +40,18 @@ pub enum SettingId {
     /// WEBTRANSPORT_MAX_SESSIONS.
     WebTransportMaxSessions,
 
+    /// SETTINGS_WT_MAX_SESSIONS.
+    WtMaxSessions,
+
+    /// SETTINGS_WT_INITIAL_MAX_DATA.
+    WebTransportInitialMaxData,
+
+    /// SETTINGS_WT_INITIAL_MAX_STREAMS_UNI.
+    WebTransportInitialMaxStreamsUni,
+
+    /// SETTINGS_WT_INITIAL_MAX_STREAMS_BIDI.
+    WebTransportInitialMaxStreamsBidi,
+
     /// Exercise setting.
     Exercise(VarInt),
 }
@@ -63,6 +75,14 @@ impl SettingId {
                 setting_ids::SETTINGS_WEBTRANSPORT_MAX_SESSIONS => {
                     Ok(Self::WebTransportMaxSessions)
                 }
+                setting_ids::SETTINGS_WT_MAX_SESSIONS => Ok(Self::WtMaxSessions),
+                setting_ids::SETTINGS_WT_INITIAL_MAX_DATA => Ok(Self::WebTransportInitialMaxData),
+                setting_ids::SETTINGS_WT_INITIAL_MAX_STREAMS_UNI => {
+                    Ok(Self::WebTransportInitialMaxStreamsUni)
+                }
+                setting_ids::SETTINGS_WT_INITIAL_MAX_STREAMS_BIDI => {
+                    Ok(Self::WebTransportInitialMaxStreamsBidi)
+                }
                 _ => Err(ParseError::UnknownSetting),
             }
         }
@@ -77,6 +97,14 @@ impl SettingId {
             Self::H3Datagram => setting_ids::SETTINGS_H3_DATAGRAM,
             Self::EnableWebTransport => setting_ids::SETTINGS_ENABLE_WEBTRANSPORT,
             Self::WebTransportMaxSessions => setting_ids::SETTINGS_WEBTRANSPORT_MAX_SESSIONS,
+            Self::WtMaxSessions => setting_ids::SETTINGS_WT_MAX_SESSIONS,
+            Self::WebTransportInitialMaxData => setting_ids::SETTINGS_WT_INITIAL_MAX_DATA,
+            Self::WebTransportInitialMaxStreamsUni => {
+                setting_ids::SETTINGS_WT_INITIAL_MAX_STREAMS_UNI
+            }
+            Self::WebTransportInitialMaxStreamsBidi => {
+                setting_ids::SETTINGS_WT_INITIAL_MAX_STREAMS_BIDI
+            }
             Self::Exercise(id) => id,
         }
     }
@@ -230,6 +258,39 @@ impl SettingsBuilder {
         self
     }
 
+    /// Sets the max number of webtransport sessions (`SETTINGS_WT_MAX_SESSIONS`).
+    ///
+    /// This is the setting used by newer drafts in place of
+    /// [`webtransport_max_sessions`](Self::webtransport_max_sessions).
+    pub fn wt_max_sessions(mut self, value: VarInt) -> Self {
+        self.0 .0.insert(SettingId::WtMaxSessions, value);
+        self
+    }
+
+    /// Sets the initial amount of data the peer can send on all the streams of a session.
+    pub fn webtransport_initial_max_data(mut self, value: VarInt) -> Self {
+        self.0
+             .0
+            .insert(SettingId::WebTransportInitialMaxData, value);
+        self
+    }
+
+    /// Sets the initial number of unidirectional streams the peer can open in a session.
+    pub fn webtransport_initial_max_streams_uni(mut self, value: VarInt) -> Self {
+        self.0
+             .0
+            .insert(SettingId::WebTransportInitialMaxStreamsUni, value);
+        self
+    }
+
+    /// Sets the initial number of bidirectional streams the peer can open in a session.
+    pub fn webtransport_initial_max_streams_bidi(mut self, value: VarInt) -> Self {
+        self.0
+             .0
+            .insert(SettingId::WebTransportInitialMaxStreamsBidi, value);
+        self
+    }
+
     /// Builds [`Settings`].
     pub fn build(self) -> Settings {
         self.0
@@ -246,4 +307,8 @@ mod setting_ids {
     pub const SETTINGS_H3_DATAGRAM: VarInt = VarInt::from_u32(0x33);
     pub const SETTINGS_ENABLE_WEBTRANSPORT: VarInt = VarInt::from_u32(0x2b60_3742);
     pub const SETTINGS_WEBTRANSPORT_MAX_SESSIONS: VarInt = VarInt::from_u32(0xc671_706a);
+    pub const SETTINGS_WT_MAX_SESSIONS: VarInt = VarInt::from_u32(0x14e9_cd29);
+    pub const SETTINGS_WT_INITIAL_MAX_DATA: VarInt = VarInt::from_u32(0x2b61);
+    pub const SETTINGS_WT_INITIAL_MAX_STREAMS_UNI: VarInt = VarInt::from_u32(0x2b64);
+    pub const SETTINGS_WT_INITIAL_MAX_STREAMS_BIDI: VarInt = VarInt::from_u32(0x2b65);
 }
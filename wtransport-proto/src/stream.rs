@@ -90,14 +90,17 @@ pub mod biremote {
         {
             loop {
                 match Frame::read(bytes_reader) {
+                    Ok(Some(frame)) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(Some(frame)) => {
                         return Ok(Some(self.validate_frame(frame)?));
                     }
                     Ok(None) => {
                         return Ok(None);
                     }
-                    Err(frame::ParseError::UnknownFrame) => {
-                        continue;
+                    Err(frame::ParseError::ReservedFrame) => {
+                        return Err(ErrorCode::FrameUnexpected);
                     }
                     Err(frame::ParseError::InvalidSessionId) => {
                         return Err(ErrorCode::Id);
@@ -121,11 +124,14 @@ pub mod biremote {
         {
             loop {
                 match Frame::read_async(reader).await {
+                    Ok(frame) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(frame) => {
                         return self.validate_frame(frame).map_err(IoReadError::H3);
                     }
-                    Err(frame::IoReadError::Parse(frame::ParseError::UnknownFrame)) => {
-                        continue;
+                    Err(frame::IoReadError::Parse(frame::ParseError::ReservedFrame)) => {
+                        return Err(IoReadError::H3(ErrorCode::FrameUnexpected));
                     }
                     Err(frame::IoReadError::Parse(frame::ParseError::InvalidSessionId)) => {
                         return Err(IoReadError::H3(ErrorCode::Id));
@@ -217,6 +223,11 @@ pub mod biremote {
                 FrameKind::Data => Ok(frame),
                 FrameKind::Headers => Ok(frame),
                 FrameKind::Settings => Err(ErrorCode::FrameUnexpected),
+                FrameKind::GoAway => Err(ErrorCode::FrameUnexpected),
+                FrameKind::CancelPush
+                | FrameKind::MaxPushId
+                | FrameKind::PriorityUpdateRequest
+                | FrameKind::PriorityUpdatePush => Err(ErrorCode::FrameUnexpected),
                 FrameKind::WebTransport => {
                     if !first_frame_done {
                         Ok(frame)
@@ -224,7 +235,7 @@ pub mod biremote {
                         Err(ErrorCode::Frame)
                     }
                 }
-                FrameKind::Exercise(_) => Ok(frame),
+                FrameKind::Exercise(_) | FrameKind::Unknown(_) => Ok(frame),
             }
         }
     }
@@ -281,14 +292,17 @@ pub mod bilocal {
         {
             loop {
                 match Frame::read(bytes_reader) {
+                    Ok(Some(frame)) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(Some(frame)) => {
                         return Ok(Some(self.validate_frame(frame)?));
                     }
                     Ok(None) => {
                         return Ok(None);
                     }
-                    Err(frame::ParseError::UnknownFrame) => {
-                        continue;
+                    Err(frame::ParseError::ReservedFrame) => {
+                        return Err(ErrorCode::FrameUnexpected);
                     }
                     Err(frame::ParseError::InvalidSessionId) => {
                         return Err(ErrorCode::Id);
@@ -312,11 +326,14 @@ pub mod bilocal {
         {
             loop {
                 match Frame::read_async(reader).await {
+                    Ok(frame) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(frame) => {
                         return self.validate_frame(frame).map_err(IoReadError::H3);
                     }
-                    Err(frame::IoReadError::Parse(frame::ParseError::UnknownFrame)) => {
-                        continue;
+                    Err(frame::IoReadError::Parse(frame::ParseError::ReservedFrame)) => {
+                        return Err(IoReadError::H3(ErrorCode::FrameUnexpected));
                     }
                     Err(frame::IoReadError::Parse(frame::ParseError::InvalidSessionId)) => {
                         return Err(IoReadError::H3(ErrorCode::Id));
@@ -474,8 +491,13 @@ pub mod bilocal {
                 FrameKind::Data => Ok(frame),
                 FrameKind::Headers => Ok(frame),
                 FrameKind::Settings => Err(ErrorCode::FrameUnexpected),
+                FrameKind::GoAway => Err(ErrorCode::FrameUnexpected),
+                FrameKind::CancelPush
+                | FrameKind::MaxPushId
+                | FrameKind::PriorityUpdateRequest
+                | FrameKind::PriorityUpdatePush => Err(ErrorCode::FrameUnexpected),
                 FrameKind::WebTransport => Err(ErrorCode::FrameUnexpected),
-                FrameKind::Exercise(_) => Ok(frame),
+                FrameKind::Exercise(_) | FrameKind::Unknown(_) => Ok(frame),
             }
         }
     }
@@ -529,8 +551,9 @@ pub mod uniremote {
         /// In case there are no enough information, [`MaybeUpgradeH3::Quic`] (i.e, `self`)
         /// will be returned.
         ///
-        /// If the stream type is unknown [`ErrorCode::StreamCreation`] is returned.
-        /// In that case, MUST NOT consider unknown stream types to be a connection error of any kind.
+        /// If the stream type is unknown, the stream is upgraded with [`StreamKind::Unknown`].
+        /// In that case, the stream should be discarded (e.g., stopped with
+        /// [`ErrorCode::StreamCreation`]) and MUST NOT be considered a connection error of any kind.
         pub fn upgrade<'a, R>(self, bytes_reader: &mut R) -> Result<MaybeUpgradeH3, ErrorCode>
         where
             R: BytesReader<'a>,
@@ -541,7 +564,6 @@ pub mod uniremote {
                     stage: H3::new(Some(stream_header)),
                 })),
                 Ok(None) => Ok(MaybeUpgradeH3::Quic(self)),
-                Err(stream_header::ParseError::UnknownStream) => Err(ErrorCode::StreamCreation),
                 Err(stream_header::ParseError::InvalidSessionId) => Err(ErrorCode::Id),
             }
         }
@@ -562,10 +584,6 @@ pub mod uniremote {
                     stage: H3::new(Some(stream_header)),
                 }),
 
-                Err(stream_header::IoReadError::Parse(
-                    stream_header::ParseError::UnknownStream,
-                )) => Err(IoReadError::H3(ErrorCode::StreamCreation)),
-
                 Err(stream_header::IoReadError::Parse(
                     stream_header::ParseError::InvalidSessionId,
                 )) => Err(IoReadError::H3(ErrorCode::Id)),
@@ -599,14 +617,17 @@ pub mod uniremote {
 
             loop {
                 match Frame::read(bytes_reader) {
+                    Ok(Some(frame)) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(Some(frame)) => {
                         return Ok(Some(self.validate_frame(frame)?));
                     }
                     Ok(None) => {
                         return Ok(None);
                     }
-                    Err(frame::ParseError::UnknownFrame) => {
-                        continue;
+                    Err(frame::ParseError::ReservedFrame) => {
+                        return Err(ErrorCode::FrameUnexpected);
                     }
                     Err(frame::ParseError::InvalidSessionId) => {
                         return Err(ErrorCode::Id);
@@ -636,11 +657,14 @@ pub mod uniremote {
 
             loop {
                 match Frame::read_async(reader).await {
+                    Ok(frame) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(frame) => {
                         return self.validate_frame(frame).map_err(IoReadError::H3);
                     }
-                    Err(frame::IoReadError::Parse(frame::ParseError::UnknownFrame)) => {
-                        continue;
+                    Err(frame::IoReadError::Parse(frame::ParseError::ReservedFrame)) => {
+                        return Err(IoReadError::H3(ErrorCode::FrameUnexpected));
                     }
                     Err(frame::IoReadError::Parse(frame::ParseError::InvalidSessionId)) => {
                         return Err(IoReadError::H3(ErrorCode::Id));
@@ -721,8 +745,13 @@ pub mod uniremote {
                 FrameKind::Data => Err(ErrorCode::FrameUnexpected),
                 FrameKind::Headers => Err(ErrorCode::FrameUnexpected),
                 FrameKind::Settings => Ok(frame),
+                FrameKind::GoAway => Ok(frame),
+                FrameKind::CancelPush => Ok(frame),
+                FrameKind::MaxPushId => Ok(frame),
+                FrameKind::PriorityUpdateRequest => Ok(frame),
+                FrameKind::PriorityUpdatePush => Ok(frame),
                 FrameKind::WebTransport => Err(ErrorCode::FrameUnexpected),
-                FrameKind::Exercise(_) => Ok(frame),
+                FrameKind::Exercise(_) | FrameKind::Unknown(_) => Ok(frame),
             }
         }
     }
@@ -931,14 +960,17 @@ pub mod session {
         {
             loop {
                 match Frame::read(bytes_reader) {
+                    Ok(Some(frame)) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(Some(frame)) => {
                         return Ok(Some(self.validate_frame(frame)?));
                     }
                     Ok(None) => {
                         return Ok(None);
                     }
-                    Err(frame::ParseError::UnknownFrame) => {
-                        continue;
+                    Err(frame::ParseError::ReservedFrame) => {
+                        return Err(ErrorCode::FrameUnexpected);
                     }
                     Err(frame::ParseError::InvalidSessionId) => {
                         return Err(ErrorCode::Id);
@@ -962,11 +994,14 @@ pub mod session {
         {
             loop {
                 match Frame::read_async(reader).await {
+                    Ok(frame) if matches!(frame.kind(), FrameKind::Unknown(_)) => {
+                        continue;
+                    }
                     Ok(frame) => {
                         return self.validate_frame(frame).map_err(IoReadError::H3);
                     }
-                    Err(frame::IoReadError::Parse(frame::ParseError::UnknownFrame)) => {
-                        continue;
+                    Err(frame::IoReadError::Parse(frame::ParseError::ReservedFrame)) => {
+                        return Err(IoReadError::H3(ErrorCode::FrameUnexpected));
                     }
                     Err(frame::IoReadError::Parse(frame::ParseError::InvalidSessionId)) => {
                         return Err(IoReadError::H3(ErrorCode::Id));
@@ -1043,8 +1078,13 @@ pub mod session {
                 FrameKind::Data => Ok(frame),
                 FrameKind::Headers => Ok(frame),
                 FrameKind::Settings => Err(ErrorCode::FrameUnexpected),
+                FrameKind::GoAway => Err(ErrorCode::FrameUnexpected),
+                FrameKind::CancelPush
+                | FrameKind::MaxPushId
+                | FrameKind::PriorityUpdateRequest
+                | FrameKind::PriorityUpdatePush => Err(ErrorCode::FrameUnexpected),
                 FrameKind::WebTransport => Err(ErrorCode::FrameUnexpected),
-                FrameKind::Exercise(_) => Ok(frame),
+                FrameKind::Exercise(_) | FrameKind::Unknown(_) => Ok(frame),
             }
         }
     }
@@ -19,10 +19,6 @@ use crate::bytes;
 /// Error stream header parsing.
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
-    /// Error for unknown stream type.
-    #[error("cannot parse HTTP3 stream header as ID is unknown")]
-    UnknownStream,
-
     /// Error for invalid session ID.
     #[error("cannot parse HTTP3 stream header as session ID is invalid")]
     InvalidSessionId,
@@ -66,11 +62,17 @@ pub enum StreamKind {
     /// QPACK Decoder stream type.
     QPackDecoder,
 
+    /// PUSH stream type.
+    Push,
+
     /// WebTransport stream type.
     WebTransport,
 
     /// Exercise stream.
     Exercise(VarInt),
+
+    /// Stream of unknown type, to be ignored.
+    Unknown(VarInt),
 }
 
 impl StreamKind {
@@ -80,14 +82,15 @@ impl StreamKind {
         id.into_inner() >= 0x21 && ((id.into_inner() - 0x21) % 0x1f == 0)
     }
 
-    const fn parse(id: VarInt) -> Option<Self> {
+    const fn parse(id: VarInt) -> Self {
         match id {
-            stream_type_ids::CONTROL_STREAM => Some(StreamKind::Control),
-            stream_type_ids::QPACK_ENCODER_STREAM => Some(StreamKind::QPackEncoder),
-            stream_type_ids::QPACK_DECODER_STREAM => Some(StreamKind::QPackDecoder),
-            stream_type_ids::WEBTRANSPORT_STREAM => Some(StreamKind::WebTransport),
-            id if StreamKind::is_id_exercise(id) => Some(StreamKind::Exercise(id)),
-            _ => None,
+            stream_type_ids::CONTROL_STREAM => StreamKind::Control,
+            stream_type_ids::PUSH_STREAM => StreamKind::Push,
+            stream_type_ids::QPACK_ENCODER_STREAM => StreamKind::QPackEncoder,
+            stream_type_ids::QPACK_DECODER_STREAM => StreamKind::QPackDecoder,
+            stream_type_ids::WEBTRANSPORT_STREAM => StreamKind::WebTransport,
+            id if StreamKind::is_id_exercise(id) => StreamKind::Exercise(id),
+            id => StreamKind::Unknown(id),
         }
     }
 
@@ -96,8 +99,9 @@ impl StreamKind {
             StreamKind::Control => stream_type_ids::CONTROL_STREAM,
             StreamKind::QPackEncoder => stream_type_ids::QPACK_ENCODER_STREAM,
             StreamKind::QPackDecoder => stream_type_ids::QPACK_DECODER_STREAM,
+            StreamKind::Push => stream_type_ids::PUSH_STREAM,
             StreamKind::WebTransport => stream_type_ids::WEBTRANSPORT_STREAM,
-            StreamKind::Exercise(id) => id,
+            StreamKind::Exercise(id) | StreamKind::Unknown(id) => id,
         }
     }
 }
@@ -120,6 +124,18 @@ impl StreamHeader {
         Self::new(StreamKind::Control, None)
     }
 
+    /// Creates a new stream header of type [`StreamKind::QPackEncoder`].
+    #[inline(always)]
+    pub fn new_qpack_encoder() -> Self {
+        Self::new(StreamKind::QPackEncoder, None)
+    }
+
+    /// Creates a new stream header of type [`StreamKind::QPackDecoder`].
+    #[inline(always)]
+    pub fn new_qpack_decoder() -> Self {
+        Self::new(StreamKind::QPackDecoder, None)
+    }
+
     /// Creates a new stream header of type [`StreamKind::WebTransport`].
     #[inline(always)]
     pub fn new_webtransport(session_id: SessionId) -> Self {
@@ -137,7 +153,7 @@ impl StreamHeader {
         R: BytesReader<'a>,
     {
         let kind = match bytes_reader.get_varint() {
-            Some(kind_id) => StreamKind::parse(kind_id).ok_or(ParseError::UnknownStream)?,
+            Some(kind_id) => StreamKind::parse(kind_id),
             None => return Ok(None),
         };
 
@@ -166,8 +182,7 @@ impl StreamHeader {
         use crate::bytes::BytesReaderAsync;
 
         let kind_id = reader.get_varint().await?;
-        let kind =
-            StreamKind::parse(kind_id).ok_or(IoReadError::Parse(ParseError::UnknownStream))?;
+        let kind = StreamKind::parse(kind_id);
 
         let session_id = if matches!(kind, StreamKind::WebTransport) {
             let session_id =
@@ -326,6 +341,7 @@ mod stream_type_ids {
     use crate::varint::VarInt;
 
     pub const CONTROL_STREAM: VarInt = VarInt::from_u32(0x0);
+    pub const PUSH_STREAM: VarInt = VarInt::from_u32(0x01);
     pub const QPACK_ENCODER_STREAM: VarInt = VarInt::from_u32(0x02);
     pub const QPACK_DECODER_STREAM: VarInt = VarInt::from_u32(0x03);
     pub const WEBTRANSPORT_STREAM: VarInt = VarInt::from_u32(0x54);
@@ -436,9 +452,11 @@ mod tests {
     fn unknown_stream() {
         let buffer = StreamHeader::serialize_any(VarInt::from_u32(0x0042_4242));
 
+        let stream_header = StreamHeader::read(&mut buffer.as_slice()).unwrap().unwrap();
+
         assert!(matches!(
-            StreamHeader::read(&mut buffer.as_slice()),
-            Err(ParseError::UnknownStream)
+            stream_header.kind(),
+            StreamKind::Unknown(id) if id.into_inner() == 0x0042_4242
         ));
     }
 
@@ -446,9 +464,13 @@ mod tests {
     async fn unknown_stream_async() {
         let buffer = StreamHeader::serialize_any(VarInt::from_u32(0x0042_4242));
 
+        let stream_header = StreamHeader::read_async(&mut buffer.as_slice())
+            .await
+            .unwrap();
+
         assert!(matches!(
-            StreamHeader::read_async(&mut buffer.as_slice()).await,
-            Err(IoReadError::Parse(ParseError::UnknownStream))
+            stream_header.kind(),
+            StreamKind::Unknown(id) if id.into_inner() == 0x0042_4242
         ));
     }
 
@@ -0,0 +1,559 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BufferWriter;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::bytes::EndOfBuffer;
+use std::borrow::Cow;
+
+/// The `:protocol` value of WebSocket requests (RFC 9220).
+pub const PROTOCOL: &str = "websocket";
+
+/// The only WebSocket version supported (`sec-websocket-version` header field).
+pub const VERSION: &str = "13";
+
+/// Error WebSocket frame parsing.
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    /// Reserved bits are set, but no extension has been negotiated.
+    #[error("reserved bits set")]
+    ReservedBits,
+
+    /// Opcode is not known.
+    #[error("unknown opcode")]
+    UnknownOpcode,
+
+    /// Control frame is fragmented or its payload is too big.
+    #[error("invalid control frame")]
+    InvalidControlFrame,
+
+    /// Payload of a close frame is malformed.
+    #[error("invalid close frame payload")]
+    InvalidClosePayload,
+
+    /// Payload required too big.
+    #[error("cannot parse frame as payload limit is reached")]
+    PayloadTooBig,
+}
+
+/// A WebSocket frame opcode.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Opcode {
+    /// Continuation of a fragmented message.
+    Continuation,
+
+    /// Text message (UTF-8).
+    Text,
+
+    /// Binary message.
+    Binary,
+
+    /// Close control frame.
+    Close,
+
+    /// Ping control frame.
+    Ping,
+
+    /// Pong control frame.
+    Pong,
+}
+
+impl Opcode {
+    /// Parses the opcode from its 4-bit value.
+    pub const fn parse(id: u8) -> Option<Self> {
+        match id {
+            0x0 => Some(Opcode::Continuation),
+            0x1 => Some(Opcode::Text),
+            0x2 => Some(Opcode::Binary),
+            0x8 => Some(Opcode::Close),
+            0x9 => Some(Opcode::Ping),
+            0xa => Some(Opcode::Pong),
+            _ => None,
+        }
+    }
+
+    /// Returns the 4-bit value of the opcode.
+    pub const fn id(self) -> u8 {
+        match self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xa,
+        }
+    }
+
+    /// Returns `true` for control frame opcodes (close, ping and pong).
+    pub const fn is_control(self) -> bool {
+        matches!(self, Opcode::Close | Opcode::Ping | Opcode::Pong)
+    }
+}
+
+/// Alias for [`WsFrame<'static>`](WsFrame);
+pub type WsFrameOwned = WsFrame<'static>;
+
+/// A WebSocket frame as defined in RFC 6455.
+///
+/// Over HTTP/3, frames are exchanged on the request stream, inside the payload of
+/// HTTP3 DATA frames.
+#[derive(Debug)]
+pub struct WsFrame<'a> {
+    fin: bool,
+    opcode: Opcode,
+    mask: Option<[u8; 4]>,
+    payload: Cow<'a, [u8]>,
+}
+
+impl<'a> WsFrame<'a> {
+    const MAX_PARSE_PAYLOAD_ALLOWED: u64 = 16 * 1024 * 1024;
+    const MAX_CONTROL_PAYLOAD: usize = 125;
+
+    /// Creates a new unmasked frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a control frame is fragmented (`fin` not set) or its payload is
+    /// greater than 125 bytes.
+    pub fn new(fin: bool, opcode: Opcode, payload: Cow<'a, [u8]>) -> Self {
+        assert!(
+            !opcode.is_control() || (fin && payload.len() <= Self::MAX_CONTROL_PAYLOAD),
+            "Invalid control frame"
+        );
+
+        Self {
+            fin,
+            opcode,
+            mask: None,
+            payload,
+        }
+    }
+
+    /// Masks the frame with `masking_key`, as required for frames sent by clients.
+    pub fn with_mask(mut self, masking_key: [u8; 4]) -> Self {
+        self.mask = Some(masking_key);
+        self
+    }
+
+    /// Reads a [`WsFrame`] from a [`BytesReader`].
+    ///
+    /// The returned payload is unmasked.
+    ///
+    /// It returns [`None`] if the `bytes_reader` does not contain enough bytes
+    /// to parse an entire frame.
+    ///
+    /// In case [`None`] or [`Err`], `bytes_reader` might be partially read.
+    pub fn read<R>(bytes_reader: &mut R) -> Result<Option<Self>, ParseError>
+    where
+        R: BytesReader<'a>,
+    {
+        let Some(&[first, second]) = bytes_reader.get_bytes(2) else {
+            return Ok(None);
+        };
+
+        if first & 0x70 != 0 {
+            return Err(ParseError::ReservedBits);
+        }
+
+        let fin = first & 0x80 != 0;
+        let opcode = Opcode::parse(first & 0x0f).ok_or(ParseError::UnknownOpcode)?;
+        let masked = second & 0x80 != 0;
+
+        let payload_len = match second & 0x7f {
+            126 => match bytes_reader.get_bytes(2) {
+                Some(bytes) => u64::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+                None => return Ok(None),
+            },
+            127 => match bytes_reader.get_bytes(8) {
+                Some(bytes) => u64::from_be_bytes(bytes.try_into().expect("8 bytes")),
+                None => return Ok(None),
+            },
+            len => u64::from(len),
+        };
+
+        if opcode.is_control() && (!fin || payload_len > Self::MAX_CONTROL_PAYLOAD as u64) {
+            return Err(ParseError::InvalidControlFrame);
+        }
+
+        if payload_len > Self::MAX_PARSE_PAYLOAD_ALLOWED {
+            return Err(ParseError::PayloadTooBig);
+        }
+
+        let mask = if masked {
+            match bytes_reader.get_bytes(4) {
+                Some(bytes) => Some(<[u8; 4]>::try_from(bytes).expect("4 bytes")),
+                None => return Ok(None),
+            }
+        } else {
+            None
+        };
+
+        let Some(payload) = bytes_reader.get_bytes(payload_len as usize) else {
+            return Ok(None);
+        };
+
+        let payload = match mask {
+            Some(masking_key) => {
+                let mut payload = payload.to_vec();
+                apply_mask(&mut payload, masking_key);
+                Cow::Owned(payload)
+            }
+            None => Cow::Borrowed(payload),
+        };
+
+        Ok(Some(Self {
+            fin,
+            opcode,
+            mask,
+            payload,
+        }))
+    }
+
+    /// Reads a [`WsFrame`] from a [`BufferReader`].
+    ///
+    /// It returns [`None`] if the `buffer_reader` does not contain enough bytes
+    /// to parse an entire frame.
+    ///
+    /// In case [`None`] or [`Err`], `buffer_reader` offset if not advanced.
+    pub fn read_from_buffer(
+        buffer_reader: &mut BufferReader<'a>,
+    ) -> Result<Option<Self>, ParseError> {
+        let mut buffer_reader_child = buffer_reader.child();
+
+        match Self::read(&mut *buffer_reader_child)? {
+            Some(frame) => {
+                buffer_reader_child.commit();
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Writes a [`WsFrame`] into a [`BytesWriter`].
+    ///
+    /// The payload is masked if a masking key is set.
+    ///
+    /// It returns [`Err`] if the `bytes_writer` does not have enough capacity
+    /// to write the entire frame.
+    /// See [`Self::write_size`] to retrieve the exact amount of required capacity.
+    ///
+    /// In case [`Err`], `bytes_writer` might be partially written.
+    pub fn write<W>(&self, bytes_writer: &mut W) -> Result<(), EndOfBuffer>
+    where
+        W: BytesWriter,
+    {
+        let first = (u8::from(self.fin) << 7) | self.opcode.id();
+        let mask_bit = if self.mask.is_some() { 0x80 } else { 0x00 };
+        let payload_len = self.payload.len();
+
+        if payload_len < 126 {
+            bytes_writer.put_bytes(&[first, mask_bit | payload_len as u8])?;
+        } else if let Ok(payload_len) = u16::try_from(payload_len) {
+            bytes_writer.put_bytes(&[first, mask_bit | 126])?;
+            bytes_writer.put_bytes(&payload_len.to_be_bytes())?;
+        } else {
+            bytes_writer.put_bytes(&[first, mask_bit | 127])?;
+            bytes_writer.put_bytes(&(payload_len as u64).to_be_bytes())?;
+        }
+
+        match self.mask {
+            Some(masking_key) => {
+                let mut payload = self.payload.to_vec();
+                apply_mask(&mut payload, masking_key);
+
+                bytes_writer.put_bytes(&masking_key)?;
+                bytes_writer.put_bytes(&payload)?;
+            }
+            None => {
+                bytes_writer.put_bytes(&self.payload)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes this [`WsFrame`] into a buffer via [`BufferWriter`].
+    ///
+    /// In case [`Err`], `buffer_writer` is not advanced.
+    pub fn write_to_buffer(&self, buffer_writer: &mut BufferWriter) -> Result<(), EndOfBuffer> {
+        if buffer_writer.capacity() < self.write_size() {
+            return Err(EndOfBuffer);
+        }
+
+        self.write(buffer_writer)
+            .expect("Enough capacity for frame");
+
+        Ok(())
+    }
+
+    /// Returns the needed capacity to write this frame into a buffer.
+    pub fn write_size(&self) -> usize {
+        let payload_len = self.payload.len();
+
+        let header_len = if payload_len < 126 {
+            2
+        } else if payload_len <= usize::from(u16::MAX) {
+            4
+        } else {
+            10
+        };
+
+        let mask_len = if self.mask.is_some() { 4 } else { 0 };
+
+        header_len + mask_len + payload_len
+    }
+
+    /// Returns whether this is the final fragment of a message.
+    #[inline(always)]
+    pub const fn fin(&self) -> bool {
+        self.fin
+    }
+
+    /// Returns the [`Opcode`] of this frame.
+    #[inline(always)]
+    pub const fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    /// Returns whether the frame was (or will be) masked on the wire.
+    #[inline(always)]
+    pub const fn is_masked(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    /// Returns the (unmasked) payload of this frame.
+    #[inline(always)]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Returns the (unmasked) payload of this frame, consuming it.
+    pub fn into_payload(self) -> Cow<'a, [u8]> {
+        self.payload
+    }
+}
+
+/// Payload of a WebSocket close frame.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CloseFrame {
+    code: u16,
+    reason: String,
+}
+
+impl CloseFrame {
+    /// Normal closure.
+    pub const NORMAL: u16 = 1000;
+
+    /// The endpoint is going away.
+    pub const GOING_AWAY: u16 = 1001;
+
+    /// The peer violated the protocol.
+    pub const PROTOCOL_ERROR: u16 = 1002;
+
+    /// The message data type cannot be accepted.
+    pub const UNSUPPORTED_DATA: u16 = 1003;
+
+    /// The close frame carried no status code.
+    ///
+    /// It is never sent on the wire.
+    pub const NO_STATUS: u16 = 1005;
+
+    /// The message data is not consistent with its type (e.g., non UTF-8 text).
+    pub const INVALID_PAYLOAD: u16 = 1007;
+
+    /// The message is too big to be processed.
+    pub const MESSAGE_TOO_BIG: u16 = 1009;
+
+    /// An unexpected condition prevented the endpoint from fulfilling the request.
+    pub const INTERNAL_ERROR: u16 = 1011;
+
+    /// Creates a new close frame payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `code` cannot be sent on the wire (see [`Self::is_valid_code`]), or
+    /// the `reason` is longer than 123 bytes.
+    pub fn new<S>(code: u16, reason: S) -> Self
+    where
+        S: ToString,
+    {
+        let reason = reason.to_string();
+
+        assert!(Self::is_valid_code(code), "Invalid close code");
+        assert!(reason.len() <= 123, "Close reason too long");
+
+        Self { code, reason }
+    }
+
+    /// Reads the close payload from a [`WsFrame`] of type [`Opcode::Close`].
+    ///
+    /// An empty payload gives [`Self::NO_STATUS`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is not a close frame.
+    pub fn with_frame(frame: &WsFrame) -> Result<Self, ParseError> {
+        assert_eq!(frame.opcode(), Opcode::Close);
+
+        match frame.payload() {
+            [] => Ok(Self {
+                code: Self::NO_STATUS,
+                reason: String::new(),
+            }),
+            [high, low, reason @ ..] => {
+                let code = u16::from_be_bytes([*high, *low]);
+
+                if !Self::is_valid_code(code) {
+                    return Err(ParseError::InvalidClosePayload);
+                }
+
+                let reason = std::str::from_utf8(reason)
+                    .map_err(|_| ParseError::InvalidClosePayload)?
+                    .to_string();
+
+                Ok(Self { code, reason })
+            }
+            [_] => Err(ParseError::InvalidClosePayload),
+        }
+    }
+
+    /// Generates a [`WsFrame`] corresponding to this close payload.
+    pub fn generate_frame(&self) -> WsFrameOwned {
+        let payload = if self.code == Self::NO_STATUS {
+            Vec::new()
+        } else {
+            let mut payload = self.code.to_be_bytes().to_vec();
+            payload.extend_from_slice(self.reason.as_bytes());
+            payload
+        };
+
+        WsFrame::new(true, Opcode::Close, Cow::Owned(payload))
+    }
+
+    /// Returns whether `code` is allowed on the wire.
+    pub fn is_valid_code(code: u16) -> bool {
+        matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+
+    /// Returns the status code.
+    #[inline(always)]
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns the reason.
+    #[inline(always)]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+fn apply_mask(payload: &mut [u8], masking_key: [u8; 4]) {
+    for (byte, mask) in payload.iter_mut().zip(masking_key.iter().cycle()) {
+        *byte ^= mask;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(frame: &WsFrame) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        frame.write(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), frame.write_size());
+        buffer
+    }
+
+    #[test]
+    fn masked_text_rfc_example() {
+        // RFC 6455, section 5.7
+        let bytes = [
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+
+        let frame = WsFrame::read(&mut bytes.as_slice()).unwrap().unwrap();
+        assert!(frame.fin());
+        assert!(frame.is_masked());
+        assert_eq!(frame.opcode(), Opcode::Text);
+        assert_eq!(frame.payload(), b"Hello");
+
+        let frame = WsFrame::new(true, Opcode::Text, Cow::Borrowed(b"Hello"))
+            .with_mask([0x37, 0xfa, 0x21, 0x3d]);
+        assert_eq!(serialize(&frame), bytes);
+    }
+
+    #[test]
+    fn payload_lengths() {
+        for len in [0, 125, 126, 65535, 65536] {
+            let payload = vec![7; len];
+            let frame = WsFrame::new(false, Opcode::Binary, Cow::Borrowed(&payload));
+            let bytes = serialize(&frame);
+
+            let frame = WsFrame::read(&mut bytes.as_slice()).unwrap().unwrap();
+            assert!(!frame.fin());
+            assert!(!frame.is_masked());
+            assert_eq!(frame.payload().len(), len);
+        }
+    }
+
+    #[test]
+    fn incomplete() {
+        let payload = vec![1; 300];
+        let frame =
+            WsFrame::new(true, Opcode::Binary, Cow::Borrowed(&payload)).with_mask([1, 2, 3, 4]);
+        let bytes = serialize(&frame);
+
+        for len in 0..bytes.len() {
+            let mut buffer_reader = BufferReader::new(&bytes[..len]);
+            assert!(WsFrame::read_from_buffer(&mut buffer_reader)
+                .unwrap()
+                .is_none());
+            assert_eq!(buffer_reader.offset(), 0);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        // Reserved bits
+        assert!(matches!(
+            WsFrame::read(&mut [0xc1, 0x00].as_slice()),
+            Err(ParseError::ReservedBits)
+        ));
+
+        // Unknown opcode
+        assert!(matches!(
+            WsFrame::read(&mut [0x83, 0x00].as_slice()),
+            Err(ParseError::UnknownOpcode)
+        ));
+
+        // Fragmented ping
+        assert!(matches!(
+            WsFrame::read(&mut [0x09, 0x00].as_slice()),
+            Err(ParseError::InvalidControlFrame)
+        ));
+
+        // Oversized ping
+        assert!(matches!(
+            WsFrame::read(&mut [0x89, 0x7e, 0x00, 0x7e].as_slice()),
+            Err(ParseError::InvalidControlFrame)
+        ));
+    }
+
+    #[test]
+    fn close_frame() {
+        let close = CloseFrame::new(CloseFrame::GOING_AWAY, "bye");
+        let bytes = serialize(&close.generate_frame());
+
+        let frame = WsFrame::read(&mut bytes.as_slice()).unwrap().unwrap();
+        assert_eq!(CloseFrame::with_frame(&frame).unwrap(), close);
+
+        let frame = WsFrame::new(true, Opcode::Close, Cow::Borrowed(&[]));
+        assert_eq!(
+            CloseFrame::with_frame(&frame).unwrap().code(),
+            CloseFrame::NO_STATUS
+        );
+
+        let frame = WsFrame::new(true, Opcode::Close, Cow::Borrowed(&[0x03, 0xed]));
+        assert!(CloseFrame::with_frame(&frame).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Admission control of incoming connections on server endpoints.
+//!
+//! A server endpoint can limit the connections it takes on, before yielding them with
+//! [`Endpoint::accept`](crate::Endpoint::accept):
+//!
+//! - [`max_connections`](crate::config::ServerConfigBuilder::max_connections) bounds the
+//!   concurrent QUIC connections. Further connection attempts are refused before any TLS
+//!   processing.
+//! - [`validate_addresses`](crate::config::ServerConfigBuilder::validate_addresses) makes every
+//!   client prove ownership of its address with a QUIC Retry, before any TLS processing.
+//! - [`max_handshakes`](crate::config::ServerConfigBuilder::max_handshakes) bounds the
+//!   connections still completing their handshake.
+//! - [`max_connections_per_ip`](crate::config::ServerConfigBuilder::max_connections_per_ip)
+//!   bounds the connections from a single address (or network prefix, see
+//!   [`ip_prefix_lengths`](crate::config::ServerConfigBuilder::ip_prefix_lengths)).
+//! - An [`AdmissionFilter`] set with
+//!   [`admission_filter`](crate::config::ServerConfigBuilder::admission_filter) decides on each
+//!   connection given the client address.
+//!
+//! The last three are enforced as soon as the connection attempt is received by the endpoint,
+//! once the client first flight has been answered. Refused connections are abandoned right
+//! away, without completing their handshake: the client connection attempt fails.
+//!
+//! #### Limitations
+//!
+//! The QUIC implementation does not expose connection attempts before answering them:
+//!
+//! - Per-connection checks (handshakes, per-address limits and the filter) cannot spare the
+//!   TLS processing of the client first flight. Only
+//!   [`max_connections`](crate::config::ServerConfigBuilder::max_connections) and
+//!   [`validate_addresses`](crate::config::ServerConfigBuilder::validate_addresses) apply
+//!   before it.
+//! - A Retry cannot be requested for a single connection:
+//!   [`Admission`](crate::admission::Admission) can only accept or refuse, and address
+//!   validation is all or nothing with
+//!   [`validate_addresses`](crate::config::ServerConfigBuilder::validate_addresses).
+//!
+//! #### Examples:
+//! ```no_run
+//! use wtransport::admission::Admission;
+//! use wtransport::Certificate;
+//! use wtransport::ServerConfig;
+//!
+//! let config = ServerConfig::builder()
+//!     .with_bind_default(4433)
+//!     .with_certificate(Certificate::self_signed(["localhost"]))
+//!     .max_handshakes(64)
+//!     .max_connections_per_ip(8)
+//!     .admission_filter(|remote_address: std::net::SocketAddr| {
+//!         if remote_address.ip().is_loopback() {
+//!             Admission::Accept
+//!         } else {
+//!             Admission::Refuse
+//!         }
+//!     })
+//!     .build();
+//! ```
+
+use std::fmt::Debug;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Decision of an [`AdmissionFilter`] on an incoming connection.
+///
+/// There is no decision to force a Retry on a single connection: see the
+/// [module limitations](self#limitations).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Admission {
+    /// The connection is accepted, subject to the other limits.
+    Accept,
+
+    /// The connection is refused.
+    Refuse,
+}
+
+/// A trait for deciding on incoming connections given the client address.
+///
+/// It is implemented for closures taking a [`SocketAddr`] and returning an [`Admission`].
+/// IPv4-mapped IPv6 addresses, as reported by dual-stack sockets, are given as IPv4 ones.
+///
+/// The decision is taken synchronously on the task calling
+/// [`Endpoint::accept`](crate::Endpoint::accept), so it should be cheap.
+pub trait AdmissionFilter {
+    /// Decides whether the connection from `remote_address` is accepted.
+    fn admit(&self, remote_address: SocketAddr) -> Admission;
+}
+
+impl<F> AdmissionFilter for F
+where
+    F: Fn(SocketAddr) -> Admission,
+{
+    fn admit(&self, remote_address: SocketAddr) -> Admission {
+        self(remote_address)
+    }
+}
+
+/// An [`AdmissionFilter`] shared among connections.
+#[derive(Clone)]
+pub(crate) struct SharedAdmissionFilter(Arc<dyn AdmissionFilter + Send + Sync>);
+
+impl SharedAdmissionFilter {
+    pub(crate) fn new<F>(filter: F) -> Self
+    where
+        F: AdmissionFilter + Send + Sync + 'static,
+    {
+        Self(Arc::new(filter))
+    }
+
+    pub(crate) fn admit(&self, remote_address: SocketAddr) -> Admission {
+        self.0.admit(remote_address)
+    }
+}
+
+impl Debug for SharedAdmissionFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionFilter").finish_non_exhaustive()
+    }
+}
+
+/// Limits enforced by a server endpoint on incoming connections.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionLimits {
+    pub(crate) max_handshakes: Option<u32>,
+    pub(crate) max_connections_per_ip: Option<u32>,
+    pub(crate) ipv4_prefix_len: u8,
+    pub(crate) ipv6_prefix_len: u8,
+    pub(crate) filter: Option<SharedAdmissionFilter>,
+}
+
+impl ConnectionLimits {
+    /// Returns `true` if no limit needs the addresses of the other connections.
+    pub(crate) fn is_unbounded(&self) -> bool {
+        self.max_handshakes.is_none() && self.max_connections_per_ip.is_none()
+    }
+
+    /// Checks whether the connection from `remote_address` can be taken on.
+    ///
+    /// `handshakes` and `connections` are the remote addresses of the connections
+    /// respectively completing their handshake and established.
+    pub(crate) fn check<H, C>(
+        &self,
+        remote_address: SocketAddr,
+        handshakes: H,
+        connections: C,
+    ) -> Result<(), Refusal>
+    where
+        H: IntoIterator<Item = SocketAddr>,
+        C: IntoIterator<Item = SocketAddr>,
+    {
+        let mut handshakes_count = 0;
+        let mut same_prefix_count = 0;
+
+        for address in handshakes {
+            handshakes_count += 1;
+            same_prefix_count += usize::from(self.same_prefix(address.ip(), remote_address.ip()));
+        }
+
+        if self
+            .max_handshakes
+            .is_some_and(|max_handshakes| handshakes_count >= max_handshakes as usize)
+        {
+            return Err(Refusal::Handshakes);
+        }
+
+        if let Some(max_connections_per_ip) = self.max_connections_per_ip {
+            same_prefix_count += connections
+                .into_iter()
+                .filter(|address| self.same_prefix(address.ip(), remote_address.ip()))
+                .count();
+
+            if same_prefix_count >= max_connections_per_ip as usize {
+                return Err(Refusal::ConnectionsPerIp);
+            }
+        }
+
+        match self.filter.as_ref().map(|filter| {
+            filter.admit(SocketAddr::new(
+                canonical(remote_address.ip()),
+                remote_address.port(),
+            ))
+        }) {
+            Some(Admission::Refuse) => Err(Refusal::Filter),
+            Some(Admission::Accept) | None => Ok(()),
+        }
+    }
+
+    fn same_prefix(&self, a: IpAddr, b: IpAddr) -> bool {
+        match (canonical(a), canonical(b)) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.ipv4_prefix_len))
+                    .unwrap_or(0);
+                u32::from(a) & mask == u32::from(b) & mask
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.ipv6_prefix_len))
+                    .unwrap_or(0);
+                u128::from(a) & mask == u128::from(b) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_handshakes: None,
+            max_connections_per_ip: None,
+            ipv4_prefix_len: 32,
+            ipv6_prefix_len: 128,
+            filter: None,
+        }
+    }
+}
+
+/// Unwraps IPv4-mapped addresses, as reported by dual-stack sockets.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => ipv6.to_ipv4_mapped().map_or(IpAddr::V6(ipv6), IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Reason an incoming connection is refused.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Refusal {
+    #[error("too many handshakes in progress")]
+    Handshakes,
+
+    #[error("too many connections from the same address")]
+    ConnectionsPerIp,
+
+    #[error("refused by admission filter")]
+    Filter,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(ips: &[&str]) -> Vec<SocketAddr> {
+        ips.iter()
+            .map(|ip| SocketAddr::new(ip.parse().unwrap(), 4433))
+            .collect()
+    }
+
+    #[test]
+    fn per_ip() {
+        let limits = ConnectionLimits {
+            max_connections_per_ip: Some(2),
+            ..Default::default()
+        };
+
+        let remote = addresses(&["10.0.0.1"])[0];
+        let others = addresses(&["10.0.0.1", "10.0.0.2", "::ffff:10.0.0.3"]);
+
+        assert!(limits.check(remote, [], others.clone()).is_ok());
+        assert!(matches!(
+            limits.check(remote, others.clone(), others.clone()),
+            Err(Refusal::ConnectionsPerIp)
+        ));
+
+        let limits = ConnectionLimits {
+            ipv4_prefix_len: 24,
+            ..limits
+        };
+
+        assert!(matches!(
+            limits.check(remote, [], others),
+            Err(Refusal::ConnectionsPerIp)
+        ));
+    }
+
+    #[test]
+    fn ipv6_prefix() {
+        let limits = ConnectionLimits {
+            max_connections_per_ip: Some(1),
+            ipv6_prefix_len: 64,
+            ..Default::default()
+        };
+
+        let remote = addresses(&["2001:db8::1"])[0];
+
+        assert!(limits
+            .check(remote, [], addresses(&["2001:db8:0:1::1", "10.0.0.1"]))
+            .is_ok());
+        assert!(matches!(
+            limits.check(remote, [], addresses(&["2001:db8::ffff"])),
+            Err(Refusal::ConnectionsPerIp)
+        ));
+    }
+
+    #[test]
+    fn handshakes_and_filter() {
+        let limits = ConnectionLimits {
+            max_handshakes: Some(1),
+            filter: Some(SharedAdmissionFilter::new(|remote_address: SocketAddr| {
+                if remote_address.ip().is_loopback() {
+                    Admission::Accept
+                } else {
+                    Admission::Refuse
+                }
+            })),
+            ..Default::default()
+        };
+
+        let remotes = addresses(&["127.0.0.1", "10.0.0.1"]);
+
+        assert!(limits.check(remotes[0], [], remotes.clone()).is_ok());
+        assert!(matches!(
+            limits.check(remotes[0], [remotes[1]], []),
+            Err(Refusal::Handshakes)
+        ));
+        assert!(matches!(
+            limits.check(remotes[1], [], []),
+            Err(Refusal::Filter)
+        ));
+    }
+}
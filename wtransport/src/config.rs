@@ -32,6 +32,19 @@
 //!     .build();
 //! ```
 
+use crate::admission::AdmissionFilter;
+use crate::admission::ConnectionLimits;
+use crate::admission::SharedAdmissionFilter;
+use crate::connect_udp::ConnectUdpHandler;
+use crate::connect_udp::SharedConnectUdpHandler;
+use crate::connect_udp::UriTemplate;
+use crate::endpoint::SessionHandler;
+use crate::endpoint::SessionPolicy;
+use crate::endpoint::SharedSessionHandler;
+use crate::http::HttpHandler;
+use crate::http::SharedHttpHandler;
+use crate::websocket::SharedWebSocketHandler;
+use crate::websocket::WebSocketHandler;
 use crate::Certificate;
 use quinn::ClientConfig as QuicClientConfig;
 use quinn::ServerConfig as QuicServerConfig;
@@ -39,6 +52,7 @@ use quinn::TransportConfig;
 use rustls::ClientConfig as TlsClientConfig;
 use rustls::RootCertStore;
 use rustls::ServerConfig as TlsServerConfig;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::future::Future;
@@ -50,6 +64,7 @@ use std::net::SocketAddrV6;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
+use wtransport_proto::session::WebTransportDraft;
 use wtransport_proto::WEBTRANSPORT_ALPN;
 
 /// Configuration for IP address socket bind.
@@ -185,6 +200,26 @@ pub struct InvalidIdleTimeout;
 /// - [`max_idle_timeout`](ServerConfigBuilder::max_idle_timeout)
 /// - [`keep_alive_interval`](ServerConfigBuilder::keep_alive_interval)
 /// - [`allow_migration`](ServerConfigBuilder::allow_migration)
+/// - [`max_connections`](ServerConfigBuilder::max_connections)
+/// - [`validate_addresses`](ServerConfigBuilder::validate_addresses)
+/// - [`max_handshakes`](ServerConfigBuilder::max_handshakes)
+/// - [`max_connections_per_ip`](ServerConfigBuilder::max_connections_per_ip)
+/// - [`ip_prefix_lengths`](ServerConfigBuilder::ip_prefix_lengths)
+/// - [`admission_filter`](ServerConfigBuilder::admission_filter)
+/// - [`max_sessions`](ServerConfigBuilder::max_sessions)
+/// - [`qpack_max_table_capacity`](ServerConfigBuilder::qpack_max_table_capacity)
+/// - [`qpack_blocked_streams`](ServerConfigBuilder::qpack_blocked_streams)
+/// - [`session_max_data`](ServerConfigBuilder::session_max_data)
+/// - [`session_max_streams_uni`](ServerConfigBuilder::session_max_streams_uni)
+/// - [`session_max_streams_bidi`](ServerConfigBuilder::session_max_streams_bidi)
+/// - [`session_buffer_streams`](ServerConfigBuilder::session_buffer_streams)
+/// - [`session_buffer_bytes`](ServerConfigBuilder::session_buffer_bytes)
+/// - [`webtransport_drafts`](ServerConfigBuilder::webtransport_drafts)
+/// - [`http_handler`](ServerConfigBuilder::http_handler)
+/// - [`connect_udp_handler`](ServerConfigBuilder::connect_udp_handler)
+/// - [`websocket_handler`](ServerConfigBuilder::websocket_handler)
+/// - [`session_policy`](ServerConfigBuilder::session_policy)
+/// - [`virtual_host`](ServerConfigBuilder::virtual_host)
 ///
 /// #### Examples:
 /// ```
@@ -205,6 +240,7 @@ pub struct ServerConfig {
     pub(crate) bind_address: SocketAddr,
     pub(crate) dual_stack_config: Ipv6DualStackConfig,
     pub(crate) quic_config: QuicServerConfig,
+    pub(crate) webtransport_config: WebTransportConfig,
 }
 
 impl ServerConfig {
@@ -375,6 +411,9 @@ impl ServerConfigBuilder<states::WantsCertificate> {
             tls_config,
             transport_config,
             migration: true,
+            max_connections: None,
+            use_retry: false,
+            webtransport_config: WebTransportConfig::default(),
         })
     }
 
@@ -406,11 +445,17 @@ impl ServerConfigBuilder<states::WantsTransportConfigServer> {
         let mut quic_config = QuicServerConfig::with_crypto(Arc::new(self.0.tls_config));
         quic_config.transport_config(Arc::new(self.0.transport_config));
         quic_config.migration(self.0.migration);
+        quic_config.use_retry(self.0.use_retry);
+
+        if let Some(max_connections) = self.0.max_connections {
+            quic_config.concurrent_connections(max_connections);
+        }
 
         ServerConfig {
             bind_address: self.0.bind_address,
             dual_stack_config: self.0.dual_stack_config,
             quic_config,
+            webtransport_config: self.0.webtransport_config,
         }
     }
 
@@ -455,6 +500,270 @@ impl ServerConfigBuilder<states::WantsTransportConfigServer> {
         self.0.migration = value;
         self
     }
+
+    /// Maximum number of concurrent QUIC connections, including the ones completing
+    /// their handshake.
+    ///
+    /// Further connection attempts are refused before any TLS processing.
+    /// See the [`admission`](crate::admission) module.
+    /// Default value is `100000`.
+    pub fn max_connections(mut self, value: u32) -> Self {
+        self.0.max_connections = Some(value);
+        self
+    }
+
+    /// Whether to validate client addresses with a QUIC Retry before any TLS processing.
+    ///
+    /// It protects against connection attempts from spoofed addresses, at the cost of
+    /// an additional round-trip for every connection.
+    /// Disabled by default.
+    pub fn validate_addresses(mut self, value: bool) -> Self {
+        self.0.use_retry = value;
+        self
+    }
+
+    /// Maximum number of connections completing their handshake.
+    ///
+    /// A connection is in handshake until its QUIC handshake completes, or its
+    /// [`IncomingSession`](crate::endpoint::IncomingSession) is dropped.
+    /// Further connection attempts are refused.
+    /// See the [`admission`](crate::admission) module.
+    /// By default, there is no limit.
+    pub fn max_handshakes(mut self, value: u32) -> Self {
+        self.0.webtransport_config.connection_limits.max_handshakes = Some(value);
+        self
+    }
+
+    /// Maximum number of connections from the same client IP address.
+    ///
+    /// Addresses can be grouped by network prefix with
+    /// [`ip_prefix_lengths`](Self::ip_prefix_lengths).
+    /// Further connection attempts are refused.
+    /// See the [`admission`](crate::admission) module.
+    /// By default, there is no limit.
+    pub fn max_connections_per_ip(mut self, value: u32) -> Self {
+        self.0
+            .webtransport_config
+            .connection_limits
+            .max_connections_per_ip = Some(value);
+        self
+    }
+
+    /// Prefix lengths grouping client addresses for
+    /// [`max_connections_per_ip`](Self::max_connections_per_ip).
+    ///
+    /// Default values are `32` and `128`, so that each address is limited on its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ipv4` is greater than `32` or `ipv6` is greater than `128`.
+    pub fn ip_prefix_lengths(mut self, ipv4: u8, ipv6: u8) -> Self {
+        assert!(ipv4 <= 32, "IPv4 prefix length must be at most 32");
+        assert!(ipv6 <= 128, "IPv6 prefix length must be at most 128");
+
+        let connection_limits = &mut self.0.webtransport_config.connection_limits;
+        connection_limits.ipv4_prefix_len = ipv4;
+        connection_limits.ipv6_prefix_len = ipv6;
+        self
+    }
+
+    /// Decides on incoming connections with `filter`, given the client address.
+    ///
+    /// See the [`admission`](crate::admission) module.
+    /// By default, no filter is set.
+    pub fn admission_filter<F>(mut self, filter: F) -> Self
+    where
+        F: AdmissionFilter + Send + Sync + 'static,
+    {
+        self.0.webtransport_config.connection_limits.filter =
+            Some(SharedAdmissionFilter::new(filter));
+        self
+    }
+
+    /// Maximum number of concurrent WebTransport sessions a client can establish
+    /// over a single QUIC connection.
+    ///
+    /// Further session requests beyond this limit are rejected.
+    /// Default value is `1`.
+    pub fn max_sessions(mut self, value: u32) -> Self {
+        self.0.webtransport_config.max_sessions = value;
+        self
+    }
+
+    /// Maximum size (in bytes) of the QPACK dynamic table used to compress HTTP3 headers.
+    ///
+    /// It is advertised to the peer, and it also bounds the table used to compress
+    /// local headers. `0` disables the dynamic table.
+    /// Default value is `4096`.
+    pub fn qpack_max_table_capacity(mut self, value: u32) -> Self {
+        self.0.webtransport_config.qpack_max_table_capacity = value;
+        self
+    }
+
+    /// Maximum number of streams the peer can block waiting for QPACK dynamic table updates.
+    ///
+    /// Default value is `16`.
+    pub fn qpack_blocked_streams(mut self, value: u32) -> Self {
+        self.0.webtransport_config.qpack_blocked_streams = value;
+        self
+    }
+
+    /// Maximum amount of data (in bytes) the peer can send on all the streams of a
+    /// WebTransport session and not yet read.
+    ///
+    /// The limit is advertised to the peer and it is replenished as data is read.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_data(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_data = Some(value);
+        self
+    }
+
+    /// Maximum number of unidirectional streams the peer can open in a WebTransport
+    /// session and not yet accepted.
+    ///
+    /// The limit is advertised to the peer and it is replenished as streams are accepted.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_streams_uni(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_streams_uni = Some(value);
+        self
+    }
+
+    /// Maximum number of bidirectional streams the peer can open in a WebTransport
+    /// session and not yet accepted.
+    ///
+    /// The limit is advertised to the peer and it is replenished as streams are accepted.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_streams_bidi(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_streams_bidi = Some(value);
+        self
+    }
+
+    /// Maximum number of streams the peer can open in a WebTransport session before
+    /// the session is established.
+    ///
+    /// Streams might arrive before the session request is processed, or while it is
+    /// still waiting to be accepted: they are held until then. Further streams beyond
+    /// this limit are rejected.
+    /// Default value is `16`.
+    pub fn session_buffer_streams(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_buffer_streams = value;
+        self
+    }
+
+    /// Maximum amount of datagram payload (in bytes) the peer can send in a WebTransport
+    /// session before the session is established.
+    ///
+    /// Datagrams might arrive before the session request is processed, or while it is
+    /// still waiting to be accepted: they are held until then. Further datagrams beyond
+    /// this limit are discarded.
+    /// Default value is `65536`.
+    pub fn session_buffer_bytes(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_buffer_bytes = value;
+        self
+    }
+
+    /// Drafts of WebTransport over HTTP/3 the server accepts.
+    ///
+    /// Only these drafts are advertised in SETTINGS. For each connection, the newest draft
+    /// also supported by the client is used, unless a session request declares another
+    /// accepted draft (see [`Connection::draft`](crate::Connection::draft)).
+    /// Clients supporting none of them are refused.
+    /// By default, all [`WebTransportDraft::ALL`] drafts are accepted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drafts` is empty.
+    pub fn webtransport_drafts<I>(mut self, drafts: I) -> Self
+    where
+        I: IntoIterator<Item = WebTransportDraft>,
+    {
+        let mut drafts = drafts.into_iter().collect::<Vec<_>>();
+        drafts.sort();
+        drafts.dedup();
+
+        assert!(!drafts.is_empty(), "At least one draft must be accepted");
+
+        self.0.webtransport_config.drafts = drafts;
+        self
+    }
+
+    /// Serves plain HTTP/3 requests (any method other than `CONNECT`) with `handler`.
+    ///
+    /// Connections from clients not supporting WebTransport are kept open for these
+    /// requests, rather than being refused: their incoming session resolves right away to
+    /// [`ConnectionError::HttpOnly`](crate::error::ConnectionError::HttpOnly).
+    /// See the [`http`](crate::http) module.
+    /// By default, no handler is set and such requests are rejected.
+    pub fn http_handler<H>(mut self, handler: H) -> Self
+    where
+        H: HttpHandler + Send + Sync + 'static,
+    {
+        self.0.webtransport_config.http_handler = Some(SharedHttpHandler::new(handler));
+        self
+    }
+
+    /// Acts as a CONNECT-UDP proxy, handling requests matching `template` with `handler`.
+    ///
+    /// Connections from clients not supporting WebTransport are kept open for these
+    /// requests, rather than being refused.
+    /// See the [`connect_udp`](crate::connect_udp) module.
+    /// By default, no handler is set and such requests are rejected.
+    pub fn connect_udp_handler<H>(mut self, template: UriTemplate, handler: H) -> Self
+    where
+        H: ConnectUdpHandler + Send + Sync + 'static,
+    {
+        self.0.webtransport_config.connect_udp_handler =
+            Some(SharedConnectUdpHandler::new(template, handler));
+        self
+    }
+
+    /// Accepts WebSocket connections over HTTP/3 (RFC 9220), handling requests with `handler`.
+    ///
+    /// Connections from clients not supporting WebTransport are kept open for these
+    /// requests, rather than being refused.
+    /// See the [`websocket`](crate::websocket) module.
+    /// By default, no handler is set and such requests are rejected.
+    pub fn websocket_handler<H>(mut self, handler: H) -> Self
+    where
+        H: WebSocketHandler + Send + Sync + 'static,
+    {
+        self.0.webtransport_config.websocket_handler = Some(SharedWebSocketHandler::new(handler));
+        self
+    }
+
+    /// Sets the policy session requests must satisfy before being yielded by
+    /// [`Endpoint::accept`](crate::Endpoint::accept).
+    ///
+    /// Requests violating the policy are automatically rejected.
+    /// See [`SessionPolicy`].
+    /// By default, all requests are admitted.
+    pub fn session_policy(mut self, policy: SessionPolicy) -> Self {
+        self.0.webtransport_config.session_policy = policy;
+        self
+    }
+
+    /// Routes session requests for `authority` to `handler`, instead of
+    /// [`Endpoint::accept`](crate::Endpoint::accept).
+    ///
+    /// `authority` is matched case-insensitively against the `:authority` of the request,
+    /// first as is and then without port: `example.com` serves requests to any port, while
+    /// `example.com:4433` only to that one.
+    /// Requests are routed after being admitted by the
+    /// [`session_policy`](Self::session_policy).
+    /// It can be called multiple times to serve several hosts.
+    pub fn virtual_host<H>(mut self, authority: &str, handler: H) -> Self
+    where
+        H: SessionHandler + Send + Sync + 'static,
+    {
+        self.0.webtransport_config.virtual_hosts.insert(
+            authority.to_ascii_lowercase(),
+            SharedSessionHandler::new(handler),
+        );
+        self
+    }
 }
 
 /// Client configuration.
@@ -528,6 +837,13 @@ impl ServerConfigBuilder<states::WantsTransportConfigServer> {
 /// - [`max_idle_timeout`](ClientConfigBuilder::max_idle_timeout)
 /// - [`keep_alive_interval`](ClientConfigBuilder::keep_alive_interval)
 /// - [`dns_resolver`](ClientConfigBuilder::dns_resolver)
+/// - [`qpack_max_table_capacity`](ClientConfigBuilder::qpack_max_table_capacity)
+/// - [`qpack_blocked_streams`](ClientConfigBuilder::qpack_blocked_streams)
+/// - [`session_max_data`](ClientConfigBuilder::session_max_data)
+/// - [`session_max_streams_uni`](ClientConfigBuilder::session_max_streams_uni)
+/// - [`session_max_streams_bidi`](ClientConfigBuilder::session_max_streams_bidi)
+/// - [`session_buffer_streams`](ClientConfigBuilder::session_buffer_streams)
+/// - [`session_buffer_bytes`](ClientConfigBuilder::session_buffer_bytes)
 ///
 /// #### Examples:
 /// ```
@@ -547,6 +863,7 @@ pub struct ClientConfig {
     pub(crate) dual_stack_config: Ipv6DualStackConfig,
     pub(crate) quic_config: QuicClientConfig,
     pub(crate) dns_resolver: Box<dyn DnsResolver + Send + Sync>,
+    pub(crate) webtransport_config: WebTransportConfig,
 }
 
 impl ClientConfig {
@@ -681,6 +998,7 @@ impl ClientConfigBuilder<states::WantsRootStore> {
             tls_config,
             transport_config,
             dns_resolver: Box::<TokioDnsResolver>::default(),
+            webtransport_config: WebTransportConfig::default(),
         })
     }
 
@@ -723,6 +1041,7 @@ impl ClientConfigBuilder<states::WantsRootStore> {
             tls_config,
             transport_config,
             dns_resolver: Box::<TokioDnsResolver>::default(),
+            webtransport_config: WebTransportConfig::default(),
         })
     }
 
@@ -769,6 +1088,7 @@ impl ClientConfigBuilder<states::WantsTransportConfigClient> {
             dual_stack_config: self.0.dual_stack_config,
             quic_config,
             dns_resolver: self.0.dns_resolver,
+            webtransport_config: self.0.webtransport_config,
         }
     }
 
@@ -818,6 +1138,81 @@ impl ClientConfigBuilder<states::WantsTransportConfigClient> {
         self.0.tls_config.key_log = Arc::new(rustls::KeyLogFile::new());
         self
     }
+
+    /// Maximum size (in bytes) of the QPACK dynamic table used to compress HTTP3 headers.
+    ///
+    /// It is advertised to the peer, and it also bounds the table used to compress
+    /// local headers. `0` disables the dynamic table.
+    /// Default value is `4096`.
+    pub fn qpack_max_table_capacity(mut self, value: u32) -> Self {
+        self.0.webtransport_config.qpack_max_table_capacity = value;
+        self
+    }
+
+    /// Maximum number of streams the peer can block waiting for QPACK dynamic table updates.
+    ///
+    /// Default value is `16`.
+    pub fn qpack_blocked_streams(mut self, value: u32) -> Self {
+        self.0.webtransport_config.qpack_blocked_streams = value;
+        self
+    }
+
+    /// Maximum amount of data (in bytes) the peer can send on all the streams of a
+    /// WebTransport session and not yet read.
+    ///
+    /// The limit is advertised to the peer and it is replenished as data is read.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_data(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_data = Some(value);
+        self
+    }
+
+    /// Maximum number of unidirectional streams the peer can open in a WebTransport
+    /// session and not yet accepted.
+    ///
+    /// The limit is advertised to the peer and it is replenished as streams are accepted.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_streams_uni(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_streams_uni = Some(value);
+        self
+    }
+
+    /// Maximum number of bidirectional streams the peer can open in a WebTransport
+    /// session and not yet accepted.
+    ///
+    /// The limit is advertised to the peer and it is replenished as streams are accepted.
+    /// The peer exceeding the limit terminates the session.
+    /// By default, there is no limit.
+    pub fn session_max_streams_bidi(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_max_streams_bidi = Some(value);
+        self
+    }
+
+    /// Maximum number of streams the peer can open in a WebTransport session before
+    /// the session is established.
+    ///
+    /// Streams might arrive before the session request is processed, or while it is
+    /// still waiting to be accepted: they are held until then. Further streams beyond
+    /// this limit are rejected.
+    /// Default value is `16`.
+    pub fn session_buffer_streams(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_buffer_streams = value;
+        self
+    }
+
+    /// Maximum amount of datagram payload (in bytes) the peer can send in a WebTransport
+    /// session before the session is established.
+    ///
+    /// Datagrams might arrive before the session request is processed, or while it is
+    /// still waiting to be accepted: they are held until then. Further datagrams beyond
+    /// this limit are discarded.
+    /// Default value is `65536`.
+    pub fn session_buffer_bytes(mut self, value: u32) -> Self {
+        self.0.webtransport_config.session_buffer_bytes = value;
+        self
+    }
 }
 
 impl Default for ServerConfigBuilder<states::WantsBindAddress> {
@@ -858,6 +1253,9 @@ pub mod states {
         pub(super) tls_config: TlsServerConfig,
         pub(super) transport_config: quinn::TransportConfig,
         pub(super) migration: bool,
+        pub(super) max_connections: Option<u32>,
+        pub(super) use_retry: bool,
+        pub(super) webtransport_config: WebTransportConfig,
     }
 
     /// Config builder state where transport properties can be set.
@@ -867,6 +1265,7 @@ pub mod states {
         pub(super) tls_config: TlsClientConfig,
         pub(super) transport_config: quinn::TransportConfig,
         pub(super) dns_resolver: Box<dyn DnsResolver + Send + Sync>,
+        pub(super) webtransport_config: WebTransportConfig,
     }
 }
 
@@ -892,6 +1291,48 @@ mod dangerous_configuration {
     }
 }
 
+/// HTTP3 and WebTransport parameters applied to each connection.
+#[derive(Debug, Clone)]
+pub(crate) struct WebTransportConfig {
+    pub(crate) max_sessions: u32,
+    pub(crate) qpack_max_table_capacity: u32,
+    pub(crate) qpack_blocked_streams: u32,
+    pub(crate) session_max_data: Option<u32>,
+    pub(crate) session_max_streams_uni: Option<u32>,
+    pub(crate) session_max_streams_bidi: Option<u32>,
+    pub(crate) session_buffer_streams: u32,
+    pub(crate) session_buffer_bytes: u32,
+    pub(crate) drafts: Vec<WebTransportDraft>,
+    pub(crate) http_handler: Option<SharedHttpHandler>,
+    pub(crate) connect_udp_handler: Option<SharedConnectUdpHandler>,
+    pub(crate) websocket_handler: Option<SharedWebSocketHandler>,
+    pub(crate) session_policy: SessionPolicy,
+    pub(crate) virtual_hosts: HashMap<String, SharedSessionHandler>,
+    pub(crate) connection_limits: ConnectionLimits,
+}
+
+impl Default for WebTransportConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 1,
+            qpack_max_table_capacity: 4096,
+            qpack_blocked_streams: 16,
+            session_max_data: None,
+            session_max_streams_uni: None,
+            session_max_streams_bidi: None,
+            session_buffer_streams: 16,
+            session_buffer_bytes: 65536,
+            drafts: WebTransportDraft::ALL.to_vec(),
+            http_handler: None,
+            connect_udp_handler: None,
+            websocket_handler: None,
+            session_policy: SessionPolicy::default(),
+            virtual_hosts: HashMap::new(),
+            connection_limits: ConnectionLimits::default(),
+        }
+    }
+}
+
 /// A type alias representing a dynamic future for asynchronous DNS resolution.
 ///
 /// `DynFutureResolver` is a trait object type alias that represents a future yielding
@@ -0,0 +1,543 @@
+//! Proxying UDP in HTTP/3 (CONNECT-UDP, RFC 9298).
+//!
+//! A server endpoint acts as a proxy once a [`ConnectUdpHandler`] is set with
+//! [`ServerConfigBuilder::connect_udp_handler`](crate::config::ServerConfigBuilder::connect_udp_handler).
+//! Clients open a [`UdpTunnel`] through the proxy with
+//! [`Endpoint::connect_udp`](crate::Endpoint::connect_udp).
+//!
+//! UDP payloads are exchanged as HTTP datagrams, prefixed with the context ID.
+//!
+//! The proxy relays to whatever target is requested: handlers must vet it (see
+//! [`UdpTunnelRequest::proxy`]).
+//!
+//! #### Examples:
+//! ```no_run
+//! use wtransport::connect_udp::UdpTunnelRequest;
+//! use wtransport::connect_udp::UriTemplate;
+//! use wtransport::http::Response;
+//! use wtransport::Certificate;
+//! use wtransport::ServerConfig;
+//!
+//! let template = UriTemplate::with_default_path("proxy.example.org").unwrap();
+//!
+//! let config = ServerConfig::builder()
+//!     .with_bind_default(4433)
+//!     .with_certificate(Certificate::self_signed(["proxy.example.org"]))
+//!     .connect_udp_handler(template, |request: UdpTunnelRequest| async move {
+//!         if request.target_host() == "game.example.org" {
+//!             let _ = request.proxy().await;
+//!         } else {
+//!             let _ = request.reject(Response::new(403)).await;
+//!         }
+//!     })
+//!     .build();
+//! ```
+
+use crate::datagram::Datagram;
+use crate::driver::send_datagram;
+use crate::driver::streams::bilocal::StreamBiLocalQuic;
+use crate::driver::streams::qpack::QPackCodec;
+use crate::driver::streams::QuicRecvStream;
+use crate::driver::streams::QuicSendStream;
+use crate::driver::utils::shared_result;
+use crate::driver::utils::SharedResultGet;
+use crate::driver::utils::SharedResultSet;
+use crate::driver::Driver;
+use crate::endpoint::Headers;
+use crate::endpoint::StatusCode;
+use crate::error::ConnectingError;
+use crate::error::ConnectionError;
+use crate::error::HttpError;
+use crate::error::SendDatagramError;
+use crate::error::UdpTunnelError;
+use crate::http::write_frame;
+use crate::http::Body;
+use crate::http::Response;
+use bytes::Bytes;
+use std::fmt::Debug;
+use std::future::Future;
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use wtransport_proto::connect_udp::ConnectUdpRequest;
+use wtransport_proto::connect_udp::UdpPayload;
+use wtransport_proto::connect_udp::CONTEXT_ID_UDP;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::ids::SessionId;
+
+#[doc(inline)]
+pub use wtransport_proto::connect_udp::InvalidTemplate;
+
+#[doc(inline)]
+pub use wtransport_proto::connect_udp::UriTemplate;
+
+/// Maximum size of a UDP payload.
+const MAX_UDP_PAYLOAD_SIZE: usize = 65527;
+
+/// A type alias representing the dynamic future returned by a [`ConnectUdpHandler`].
+pub type DynFutureConnectUdpHandler = dyn Future<Output = ()> + Send;
+
+/// A trait for asynchronously handling CONNECT-UDP requests.
+///
+/// It is implemented for closures taking a [`UdpTunnelRequest`] and returning a future.
+pub trait ConnectUdpHandler {
+    /// Handles a request, accepting or rejecting the tunnel.
+    ///
+    /// The returned future is spawned on the *Tokio* runtime.
+    fn handle(&self, request: UdpTunnelRequest) -> Pin<Box<DynFutureConnectUdpHandler>>;
+}
+
+impl<F, R> ConnectUdpHandler for F
+where
+    F: Fn(UdpTunnelRequest) -> R,
+    R: Future<Output = ()> + Send + 'static,
+{
+    fn handle(&self, request: UdpTunnelRequest) -> Pin<Box<DynFutureConnectUdpHandler>> {
+        Box::pin(self(request))
+    }
+}
+
+/// A [`ConnectUdpHandler`] shared among connections, along with its URI template.
+#[derive(Clone)]
+pub(crate) struct SharedConnectUdpHandler {
+    template: Arc<UriTemplate>,
+    handler: Arc<dyn ConnectUdpHandler + Send + Sync>,
+}
+
+impl SharedConnectUdpHandler {
+    pub(crate) fn new<H>(template: UriTemplate, handler: H) -> Self
+    where
+        H: ConnectUdpHandler + Send + Sync + 'static,
+    {
+        Self {
+            template: Arc::new(template),
+            handler: Arc::new(handler),
+        }
+    }
+
+    pub(crate) fn template(&self) -> &UriTemplate {
+        &self.template
+    }
+
+    pub(crate) fn handle(&self, request: UdpTunnelRequest) -> Pin<Box<DynFutureConnectUdpHandler>> {
+        self.handler.handle(request)
+    }
+}
+
+impl Debug for SharedConnectUdpHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectUdpHandler")
+            .field("template", &self.template.as_str())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A CONNECT-UDP request received by a server endpoint.
+///
+/// Use [`accept`](Self::accept) or [`reject`](Self::reject), or simply
+/// [`proxy`](Self::proxy) to relay UDP to the requested target. Dropping the request
+/// cancels it.
+pub struct UdpTunnelRequest {
+    quic_connection: quinn::Connection,
+    qpack: QPackCodec,
+    headers: Headers,
+    target_host: String,
+    target_port: u16,
+    stream: Option<(QuicSendStream, QuicRecvStream)>,
+    datagrams: Option<mpsc::Receiver<Datagram>>,
+}
+
+impl UdpTunnelRequest {
+    pub(crate) fn new(
+        quic_connection: quinn::Connection,
+        qpack: QPackCodec,
+        stream: (QuicSendStream, QuicRecvStream),
+        headers: Headers,
+        target: (String, u16),
+        datagrams: mpsc::Receiver<Datagram>,
+    ) -> Self {
+        Self {
+            quic_connection,
+            qpack,
+            headers,
+            target_host: target.0,
+            target_port: target.1,
+            stream: Some(stream),
+            datagrams: Some(datagrams),
+        }
+    }
+
+    /// Checks `headers` carry the pseudo-header fields required by a request.
+    pub(crate) fn validate(headers: &Headers) -> bool {
+        headers.get(":scheme") == Some("https")
+            && headers.get(":authority").is_some()
+            && headers.get(":path").is_some()
+    }
+
+    /// Returns the host the client wants to reach.
+    pub fn target_host(&self) -> &str {
+        &self.target_host
+    }
+
+    /// Returns the UDP port the client wants to reach.
+    pub fn target_port(&self) -> u16 {
+        self.target_port
+    }
+
+    /// Returns the `:authority` field of the request.
+    pub fn authority(&self) -> &str {
+        self.headers.get(":authority").expect("Validated request")
+    }
+
+    /// Returns all header fields associated with the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the client's UDP address.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.quic_connection.remote_address()
+    }
+
+    /// Accepts the request, establishing the tunnel.
+    pub async fn accept(mut self) -> Result<UdpTunnel, HttpError> {
+        let (mut send_stream, recv_stream) = self.stream.take().expect("Request not handled yet");
+        let stream_id = send_stream.id();
+
+        let headers = [(":status", "200"), ("capsule-protocol", "?1")]
+            .into_iter()
+            .collect();
+
+        write_frame(
+            &mut send_stream,
+            self.qpack.encode_headers(stream_id, &headers),
+        )
+        .await?;
+
+        let session_id =
+            SessionId::try_from_session_stream(stream_id).expect("Request stream is valid");
+
+        Ok(UdpTunnel::new(
+            self.quic_connection.clone(),
+            None,
+            session_id,
+            send_stream,
+            Body::new(
+                self.quic_connection.clone(),
+                self.qpack.clone(),
+                recv_stream,
+            ),
+            self.datagrams.take().expect("Request not handled yet"),
+        ))
+    }
+
+    /// Rejects the request with `response`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response status is successful (`2xx`).
+    pub async fn reject(mut self, response: Response) -> Result<(), HttpError> {
+        assert!(
+            !(200..=299).contains(&response.status()),
+            "Rejection response cannot be successful"
+        );
+
+        let (mut send_stream, mut recv_stream) =
+            self.stream.take().expect("Request not handled yet");
+        let _ = recv_stream.stop(ErrorCode::NoError.to_code());
+
+        response.send(&mut send_stream, &self.qpack).await
+    }
+
+    /// Relays UDP between the client and the requested target.
+    ///
+    /// The target host is resolved and a UDP socket is connected to it; in case of
+    /// failure, the request is rejected with a `502` response. Otherwise, it returns
+    /// once the tunnel is closed.
+    ///
+    /// # Security
+    ///
+    /// Any target is relayed to, including loopback, link-local and private addresses
+    /// reachable from the proxy. The handler is responsible for vetting
+    /// [`target_host`](Self::target_host) and [`target_port`](Self::target_port) (and
+    /// possibly the address they resolve to) before calling this method, and for
+    /// [rejecting](Self::reject) the request otherwise.
+    pub async fn proxy(self) -> io::Result<()> {
+        let socket = match self.bind_target().await {
+            Ok(socket) => socket,
+            Err(error) => {
+                debug!("Cannot reach CONNECT-UDP target: {}", error);
+                let _ = self.reject(Response::new(502)).await;
+                return Err(error);
+            }
+        };
+
+        let tunnel = self
+            .accept()
+            .await
+            .map_err(|error| io::Error::new(io::ErrorKind::ConnectionAborted, error))?;
+
+        tunnel.relay(&socket).await
+    }
+
+    async fn bind_target(&self) -> io::Result<UdpSocket> {
+        let target = tokio::net::lookup_host((self.target_host.as_str(), self.target_port))
+            .await?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "target host not found"))?;
+
+        let bind_address: SocketAddr = if target.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = UdpSocket::bind(bind_address).await?;
+        socket.connect(target).await?;
+
+        Ok(socket)
+    }
+}
+
+impl Drop for UdpTunnelRequest {
+    fn drop(&mut self) {
+        if let Some((send_stream, mut recv_stream)) = self.stream.take() {
+            let _ = recv_stream.stop(ErrorCode::RequestCancelled.to_code());
+            send_stream.reset(ErrorCode::RequestCancelled.to_code());
+        }
+    }
+}
+
+/// A CONNECT-UDP tunnel.
+///
+/// UDP payloads are exchanged with [`send`](Self::send) and [`receive`](Self::receive).
+/// Dropping the tunnel closes it.
+pub struct UdpTunnel {
+    quic_connection: quinn::Connection,
+    _driver: Option<Arc<Driver>>,
+    session_id: SessionId,
+    datagrams: Mutex<mpsc::Receiver<Datagram>>,
+    closed: SharedResultGet<()>,
+    _send_stream: QuicSendStream,
+    stream_watcher: JoinHandle<()>,
+}
+
+impl UdpTunnel {
+    fn new(
+        quic_connection: quinn::Connection,
+        driver: Option<Arc<Driver>>,
+        session_id: SessionId,
+        send_stream: QuicSendStream,
+        body: Body,
+        datagrams: mpsc::Receiver<Datagram>,
+    ) -> Self {
+        let (closed_set, closed) = shared_result();
+
+        Self {
+            quic_connection,
+            _driver: driver,
+            session_id,
+            datagrams: Mutex::new(datagrams),
+            closed,
+            _send_stream: send_stream,
+            stream_watcher: tokio::spawn(Self::watch_stream(body, closed_set)),
+        }
+    }
+
+    /// Requests a tunnel through the proxy connected with `quic_connection`.
+    pub(crate) async fn open(
+        quic_connection: quinn::Connection,
+        driver: Arc<Driver>,
+        request: ConnectUdpRequest,
+    ) -> Result<Self, ConnectingError> {
+        let (mut send_stream, recv_stream) = StreamBiLocalQuic::open_bi(&quic_connection)
+            .await
+            .ok_or_else(|| ConnectingError::with_no_connection(&quic_connection))?
+            .upgrade()
+            .into_stream();
+
+        let stream_id = send_stream.id();
+        let session_id =
+            SessionId::try_from_session_stream(stream_id).expect("Request stream is valid");
+
+        let datagrams = driver
+            .open_tunnel(session_id)
+            .await
+            .map_err(|driver_error| {
+                ConnectingError::ConnectionError(ConnectionError::with_driver_error(
+                    driver_error,
+                    &quic_connection,
+                ))
+            })?;
+
+        let to_connecting_error = |error| match error {
+            HttpError::NotConnected => ConnectingError::with_no_connection(&quic_connection),
+            HttpError::LocalH3Error(error) => {
+                ConnectingError::ConnectionError(ConnectionError::LocalH3Error(error))
+            }
+            HttpError::Connecting(error) => error,
+            HttpError::Reset(_) | HttpError::Stopped(_) | HttpError::QuicProto => {
+                ConnectingError::rejected_without_response()
+            }
+        };
+
+        let frame = driver.encode_headers(stream_id, request.headers());
+        write_frame(&mut send_stream, frame)
+            .await
+            .map_err(to_connecting_error)?;
+
+        let mut body = Body::new(quic_connection.clone(), driver.qpack().clone(), recv_stream);
+
+        let (status, headers) = body
+            .read_response_headers()
+            .await
+            .map_err(to_connecting_error)?;
+
+        if !(200..=299).contains(&status) {
+            return Err(ConnectingError::SessionRejected {
+                status: StatusCode::try_from(status).ok(),
+                headers,
+            });
+        }
+
+        Ok(Self::new(
+            quic_connection,
+            Some(driver),
+            session_id,
+            send_stream,
+            body,
+            datagrams,
+        ))
+    }
+
+    /// Sends a UDP payload through the tunnel.
+    pub fn send<D>(&self, payload: D) -> Result<(), SendDatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        if self.closed.is_set() {
+            return Err(SendDatagramError::NotConnected);
+        }
+
+        let payload = UdpPayload::new(payload.as_ref());
+
+        let mut buffer = vec![0; payload.write_size()];
+        payload.write(&mut buffer).expect("Preallocated capacity");
+
+        send_datagram(&self.quic_connection, self.session_id, &buffer)
+    }
+
+    /// Receives a UDP payload from the tunnel.
+    ///
+    /// Datagrams with an unknown context ID are discarded.
+    pub async fn receive(&self) -> Result<Bytes, UdpTunnelError> {
+        let mut datagrams = self.datagrams.lock().await;
+
+        loop {
+            let datagram = tokio::select! {
+                datagram = datagrams.recv() => datagram,
+                _ = self.closed.result() => None,
+            };
+
+            let Some(datagram) = datagram else {
+                return Err(self.close_error());
+            };
+
+            let payload = datagram.payload();
+
+            let offset = match UdpPayload::read(&payload) {
+                Ok(udp_payload) if udp_payload.context_id() == CONTEXT_ID_UDP => {
+                    payload.len() - udp_payload.payload().len()
+                }
+                _ => {
+                    debug!("Discarding datagram with unknown context ID");
+                    continue;
+                }
+            };
+
+            return Ok(payload.slice(offset..));
+        }
+    }
+
+    /// Relays UDP payloads between the tunnel and a connected `socket`.
+    ///
+    /// It returns once the tunnel is closed by the peer.
+    pub async fn relay(&self, socket: &UdpSocket) -> io::Result<()> {
+        let mut buffer = vec![0; MAX_UDP_PAYLOAD_SIZE];
+
+        loop {
+            tokio::select! {
+                payload = self.receive() => match payload {
+                    Ok(payload) => {
+                        if let Err(error) = socket.send(&payload).await {
+                            debug!("Cannot relay UDP payload: {}", error);
+                        }
+                    }
+                    Err(UdpTunnelError::Closed) => return Ok(()),
+                    Err(error) => {
+                        return Err(io::Error::new(io::ErrorKind::ConnectionAborted, error));
+                    }
+                },
+                read = socket.recv(&mut buffer) => match read {
+                    Ok(len) => {
+                        if let Err(error) = self.send(&buffer[..len]) {
+                            debug!("Cannot relay UDP payload: {}", error);
+                        }
+                    }
+                    // An ICMP error might be reported on a previous send
+                    Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => {}
+                    Err(error) => return Err(error),
+                },
+            }
+        }
+    }
+
+    /// Returns the maximum UDP payload size that can be sent through the tunnel.
+    ///
+    /// Returns `None` if datagrams are unsupported by the peer or disabled locally.
+    pub fn max_payload_size(&self) -> Option<usize> {
+        self.quic_connection
+            .max_datagram_size()
+            .map(|quic_max_size| {
+                quic_max_size
+                    - Datagram::header_size(self.session_id)
+                    - UdpPayload::new(&[]).write_size()
+            })
+    }
+
+    /// Awaits the tunnel is closed.
+    pub async fn closed(&self) {
+        let _ = self.closed.result().await;
+    }
+
+    fn close_error(&self) -> UdpTunnelError {
+        if self.quic_connection.close_reason().is_some() {
+            UdpTunnelError::NotConnected
+        } else {
+            UdpTunnelError::Closed
+        }
+    }
+
+    /// Reads the request stream until its end, discarding capsules.
+    async fn watch_stream(mut body: Body, closed: SharedResultSet<()>) {
+        let mut buffer = [0; 1024];
+
+        while let Ok(Some(_)) = body.read(&mut buffer).await {}
+
+        closed.set(());
+    }
+}
+
+impl Drop for UdpTunnel {
+    fn drop(&mut self) {
+        self.stream_watcher.abort();
+    }
+}
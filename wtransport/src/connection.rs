@@ -93,39 +93,68 @@
 //! ```
 
 use crate::datagram::Datagram;
+use crate::driver::session::SessionTermination;
 use crate::driver::utils::varint_w2q;
 use crate::driver::Driver;
+use crate::driver::SessionQueues;
+use crate::endpoint::Headers;
 use crate::error::ConnectionError;
+use crate::error::H3Error;
 use crate::error::SendDatagramError;
+use crate::error::SessionCloseReason;
 use crate::stream::OpeningBiStream;
 use crate::stream::OpeningUniStream;
 use crate::stream::RecvStream;
 use crate::stream::SendStream;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::session::SessionResponse;
 use wtransport_proto::varint::VarInt;
 
+#[doc(inline)]
+pub use wtransport_proto::settings::SettingId;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::Settings;
+
+#[doc(inline)]
+pub use wtransport_proto::session::WebTransportDraft;
+
 /// A WebTransport session connection.
 ///
 /// For more details, see the [module documentation](crate::connection).
 #[derive(Debug)]
 pub struct Connection {
     quic_connection: quinn::Connection,
-    driver: Driver,
+    driver: Arc<Driver>,
+    session: SessionQueues,
     session_id: SessionId,
+    draft: WebTransportDraft,
+    protocol: Option<String>,
+    response: SessionResponse,
 }
 
 impl Connection {
     pub(crate) fn new(
         quic_connection: quinn::Connection,
-        driver: Driver,
-        session_id: SessionId,
+        driver: Arc<Driver>,
+        session: SessionQueues,
+        draft: WebTransportDraft,
+        response: SessionResponse,
     ) -> Self {
+        let session_id = session.session_id();
+        let protocol = response.protocol();
+
         Self {
             quic_connection,
             driver,
+            session,
             session_id,
+            draft,
+            protocol,
+            response,
         }
     }
 
@@ -142,12 +171,11 @@ impl Connection {
     pub async fn accept_uni(&self) -> Result<RecvStream, ConnectionError> {
         let stream = self
             .driver
-            .accept_uni(self.session_id)
+            .accept_uni(&self.session)
             .await
             .map_err(|driver_error| {
                 ConnectionError::with_driver_error(driver_error, &self.quic_connection)
-            })?
-            .into_stream();
+            })?;
 
         Ok(RecvStream::new(stream))
     }
@@ -166,14 +194,20 @@ impl Connection {
     pub async fn accept_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
         let stream = self
             .driver
-            .accept_bi(self.session_id)
+            .accept_bi(&self.session)
             .await
             .map_err(|driver_error| {
                 ConnectionError::with_driver_error(driver_error, &self.quic_connection)
-            })?
-            .into_stream();
+            })?;
 
-        Ok((SendStream::new(stream.0), RecvStream::new(stream.1)))
+        Ok((
+            SendStream::new(
+                stream.0,
+                self.session.flow_control().clone(),
+                self.session.streams(),
+            ),
+            RecvStream::new(stream.1),
+        ))
     }
 
     /// Asynchronously opens a new unidirectional stream.
@@ -204,7 +238,7 @@ impl Connection {
     /// ```
     pub async fn open_uni(&self) -> Result<OpeningUniStream, ConnectionError> {
         self.driver
-            .open_uni(self.session_id)
+            .open_uni(&self.session)
             .await
             .map_err(|driver_error| {
                 ConnectionError::with_driver_error(driver_error, &self.quic_connection)
@@ -239,7 +273,7 @@ impl Connection {
     /// ```
     pub async fn open_bi(&self) -> Result<OpeningBiStream, ConnectionError> {
         self.driver
-            .open_bi(self.session_id)
+            .open_bi(&self.session)
             .await
             .map_err(|driver_error| {
                 ConnectionError::with_driver_error(driver_error, &self.quic_connection)
@@ -264,7 +298,7 @@ impl Connection {
     /// ```
     pub async fn receive_datagram(&self) -> Result<Datagram, ConnectionError> {
         self.driver
-            .receive_datagram(self.session_id)
+            .receive_datagram(&self.session)
             .await
             .map_err(|driver_error| {
                 ConnectionError::with_driver_error(driver_error, &self.quic_connection)
@@ -294,14 +328,82 @@ impl Connection {
         self.driver.send_datagram(self.session_id, payload.as_ref())
     }
 
-    /// Closes the connection immediately.
+    /// Closes the underlying QUIC connection immediately.
+    ///
+    /// **Note**: this terminates all WebTransport sessions sharing the same QUIC connection.
+    /// See [`close_session`](Self::close_session) for closing this session only.
     pub fn close(&self, error_code: VarInt, reason: &[u8]) {
         self.quic_connection.close(varint_w2q(error_code), reason);
     }
 
-    /// Waits for the connection to be closed for any reason.
-    pub async fn closed(&self) {
-        let _ = self.quic_connection.closed().await;
+    /// Closes this WebTransport session.
+    ///
+    /// A `CLOSE_WEBTRANSPORT_SESSION` capsule carrying `error_code` and `reason` is sent
+    /// to the peer, then the session stream is finished.
+    /// The `reason` is truncated to 1024 bytes (on a character boundary).
+    ///
+    /// The underlying QUIC connection, and other sessions on it, are not affected.
+    ///
+    /// Calling this method more than once has no effect.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wtransport::Connection;
+    /// # async fn run(connection: Connection) {
+    /// connection.close_session(42, "game over");
+    /// let reason = connection.closed().await;
+    /// # }
+    /// ```
+    pub fn close_session(&self, error_code: u32, reason: &str) {
+        self.driver.close_session(&self.session, error_code, reason);
+    }
+
+    /// Asks the peer to gracefully wind down this WebTransport session.
+    ///
+    /// A `DRAIN_WEBTRANSPORT_SESSION` capsule is sent to the peer. The session is not
+    /// closed: streams and datagrams can still be exchanged until either side closes it.
+    pub fn drain(&self) {
+        self.driver.drain_session(&self.session);
+    }
+
+    /// Waits for the peer to ask to drain this session.
+    ///
+    /// It completes when a `DRAIN_WEBTRANSPORT_SESSION` capsule is received, or when the
+    /// peer sends an HTTP3 GOAWAY on the underlying connection.
+    /// It also completes if the session is closed in the meantime.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wtransport::Connection;
+    /// # async fn run(connection: Connection) {
+    /// connection.draining().await;
+    /// // Finish pending work...
+    /// connection.close_session(0, "");
+    /// # }
+    /// ```
+    pub async fn draining(&self) {
+        self.driver.session_draining(&self.session).await;
+    }
+
+    /// Waits for the session to be closed for any reason.
+    ///
+    /// Returns the reason the session terminated with.
+    pub async fn closed(&self) -> SessionCloseReason {
+        match self.driver.session_closed(&self.session).await {
+            Ok(SessionTermination::Closed(close)) => SessionCloseReason::Closed(close),
+            Ok(SessionTermination::LocallyClosed) => SessionCloseReason::LocallyClosed,
+            Ok(SessionTermination::Reset(error_code)) => {
+                SessionCloseReason::StreamReset(error_code)
+            }
+            Ok(SessionTermination::Aborted(error_code)) => {
+                SessionCloseReason::LocalH3Error(H3Error::new(error_code))
+            }
+            Err(driver_error) => SessionCloseReason::ConnectionError(
+                ConnectionError::with_driver_error(driver_error, &self.quic_connection),
+            ),
+        }
     }
 
     /// Returns the WebTransport session identifier.
@@ -310,6 +412,37 @@ impl Connection {
         self.session_id
     }
 
+    /// Returns the HTTP3 SETTINGS advertised by the peer.
+    ///
+    /// They expose peer's limits, e.g., [`SettingId::WebTransportMaxSessions`]
+    /// or [`SettingId::MaxFieldSectionSize`].
+    pub fn settings(&self) -> &Settings {
+        self.driver
+            .remote_settings()
+            .expect("Settings are received before session establishment")
+    }
+
+    /// Returns the draft of WebTransport over HTTP/3 negotiated for the session.
+    pub fn draft(&self) -> WebTransportDraft {
+        self.draft
+    }
+
+    /// Returns the application protocol selected by the server for the session, if any.
+    ///
+    /// See [`ConnectRequestBuilder::add_protocol`](crate::endpoint::ConnectRequestBuilder::add_protocol)
+    /// and [`SessionRequest::accept_with_protocol`](crate::endpoint::SessionRequest::accept_with_protocol).
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Returns the header fields of the response which established the session.
+    ///
+    /// On the client side, they are the headers sent by the server; on the server side,
+    /// the ones sent to the client.
+    pub fn response_headers(&self) -> &Headers {
+        self.response.headers()
+    }
+
     /// Returns the peer's UDP address.
     ///
     /// **Note**: as QUIC supports migration, remote address may change
@@ -350,4 +483,14 @@ impl Connection {
     pub fn rtt(&self) -> Duration {
         self.quic_connection.rtt()
     }
+
+    #[inline(always)]
+    pub(crate) fn quic_connection(&self) -> &quinn::Connection {
+        &self.quic_connection
+    }
+
+    #[inline(always)]
+    pub(crate) fn driver(&self) -> &Arc<Driver> {
+        &self.driver
+    }
 }
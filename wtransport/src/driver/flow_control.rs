@@ -0,0 +1,624 @@
+use crate::config::WebTransportConfig;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use tokio::sync::Notify;
+use wtransport_proto::capsule::capsules::StreamDirection;
+use wtransport_proto::capsule::capsules::WtDataBlocked;
+use wtransport_proto::capsule::capsules::WtMaxData;
+use wtransport_proto::capsule::capsules::WtMaxStreams;
+use wtransport_proto::capsule::capsules::WtStreamsBlocked;
+use wtransport_proto::capsule::CapsuleOwned;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::settings::SettingId;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::varint::VarInt;
+
+/// The session is terminated and no more credit can be granted.
+#[derive(Debug)]
+pub struct FlowControlClosed;
+
+/// Session-level flow control (`WT_MAX_DATA` and `WT_MAX_STREAMS`).
+///
+/// Limits granted by the peer bound data and streams sent locally; local limits
+/// bound the peer and they are replenished as data is read and streams are accepted.
+/// A limit is enforced only once it has been advertised.
+///
+/// Capsules to be sent on the session stream are queued and the session stream task is
+/// notified (see [`Self::events`]).
+#[derive(Debug, Clone)]
+pub struct SessionFlowControl(Arc<Shared>);
+
+impl SessionFlowControl {
+    pub fn new() -> Self {
+        Self(Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            events: Notify::new(),
+        }))
+    }
+
+    /// Sets the limits the peer is subject to.
+    pub fn set_local_limits(&self, config: &WebTransportConfig) {
+        let mut state = self.lock();
+        state.recv_data = RecvCredit::new(config.session_max_data);
+        state.recv_streams_uni = RecvCredit::new(config.session_max_streams_uni);
+        state.recv_streams_bi = RecvCredit::new(config.session_max_streams_bidi);
+    }
+
+    /// Sets the initial limits advertised by the peer in its SETTINGS.
+    pub fn set_remote_limits(&self, settings: &Settings) {
+        let mut state = self.lock();
+
+        if let Some(max) = settings.get(SettingId::WebTransportInitialMaxData) {
+            state.send_data.update_max(max.into_inner());
+        }
+
+        if let Some(max) = settings.get(SettingId::WebTransportInitialMaxStreamsUni) {
+            state.send_streams_uni.update_max(max.into_inner());
+        }
+
+        if let Some(max) = settings.get(SettingId::WebTransportInitialMaxStreamsBidi) {
+            state.send_streams_bi.update_max(max.into_inner());
+        }
+    }
+
+    /// Handles a `WT_MAX_DATA` capsule received from the peer.
+    pub fn on_max_data(&self, capsule: WtMaxData) {
+        self.lock()
+            .send_data
+            .update_max(capsule.max_data().into_inner());
+    }
+
+    /// Handles a `WT_MAX_STREAMS` capsule received from the peer.
+    pub fn on_max_streams(&self, capsule: WtMaxStreams) {
+        self.lock()
+            .send_streams(capsule.direction())
+            .update_max(capsule.max_streams().into_inner());
+    }
+
+    /// Acquires the credit to open a stream in direction `direction`.
+    ///
+    /// It sends `WT_STREAMS_BLOCKED` in case the limit is reached.
+    pub fn poll_open_stream(
+        &self,
+        cx: &mut Context<'_>,
+        direction: StreamDirection,
+    ) -> Poll<Result<(), FlowControlClosed>> {
+        let mut state = self.lock();
+
+        if state.closed {
+            return Poll::Ready(Err(FlowControlClosed));
+        }
+
+        match state.send_streams(direction).poll_acquire(cx, 1) {
+            Poll::Ready(_) => Poll::Ready(Ok(())),
+            Poll::Pending => {
+                if let Some(max) = state.send_streams(direction).blocked() {
+                    let capsule = WtStreamsBlocked::new(direction, varint(max));
+                    self.queue_capsule(&mut state, capsule.generate_capsule());
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Awaits the credit to open a stream in direction `direction`.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    pub async fn open_stream(&self, direction: StreamDirection) -> Result<(), FlowControlClosed> {
+        std::future::poll_fn(|cx| self.poll_open_stream(cx, direction)).await
+    }
+
+    /// Acquires the credit to send up to `max` bytes.
+    ///
+    /// On success, returns the number of bytes (at least one) which can be sent.
+    /// It sends `WT_DATA_BLOCKED` in case the limit is reached.
+    pub fn poll_reserve_data(
+        &self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<Result<usize, FlowControlClosed>> {
+        let mut state = self.lock();
+
+        if state.closed {
+            return Poll::Ready(Err(FlowControlClosed));
+        }
+
+        match state.send_data.poll_acquire(cx, max as u64) {
+            Poll::Ready(reserved) => Poll::Ready(Ok(reserved as usize)),
+            Poll::Pending => {
+                if let Some(max) = state.send_data.blocked() {
+                    let capsule = WtDataBlocked::new(varint(max));
+                    self.queue_capsule(&mut state, capsule.generate_capsule());
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Gives back credit previously reserved but not used for sending data.
+    pub fn release_data(&self, amount: usize) {
+        if amount > 0 {
+            self.lock().send_data.release(amount as u64);
+        }
+    }
+
+    /// Accounts for a stream opened by the peer.
+    ///
+    /// Returns `false` if the peer exceeded the limit: in that case, the session is aborted.
+    pub fn on_stream_received(&self, direction: StreamDirection) -> bool {
+        let mut state = self.lock();
+
+        if state.recv_streams(direction).on_received(1) {
+            return true;
+        }
+
+        self.violation(&mut state);
+        false
+    }
+
+    /// Accounts for a stream opened by the peer being accepted by the application.
+    ///
+    /// It sends `WT_MAX_STREAMS` in case the limit needs to be raised.
+    pub fn on_stream_accepted(&self, direction: StreamDirection) {
+        let mut state = self.lock();
+
+        if let Some(max) = state.recv_streams(direction).on_released(1) {
+            let capsule = WtMaxStreams::new(direction, varint(max));
+            self.queue_capsule(&mut state, capsule.generate_capsule());
+        }
+    }
+
+    /// Returns `true` if the peer is subject to a limit on data.
+    pub fn limits_data(&self) -> bool {
+        self.lock().recv_data.window.is_some()
+    }
+
+    /// Accounts for `amount` bytes received from the peer, read or not by the application.
+    ///
+    /// Returns `false` if the peer exceeded the limit: in that case, the session is aborted.
+    pub fn on_data_received(&self, amount: usize) -> bool {
+        let mut state = self.lock();
+
+        if state.recv_data.on_received(amount as u64) {
+            return true;
+        }
+
+        self.violation(&mut state);
+        false
+    }
+
+    /// Accounts for `amount` bytes read by the application.
+    ///
+    /// It sends `WT_MAX_DATA` in case the limit needs to be raised.
+    pub fn on_data_read(&self, amount: usize) {
+        if amount == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+
+        if let Some(max) = state.recv_data.on_released(amount as u64) {
+            let capsule = WtMaxData::new(varint(max));
+            self.queue_capsule(&mut state, capsule.generate_capsule());
+        }
+    }
+
+    /// Takes the capsules to be sent on the session stream.
+    pub fn take_capsules(&self) -> Vec<CapsuleOwned> {
+        std::mem::take(&mut self.lock().capsules)
+    }
+
+    /// Returns the error in case the peer violated the limits.
+    pub fn error(&self) -> Option<ErrorCode> {
+        self.lock().error
+    }
+
+    /// Awaits new capsules to be sent or a violation of the limits.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    pub async fn events(&self) {
+        self.0.events.notified().await;
+    }
+
+    /// Marks the session as terminated.
+    ///
+    /// Tasks awaiting credit are woken up and they get [`FlowControlClosed`].
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        state.send_data.wake();
+        state.send_streams_uni.wake();
+        state.send_streams_bi.wake();
+    }
+
+    fn queue_capsule(&self, state: &mut State, capsule: CapsuleOwned) {
+        state.capsules.push(capsule);
+        self.0.events.notify_one();
+    }
+
+    fn violation(&self, state: &mut State) {
+        if state.error.is_none() {
+            state.error = Some(ErrorCode::FlowControl);
+            self.0.events.notify_one();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0.state.lock().expect("Flow control lock poisoned")
+    }
+}
+
+impl Default for SessionFlowControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    events: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    send_data: SendCredit,
+    send_streams_uni: SendCredit,
+    send_streams_bi: SendCredit,
+    recv_data: RecvCredit,
+    recv_streams_uni: RecvCredit,
+    recv_streams_bi: RecvCredit,
+    capsules: Vec<CapsuleOwned>,
+    error: Option<ErrorCode>,
+    closed: bool,
+}
+
+impl State {
+    fn send_streams(&mut self, direction: StreamDirection) -> &mut SendCredit {
+        match direction {
+            StreamDirection::Bi => &mut self.send_streams_bi,
+            StreamDirection::Uni => &mut self.send_streams_uni,
+        }
+    }
+
+    fn recv_streams(&mut self, direction: StreamDirection) -> &mut RecvCredit {
+        match direction {
+            StreamDirection::Bi => &mut self.recv_streams_bi,
+            StreamDirection::Uni => &mut self.recv_streams_uni,
+        }
+    }
+}
+
+/// Credit granted by the peer.
+#[derive(Debug, Default)]
+struct SendCredit {
+    /// Cumulative limit; `None` if the peer has not advertised any.
+    max: Option<u64>,
+    used: u64,
+    /// Limit at which the blocked capsule has been last sent.
+    blocked_at: Option<u64>,
+    wakers: Vec<Waker>,
+}
+
+impl SendCredit {
+    fn poll_acquire(&mut self, cx: &mut Context<'_>, amount: u64) -> Poll<u64> {
+        let available = self
+            .max
+            .map_or(u64::MAX, |max| max.saturating_sub(self.used));
+
+        if available > 0 || amount == 0 {
+            let acquired = available.min(amount);
+            self.used += acquired;
+            return Poll::Ready(acquired);
+        }
+
+        if !self.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            self.wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+
+    fn release(&mut self, amount: u64) {
+        self.used -= amount;
+        self.wake();
+    }
+
+    /// Limits can only increase: smaller values are ignored.
+    fn update_max(&mut self, max: u64) {
+        if self.max.is_some_and(|current| current >= max) {
+            return;
+        }
+
+        self.max = Some(max);
+        self.wake();
+    }
+
+    /// Returns the limit to be reported as blocking, if not already reported.
+    fn blocked(&mut self) -> Option<u64> {
+        let max = self.max?;
+
+        if self.blocked_at == Some(max) {
+            return None;
+        }
+
+        self.blocked_at = Some(max);
+        Some(max)
+    }
+
+    fn wake(&mut self) {
+        for waker in self.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// Credit granted to the peer.
+#[derive(Debug, Default)]
+struct RecvCredit {
+    /// Window size; `None` if the peer is not limited.
+    window: Option<u64>,
+    /// Cumulative limit advertised to the peer.
+    max: u64,
+    /// Amount consumed by the peer.
+    received: u64,
+    /// Amount consumed by the application.
+    released: u64,
+}
+
+impl RecvCredit {
+    fn new(window: Option<u32>) -> Self {
+        let window = window.map(u64::from);
+
+        Self {
+            window,
+            max: window.unwrap_or(0),
+            received: 0,
+            released: 0,
+        }
+    }
+
+    /// Returns `false` if the peer exceeded the limit.
+    fn on_received(&mut self, amount: u64) -> bool {
+        self.received += amount;
+        self.window.is_none() || self.received <= self.max
+    }
+
+    /// Returns the new limit to be advertised, if any.
+    ///
+    /// The limit is raised once less than half of the window is left.
+    fn on_released(&mut self, amount: u64) -> Option<u64> {
+        let window = self.window?;
+        self.released += amount;
+
+        if self.max.saturating_sub(self.released) * 2 >= window {
+            return None;
+        }
+
+        let max = (self.released + window).min(VarInt::MAX.into_inner());
+
+        if max == self.max {
+            return None;
+        }
+
+        self.max = max;
+        Some(max)
+    }
+}
+
+fn varint(value: u64) -> VarInt {
+    VarInt::try_from_u64(value).expect("Limits are within varint bounds")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn config(max_data: u32, max_streams: u32) -> WebTransportConfig {
+        WebTransportConfig {
+            session_max_data: Some(max_data),
+            session_max_streams_uni: Some(max_streams),
+            session_max_streams_bidi: Some(max_streams),
+            ..Default::default()
+        }
+    }
+
+    fn settings(max_data: u32, max_streams: u32) -> Settings {
+        Settings::builder()
+            .webtransport_initial_max_data(VarInt::from_u32(max_data))
+            .webtransport_initial_max_streams_uni(VarInt::from_u32(max_streams))
+            .webtransport_initial_max_streams_bidi(VarInt::from_u32(max_streams))
+            .build()
+    }
+
+    struct NoopWaker;
+
+    impl std::task::Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll<F, T>(f: F) -> T
+    where
+        F: FnOnce(&mut Context<'_>) -> T,
+    {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        f(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn unlimited() {
+        let flow_control = SessionFlowControl::new();
+
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 1 << 20)),
+            Poll::Ready(Ok(1048576))
+        ));
+
+        for _ in 0..16 {
+            assert!(flow_control.on_stream_received(StreamDirection::Uni));
+            assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Uni)).is_ready());
+        }
+
+        assert!(flow_control.on_data_received(1 << 20));
+        flow_control.on_data_read(1 << 20);
+        assert!(flow_control.take_capsules().is_empty());
+        assert!(flow_control.error().is_none());
+    }
+
+    #[test]
+    fn send_data_blocked() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_remote_limits(&settings(10, 0));
+
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 8)),
+            Poll::Ready(Ok(8))
+        ));
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 8)),
+            Poll::Ready(Ok(2))
+        ));
+        assert!(poll(|cx| flow_control.poll_reserve_data(cx, 8)).is_pending());
+        assert!(poll(|cx| flow_control.poll_reserve_data(cx, 8)).is_pending());
+
+        let capsules = flow_control.take_capsules();
+        assert_eq!(capsules.len(), 1);
+        assert_eq!(
+            WtDataBlocked::with_capsule(&capsules[0]).unwrap(),
+            WtDataBlocked::new(VarInt::from_u32(10))
+        );
+
+        flow_control.release_data(1);
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 8)),
+            Poll::Ready(Ok(1))
+        ));
+
+        flow_control.on_max_data(WtMaxData::new(VarInt::from_u32(15)));
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 8)),
+            Poll::Ready(Ok(5))
+        ));
+    }
+
+    #[test]
+    fn send_streams_blocked() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_remote_limits(&settings(0, 1));
+
+        assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Bi)).is_ready());
+        assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Bi)).is_pending());
+        assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Uni)).is_ready());
+
+        let capsules = flow_control.take_capsules();
+        assert_eq!(capsules.len(), 1);
+        assert_eq!(
+            WtStreamsBlocked::with_capsule(&capsules[0]).unwrap(),
+            WtStreamsBlocked::new(StreamDirection::Bi, VarInt::from_u32(1))
+        );
+
+        // Limits cannot decrease
+        flow_control.on_max_streams(WtMaxStreams::new(StreamDirection::Bi, VarInt::from_u32(0)));
+        assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Bi)).is_pending());
+
+        flow_control.on_max_streams(WtMaxStreams::new(StreamDirection::Bi, VarInt::from_u32(2)));
+        assert!(poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Bi)).is_ready());
+    }
+
+    #[test]
+    fn replenish_streams() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_local_limits(&config(0, 4));
+
+        for _ in 0..4 {
+            assert!(flow_control.on_stream_received(StreamDirection::Uni));
+        }
+
+        flow_control.on_stream_accepted(StreamDirection::Uni);
+        flow_control.on_stream_accepted(StreamDirection::Uni);
+        assert!(flow_control.take_capsules().is_empty());
+
+        flow_control.on_stream_accepted(StreamDirection::Uni);
+        let capsules = flow_control.take_capsules();
+        assert_eq!(capsules.len(), 1);
+        assert_eq!(
+            WtMaxStreams::with_capsule(&capsules[0]).unwrap(),
+            WtMaxStreams::new(StreamDirection::Uni, VarInt::from_u32(7))
+        );
+
+        for _ in 0..3 {
+            assert!(flow_control.on_stream_received(StreamDirection::Uni));
+        }
+
+        assert!(flow_control.error().is_none());
+    }
+
+    #[test]
+    fn replenish_data() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_local_limits(&config(100, 0));
+
+        assert!(flow_control.on_data_received(100));
+        flow_control.on_data_read(50);
+        assert!(flow_control.take_capsules().is_empty());
+
+        flow_control.on_data_read(10);
+        let capsules = flow_control.take_capsules();
+        assert_eq!(capsules.len(), 1);
+        assert_eq!(
+            WtMaxData::with_capsule(&capsules[0]).unwrap(),
+            WtMaxData::new(VarInt::from_u32(160))
+        );
+    }
+
+    #[test]
+    fn streams_violation() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_local_limits(&config(0, 1));
+
+        assert!(flow_control.on_stream_received(StreamDirection::Bi));
+        assert!(flow_control.error().is_none());
+
+        assert!(!flow_control.on_stream_received(StreamDirection::Bi));
+        assert!(matches!(flow_control.error(), Some(ErrorCode::FlowControl)));
+    }
+
+    #[test]
+    fn data_violation() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_local_limits(&config(10, 0));
+
+        // Nothing is read by the application
+        assert!(flow_control.on_data_received(6));
+        assert!(flow_control.error().is_none());
+
+        assert!(!flow_control.on_data_received(5));
+        assert!(matches!(flow_control.error(), Some(ErrorCode::FlowControl)));
+    }
+
+    #[test]
+    fn closed() {
+        let flow_control = SessionFlowControl::new();
+        flow_control.set_remote_limits(&settings(0, 0));
+
+        assert!(poll(|cx| flow_control.poll_reserve_data(cx, 1)).is_pending());
+
+        flow_control.close();
+        assert!(matches!(
+            poll(|cx| flow_control.poll_reserve_data(cx, 1)),
+            Poll::Ready(Err(FlowControlClosed))
+        ));
+        assert!(matches!(
+            poll(|cx| flow_control.poll_open_stream(cx, StreamDirection::Uni)),
+            Poll::Ready(Err(FlowControlClosed))
+        ));
+    }
+}
@@ -1,210 +1,403 @@
+use crate::config::WebTransportConfig;
 use crate::datagram::Datagram;
+use crate::driver::flow_control::SessionFlowControl;
+use crate::driver::session::SessionAction;
+use crate::driver::session::SessionTermination;
 use crate::driver::streams::biremote::StreamBiRemoteH3;
 use crate::driver::streams::biremote::StreamBiRemoteWT;
+use crate::driver::streams::inbound::InboundStream;
+use crate::driver::streams::qpack::QPackCodec;
 use crate::driver::streams::session::StreamSession;
 use crate::driver::streams::uniremote::StreamUniRemoteWT;
+use crate::driver::streams::QuicSendStream;
+use crate::driver::streams::SessionStreams;
 use crate::driver::streams::Stream;
-use crate::driver::utils::bichannel;
 use crate::driver::utils::shared_result;
-use crate::driver::utils::SendError;
 use crate::driver::utils::SharedResultGet;
 use crate::driver::utils::SharedResultSet;
 use crate::error::SendDatagramError;
 use crate::stream::OpeningBiStream;
 use crate::stream::OpeningUniStream;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::task::ready;
+use std::task::Context;
+use std::task::Poll;
 use tokio::sync::mpsc;
+use tokio::sync::watch;
 use tokio::sync::Mutex;
 use tracing::debug;
 use tracing::debug_span;
 use tracing::instrument;
 use tracing::trace;
 use tracing::Instrument;
-use utils::BiChannelEndpoint;
+use wtransport_proto::capsule::capsules::CloseWebTransportSession;
+use wtransport_proto::capsule::capsules::StreamDirection;
 use wtransport_proto::error::ErrorCode;
 use wtransport_proto::frame::Frame;
+use wtransport_proto::headers::Headers;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::ids::StreamId;
 use wtransport_proto::session::SessionRequest;
 use wtransport_proto::settings::Settings;
 
+/// Maximum number of datagrams queued for a session before discarding.
+const SESSION_DATAGRAMS_CAPACITY: usize = 64;
+
 #[derive(Copy, Clone, Debug)]
 pub enum DriverError {
     Proto(ErrorCode),
     NotConnected,
+    SessionGone,
+}
+
+type ReadySession = (StreamSession, SessionQueues);
+
+/// Number of sessions on a connection, by state.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionCounts {
+    /// Sessions whose session stream is running.
+    pub active: usize,
+
+    /// Sessions whose session stream ran and is no longer running.
+    pub closed: usize,
 }
 
+type SessionStreamFuture =
+    Pin<Box<dyn Future<Output = Result<SessionTermination, DriverError>> + Send>>;
+
 #[derive(Debug)]
 pub struct Driver {
     quic_connection: quinn::Connection,
     ready_settings: Mutex<mpsc::Receiver<Settings>>,
-    ready_sessions: BiChannelEndpoint<StreamSession>,
-    ready_uni_wt_streams: Mutex<mpsc::Receiver<StreamUniRemoteWT>>,
-    ready_bi_wt_streams: Mutex<mpsc::Receiver<StreamBiRemoteWT>>,
-    ready_datagrams: Mutex<mpsc::Receiver<Datagram>>,
+    remote_settings: OnceLock<Settings>,
+    ready_sessions: SessionAcceptor,
+    session_commands: mpsc::Sender<SessionCommand>,
+    session_counts: watch::Receiver<SessionCounts>,
+    qpack: QPackCodec,
     driver_result: SharedResultGet<DriverError>,
 }
 
 impl Driver {
-    pub fn init(quic_connection: quinn::Connection) -> Self {
+    pub fn init(
+        quic_connection: quinn::Connection,
+        webtransport_config: WebTransportConfig,
+    ) -> Self {
         let ready_settings = mpsc::channel(1);
-        let ready_sessions = bichannel(1);
-        let ready_uni_wt_streams = mpsc::channel(4);
-        let ready_bi_wt_streams = mpsc::channel(1);
-        let ready_datagrams = mpsc::channel(1);
+        let ready_sessions = mpsc::channel(1);
+        let session_commands = mpsc::channel(4);
+        let session_counts = watch::channel(SessionCounts::default());
         let driver_result = shared_result();
 
+        let worker = worker::Worker::new(
+            quic_connection.clone(),
+            webtransport_config,
+            ready_settings.0,
+            ready_sessions.0,
+            session_commands.1,
+            session_counts.0,
+            driver_result.0,
+        );
+
+        let qpack = worker.qpack().clone();
+
         tokio::spawn(
-            worker::Worker::new(
-                quic_connection.clone(),
-                ready_settings.0,
-                ready_sessions.0,
-                ready_uni_wt_streams.0,
-                ready_bi_wt_streams.0,
-                ready_datagrams.0,
-                driver_result.0,
-            )
-            .run()
-            .instrument(debug_span!("Driver", quic_id = quic_connection.stable_id())),
+            worker
+                .run()
+                .instrument(debug_span!("Driver", quic_id = quic_connection.stable_id())),
         );
 
         Self {
             quic_connection,
             ready_settings: Mutex::new(ready_settings.1),
-            ready_sessions: ready_sessions.1,
-            ready_uni_wt_streams: Mutex::new(ready_uni_wt_streams.1),
-            ready_bi_wt_streams: Mutex::new(ready_bi_wt_streams.1),
-            ready_datagrams: Mutex::new(ready_datagrams.1),
+            remote_settings: OnceLock::new(),
+            ready_sessions: SessionAcceptor(Arc::new(Mutex::new(ready_sessions.1))),
+            session_commands: session_commands.0,
+            session_counts: session_counts.1,
+            qpack,
             driver_result: driver_result.1,
         }
     }
 
+    /// Awaits the peer's SETTINGS.
+    ///
+    /// Once received, settings are also available with [`Self::remote_settings`].
     pub async fn accept_settings(&self) -> Result<Settings, DriverError> {
         let mut lock = self.ready_settings.lock().await;
 
+        if let Some(settings) = self.remote_settings.get() {
+            return Ok(settings.clone());
+        }
+
         match lock.recv().await {
-            Some(settings) => Ok(settings),
+            Some(settings) => Ok(self.remote_settings.get_or_init(|| settings).clone()),
             None => Err(self.result().await),
         }
     }
 
-    pub async fn accept_session(&self) -> Result<StreamSession, DriverError> {
-        match self.ready_sessions.recv().await {
+    /// Returns the peer's SETTINGS, if already received.
+    pub fn remote_settings(&self) -> Option<&Settings> {
+        self.remote_settings.get()
+    }
+
+    pub async fn accept_session(&self) -> Result<ReadySession, DriverError> {
+        match self.ready_sessions.accept().await {
             Some(session) => Ok(session),
             None => Err(self.result().await),
         }
     }
 
+    /// Returns an handle for accepting incoming sessions which does not keep the driver alive.
+    pub fn session_acceptor(&self) -> SessionAcceptor {
+        self.ready_sessions.clone()
+    }
+
     pub async fn open_session(
         &self,
         session_request: SessionRequest,
-    ) -> Result<StreamSession, DriverError> {
+    ) -> Result<ReadySession, DriverError> {
         let stream = Stream::open_bi(&self.quic_connection)
             .await
             .ok_or(DriverError::NotConnected)?
             .upgrade()
             .into_session(session_request);
 
-        Ok(stream)
+        let session_id = stream.session_id();
+        let (slots, queues) = session_channels(session_id);
+
+        self.send_session_command(SessionCommand::Open(session_id, slots))
+            .await?;
+
+        Ok((stream, queues))
+    }
+
+    pub fn qpack(&self) -> &QPackCodec {
+        &self.qpack
+    }
+
+    /// Encodes headers to be sent on stream `stream_id`.
+    pub fn encode_headers(&self, stream_id: StreamId, headers: &Headers) -> Frame<'static> {
+        self.qpack.encode_headers(stream_id, headers)
+    }
+
+    /// Decodes headers received on stream `stream_id`.
+    ///
+    /// It might await dynamic table updates from the peer.
+    pub async fn decode_headers(
+        &self,
+        stream_id: StreamId,
+        frame: &Frame<'_>,
+    ) -> Result<Headers, ErrorCode> {
+        self.qpack.decode_headers(stream_id, frame).await
     }
 
     pub async fn register_session(&self, stream_session: StreamSession) -> Result<(), DriverError> {
-        match self.ready_sessions.send(stream_session).await {
-            Ok(()) => Ok(()),
-            Err(SendError) => Err(self.result().await),
+        self.send_session_command(SessionCommand::Register(stream_session))
+            .await
+    }
+
+    pub async fn accept_uni(&self, session: &SessionQueues) -> Result<InboundStream, DriverError> {
+        let mut lock = session.uni_streams.lock().await;
+
+        tokio::select! {
+            biased;
+
+            error = self.session_gone(session) => {
+                while let Ok(stream) = lock.try_recv() {
+                    stream.stop(ErrorCode::SessionGone.to_code());
+                }
+
+                Err(error)
+            }
+            stream = lock.recv() => match stream {
+                Some(stream) => {
+                    session.flow_control.on_stream_accepted(StreamDirection::Uni);
+                    Ok(stream)
+                }
+                None => Err(self.result().await),
+            },
         }
     }
 
-    pub async fn accept_uni(
+    pub async fn accept_bi(
         &self,
-        session_id: SessionId,
-    ) -> Result<StreamUniRemoteWT, DriverError> {
-        let mut lock = self.ready_uni_wt_streams.lock().await;
+        session: &SessionQueues,
+    ) -> Result<(QuicSendStream, InboundStream), DriverError> {
+        let mut lock = session.bi_streams.lock().await;
 
-        loop {
-            let stream = match lock.recv().await {
-                Some(stream) => stream,
-                None => return Err(self.result().await),
-            };
+        tokio::select! {
+            biased;
+
+            error = self.session_gone(session) => {
+                while let Ok((send, recv)) = lock.try_recv() {
+                    send.reset(ErrorCode::SessionGone.to_code());
+                    recv.stop(ErrorCode::SessionGone.to_code());
+                }
 
-            if stream.session_id() == session_id {
-                return Ok(stream);
+                Err(error)
             }
+            stream = lock.recv() => match stream {
+                Some(stream) => {
+                    session.flow_control.on_stream_accepted(StreamDirection::Bi);
+                    Ok(stream)
+                }
+                None => Err(self.result().await),
+            },
+        }
+    }
 
-            debug!(
-                "Discarding WT stream (stream_id: {}, session_id: {})",
-                stream.id(),
-                stream.session_id()
-            );
+    pub async fn receive_datagram(&self, session: &SessionQueues) -> Result<Datagram, DriverError> {
+        let mut lock = session.datagrams.lock().await;
 
-            stream
-                .into_stream()
-                .stop(ErrorCode::BufferedStreamRejected.to_code())
-                .expect("Stream not already stopped");
+        tokio::select! {
+            biased;
+
+            error = self.session_gone(session) => Err(error),
+            datagram = lock.recv() => match datagram {
+                Some(datagram) => Ok(datagram),
+                None => Err(self.result().await),
+            },
         }
     }
 
-    pub async fn accept_bi(&self, session_id: SessionId) -> Result<StreamBiRemoteWT, DriverError> {
-        let mut lock = self.ready_bi_wt_streams.lock().await;
-
-        loop {
-            let stream = match lock.recv().await {
-                Some(stream) => stream,
-                None => return Err(self.result().await),
-            };
+    /// Closes the session sending `CLOSE_WEBTRANSPORT_SESSION` on its stream.
+    ///
+    /// Calling this method more than once has no effect.
+    pub fn close_session(&self, session: &SessionQueues, error_code: u32, reason: &str) {
+        let close = CloseWebTransportSession::new(error_code, reason);
+        let _ = session.actions.send(SessionAction::Close(close));
+    }
 
-            if stream.session_id() == session_id {
-                return Ok(stream);
-            }
+    /// Asks the peer to drain the session sending `DRAIN_WEBTRANSPORT_SESSION` on its stream.
+    pub fn drain_session(&self, session: &SessionQueues) {
+        let _ = session.actions.send(SessionAction::Drain);
+    }
 
-            debug!(
-                "Discarding WT stream (stream_id: {}, session_id: {})",
-                stream.id(),
-                stream.session_id()
-            );
+    /// Awaits the peer asks to drain the session.
+    ///
+    /// It also returns if the session is terminated in the meantime.
+    pub async fn session_draining(&self, session: &SessionQueues) {
+        tokio::select! {
+            biased;
 
-            stream
-                .into_stream()
-                .1
-                .stop(ErrorCode::BufferedStreamRejected.to_code())
-                .expect("Stream not already stopped");
+            Some(()) = session.draining.result() => {}
+            _ = self.session_closed(session) => {}
         }
     }
 
-    pub async fn receive_datagram(&self, session_id: SessionId) -> Result<Datagram, DriverError> {
-        let mut lock = self.ready_datagrams.lock().await;
+    /// Sends a GOAWAY frame on the control stream.
+    ///
+    /// Session requests received afterwards are rejected.
+    pub async fn go_away(&self) -> Result<(), DriverError> {
+        self.send_session_command(SessionCommand::GoAway).await
+    }
 
-        loop {
-            let datagram = match lock.recv().await {
-                Some(datagram) => datagram,
-                None => {
-                    return Err(self.result().await);
-                }
-            };
+    /// Returns the address of the peer.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.quic_connection.remote_address()
+    }
+
+    /// Returns the number of running and terminated sessions on the connection.
+    ///
+    /// Once the worker is gone, sessions still running at that time keep being reported
+    /// as active: they have not been closed, but abandoned along with the connection.
+    pub fn session_counts(&self) -> SessionCounts {
+        *self.session_counts.borrow()
+    }
 
-            if datagram.session_id() == session_id {
-                return Ok(datagram);
+    /// Awaits there are no more running sessions on the connection.
+    pub async fn sessions_closed(&self) {
+        let mut session_counts = self.session_counts.clone();
+
+        while session_counts.borrow_and_update().active > 0 {
+            if session_counts.changed().await.is_err() {
+                break;
             }
+        }
+    }
 
-            debug!(
-                "Incoming datagram discarded (session_id: {})",
-                datagram.session_id()
-            );
+    /// Awaits the session is terminated.
+    pub async fn session_closed(
+        &self,
+        session: &SessionQueues,
+    ) -> Result<SessionTermination, DriverError> {
+        tokio::select! {
+            biased;
+
+            termination = session.session_result.result() => match termination {
+                Some(termination) => Ok(termination),
+                None => Err(self.result().await),
+            },
+            error = self.result() => Err(error),
         }
     }
 
-    pub async fn open_uni(&self, session_id: SessionId) -> Result<OpeningUniStream, DriverError> {
+    /// Opens a stream in the session.
+    ///
+    /// It awaits the peer to grant credit in case the session limit on streams is reached.
+    pub async fn open_uni(&self, session: &SessionQueues) -> Result<OpeningUniStream, DriverError> {
+        if session.is_terminated() {
+            return Err(DriverError::SessionGone);
+        }
+
+        tokio::select! {
+            biased;
+
+            error = self.session_gone(session) => return Err(error),
+            result = session.flow_control.open_stream(StreamDirection::Uni) => {
+                if result.is_err() {
+                    return Err(self.session_gone(session).await);
+                }
+            }
+        }
+
+        let session_id = session.session_id();
         let quic_stream = Stream::open_uni(&self.quic_connection)
             .await
             .ok_or(DriverError::NotConnected)?;
 
-        Ok(OpeningUniStream::new(session_id, quic_stream))
+        Ok(OpeningUniStream::new(
+            session_id,
+            quic_stream,
+            session.flow_control.clone(),
+            session.streams.clone(),
+        ))
     }
 
-    pub async fn open_bi(&self, session_id: SessionId) -> Result<OpeningBiStream, DriverError> {
+    /// Opens a stream in the session.
+    ///
+    /// It awaits the peer to grant credit in case the session limit on streams is reached.
+    pub async fn open_bi(&self, session: &SessionQueues) -> Result<OpeningBiStream, DriverError> {
+        if session.is_terminated() {
+            return Err(DriverError::SessionGone);
+        }
+
+        tokio::select! {
+            biased;
+
+            error = self.session_gone(session) => return Err(error),
+            result = session.flow_control.open_stream(StreamDirection::Bi) => {
+                if result.is_err() {
+                    return Err(self.session_gone(session).await);
+                }
+            }
+        }
+
+        let session_id = session.session_id();
         let quic_stream = Stream::open_bi(&self.quic_connection)
             .await
             .ok_or(DriverError::NotConnected)?;
 
-        Ok(OpeningBiStream::new(session_id, quic_stream))
+        Ok(OpeningBiStream::new(
+            session_id,
+            quic_stream,
+            session.flow_control.clone(),
+            session.streams.clone(),
+        ))
     }
 
     pub fn send_datagram(
@@ -212,21 +405,43 @@ impl Driver {
         session_id: SessionId,
         payload: &[u8],
     ) -> Result<(), SendDatagramError> {
-        let quic_datagram = Datagram::write(session_id, payload).into_quic_bytes();
+        send_datagram(&self.quic_connection, session_id, payload)
+    }
 
-        match self.quic_connection.send_datagram(quic_datagram) {
-            Ok(()) => Ok(()),
-            Err(quinn::SendDatagramError::UnsupportedByPeer) => {
-                Err(SendDatagramError::UnsupportedByPeer)
-            }
-            Err(quinn::SendDatagramError::Disabled) => {
-                unreachable!()
-            }
+    /// Routes incoming datagrams associated with `session_id` to the returned queue.
+    ///
+    /// Used for requests exchanging datagrams other than WebTransport sessions.
+    pub async fn open_tunnel(
+        &self,
+        session_id: SessionId,
+    ) -> Result<mpsc::Receiver<Datagram>, DriverError> {
+        let (sender, receiver) = mpsc::channel(SESSION_DATAGRAMS_CAPACITY);
 
-            Err(quinn::SendDatagramError::TooLarge) => Err(SendDatagramError::TooLarge),
-            Err(quinn::SendDatagramError::ConnectionLost(_)) => {
-                Err(SendDatagramError::NotConnected)
-            }
+        self.send_session_command(SessionCommand::Tunnel(session_id, sender))
+            .await?;
+
+        Ok(receiver)
+    }
+
+    /// Awaits the session is terminated.
+    ///
+    /// Returns [`DriverError::SessionGone`], or the driver error if the whole connection is gone.
+    async fn session_gone(&self, session: &SessionQueues) -> DriverError {
+        match self.session_closed(session).await {
+            Ok(_termination) => DriverError::SessionGone,
+            Err(driver_error) => driver_error,
+        }
+    }
+
+    /// Awaits the connection is terminated, returning the driver error.
+    pub async fn closed(&self) -> DriverError {
+        self.result().await
+    }
+
+    async fn send_session_command(&self, command: SessionCommand) -> Result<(), DriverError> {
+        match self.session_commands.send(command).await {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::SendError(_)) => Err(self.result().await),
         }
     }
 
@@ -238,64 +453,394 @@ impl Driver {
     }
 }
 
+/// Sends an HTTP3 datagram associated with `session_id`.
+pub fn send_datagram(
+    quic_connection: &quinn::Connection,
+    session_id: SessionId,
+    payload: &[u8],
+) -> Result<(), SendDatagramError> {
+    let quic_datagram = Datagram::write(session_id, payload).into_quic_bytes();
+
+    match quic_connection.send_datagram(quic_datagram) {
+        Ok(()) => Ok(()),
+        Err(quinn::SendDatagramError::UnsupportedByPeer) => {
+            Err(SendDatagramError::UnsupportedByPeer)
+        }
+        Err(quinn::SendDatagramError::Disabled) => {
+            unreachable!()
+        }
+
+        Err(quinn::SendDatagramError::TooLarge) => Err(SendDatagramError::TooLarge),
+        Err(quinn::SendDatagramError::ConnectionLost(_)) => Err(SendDatagramError::NotConnected),
+    }
+}
+
+/// Incoming session requests queue, shared among all handles of the same driver.
+#[derive(Debug, Clone)]
+pub struct SessionAcceptor(Arc<Mutex<mpsc::Receiver<ReadySession>>>);
+
+impl SessionAcceptor {
+    /// Awaits the next incoming session request.
+    ///
+    /// Returns `None` when the driver is terminated.
+    pub async fn accept(&self) -> Option<ReadySession> {
+        self.0.lock().await.recv().await
+    }
+}
+
+/// Incoming streams and datagrams routed to a single session.
+#[derive(Debug)]
+pub struct SessionQueues {
+    session_id: SessionId,
+    uni_streams: Mutex<mpsc::UnboundedReceiver<InboundStream>>,
+    bi_streams: Mutex<mpsc::UnboundedReceiver<(QuicSendStream, InboundStream)>>,
+    datagrams: Mutex<mpsc::Receiver<Datagram>>,
+    actions: mpsc::UnboundedSender<SessionAction>,
+    draining: SharedResultGet<()>,
+    session_result: SharedResultGet<SessionTermination>,
+    flow_control: SessionFlowControl,
+    streams: SessionStreams,
+}
+
+impl SessionQueues {
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns `true` if the session has been terminated.
+    #[inline(always)]
+    pub fn is_terminated(&self) -> bool {
+        self.session_result.is_set()
+    }
+
+    #[inline(always)]
+    pub fn flow_control(&self) -> &SessionFlowControl {
+        &self.flow_control
+    }
+
+    #[inline(always)]
+    pub fn streams(&self) -> &SessionStreams {
+        &self.streams
+    }
+}
+
+impl Drop for SessionQueues {
+    fn drop(&mut self) {
+        // Streams not accepted by the application are gone along with the session
+        while let Ok(stream) = self.uni_streams.get_mut().try_recv() {
+            stream.stop(ErrorCode::SessionGone.to_code());
+        }
+
+        while let Ok((send, recv)) = self.bi_streams.get_mut().try_recv() {
+            send.reset(ErrorCode::SessionGone.to_code());
+            recv.stop(ErrorCode::SessionGone.to_code());
+        }
+    }
+}
+
+/// Worker-side endpoints of [`SessionQueues`].
+struct SessionSlots {
+    session_stream: Option<SessionStreamFuture>,
+    terminated: bool,
+    uni_streams: mpsc::UnboundedSender<InboundStream>,
+    bi_streams: mpsc::UnboundedSender<(QuicSendStream, InboundStream)>,
+    datagrams: mpsc::Sender<Datagram>,
+    actions: Option<mpsc::UnboundedReceiver<SessionAction>>,
+    draining: SharedResultSet<()>,
+    session_result: SharedResultSet<SessionTermination>,
+    flow_control: SessionFlowControl,
+    streams: SessionStreams,
+    buffer: SessionBuffer,
+}
+
+impl SessionSlots {
+    /// Returns `true` if the [`SessionQueues`] counterpart has been dropped.
+    fn is_closed(&self) -> bool {
+        self.uni_streams.is_closed()
+    }
+
+    /// Returns `true` if the session stream is running.
+    fn is_running(&self) -> bool {
+        self.session_stream.is_some()
+    }
+
+    /// Starts running the session stream.
+    ///
+    /// Returns `false` if the session stream has already been registered.
+    fn register(&mut self, stream_session: StreamSession) -> bool {
+        match self.actions.take() {
+            Some(actions) => {
+                self.session_stream = Some(Box::pin(session::run(
+                    stream_session,
+                    actions,
+                    self.draining.clone(),
+                    self.flow_control.clone(),
+                )));
+                true
+            }
+            None => {
+                debug!(
+                    "Session {} is already registered",
+                    stream_session.session_id()
+                );
+                false
+            }
+        }
+    }
+
+    /// Polls the session stream.
+    ///
+    /// Returns [`Poll::Ready`] with [`Ok`] once the session is terminated.
+    /// Once terminated, successive calls will always return [`Poll::Pending`].
+    fn poll_session_stream(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DriverError>> {
+        let session_stream = match self.session_stream.as_mut() {
+            Some(session_stream) => session_stream,
+            None => return Poll::Pending,
+        };
+
+        let result = ready!(session_stream.as_mut().poll(cx));
+        self.session_stream = None;
+        self.flow_control.close();
+
+        let termination = result?;
+        debug!("Session terminated: {:?}", termination);
+        self.terminated = true;
+        self.streams.terminate();
+        self.session_result.set(termination);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SessionSlots {
+    fn drop(&mut self) {
+        self.flow_control.close();
+        self.streams.terminate();
+    }
+}
+
+fn session_channels(session_id: SessionId) -> (SessionSlots, SessionQueues) {
+    let uni_streams = mpsc::unbounded_channel();
+    let bi_streams = mpsc::unbounded_channel();
+    let datagrams = mpsc::channel(SESSION_DATAGRAMS_CAPACITY);
+    let actions = mpsc::unbounded_channel();
+    let draining = shared_result();
+    let session_result = shared_result();
+    let flow_control = SessionFlowControl::new();
+    let streams = SessionStreams::default();
+
+    let slots = SessionSlots {
+        session_stream: None,
+        terminated: false,
+        uni_streams: uni_streams.0,
+        bi_streams: bi_streams.0,
+        datagrams: datagrams.0,
+        actions: Some(actions.1),
+        draining: draining.0,
+        session_result: session_result.0,
+        flow_control: flow_control.clone(),
+        streams: streams.clone(),
+        buffer: SessionBuffer::default(),
+    };
+
+    let queues = SessionQueues {
+        session_id,
+        uni_streams: Mutex::new(uni_streams.1),
+        bi_streams: Mutex::new(bi_streams.1),
+        datagrams: Mutex::new(datagrams.1),
+        actions: actions.0,
+        draining: draining.1,
+        session_result: session_result.1,
+        flow_control,
+        streams,
+    };
+
+    (slots, queues)
+}
+
+/// Accounts for streams and datagrams held for a session not established yet.
+#[derive(Default)]
+struct SessionBuffer {
+    streams: u32,
+    bytes: usize,
+}
+
+impl SessionBuffer {
+    /// Accounts for a new stream.
+    ///
+    /// Returns `false` if the buffer limit would be exceeded.
+    fn push_stream(&mut self, config: &WebTransportConfig) -> bool {
+        if self.streams >= config.session_buffer_streams {
+            return false;
+        }
+
+        self.streams += 1;
+        true
+    }
+
+    /// Accounts for a new datagram of `len` payload bytes.
+    ///
+    /// Returns `false` if the buffer limit would be exceeded.
+    fn push_datagram(&mut self, len: usize, config: &WebTransportConfig) -> bool {
+        if self.bytes + len > config.session_buffer_bytes as usize {
+            return false;
+        }
+
+        self.bytes += len;
+        true
+    }
+}
+
+/// A stream or datagram received for a session whose request is not processed yet.
+enum EarlyIncoming {
+    UniStream(StreamUniRemoteWT),
+    BiStream(StreamBiRemoteWT),
+    Datagram(Datagram),
+}
+
+impl EarlyIncoming {
+    fn reject(self) {
+        match self {
+            EarlyIncoming::UniStream(stream) => {
+                debug!("Discarding early WT stream (stream_id: {})", stream.id());
+                stream
+                    .into_stream()
+                    .stop(ErrorCode::BufferedStreamRejected.to_code())
+                    .expect("Stream not already stopped");
+            }
+            EarlyIncoming::BiStream(stream) => {
+                debug!("Discarding early WT stream (stream_id: {})", stream.id());
+                stream
+                    .into_stream()
+                    .1
+                    .stop(ErrorCode::BufferedStreamRejected.to_code())
+                    .expect("Stream not already stopped");
+            }
+            EarlyIncoming::Datagram(datagram) => {
+                debug!(
+                    "Incoming datagram discarded (session_id: {})",
+                    datagram.session_id()
+                );
+            }
+        }
+    }
+}
+
+/// Streams and datagrams held until the request of their session is processed.
+#[derive(Default)]
+struct PendingSession {
+    buffer: SessionBuffer,
+    incoming: Vec<EarlyIncoming>,
+}
+
+enum SessionCommand {
+    /// A session has been opened locally and its incoming data must be routed.
+    Open(SessionId, SessionSlots),
+
+    /// A session has been established and its stream is handed over to the worker.
+    Register(StreamSession),
+
+    /// A tunnel (e.g., CONNECT-UDP) has been requested locally and its datagrams must be routed.
+    Tunnel(SessionId, mpsc::Sender<Datagram>),
+
+    /// The connection is shutting down: GOAWAY must be sent to the peer.
+    GoAway,
+}
+
 mod worker {
     use super::*;
-    use crate::driver::streams::qpack::RemoteQPackDecStream;
-    use crate::driver::streams::qpack::RemoteQPackEncStream;
+    use crate::connect_udp::SharedConnectUdpHandler;
+    use crate::connect_udp::UdpTunnelRequest;
+    use crate::driver::streams::qpack::QPackStreams;
     use crate::driver::streams::settings::LocalSettingsStream;
     use crate::driver::streams::settings::RemoteSettingsStream;
+    use crate::driver::streams::unilocal::StreamUniLocalH3;
     use crate::driver::streams::uniremote::StreamUniRemoteH3;
     use crate::driver::streams::ProtoReadError;
     use crate::driver::streams::ProtoWriteError;
-    use crate::driver::utils::TrySendError;
+    use crate::http::IncomingRequest;
+    use crate::http::Response;
+    use crate::websocket::SharedWebSocketHandler;
+    use crate::websocket::WebSocketRequest;
     use utils::varint_w2q;
+    use wtransport_proto::connect_udp;
     use wtransport_proto::frame::FrameKind;
-    use wtransport_proto::headers::Headers;
+    use wtransport_proto::goaway::GoAway;
     use wtransport_proto::session::HeadersParseError;
     use wtransport_proto::stream_header::StreamHeader;
     use wtransport_proto::stream_header::StreamKind;
+    use wtransport_proto::varint::VarInt;
+    use wtransport_proto::websocket;
+
+    /// A request stream along with its first frame and, for HEADERS, the decoded headers.
+    type ReadyBiH3Stream = (StreamBiRemoteH3, Frame<'static>, Option<Headers>);
 
     pub struct Worker {
         quic_connection: quinn::Connection,
+        webtransport_config: WebTransportConfig,
+        qpack: QPackCodec,
         ready_settings: mpsc::Sender<Settings>,
-        ready_sessions: BiChannelEndpoint<StreamSession>,
-        ready_uni_wt_streams: mpsc::Sender<StreamUniRemoteWT>,
-        ready_bi_wt_streams: mpsc::Sender<StreamBiRemoteWT>,
-        ready_datagrams: mpsc::Sender<Datagram>,
+        ready_sessions: mpsc::Sender<ReadySession>,
+        session_commands: mpsc::Receiver<SessionCommand>,
+        session_counts: watch::Sender<SessionCounts>,
+        registered_sessions: usize,
         driver_result: SharedResultSet<DriverError>,
         local_settings_stream: LocalSettingsStream,
         remote_settings_stream: RemoteSettingsStream,
-        remote_qpack_enc_stream: RemoteQPackEncStream,
-        remote_qpack_dec_stream: RemoteQPackDecStream,
-        stream_session: Option<StreamSession>,
+        qpack_streams: QPackStreams,
+        sessions: HashMap<SessionId, SessionSlots>,
+        tunnels: HashMap<SessionId, mpsc::Sender<Datagram>>,
+        pending_sessions: HashMap<SessionId, PendingSession>,
+        last_request_id: Option<StreamId>,
+        last_session_id: Option<SessionId>,
+        local_goaway: Option<GoAway>,
+        remote_goaway: Option<GoAway>,
+        remote_settings: Option<Settings>,
     }
 
     impl Worker {
         pub fn new(
             quic_connection: quinn::Connection,
+            webtransport_config: WebTransportConfig,
             ready_settings: mpsc::Sender<Settings>,
-            ready_sessions: BiChannelEndpoint<StreamSession>,
-            ready_uni_wt_streams: mpsc::Sender<StreamUniRemoteWT>,
-            ready_bi_wt_streams: mpsc::Sender<StreamBiRemoteWT>,
-            ready_datagrams: mpsc::Sender<Datagram>,
+            ready_sessions: mpsc::Sender<ReadySession>,
+            session_commands: mpsc::Receiver<SessionCommand>,
+            session_counts: watch::Sender<SessionCounts>,
             driver_result: SharedResultSet<DriverError>,
         ) -> Self {
+            let local_settings_stream = LocalSettingsStream::empty(&webtransport_config);
+            let qpack = QPackCodec::new(&webtransport_config);
+            let qpack_streams = QPackStreams::empty(qpack.clone());
+
             Self {
                 quic_connection,
+                webtransport_config,
+                qpack,
                 ready_settings,
                 ready_sessions,
-                ready_uni_wt_streams,
-                ready_bi_wt_streams,
-                ready_datagrams,
+                session_commands,
+                session_counts,
+                registered_sessions: 0,
                 driver_result,
-                local_settings_stream: LocalSettingsStream::empty(),
+                local_settings_stream,
                 remote_settings_stream: RemoteSettingsStream::empty(),
-                remote_qpack_enc_stream: RemoteQPackEncStream::empty(),
-                remote_qpack_dec_stream: RemoteQPackDecStream::empty(),
-                stream_session: None,
+                qpack_streams,
+                sessions: HashMap::new(),
+                tunnels: HashMap::new(),
+                pending_sessions: HashMap::new(),
+                last_request_id: None,
+                last_session_id: None,
+                local_goaway: None,
+                remote_goaway: None,
+                remote_settings: None,
             }
         }
 
+        pub fn qpack(&self) -> &QPackCodec {
+            &self.qpack
+        }
+
         pub async fn run(mut self) {
             debug!("Started");
 
@@ -316,28 +861,32 @@ mod worker {
 
         async fn run_impl(&mut self) -> Result<(), DriverError> {
             let mut remote_settings_watcher = self.remote_settings_stream.subscribe();
+            let mut remote_goaway_watcher = self.remote_settings_stream.subscribe_goaway();
             let mut ready_uni_h3_streams = mpsc::channel(4);
             let mut ready_bi_h3_streams = mpsc::channel(1);
+            let mut ready_uni_wt_streams = mpsc::channel(4);
+            let mut ready_bi_wt_streams = mpsc::channel(1);
 
             self.open_and_send_settings().await?;
+            self.open_qpack_streams().await?;
 
             loop {
                 tokio::select! {
                     result = Self::accept_uni(&self.quic_connection,
                                               &ready_uni_h3_streams.0,
-                                              &self.ready_uni_wt_streams) => {
+                                              &ready_uni_wt_streams.0) => {
                         result?;
                     }
 
                     result = Self::accept_bi(&self.quic_connection,
+                                             &self.qpack,
                                              &ready_bi_h3_streams.0,
-                                             &self.ready_bi_wt_streams) => {
+                                             &ready_bi_wt_streams.0) => {
                         result?;
                     }
 
-                    result = Self::accept_datagram(&self.quic_connection,
-                                                   &self.ready_datagrams) => {
-                        result?;
+                    datagram = Self::accept_datagram(&self.quic_connection) => {
+                        self.handle_datagram(datagram?);
                     }
 
                     uni_h3_stream = ready_uni_h3_streams.1.recv() => {
@@ -346,8 +895,18 @@ mod worker {
                     }
 
                     bi_h3_stream = ready_bi_h3_streams.1.recv() => {
-                        let (bi_h3_stream, first_frame) = bi_h3_stream.expect("Sender cannot be dropped")?;
-                        self.handle_bi_h3_stream(bi_h3_stream, first_frame)?;
+                        let (bi_h3_stream, first_frame, headers) = bi_h3_stream.expect("Sender cannot be dropped")?;
+                        self.handle_bi_h3_stream(bi_h3_stream, first_frame, headers)?;
+                    }
+
+                    uni_wt_stream = ready_uni_wt_streams.1.recv() => {
+                        let uni_wt_stream = uni_wt_stream.expect("Sender cannot be dropped");
+                        self.handle_uni_wt_stream(uni_wt_stream);
+                    }
+
+                    bi_wt_stream = ready_bi_wt_streams.1.recv() => {
+                        let bi_wt_stream = bi_wt_stream.expect("Sender cannot be dropped");
+                        self.handle_bi_wt_stream(bi_wt_stream);
                     }
 
 
@@ -356,22 +915,24 @@ mod worker {
                         self.handle_remote_settings(settings)?;
                     }
 
-                    stream_session = self.ready_sessions.recv() => {
-                        match stream_session {
-                            Some(stream_session) => {
-                                if self.stream_session.is_none() {
-                                    self.stream_session = Some(stream_session);
-                                }
-                            }
+                    goaway = remote_goaway_watcher.accept_goaway() => {
+                        let goaway = goaway.expect("Channel cannot be dropped");
+                        self.handle_remote_goaway(goaway);
+                    }
+
+                    command = self.session_commands.recv() => {
+                        match command {
+                            Some(command) => self.handle_session_command(command).await?,
                             None => return Err(DriverError::NotConnected),
                         };
                     }
 
                     error = Self::run_control_streams(&mut self.local_settings_stream,
                                                       &mut self.remote_settings_stream,
-                                                      &mut self.remote_qpack_enc_stream,
-                                                      &mut self.remote_qpack_dec_stream,
-                                                      &mut self.stream_session) => {
+                                                      &mut self.qpack_streams,
+                                                      &mut self.sessions,
+                                                      self.registered_sessions,
+                                                      &self.session_counts) => {
                         return Err(error);
                     }
 
@@ -379,27 +940,52 @@ mod worker {
                         return Err(DriverError::NotConnected);
                     }
                 }
+
+                Self::update_session_counts(
+                    &self.sessions,
+                    self.registered_sessions,
+                    &self.session_counts,
+                );
             }
         }
 
         async fn open_and_send_settings(&mut self) -> Result<(), DriverError> {
             assert!(self.local_settings_stream.is_empty());
 
-            let stream = match Stream::open_uni(&self.quic_connection)
+            let stream =
+                Self::open_uni_h3(&self.quic_connection, StreamHeader::new_control()).await?;
+
+            self.local_settings_stream.set_stream(stream);
+            self.local_settings_stream.send_settings().await
+        }
+
+        async fn open_qpack_streams(&mut self) -> Result<(), DriverError> {
+            let encoder =
+                Self::open_uni_h3(&self.quic_connection, StreamHeader::new_qpack_encoder()).await?;
+            let decoder =
+                Self::open_uni_h3(&self.quic_connection, StreamHeader::new_qpack_decoder()).await?;
+
+            self.qpack_streams.set_local_streams(encoder, decoder);
+
+            Ok(())
+        }
+
+        async fn open_uni_h3(
+            quic_connection: &quinn::Connection,
+            stream_header: StreamHeader,
+        ) -> Result<StreamUniLocalH3, DriverError> {
+            match Stream::open_uni(quic_connection)
                 .await
                 .ok_or(DriverError::NotConnected)?
-                .upgrade(StreamHeader::new_control())
+                .upgrade(stream_header)
                 .await
             {
-                Ok(h3_stream) => h3_stream,
-                Err(ProtoWriteError::NotConnected) => return Err(DriverError::NotConnected),
+                Ok(h3_stream) => Ok(h3_stream),
+                Err(ProtoWriteError::NotConnected) => Err(DriverError::NotConnected),
                 Err(ProtoWriteError::Stopped) => {
-                    return Err(DriverError::Proto(ErrorCode::ClosedCriticalStream));
+                    Err(DriverError::Proto(ErrorCode::ClosedCriticalStream))
                 }
-            };
-
-            self.local_settings_stream.set_stream(stream);
-            self.local_settings_stream.send_settings().await
+            }
         }
 
         async fn accept_uni(
@@ -414,10 +1000,11 @@ mod worker {
                 .await
                 .expect("Receiver cannot be dropped");
 
-            let wt_slot = match ready_uni_wt_streams.clone().reserve_owned().await {
-                Ok(wt_slot) => wt_slot,
-                Err(mpsc::error::SendError(_)) => return Err(DriverError::NotConnected),
-            };
+            let wt_slot = ready_uni_wt_streams
+                .clone()
+                .reserve_owned()
+                .await
+                .expect("Receiver cannot be dropped");
 
             let stream_quic = Stream::accept_uni(quic_connection)
                 .await
@@ -457,9 +1044,8 @@ mod worker {
 
         async fn accept_bi(
             quic_connection: &quinn::Connection,
-            ready_bi_h3_streams: &mpsc::Sender<
-                Result<(StreamBiRemoteH3, Frame<'static>), DriverError>,
-            >,
+            qpack: &QPackCodec,
+            ready_bi_h3_streams: &mpsc::Sender<Result<ReadyBiH3Stream, DriverError>>,
             ready_bi_wt_streams: &mpsc::Sender<StreamBiRemoteWT>,
         ) -> Result<(), DriverError> {
             trace!("H3 bi queue capacity: {}", ready_bi_h3_streams.capacity());
@@ -469,10 +1055,11 @@ mod worker {
                 .await
                 .expect("Receiver cannot be dropped");
 
-            let wt_slot = match ready_bi_wt_streams.clone().reserve_owned().await {
-                Ok(wt_slot) => wt_slot,
-                Err(mpsc::error::SendError(_)) => return Err(DriverError::NotConnected),
-            };
+            let wt_slot = ready_bi_wt_streams
+                .clone()
+                .reserve_owned()
+                .await
+                .expect("Receiver cannot be dropped");
 
             let stream_quic = Stream::accept_bi(quic_connection)
                 .await
@@ -481,18 +1068,24 @@ mod worker {
             let stream_id = stream_quic.id();
             debug!("New incoming bi stream ({})", stream_id);
 
+            let qpack = qpack.clone();
+
             tokio::spawn(
                 async move {
                     let mut stream_h3 = stream_quic.upgrade();
 
-                    let frame = match stream_h3.read_frame().await {
-                        Ok(frame) => frame,
-                        Err(ProtoReadError::H3(error_code)) => {
-                            h3_slot.send(Err(DriverError::Proto(error_code)));
-                            return;
-                        }
-                        Err(ProtoReadError::IO(_)) => {
-                            return;
+                    // Reserved (exercise) frames preceding the request are ignored
+                    let frame = loop {
+                        match stream_h3.read_frame().await {
+                            Ok(frame) if matches!(frame.kind(), FrameKind::Exercise(_)) => {}
+                            Ok(frame) => break frame,
+                            Err(ProtoReadError::H3(error_code)) => {
+                                h3_slot.send(Err(DriverError::Proto(error_code)));
+                                return;
+                            }
+                            Err(ProtoReadError::IO(_)) => {
+                                return;
+                            }
                         }
                     };
 
@@ -504,7 +1097,19 @@ mod worker {
                             wt_slot.send(stream_wt);
                         }
                         None => {
-                            h3_slot.send(Ok((stream_h3, frame)));
+                            let headers = if matches!(frame.kind(), FrameKind::Headers) {
+                                match qpack.decode_headers(stream_id, &frame).await {
+                                    Ok(headers) => Some(headers),
+                                    Err(error_code) => {
+                                        h3_slot.send(Err(DriverError::Proto(error_code)));
+                                        return;
+                                    }
+                                }
+                            } else {
+                                None
+                            };
+
+                            h3_slot.send(Ok((stream_h3, frame, headers)));
                         }
                     }
                 }
@@ -516,13 +1121,7 @@ mod worker {
 
         async fn accept_datagram(
             quic_connection: &quinn::Connection,
-            ready_datagrams: &mpsc::Sender<Datagram>,
-        ) -> Result<(), DriverError> {
-            let slot = match ready_datagrams.reserve().await {
-                Ok(slot) => slot,
-                Err(mpsc::error::SendError(_)) => return Err(DriverError::NotConnected),
-            };
-
+        ) -> Result<Datagram, DriverError> {
             let quic_dgram = match quic_connection.read_datagram().await {
                 Ok(quic_dgram) => quic_dgram,
                 Err(_) => return Err(DriverError::NotConnected),
@@ -538,9 +1137,7 @@ mod worker {
                 datagram.session_id()
             );
 
-            slot.send(datagram);
-
-            Ok(())
+            Ok(datagram)
         }
 
         fn handle_uni_h3_stream(&mut self, stream: StreamUniRemoteH3) -> Result<(), DriverError> {
@@ -552,22 +1149,25 @@ mod worker {
 
                     self.remote_settings_stream.set_stream(stream);
                 }
-                StreamKind::QPackEncoder => {
-                    if !self.remote_qpack_enc_stream.is_empty() {
-                        return Err(DriverError::Proto(ErrorCode::StreamCreation));
-                    }
-
-                    self.remote_qpack_enc_stream.set_stream(stream);
+                StreamKind::QPackEncoder | StreamKind::QPackDecoder => {
+                    self.qpack_streams
+                        .set_remote_stream(stream)
+                        .map_err(DriverError::Proto)?;
                 }
-                StreamKind::QPackDecoder => {
-                    if !self.remote_qpack_dec_stream.is_empty() {
+                StreamKind::Push => {
+                    // Only a server can open push streams, and only after MAX_PUSH_ID
+                    // which is never sent
+                    if stream.id().is_client_initiated() {
                         return Err(DriverError::Proto(ErrorCode::StreamCreation));
                     }
 
-                    self.remote_qpack_dec_stream.set_stream(stream);
+                    return Err(DriverError::Proto(ErrorCode::Id));
                 }
                 StreamKind::WebTransport => unreachable!(),
-                StreamKind::Exercise(_) => {}
+                StreamKind::Exercise(_) | StreamKind::Unknown(_) => {
+                    debug!("Ignoring stream of unknown type");
+                    stream.stop(ErrorCode::StreamCreation.to_code());
+                }
             }
 
             Ok(())
@@ -576,54 +1176,150 @@ mod worker {
         #[instrument(skip_all, name = "Stream", fields(id = %stream.id()))]
         fn handle_bi_h3_stream(
             &mut self,
-            mut stream: StreamBiRemoteH3,
+            stream: StreamBiRemoteH3,
             first_frame: Frame<'static>,
+            headers: Option<Headers>,
         ) -> Result<(), DriverError> {
             match first_frame.kind() {
                 FrameKind::Data => {
                     return Err(DriverError::Proto(ErrorCode::FrameUnexpected));
                 }
                 FrameKind::Headers => {
-                    let headers = match Headers::with_frame(&first_frame) {
-                        Ok(headers) => headers,
-                        Err(error_code) => return Err(DriverError::Proto(error_code)),
-                    };
+                    let headers = headers.expect("Headers are decoded along with the frame");
 
                     debug!("Headers: {:?}", headers);
 
-                    let stream_session = match SessionRequest::try_from(headers) {
-                        Ok(session_request) => stream.into_session(session_request),
-                        Err(HeadersParseError::MethodNotConnect) => {
-                            stream
-                                .stop(ErrorCode::RequestRejected.to_code())
-                                .expect("Stream not already stopped");
-                            return Ok(());
-                        }
-                        // TODO(biagio): we might have more granularity with errors
-                        Err(_) => {
-                            stream
-                                .stop(ErrorCode::Message.to_code())
-                                .expect("Stream not already stopped");
-                            return Ok(());
-                        }
-                    };
+                    let stream_id = stream.id();
+                    self.handle_request(stream, headers)?;
 
-                    match self.ready_sessions.try_send(stream_session) {
-                        Ok(()) => {}
-                        Err(TrySendError::Full(mut stream)) => {
-                            debug!("Discarding session request: sessions queue is full");
-                            stream
-                                .stop(ErrorCode::RequestRejected.to_code())
-                                .expect("Stream not already stopped");
-                        }
-                        Err(TrySendError::Closed(_)) => return Err(DriverError::NotConnected),
+                    // Streams and datagrams of a rejected request are never claimed
+                    if let Ok(session_id) = SessionId::try_from_session_stream(stream_id) {
+                        self.discard_pending_session(session_id);
                     }
                 }
-                FrameKind::Settings => {
+                FrameKind::Settings
+                | FrameKind::GoAway
+                | FrameKind::CancelPush
+                | FrameKind::MaxPushId
+                | FrameKind::PriorityUpdateRequest
+                | FrameKind::PriorityUpdatePush => {
                     return Err(DriverError::Proto(ErrorCode::FrameUnexpected));
                 }
                 FrameKind::WebTransport => unreachable!(),
-                FrameKind::Exercise(_) => {}
+                FrameKind::Exercise(_) | FrameKind::Unknown(_) => {}
+            }
+
+            Ok(())
+        }
+
+        fn handle_request(
+            &mut self,
+            mut stream: StreamBiRemoteH3,
+            headers: Headers,
+        ) -> Result<(), DriverError> {
+            let stream_id = stream.id();
+
+            if matches!(self.local_goaway, Some(goaway) if stream_id.into_varint() >= goaway.id()) {
+                debug!("Discarding request: connection is going away");
+                stream
+                    .stop(ErrorCode::RequestRejected.to_code())
+                    .expect("Stream not already stopped");
+                return Ok(());
+            }
+
+            if self
+                .last_request_id
+                .map_or(true, |last| last.into_u64() < stream_id.into_u64())
+            {
+                self.last_request_id = Some(stream_id);
+            }
+
+            if headers.get(":method") != Some("CONNECT") {
+                if let Some(http_handler) = &self.webtransport_config.http_handler {
+                    if !IncomingRequest::validate(&headers) {
+                        stream
+                            .stop(ErrorCode::Message.to_code())
+                            .expect("Stream not already stopped");
+                        return Ok(());
+                    }
+
+                    let request = IncomingRequest::new(
+                        self.quic_connection.clone(),
+                        self.qpack.clone(),
+                        stream.into_stream(),
+                        headers,
+                    );
+
+                    tokio::spawn(http_handler.handle(request));
+                    return Ok(());
+                }
+            }
+
+            if headers.get(":method") == Some("CONNECT")
+                && headers.get(":protocol") == Some(connect_udp::PROTOCOL)
+            {
+                if let Some(handler) = self.webtransport_config.connect_udp_handler.clone() {
+                    self.handle_connect_udp(stream, headers, handler);
+                    return Ok(());
+                }
+            }
+
+            if headers.get(":method") == Some("CONNECT")
+                && headers.get(":protocol") == Some(websocket::PROTOCOL)
+            {
+                if let Some(handler) = self.webtransport_config.websocket_handler.clone() {
+                    self.handle_websocket(stream, headers, handler);
+                    return Ok(());
+                }
+            }
+
+            let mut stream_session = match SessionRequest::try_from(headers) {
+                Ok(session_request) => stream.into_session(session_request),
+                Err(HeadersParseError::MethodNotConnect) => {
+                    stream
+                        .stop(ErrorCode::RequestRejected.to_code())
+                        .expect("Stream not already stopped");
+                    return Ok(());
+                }
+                // TODO(biagio): we might have more granularity with errors
+                Err(_) => {
+                    stream
+                        .stop(ErrorCode::Message.to_code())
+                        .expect("Stream not already stopped");
+                    return Ok(());
+                }
+            };
+
+            self.sessions.retain(|_, slots| !slots.is_closed());
+
+            let active_sessions = self
+                .sessions
+                .values()
+                .filter(|slots| !slots.terminated)
+                .count();
+
+            if active_sessions >= self.webtransport_config.max_sessions as usize {
+                debug!("Discarding session request: too many sessions");
+                stream_session
+                    .stop(ErrorCode::RequestRejected.to_code())
+                    .expect("Stream not already stopped");
+                return Ok(());
+            }
+
+            let session_id = stream_session.session_id();
+            let (slots, queues) = session_channels(session_id);
+
+            match self.ready_sessions.try_send((stream_session, queues)) {
+                Ok(()) => {
+                    self.insert_session(session_id, slots);
+                }
+                Err(mpsc::error::TrySendError::Full((mut stream, _))) => {
+                    debug!("Discarding session request: sessions queue is full");
+                    stream
+                        .stop(ErrorCode::RequestRejected.to_code())
+                        .expect("Stream not already stopped");
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return Err(DriverError::NotConnected),
             }
 
             Ok(())
@@ -632,22 +1328,484 @@ mod worker {
         async fn run_control_streams(
             local_settings: &mut LocalSettingsStream,
             remote_settings: &mut RemoteSettingsStream,
-            remote_qpack_enc: &mut RemoteQPackEncStream,
-            remote_qpack_dec: &mut RemoteQPackDecStream,
-            _stream_session: &mut Option<StreamSession>,
+            qpack_streams: &mut QPackStreams,
+            sessions: &mut HashMap<SessionId, SessionSlots>,
+            registered_sessions: usize,
+            session_counts: &watch::Sender<SessionCounts>,
         ) -> DriverError {
-            // TODO(biagio): run stream_session
             tokio::select! {
                 error = local_settings.run() => error,
                 error = remote_settings.run() => error,
-                error = remote_qpack_enc.run() => error,
-                error = remote_qpack_dec.run() => error,
+                error = qpack_streams.run() => error,
+                error = Self::run_sessions(sessions, registered_sessions, session_counts) => error,
+            }
+        }
+
+        async fn run_sessions(
+            sessions: &mut HashMap<SessionId, SessionSlots>,
+            registered_sessions: usize,
+            session_counts: &watch::Sender<SessionCounts>,
+        ) -> DriverError {
+            std::future::poll_fn(|cx| {
+                let mut terminated = false;
+
+                for slots in sessions.values_mut() {
+                    match slots.poll_session_stream(cx) {
+                        Poll::Ready(Ok(())) => terminated = true,
+                        Poll::Ready(Err(error)) => return Poll::Ready(error),
+                        Poll::Pending => {}
+                    }
+                }
+
+                if terminated {
+                    Self::update_session_counts(sessions, registered_sessions, session_counts);
+                }
+
+                Poll::Pending
+            })
+            .await
+        }
+
+        /// Publishes the number of running sessions.
+        ///
+        /// Every session stream registered is either running or closed, whether it
+        /// terminated or its slots were discarded.
+        fn update_session_counts(
+            sessions: &HashMap<SessionId, SessionSlots>,
+            registered_sessions: usize,
+            session_counts: &watch::Sender<SessionCounts>,
+        ) {
+            let active = sessions.values().filter(|slots| slots.is_running()).count();
+            let counts = SessionCounts {
+                active,
+                closed: registered_sessions - active,
+            };
+
+            session_counts.send_if_modified(|current| {
+                let modified = *current != counts;
+                *current = counts;
+                modified
+            });
+        }
+
+        fn insert_session(&mut self, session_id: SessionId, slots: SessionSlots) {
+            if self.remote_goaway.is_some() {
+                slots.draining.set(());
+            }
+
+            slots
+                .flow_control
+                .set_local_limits(&self.webtransport_config);
+
+            if let Some(settings) = &self.remote_settings {
+                slots.flow_control.set_remote_limits(settings);
+            }
+
+            self.sessions.insert(session_id, slots);
+
+            if self.last_session_id.map_or(true, |last| last < session_id) {
+                self.last_session_id = Some(session_id);
+            }
+
+            if let Some(pending) = self.pending_sessions.remove(&session_id) {
+                for incoming in pending.incoming {
+                    match incoming {
+                        EarlyIncoming::UniStream(stream) => self.handle_uni_wt_stream(stream),
+                        EarlyIncoming::BiStream(stream) => self.handle_bi_wt_stream(stream),
+                        EarlyIncoming::Datagram(datagram) => self.handle_datagram(datagram),
+                    }
+                }
+            }
+        }
+
+        fn handle_connect_udp(
+            &mut self,
+            mut stream: StreamBiRemoteH3,
+            headers: Headers,
+            handler: SharedConnectUdpHandler,
+        ) {
+            let session_id = match SessionId::try_from_session_stream(stream.id()) {
+                Ok(session_id) if UdpTunnelRequest::validate(&headers) => session_id,
+                _ => {
+                    stream
+                        .stop(ErrorCode::Message.to_code())
+                        .expect("Stream not already stopped");
+                    return;
+                }
+            };
+
+            let path = headers.get(":path").expect("Validated request");
+
+            let Some((target_host, target_port)) = handler.template().match_path(path) else {
+                debug!("CONNECT-UDP request does not match the URI template");
+
+                let (mut send_stream, mut recv_stream) = stream.into_stream();
+                let _ = recv_stream.stop(ErrorCode::NoError.to_code());
+                let qpack = self.qpack.clone();
+
+                tokio::spawn(async move {
+                    let _ = Response::new(400).send(&mut send_stream, &qpack).await;
+                });
+
+                return;
+            };
+
+            let (sender, receiver) = mpsc::channel(SESSION_DATAGRAMS_CAPACITY);
+            self.insert_tunnel(session_id, sender);
+
+            let request = UdpTunnelRequest::new(
+                self.quic_connection.clone(),
+                self.qpack.clone(),
+                stream.into_stream(),
+                headers,
+                (target_host, target_port),
+                receiver,
+            );
+
+            tokio::spawn(handler.handle(request));
+        }
+
+        fn handle_websocket(
+            &mut self,
+            mut stream: StreamBiRemoteH3,
+            headers: Headers,
+            handler: SharedWebSocketHandler,
+        ) {
+            match WebSocketRequest::validate(&headers) {
+                Ok(()) => {}
+                Err(None) => {
+                    stream
+                        .stop(ErrorCode::Message.to_code())
+                        .expect("Stream not already stopped");
+                    return;
+                }
+                Err(Some(response)) => {
+                    let (mut send_stream, mut recv_stream) = stream.into_stream();
+                    let _ = recv_stream.stop(ErrorCode::NoError.to_code());
+                    let qpack = self.qpack.clone();
+
+                    tokio::spawn(async move {
+                        let _ = response.send(&mut send_stream, &qpack).await;
+                    });
+
+                    return;
+                }
+            }
+
+            let request = WebSocketRequest::new(
+                self.quic_connection.clone(),
+                self.qpack.clone(),
+                stream.into_stream(),
+                headers,
+            );
+
+            tokio::spawn(handler.handle(request));
+        }
+
+        fn insert_tunnel(&mut self, session_id: SessionId, datagrams: mpsc::Sender<Datagram>) {
+            self.tunnels.retain(|_, tunnel| !tunnel.is_closed());
+            self.tunnels.insert(session_id, datagrams);
+
+            if let Some(pending) = self.pending_sessions.remove(&session_id) {
+                for incoming in pending.incoming {
+                    match incoming {
+                        EarlyIncoming::Datagram(datagram) => self.handle_datagram(datagram),
+                        incoming => incoming.reject(),
+                    }
+                }
+            }
+        }
+
+        /// Holds a stream or datagram whose session request might not be processed yet.
+        ///
+        /// It is rejected if its session cannot be requested anymore, or once the
+        /// buffer limits are exceeded.
+        fn buffer_early_incoming(&mut self, session_id: SessionId, incoming: EarlyIncoming) {
+            let session_stream = session_id.session_stream().into_u64();
+
+            let expected = self
+                .last_request_id
+                .map_or(true, |last| last.into_u64() < session_stream)
+                && self.last_session_id.map_or(true, |last| last < session_id);
+
+            let admitted = self.pending_sessions.contains_key(&session_id)
+                || self.pending_sessions.len() < self.webtransport_config.max_sessions as usize;
+
+            if !expected || !admitted {
+                incoming.reject();
+                return;
+            }
+
+            let pending = self.pending_sessions.entry(session_id).or_default();
+
+            let accepted = match &incoming {
+                EarlyIncoming::UniStream(_) | EarlyIncoming::BiStream(_) => {
+                    pending.buffer.push_stream(&self.webtransport_config)
+                }
+                EarlyIncoming::Datagram(datagram) => pending
+                    .buffer
+                    .push_datagram(datagram.len(), &self.webtransport_config),
+            };
+
+            if accepted {
+                trace!("Buffering early incoming (session_id: {})", session_id);
+                pending.incoming.push(incoming);
+            } else {
+                debug!("Session buffer is full (session_id: {})", session_id);
+                incoming.reject();
+            }
+        }
+
+        fn discard_pending_session(&mut self, session_id: SessionId) {
+            if let Some(pending) = self.pending_sessions.remove(&session_id) {
+                pending.incoming.into_iter().for_each(EarlyIncoming::reject);
+            }
+        }
+
+        fn handle_uni_wt_stream(&mut self, stream: StreamUniRemoteWT) {
+            let session_id = stream.session_id();
+
+            let stream = match self.sessions.get_mut(&session_id) {
+                Some(slots) if slots.terminated => {
+                    debug!(
+                        "Resetting WT stream of terminated session (stream_id: {}, session_id: {})",
+                        stream.id(),
+                        session_id
+                    );
+                    stream.stop(ErrorCode::SessionGone.to_code());
+                    return;
+                }
+                Some(slots) if !slots.flow_control.on_stream_received(StreamDirection::Uni) => {
+                    debug!(
+                        "Session limit on streams exceeded (stream_id: {}, session_id: {})",
+                        stream.id(),
+                        session_id
+                    );
+                    stream.stop(ErrorCode::FlowControl.to_code());
+                    return;
+                }
+                Some(slots) => {
+                    if !slots.is_running() && !slots.buffer.push_stream(&self.webtransport_config) {
+                        debug!("Session buffer is full (session_id: {})", session_id);
+                        stream
+                    } else {
+                        let stream_id = stream.id();
+                        let stream = InboundStream::spawn(
+                            stream.into_stream(),
+                            slots.flow_control.clone(),
+                            &slots.streams,
+                        );
+
+                        if let Err(mpsc::error::SendError(stream)) = slots.uni_streams.send(stream)
+                        {
+                            debug!(
+                                "Discarding WT stream (stream_id: {}, session_id: {})",
+                                stream_id, session_id
+                            );
+
+                            self.sessions.remove(&session_id);
+                            stream.stop(ErrorCode::BufferedStreamRejected.to_code());
+                        }
+
+                        return;
+                    }
+                }
+                None => {
+                    self.buffer_early_incoming(session_id, EarlyIncoming::UniStream(stream));
+                    return;
+                }
+            };
+
+            debug!(
+                "Discarding WT stream (stream_id: {}, session_id: {})",
+                stream.id(),
+                session_id
+            );
+
+            stream
+                .into_stream()
+                .stop(ErrorCode::BufferedStreamRejected.to_code())
+                .expect("Stream not already stopped");
+        }
+
+        fn handle_bi_wt_stream(&mut self, stream: StreamBiRemoteWT) {
+            let session_id = stream.session_id();
+
+            let stream = match self.sessions.get_mut(&session_id) {
+                Some(slots) if slots.terminated => {
+                    debug!(
+                        "Resetting WT stream of terminated session (stream_id: {}, session_id: {})",
+                        stream.id(),
+                        session_id
+                    );
+                    stream.reset(ErrorCode::SessionGone.to_code());
+                    return;
+                }
+                Some(slots) if !slots.flow_control.on_stream_received(StreamDirection::Bi) => {
+                    debug!(
+                        "Session limit on streams exceeded (stream_id: {}, session_id: {})",
+                        stream.id(),
+                        session_id
+                    );
+                    stream.reset(ErrorCode::FlowControl.to_code());
+                    return;
+                }
+                Some(slots) => {
+                    if !slots.is_running() && !slots.buffer.push_stream(&self.webtransport_config) {
+                        debug!("Session buffer is full (session_id: {})", session_id);
+                        stream
+                    } else {
+                        let stream_id = stream.id();
+                        let (send, recv) = stream.into_stream();
+                        let recv =
+                            InboundStream::spawn(recv, slots.flow_control.clone(), &slots.streams);
+
+                        if let Err(mpsc::error::SendError((_send, recv))) =
+                            slots.bi_streams.send((send, recv))
+                        {
+                            debug!(
+                                "Discarding WT stream (stream_id: {}, session_id: {})",
+                                stream_id, session_id
+                            );
+
+                            self.sessions.remove(&session_id);
+                            recv.stop(ErrorCode::BufferedStreamRejected.to_code());
+                        }
+
+                        return;
+                    }
+                }
+                None => {
+                    self.buffer_early_incoming(session_id, EarlyIncoming::BiStream(stream));
+                    return;
+                }
+            };
+
+            debug!(
+                "Discarding WT stream (stream_id: {}, session_id: {})",
+                stream.id(),
+                session_id
+            );
+
+            stream
+                .into_stream()
+                .1
+                .stop(ErrorCode::BufferedStreamRejected.to_code())
+                .expect("Stream not already stopped");
+        }
+
+        fn handle_datagram(&mut self, datagram: Datagram) {
+            let session_id = datagram.session_id();
+
+            if let Some(tunnel) = self.tunnels.get(&session_id) {
+                match tunnel.try_send(datagram) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        debug!("Incoming datagram discarded: tunnel queue is full");
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => {
+                        debug!("Incoming datagram discarded (tunnel_id: {})", session_id);
+                        self.tunnels.remove(&session_id);
+                    }
+                }
+
+                return;
+            }
+
+            match self.sessions.get_mut(&session_id) {
+                Some(slots) if slots.terminated => {
+                    debug!("Incoming datagram discarded: session terminated");
+                }
+                Some(slots) => {
+                    if !slots.is_running()
+                        && !slots
+                            .buffer
+                            .push_datagram(datagram.len(), &self.webtransport_config)
+                    {
+                        debug!("Incoming datagram discarded: session buffer is full");
+                        return;
+                    }
+
+                    match slots.datagrams.try_send(datagram) {
+                        Ok(()) => {}
+                        Err(mpsc::error::TrySendError::Full(_)) => {
+                            debug!("Incoming datagram discarded: session queue is full");
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => {
+                            debug!("Incoming datagram discarded (session_id: {})", session_id);
+                            self.sessions.remove(&session_id);
+                        }
+                    }
+                }
+                None => {
+                    self.buffer_early_incoming(session_id, EarlyIncoming::Datagram(datagram));
+                }
+            }
+        }
+
+        async fn handle_session_command(
+            &mut self,
+            command: SessionCommand,
+        ) -> Result<(), DriverError> {
+            match command {
+                SessionCommand::Open(session_id, slots) => {
+                    self.insert_session(session_id, slots);
+                }
+                SessionCommand::Tunnel(session_id, datagrams) => {
+                    self.insert_tunnel(session_id, datagrams);
+                }
+                SessionCommand::Register(stream_session) => {
+                    match self.sessions.get_mut(&stream_session.session_id()) {
+                        Some(slots) => {
+                            if slots.register(stream_session) {
+                                self.registered_sessions += 1;
+                            }
+                        }
+                        None => debug!("Session {} is already closed", stream_session.session_id()),
+                    }
+                }
+                SessionCommand::GoAway => {
+                    if self.local_goaway.is_some() {
+                        return Ok(());
+                    }
+
+                    // Requests up to the last one received might still be processed
+                    let id = self.last_request_id.map_or(VarInt::from_u32(0), |last| {
+                        VarInt::try_from_u64(last.into_u64() + 4).expect("Stream ID within bounds")
+                    });
+
+                    let goaway = GoAway::new(id);
+                    debug!("Sending GOAWAY (id: {})", id);
+
+                    self.local_settings_stream.send_goaway(goaway).await?;
+                    self.local_goaway = Some(goaway);
+                }
+            }
+
+            Ok(())
+        }
+
+        fn handle_remote_goaway(&mut self, goaway: GoAway) {
+            debug!("Received GOAWAY (id: {})", goaway.id());
+
+            self.remote_goaway = Some(goaway);
+
+            for slots in self.sessions.values() {
+                slots.draining.set(());
             }
         }
 
         fn handle_remote_settings(&mut self, settings: Settings) -> Result<(), DriverError> {
             debug!("Received: {:?}", settings);
 
+            self.qpack.set_peer_settings(&settings);
+
+            for slots in self.sessions.values() {
+                slots.flow_control.set_remote_limits(&settings);
+            }
+
+            self.remote_settings = Some(settings.clone());
+
             match self.ready_settings.try_send(settings) {
                 Ok(()) => Ok(()),
                 Err(mpsc::error::TrySendError::Closed(_)) => Err(DriverError::NotConnected),
@@ -659,5 +1817,7 @@ mod worker {
     }
 }
 
+pub(crate) mod flow_control;
+pub(crate) mod session;
 pub(crate) mod streams;
 pub(crate) mod utils;
@@ -0,0 +1,321 @@
+use crate::driver::flow_control::SessionFlowControl;
+use crate::driver::streams::session::StreamSession;
+use crate::driver::utils::varint_q2w;
+use crate::driver::utils::SharedResultSet;
+use crate::driver::DriverError;
+use crate::error::SessionClose;
+use tokio::sync::mpsc;
+use tracing::debug;
+use wtransport_proto::bytes::BufferReader;
+use wtransport_proto::capsule::capsules::CloseWebTransportSession;
+use wtransport_proto::capsule::capsules::DrainWebTransportSession;
+use wtransport_proto::capsule::capsules::WtDataBlocked;
+use wtransport_proto::capsule::capsules::WtMaxData;
+use wtransport_proto::capsule::capsules::WtMaxStreams;
+use wtransport_proto::capsule::capsules::WtStreamsBlocked;
+use wtransport_proto::capsule::Capsule;
+use wtransport_proto::capsule::CapsuleKind;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::varint::VarInt;
+
+/// How a WebTransport session terminated.
+#[derive(Clone, Debug)]
+pub enum SessionTermination {
+    /// The peer closed the session (capsule or clean FIN).
+    Closed(SessionClose),
+
+    /// The session has been closed locally.
+    LocallyClosed,
+
+    /// The peer reset the session stream.
+    Reset(VarInt),
+
+    /// The session stream violated the protocol and it has been aborted.
+    Aborted(ErrorCode),
+}
+
+/// A local request for the session stream.
+#[derive(Debug)]
+pub enum SessionAction {
+    /// Sends a `DRAIN_WEBTRANSPORT_SESSION` capsule.
+    Drain,
+
+    /// Sends a `CLOSE_WEBTRANSPORT_SESSION` capsule and finishes the stream.
+    Close(CloseWebTransportSession),
+}
+
+/// Runs the session stream until the session terminates.
+///
+/// It parses capsules carried on the session stream and performs local [`SessionAction`]s.
+/// A FIN or a reset of the stream, as well as unexpected frames, terminate the session.
+/// When the peer sends `DRAIN_WEBTRANSPORT_SESSION`, `draining` is set.
+/// Flow control capsules are exchanged on behalf of `flow_control`.
+///
+/// Returns [`Err`] only on errors affecting the whole connection.
+pub async fn run(
+    mut stream: StreamSession,
+    mut actions: mpsc::UnboundedReceiver<SessionAction>,
+    draining: SharedResultSet<()>,
+    flow_control: SessionFlowControl,
+) -> Result<SessionTermination, DriverError> {
+    let mut reader = SessionReader::new(draining, flow_control.clone());
+
+    let termination = loop {
+        tokio::select! {
+            result = reader.read(&mut stream) => {
+                match result? {
+                    Some(termination) => break termination,
+                    None => continue,
+                }
+            }
+
+            action = actions.recv() => {
+                let close = match action {
+                    Some(SessionAction::Drain) => {
+                        debug!("Draining session");
+
+                        if stream.write_capsule(DrainWebTransportSession.generate_capsule()).await.is_err() {
+                            debug!("Cannot send drain capsule");
+                        }
+
+                        continue;
+                    }
+                    Some(SessionAction::Close(close)) => close,
+                    // The sender is dropped along with the connection handle: finish the stream anyway.
+                    None => CloseWebTransportSession::new(0, ""),
+                };
+
+                debug!("Closing session (code: {})", close.error_code());
+
+                if stream.write_capsule(close.generate_capsule()).await.is_err() {
+                    debug!("Cannot send close capsule");
+                }
+
+                break SessionTermination::LocallyClosed;
+            }
+
+            () = flow_control.events() => {
+                for capsule in flow_control.take_capsules() {
+                    if stream.write_capsule(capsule).await.is_err() {
+                        debug!("Cannot send flow control capsule");
+                    }
+                }
+
+                if let Some(error_code) = flow_control.error() {
+                    debug!("Session flow control violated by peer");
+                    break SessionTermination::Aborted(error_code);
+                }
+            }
+        }
+    };
+
+    match &termination {
+        SessionTermination::Aborted(error_code) => stream.reset(error_code.to_code()),
+        _ => stream.finish().await,
+    }
+
+    Ok(termination)
+}
+
+/// Incremental parser of frames and capsules received on a session stream.
+///
+/// Data is buffered until an entire frame or capsule can be parsed. Payloads of capsules
+/// of unknown type are discarded as they are received, whatever their size. Buffers
+/// exceeding [`Self::MAX_BUFFERED`] abort the session with `H3_MESSAGE_ERROR`.
+struct SessionReader {
+    chunk: Box<[u8]>,
+    frames: Vec<u8>,
+    capsules: Vec<u8>,
+    /// Payload still to be discarded of a capsule of unknown type.
+    skip: u64,
+    draining: SharedResultSet<()>,
+    flow_control: SessionFlowControl,
+}
+
+impl SessionReader {
+    const CHUNK_SIZE: usize = 4096;
+
+    /// Large enough for the biggest capsule which can be parsed, along with its header.
+    const MAX_BUFFERED: usize = 65535 + 16;
+
+    fn new(draining: SharedResultSet<()>, flow_control: SessionFlowControl) -> Self {
+        Self {
+            chunk: vec![0; Self::CHUNK_SIZE].into_boxed_slice(),
+            frames: Vec::new(),
+            capsules: Vec::new(),
+            skip: 0,
+            draining,
+            flow_control,
+        }
+    }
+
+    /// Reads the next chunk of data from the stream.
+    ///
+    /// Returns the session termination, if any.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    async fn read(
+        &mut self,
+        stream: &mut StreamSession,
+    ) -> Result<Option<SessionTermination>, DriverError> {
+        match stream.read(&mut self.chunk).await {
+            Ok(Some(read)) => {
+                self.frames.extend_from_slice(&self.chunk[..read]);
+                let termination = self.process(stream)?;
+
+                if termination.is_none()
+                    && (self.frames.len() > Self::MAX_BUFFERED
+                        || self.capsules.len() > Self::MAX_BUFFERED)
+                {
+                    debug!("Session stream buffer limit exceeded");
+                    return Ok(Some(SessionTermination::Aborted(ErrorCode::Message)));
+                }
+
+                Ok(termination)
+            }
+            Ok(None) => {
+                if !self.frames.is_empty() {
+                    return Err(DriverError::Proto(ErrorCode::Frame));
+                }
+
+                if !self.capsules.is_empty() || self.skip > 0 {
+                    return Ok(Some(SessionTermination::Aborted(ErrorCode::Message)));
+                }
+
+                debug!("Session stream finished");
+                Ok(Some(SessionTermination::Closed(SessionClose::default())))
+            }
+            Err(quinn::ReadError::Reset(error_code)) => {
+                let error_code = varint_q2w(error_code);
+                debug!("Session stream reset (code: {})", error_code);
+                Ok(Some(SessionTermination::Reset(error_code)))
+            }
+            Err(_) => Err(DriverError::NotConnected),
+        }
+    }
+
+    fn process(
+        &mut self,
+        stream: &StreamSession,
+    ) -> Result<Option<SessionTermination>, DriverError> {
+        let mut buffer_reader = BufferReader::new(&self.frames);
+
+        while let Some(frame) = stream
+            .read_frame_from_buffer(&mut buffer_reader)
+            .map_err(DriverError::Proto)?
+        {
+            match frame.kind() {
+                FrameKind::Data => self.capsules.extend_from_slice(frame.payload()),
+                FrameKind::Exercise(_) => {}
+                kind => {
+                    debug!("Unexpected frame on session stream: {:?}", kind);
+                    return Ok(Some(SessionTermination::Aborted(ErrorCode::Message)));
+                }
+            }
+        }
+
+        let consumed = buffer_reader.offset();
+        self.frames.drain(..consumed);
+
+        let mut buffer_reader = BufferReader::new(&self.capsules);
+        let mut termination = None;
+
+        loop {
+            if self.skip > 0 {
+                let skipped = self.skip.min(buffer_reader.capacity() as u64);
+                buffer_reader
+                    .skip(skipped as usize)
+                    .expect("Skipped data is buffered");
+                self.skip -= skipped;
+
+                if self.skip > 0 {
+                    break;
+                }
+            }
+
+            if let Some(payload_len) = Capsule::read_unknown_header(&mut buffer_reader) {
+                self.skip = payload_len;
+                continue;
+            }
+
+            let capsule = match Capsule::read_from_buffer(&mut buffer_reader) {
+                Ok(Some(capsule)) => capsule,
+                Ok(None) => break,
+                Err(_) => {
+                    termination = Some(SessionTermination::Aborted(ErrorCode::Message));
+                    break;
+                }
+            };
+
+            match capsule.kind() {
+                CapsuleKind::CloseWebTransportSession => {
+                    termination = Some(match CloseWebTransportSession::with_capsule(&capsule) {
+                        Ok(close) => {
+                            debug!("Session closed by peer (code: {})", close.error_code());
+                            SessionTermination::Closed(SessionClose::new(
+                                close.error_code(),
+                                close.reason().to_string(),
+                            ))
+                        }
+                        Err(error_code) => SessionTermination::Aborted(error_code),
+                    });
+                    break;
+                }
+                CapsuleKind::DrainWebTransportSession => {
+                    if let Err(error_code) = DrainWebTransportSession::with_capsule(&capsule) {
+                        termination = Some(SessionTermination::Aborted(error_code));
+                        break;
+                    }
+
+                    debug!("Session drain requested by peer");
+                    self.draining.set(());
+                }
+                CapsuleKind::WtMaxData => match WtMaxData::with_capsule(&capsule) {
+                    Ok(max_data) => self.flow_control.on_max_data(max_data),
+                    Err(error_code) => {
+                        termination = Some(SessionTermination::Aborted(error_code));
+                        break;
+                    }
+                },
+                CapsuleKind::WtMaxStreamsBidi | CapsuleKind::WtMaxStreamsUni => {
+                    match WtMaxStreams::with_capsule(&capsule) {
+                        Ok(max_streams) => self.flow_control.on_max_streams(max_streams),
+                        Err(error_code) => {
+                            termination = Some(SessionTermination::Aborted(error_code));
+                            break;
+                        }
+                    }
+                }
+                CapsuleKind::WtDataBlocked => match WtDataBlocked::with_capsule(&capsule) {
+                    Ok(blocked) => debug!("Peer blocked on data (limit: {})", blocked.max_data()),
+                    Err(error_code) => {
+                        termination = Some(SessionTermination::Aborted(error_code));
+                        break;
+                    }
+                },
+                CapsuleKind::WtStreamsBlockedBidi | CapsuleKind::WtStreamsBlockedUni => {
+                    match WtStreamsBlocked::with_capsule(&capsule) {
+                        Ok(blocked) => debug!(
+                            "Peer blocked on streams (direction: {:?}, limit: {})",
+                            blocked.direction(),
+                            blocked.max_streams()
+                        ),
+                        Err(error_code) => {
+                            termination = Some(SessionTermination::Aborted(error_code));
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let consumed = buffer_reader.offset();
+        self.capsules.drain(..consumed);
+
+        Ok(termination)
+    }
+}
@@ -0,0 +1,239 @@
+use crate::driver::flow_control::SessionFlowControl;
+use crate::driver::streams::QuicRecvStream;
+use crate::driver::streams::SessionStreams;
+use crate::error::StreamReadError;
+use bytes::Buf;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use tokio::io::ReadBuf;
+use tokio::sync::Notify;
+use tracing::trace;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::varint::VarInt;
+
+/// Bytes read ahead on a stream whose session does not limit data.
+const READ_AHEAD: usize = 64 * 1024;
+
+/// Receive side of a WebTransport stream.
+///
+/// Data is received in the background as soon as it arrives, regardless of the
+/// application reading it, so that it is accounted in the session limit on data
+/// (see [`SessionFlowControl::on_data_received`]). Credit is given back to the peer as
+/// the application reads.
+///
+/// The stream is stopped with `WEBTRANSPORT_SESSION_GONE` as soon as its session is gone.
+#[derive(Debug)]
+pub struct InboundStream {
+    id: StreamId,
+    shared: Arc<Shared>,
+    flow_control: SessionFlowControl,
+}
+
+impl InboundStream {
+    /// Starts receiving data on `stream` on behalf of the session.
+    pub fn spawn(
+        stream: QuicRecvStream,
+        flow_control: SessionFlowControl,
+        session: &SessionStreams,
+    ) -> Self {
+        let id = stream.id();
+        let read_ahead = if flow_control.limits_data() {
+            usize::MAX
+        } else {
+            READ_AHEAD
+        };
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            events: Notify::new(),
+        });
+
+        tokio::spawn(run(
+            stream,
+            shared.clone(),
+            flow_control.clone(),
+            session.clone(),
+            read_ahead,
+        ));
+
+        Self {
+            id,
+            shared,
+            flow_control,
+        }
+    }
+
+    /// Reads buffered data into `buf`.
+    ///
+    /// Nothing is read (and it is ready) once the stream is finished.
+    pub fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<(), StreamReadError>> {
+        let mut state = self.shared.lock();
+        let mut read = 0;
+
+        while buf.remaining() > 0 {
+            let Some(chunk) = state.chunks.front_mut() else {
+                break;
+            };
+
+            let len = chunk.len().min(buf.remaining());
+            buf.put_slice(&chunk[..len]);
+            chunk.advance(len);
+            read += len;
+
+            if chunk.is_empty() {
+                state.chunks.pop_front();
+            }
+        }
+
+        if read > 0 {
+            state.buffered -= read;
+            drop(state);
+
+            self.shared.events.notify_one();
+            self.flow_control.on_data_read(read);
+            return Poll::Ready(Ok(()));
+        }
+
+        match &state.end {
+            Some(End::Finished) => Poll::Ready(Ok(())),
+            Some(End::Failed(error)) => Poll::Ready(Err(error.clone().into())),
+            Some(End::SessionGone) => Poll::Ready(Err(StreamReadError::SessionGone)),
+            None => {
+                state.reader = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Stops receiving data, notifying the peer with `error_code`.
+    pub fn stop(self, error_code: VarInt) {
+        self.shared.lock().stop.get_or_insert(error_code);
+        self.shared.events.notify_one();
+    }
+
+    #[inline(always)]
+    pub fn id(&self) -> StreamId {
+        self.id
+    }
+}
+
+impl Drop for InboundStream {
+    fn drop(&mut self) {
+        // Same as dropping a QUIC stream not read to the end
+        self.shared.lock().stop.get_or_insert(VarInt::from_u32(0));
+        self.shared.events.notify_one();
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notifies the background task of reads and stop requests.
+    events: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("Inbound stream lock not poisoned")
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    chunks: VecDeque<Bytes>,
+    buffered: usize,
+    end: Option<End>,
+    stop: Option<VarInt>,
+    reader: Option<Waker>,
+}
+
+impl State {
+    fn end(&mut self, end: End) {
+        self.end = Some(end);
+
+        if let Some(reader) = self.reader.take() {
+            reader.wake();
+        }
+    }
+}
+
+#[derive(Debug)]
+enum End {
+    Finished,
+    Failed(quinn::ReadError),
+    SessionGone,
+}
+
+async fn run(
+    mut stream: QuicRecvStream,
+    shared: Arc<Shared>,
+    flow_control: SessionFlowControl,
+    session: SessionStreams,
+    read_ahead: usize,
+) {
+    let session = session.watch();
+
+    loop {
+        let (stop, full) = {
+            let state = shared.lock();
+            (state.stop, state.buffered >= read_ahead)
+        };
+
+        if let Some(error_code) = stop {
+            let _ = stream.stop(error_code);
+            return;
+        }
+
+        tokio::select! {
+            biased;
+
+            () = poll_fn(|cx| session.poll_terminated(cx)) => {
+                trace!("Session gone: stopping stream {}", stream.id());
+                let _ = stream.stop(ErrorCode::SessionGone.to_code());
+
+                let mut state = shared.lock();
+                state.chunks.clear();
+                state.end(End::SessionGone);
+                return;
+            }
+            () = shared.events.notified() => {}
+            chunk = stream.read_chunk(), if !full => {
+                let mut state = shared.lock();
+
+                match chunk {
+                    Ok(Some(chunk)) => {
+                        // A violation aborts the session, stopping the stream
+                        flow_control.on_data_received(chunk.len());
+
+                        state.buffered += chunk.len();
+                        state.chunks.push_back(chunk);
+
+                        if let Some(reader) = state.reader.take() {
+                            reader.wake();
+                        }
+                    }
+                    Ok(None) => {
+                        state.end(End::Finished);
+                        return;
+                    }
+                    Err(error) => {
+                        state.end(End::Failed(error));
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
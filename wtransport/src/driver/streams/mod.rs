@@ -1,14 +1,23 @@
 use crate::driver::utils::streamid_q2w;
 use crate::driver::utils::varint_q2w;
 use crate::driver::utils::varint_w2q;
+use crate::error::HttpError;
 use crate::error::StreamReadError;
-use crate::error::StreamReadExactError;
 use crate::error::StreamWriteError;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::poll_fn;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
 use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
+use std::task::Waker;
 use tokio::io::ReadBuf;
+use wtransport_proto::error::http3_to_webtransport_code;
+use wtransport_proto::error::ErrorCode;
 use wtransport_proto::frame::Frame;
 use wtransport_proto::ids::SessionId;
 use wtransport_proto::ids::StreamId;
@@ -25,26 +34,43 @@ pub type ProtoWriteError = wtransport_proto::stream::IoWriteError;
 #[derive(Debug)]
 pub struct AlreadyStop;
 
+/// Maps an I/O error of a quinn stream back to the originating stream error.
+fn io_to_quic_error<E>(io_error: std::io::Error) -> E
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    *io_error
+        .into_inner()
+        .expect("quinn streams only fail with their own errors")
+        .downcast::<E>()
+        .expect("quinn streams only fail with their own errors")
+}
+
 #[derive(Debug)]
 pub struct QuicSendStream(quinn::SendStream);
 
 impl QuicSendStream {
+    pub fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, quinn::WriteError>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf).map_err(io_to_quic_error)
+    }
+
     #[inline(always)]
-    pub async fn write(&mut self, buf: &[u8]) -> Result<usize, StreamWriteError> {
-        let written = self.0.write(buf).await?;
-        Ok(written)
+    pub async fn write(&mut self, buf: &[u8]) -> Result<usize, quinn::WriteError> {
+        self.0.write(buf).await
     }
 
     #[inline(always)]
-    pub async fn write_all(&mut self, buf: &[u8]) -> Result<(), StreamWriteError> {
-        self.0.write_all(buf).await?;
-        Ok(())
+    pub fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), quinn::WriteError>> {
+        self.0.poll_finish(cx)
     }
 
     #[inline(always)]
-    pub async fn finish(&mut self) -> Result<(), StreamWriteError> {
-        self.0.finish().await?;
-        Ok(())
+    pub async fn finish(&mut self) -> Result<(), quinn::WriteError> {
+        self.0.finish().await
     }
 
     #[inline(always)]
@@ -57,20 +83,41 @@ impl QuicSendStream {
         self.0.priority().expect("Stream has been reset")
     }
 
-    pub async fn stopped(&mut self) -> StreamWriteError {
-        match self.0.stopped().await {
-            Ok(code) => StreamWriteError::Stopped(varint_q2w(code)),
-            Err(quinn::StoppedError::ConnectionLost(_)) => StreamWriteError::NotConnected,
-            Err(quinn::StoppedError::UnknownStream) => StreamWriteError::QuicProto,
-            Err(quinn::StoppedError::ZeroRttRejected) => StreamWriteError::QuicProto,
+    pub fn poll_stopped(&mut self, cx: &mut Context<'_>) -> Poll<quinn::WriteError> {
+        match ready!(self.0.poll_stopped(cx)) {
+            Ok(code) => Poll::Ready(quinn::WriteError::Stopped(code)),
+            Err(quinn::StoppedError::ConnectionLost(error)) => {
+                Poll::Ready(quinn::WriteError::ConnectionLost(error))
+            }
+            Err(quinn::StoppedError::UnknownStream) => {
+                Poll::Ready(quinn::WriteError::UnknownStream)
+            }
+            Err(quinn::StoppedError::ZeroRttRejected) => {
+                Poll::Ready(quinn::WriteError::ZeroRttRejected)
+            }
         }
     }
 
+    #[inline(always)]
+    pub async fn stopped(&mut self) -> quinn::WriteError {
+        poll_fn(|cx| self.poll_stopped(cx)).await
+    }
+
+    /// Resets the stream.
+    ///
+    /// It has no effect if the stream has been already reset (e.g., because its
+    /// session is gone).
     #[inline(always)]
     pub fn reset(mut self, error_code: VarInt) {
-        self.0
-            .reset(varint_w2q(error_code))
-            .expect("Stream has been already reset");
+        self.reset_mut(error_code);
+    }
+
+    /// Resets the stream, keeping it around.
+    ///
+    /// It has no effect if the stream has been already reset.
+    #[inline(always)]
+    pub fn reset_mut(&mut self, error_code: VarInt) {
+        let _ = self.0.reset(varint_w2q(error_code));
     }
 
     #[inline(always)]
@@ -146,23 +193,21 @@ impl tokio::io::AsyncWrite for QuicSendStream {
 pub struct QuicRecvStream(quinn::RecvStream);
 
 impl QuicRecvStream {
+    /// Reads the next chunk of data, in order.
+    ///
+    /// Returns `None` once the stream is finished.
     #[inline(always)]
-    pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, StreamReadError> {
-        match self.0.read(buf).await? {
-            Some(read) => Ok(Some(read)),
-            None => Ok(None),
-        }
+    pub async fn read_chunk(&mut self) -> Result<Option<Bytes>, quinn::ReadError> {
+        Ok(self
+            .0
+            .read_chunk(usize::MAX, true)
+            .await?
+            .map(|chunk| chunk.bytes))
     }
 
     #[inline(always)]
-    pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), StreamReadExactError> {
-        self.0
-            .read_exact(buf)
-            .await
-            .map_err(|quic_error| match quic_error {
-                quinn::ReadExactError::FinishedEarly => StreamReadExactError::FinishedEarly,
-                quinn::ReadExactError::ReadError(read) => StreamReadExactError::Read(read.into()),
-            })
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, quinn::ReadError> {
+        self.0.read(buf).await
     }
 
     #[inline(always)]
@@ -174,18 +219,6 @@ impl QuicRecvStream {
     pub fn id(&self) -> StreamId {
         streamid_q2w(self.0.id())
     }
-
-    #[cfg(feature = "quinn")]
-    #[inline(always)]
-    pub fn quic_stream(&self) -> &quinn::RecvStream {
-        &self.0
-    }
-
-    #[cfg(feature = "quinn")]
-    #[inline(always)]
-    pub fn quic_stream_mut(&mut self) -> &mut quinn::RecvStream {
-        &mut self.0
-    }
 }
 
 impl wtransport_proto::bytes::AsyncRead for QuicRecvStream {
@@ -219,6 +252,83 @@ impl tokio::io::AsyncRead for QuicRecvStream {
     }
 }
 
+/// Termination of a WebTransport session, shared with the streams belonging to it.
+///
+/// Streams are owned by the application, so the driver cannot reset them itself: each
+/// stream watches the session (see [`Self::watch`]) and resets or stops itself with
+/// `WEBTRANSPORT_SESSION_GONE` once it is used, or dropped, after the termination.
+#[derive(Clone, Debug, Default)]
+pub struct SessionStreams(Arc<Mutex<SessionStreamsState>>);
+
+#[derive(Debug, Default)]
+struct SessionStreamsState {
+    terminated: bool,
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl SessionStreams {
+    /// Returns the handle of a stream belonging to the session.
+    pub fn watch(&self) -> SessionWatch {
+        let mut state = self.lock();
+        let key = state.next_key;
+        state.next_key += 1;
+
+        SessionWatch {
+            streams: self.clone(),
+            key,
+        }
+    }
+
+    /// Marks the session as terminated, waking up the streams pending on it.
+    pub fn terminate(&self) {
+        let mut state = self.lock();
+        state.terminated = true;
+
+        for (_, waker) in state.wakers.drain() {
+            waker.wake();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionStreamsState> {
+        self.0.lock().expect("Session streams lock not poisoned")
+    }
+}
+
+/// Handle through which a stream learns that its session is gone.
+#[derive(Debug)]
+pub struct SessionWatch {
+    streams: SessionStreams,
+    key: u64,
+}
+
+impl SessionWatch {
+    /// Returns `true` if the session has been terminated.
+    pub fn is_terminated(&self) -> bool {
+        self.streams.lock().terminated
+    }
+
+    /// Polls the termination of the session.
+    ///
+    /// Only the waker of the last call is woken up.
+    pub fn poll_terminated(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.streams.lock();
+
+        if state.terminated {
+            return Poll::Ready(());
+        }
+
+        state.wakers.insert(self.key, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for SessionWatch {
+    fn drop(&mut self) {
+        self.streams.lock().wakers.remove(&self.key);
+    }
+}
+
 #[derive(Debug)]
 pub struct Stream<S, P> {
     stream: S,
@@ -279,6 +389,10 @@ pub mod biremote {
             self.stream.0.id()
         }
 
+        pub fn into_stream(self) -> (QuicSendStream, QuicRecvStream) {
+            self.stream
+        }
+
         pub fn into_session(self, session_request: SessionRequest) -> session::StreamSession {
             session::StreamSession {
                 stream: self.stream,
@@ -302,6 +416,11 @@ pub mod biremote {
         pub fn into_stream(self) -> (QuicSendStream, QuicRecvStream) {
             self.stream
         }
+
+        pub fn reset(mut self, error_code: VarInt) {
+            let _ = self.stream.1.stop(error_code);
+            self.stream.0.reset(error_code);
+        }
     }
 }
 
@@ -335,6 +454,10 @@ pub mod bilocal {
     }
 
     impl StreamBiLocalH3 {
+        pub fn into_stream(self) -> (QuicSendStream, QuicRecvStream) {
+            self.stream
+        }
+
         pub async fn upgrade(
             mut self,
             session_id: SessionId,
@@ -417,6 +540,15 @@ pub mod uniremote {
         pub fn stream_mut(&mut self) -> &mut QuicRecvStream {
             &mut self.stream
         }
+
+        #[inline(always)]
+        pub fn id(&self) -> StreamId {
+            self.stream.id()
+        }
+
+        pub fn stop(mut self, error_code: VarInt) {
+            let _ = self.stream.stop(error_code);
+        }
     }
 
     impl StreamUniRemoteWT {
@@ -434,6 +566,10 @@ pub mod uniremote {
         pub fn into_stream(self) -> QuicRecvStream {
             self.stream
         }
+
+        pub fn stop(mut self, error_code: VarInt) {
+            let _ = self.stream.stop(error_code);
+        }
     }
 }
 
@@ -481,10 +617,14 @@ pub mod unilocal {
             self.proto.kind()
         }
 
-        pub async fn stopped(&mut self) -> StreamWriteError {
+        pub async fn stopped(&mut self) -> quinn::WriteError {
             self.stream.stopped().await
         }
 
+        pub fn stream_mut(&mut self) -> &mut QuicSendStream {
+            &mut self.stream
+        }
+
         pub fn upgrade(self) -> StreamUniLocalWT {
             StreamUniLocalWT {
                 stream: self.stream,
@@ -502,6 +642,10 @@ pub mod unilocal {
 
 pub mod session {
     use super::*;
+    use std::borrow::Cow;
+    use wtransport_proto::bytes::BufferReader;
+    use wtransport_proto::capsule::Capsule;
+    use wtransport_proto::error::ErrorCode;
 
     pub type StreamSession =
         Stream<(QuicSendStream, QuicRecvStream), stream_proto::session::StreamSession>;
@@ -511,12 +655,33 @@ pub mod session {
             self.proto.read_frame_async(&mut self.stream.1).await
         }
 
+        pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, quinn::ReadError> {
+            self.stream.1.read(buf).await
+        }
+
+        pub fn read_frame_from_buffer<'a>(
+            &self,
+            buffer_reader: &mut BufferReader<'a>,
+        ) -> Result<Option<Frame<'a>>, ErrorCode> {
+            self.proto.read_frame_from_buffer(buffer_reader)
+        }
+
         pub async fn write_frame<'a>(&mut self, frame: Frame<'a>) -> Result<(), ProtoWriteError> {
             self.proto
                 .write_frame_async(frame, &mut self.stream.0)
                 .await
         }
 
+        pub async fn write_capsule<'a>(
+            &mut self,
+            capsule: Capsule<'a>,
+        ) -> Result<(), ProtoWriteError> {
+            let mut payload = Vec::with_capacity(capsule.write_size());
+            capsule.write(&mut payload).expect("Vec does not have EOF");
+
+            self.write_frame(Frame::new_data(Cow::Owned(payload))).await
+        }
+
         pub fn stop(&mut self, error_code: VarInt) -> Result<(), AlreadyStop> {
             self.stream.1.stop(error_code)
         }
@@ -536,13 +701,29 @@ pub mod session {
         pub async fn finish(mut self) {
             let _ = self.stream.0.finish().await;
         }
+
+        pub fn reset(mut self, error_code: VarInt) {
+            let _ = self.stream.1.stop(error_code);
+            self.stream.0.reset(error_code);
+        }
     }
 }
 
+/// Maps the error of a WebTransport stream, whose codes are application error codes.
 impl From<quinn::WriteError> for StreamWriteError {
     fn from(error: quinn::WriteError) -> Self {
         match error {
-            quinn::WriteError::Stopped(code) => StreamWriteError::Stopped(varint_q2w(code)),
+            quinn::WriteError::Stopped(code) => {
+                let code = varint_q2w(code);
+
+                match http3_to_webtransport_code(code) {
+                    Some(code) => StreamWriteError::Stopped(code),
+                    None if code == ErrorCode::SessionGone.to_code() => {
+                        StreamWriteError::SessionGone
+                    }
+                    None => StreamWriteError::StoppedHttp3(code),
+                }
+            }
             quinn::WriteError::ConnectionLost(_) => StreamWriteError::NotConnected,
             quinn::WriteError::UnknownStream => StreamWriteError::QuicProto,
             quinn::WriteError::ZeroRttRejected => StreamWriteError::QuicProto,
@@ -550,10 +731,21 @@ impl From<quinn::WriteError> for StreamWriteError {
     }
 }
 
+/// Maps the error of a WebTransport stream, whose codes are application error codes.
 impl From<quinn::ReadError> for StreamReadError {
     fn from(error: quinn::ReadError) -> Self {
         match error {
-            quinn::ReadError::Reset(code) => StreamReadError::Reset(varint_q2w(code)),
+            quinn::ReadError::Reset(code) => {
+                let code = varint_q2w(code);
+
+                match http3_to_webtransport_code(code) {
+                    Some(code) => StreamReadError::Reset(code),
+                    None if code == ErrorCode::SessionGone.to_code() => {
+                        StreamReadError::SessionGone
+                    }
+                    None => StreamReadError::ResetHttp3(code),
+                }
+            }
             quinn::ReadError::ConnectionLost(_) => StreamReadError::NotConnected,
             quinn::ReadError::UnknownStream => StreamReadError::QuicProto,
             quinn::ReadError::IllegalOrderedRead => StreamReadError::QuicProto,
@@ -562,5 +754,79 @@ impl From<quinn::ReadError> for StreamReadError {
     }
 }
 
+/// Maps the error of an HTTP3 request stream, whose codes are HTTP3 error codes.
+impl From<quinn::WriteError> for HttpError {
+    fn from(error: quinn::WriteError) -> Self {
+        match error {
+            quinn::WriteError::Stopped(code) => HttpError::Stopped(varint_q2w(code)),
+            quinn::WriteError::ConnectionLost(_) => HttpError::NotConnected,
+            quinn::WriteError::UnknownStream => HttpError::QuicProto,
+            quinn::WriteError::ZeroRttRejected => HttpError::QuicProto,
+        }
+    }
+}
+
+/// Maps the error of an HTTP3 request stream, whose codes are HTTP3 error codes.
+impl From<quinn::ReadError> for HttpError {
+    fn from(error: quinn::ReadError) -> Self {
+        match error {
+            quinn::ReadError::Reset(code) => HttpError::Reset(varint_q2w(code)),
+            quinn::ReadError::ConnectionLost(_) => HttpError::NotConnected,
+            quinn::ReadError::UnknownStream => HttpError::QuicProto,
+            quinn::ReadError::IllegalOrderedRead => HttpError::QuicProto,
+            quinn::ReadError::ZeroRttRejected => HttpError::QuicProto,
+        }
+    }
+}
+
+pub mod inbound;
 pub mod qpack;
 pub mod settings;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wtransport_proto::error::webtransport_to_http3_code;
+
+    fn stopped(code: VarInt) -> StreamWriteError {
+        quinn::WriteError::Stopped(varint_w2q(code)).into()
+    }
+
+    fn reset(code: VarInt) -> StreamReadError {
+        quinn::ReadError::Reset(varint_w2q(code)).into()
+    }
+
+    #[test]
+    fn application_error_codes() {
+        let first = webtransport_to_http3_code(0);
+        let last = webtransport_to_http3_code(u32::MAX);
+
+        assert!(matches!(stopped(first), StreamWriteError::Stopped(0)));
+        assert!(matches!(stopped(last), StreamWriteError::Stopped(u32::MAX)));
+        assert!(matches!(reset(first), StreamReadError::Reset(0)));
+        assert!(matches!(reset(last), StreamReadError::Reset(u32::MAX)));
+
+        let code = ErrorCode::SessionGone.to_code();
+        assert!(matches!(stopped(code), StreamWriteError::SessionGone));
+        assert!(matches!(reset(code), StreamReadError::SessionGone));
+    }
+
+    #[test]
+    fn http3_error_codes() {
+        let first = webtransport_to_http3_code(0).into_inner();
+        let last = webtransport_to_http3_code(u32::MAX).into_inner();
+        let reserved = (first..).find(|code| (code - 0x21) % 0x1f == 0).unwrap();
+
+        let codes = [
+            ErrorCode::NoError.to_code(),
+            VarInt::try_from_u64(first - 1).unwrap(),
+            VarInt::try_from_u64(reserved).unwrap(),
+            VarInt::try_from_u64(last + 1).unwrap(),
+        ];
+
+        for code in codes {
+            assert!(matches!(stopped(code), StreamWriteError::StoppedHttp3(c) if c == code));
+            assert!(matches!(reset(code), StreamReadError::ResetHttp3(c) if c == code));
+        }
+    }
+}
@@ -1,22 +1,321 @@
+use crate::config::WebTransportConfig;
+use crate::driver::streams::unilocal::StreamUniLocalH3;
 use crate::driver::streams::uniremote::StreamUniRemoteH3;
 use crate::driver::DriverError;
-use crate::error::StreamReadError;
-use crate::error::StreamReadExactError;
 use std::future::pending;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::watch;
+use tokio::sync::Notify;
 use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::headers::Headers;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::qpack::DynamicDecoder;
+use wtransport_proto::qpack::DynamicEncoder;
+use wtransport_proto::settings::SettingId;
+use wtransport_proto::settings::Settings;
 use wtransport_proto::stream_header::StreamKind;
 
+/// QPACK dynamic tables of a connection, shared among the driver and request streams.
+#[derive(Debug, Clone)]
+pub struct QPackCodec(Arc<QPackShared>);
+
+#[derive(Debug)]
+struct QPackShared {
+    encoder: Mutex<DynamicEncoder>,
+    decoder: Mutex<DynamicDecoder>,
+    encoder_instructions: Notify,
+    decoder_instructions: Notify,
+    decoder_inserts: watch::Sender<usize>,
+}
+
+impl QPackCodec {
+    pub fn new(webtransport_config: &WebTransportConfig) -> Self {
+        let max_table_capacity = webtransport_config.qpack_max_table_capacity as usize;
+        let blocked_streams = webtransport_config.qpack_blocked_streams as usize;
+
+        Self(Arc::new(QPackShared {
+            encoder: Mutex::new(DynamicEncoder::new(max_table_capacity)),
+            decoder: Mutex::new(DynamicDecoder::new(max_table_capacity, blocked_streams)),
+            encoder_instructions: Notify::new(),
+            decoder_instructions: Notify::new(),
+            decoder_inserts: watch::channel(0).0,
+        }))
+    }
+
+    /// Enables the encoder dynamic table as allowed by peer's settings.
+    pub fn set_peer_settings(&self, settings: &Settings) {
+        let peer_max_table_capacity = settings
+            .get(SettingId::QPackMaxTableCapacity)
+            .map_or(0, |value| value.into_inner());
+
+        self.0
+            .encoder
+            .lock()
+            .expect("Lock cannot be poisoned")
+            .set_peer_max_table_capacity(usize::try_from(peer_max_table_capacity).unwrap_or(0));
+
+        self.0.encoder_instructions.notify_one();
+    }
+
+    /// Encodes headers to be sent on stream `stream_id`.
+    pub fn encode_headers(&self, stream_id: StreamId, headers: &Headers) -> Frame<'static> {
+        let frame = headers.generate_frame_dynamic(
+            stream_id,
+            &mut self.0.encoder.lock().expect("Lock cannot be poisoned"),
+        );
+
+        self.0.encoder_instructions.notify_one();
+        frame
+    }
+
+    /// Decodes headers received on stream `stream_id`.
+    ///
+    /// If the stream is blocked, it awaits the required dynamic table updates.
+    /// Dropping the future of a blocked stream cancels it.
+    pub async fn decode_headers(
+        &self,
+        stream_id: StreamId,
+        frame: &Frame<'_>,
+    ) -> Result<Headers, ErrorCode> {
+        let mut inserts = self.0.decoder_inserts.subscribe();
+        let _guard = CancelStreamGuard {
+            codec: self,
+            stream_id,
+        };
+
+        loop {
+            let headers = Headers::with_frame_dynamic(
+                frame,
+                stream_id,
+                &mut self.0.decoder.lock().expect("Lock cannot be poisoned"),
+            );
+
+            self.0.decoder_instructions.notify_one();
+
+            match headers? {
+                Some(headers) => return Ok(headers),
+                None => inserts
+                    .changed()
+                    .await
+                    .expect("Sender is owned by the codec"),
+            }
+        }
+    }
+
+    fn feed_encoder_stream(&self, data: &[u8]) -> Result<(), ErrorCode> {
+        let mut decoder = self.0.decoder.lock().expect("Lock cannot be poisoned");
+
+        decoder
+            .feed_encoder_stream(data)
+            .map_err(|_| ErrorCode::EncoderStream)?;
+
+        self.0.decoder_inserts.send_replace(decoder.insert_count());
+        self.0.decoder_instructions.notify_one();
+
+        Ok(())
+    }
+
+    fn feed_decoder_stream(&self, data: &[u8]) -> Result<(), ErrorCode> {
+        self.0
+            .encoder
+            .lock()
+            .expect("Lock cannot be poisoned")
+            .feed_decoder_stream(data)
+            .map_err(|_| ErrorCode::DecoderStream)
+    }
+
+    fn take_instructions(&self, kind: StreamKind) -> Vec<u8> {
+        match kind {
+            StreamKind::QPackEncoder => self
+                .0
+                .encoder
+                .lock()
+                .expect("Lock cannot be poisoned")
+                .take_instructions(),
+            StreamKind::QPackDecoder => self
+                .0
+                .decoder
+                .lock()
+                .expect("Lock cannot be poisoned")
+                .take_instructions(),
+            _ => unreachable!(),
+        }
+    }
+
+    async fn instructions_ready(&self, kind: StreamKind) {
+        match kind {
+            StreamKind::QPackEncoder => self.0.encoder_instructions.notified().await,
+            StreamKind::QPackDecoder => self.0.decoder_instructions.notified().await,
+            _ => unreachable!(),
+        }
+    }
+}
+
+struct CancelStreamGuard<'a> {
+    codec: &'a QPackCodec,
+    stream_id: StreamId,
+}
+
+impl Drop for CancelStreamGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut decoder) = self.codec.0.decoder.lock() {
+            decoder.cancel_stream(self.stream_id);
+        }
+
+        self.codec.0.decoder_instructions.notify_one();
+    }
+}
+
+/// QPACK encoder and decoder streams of a connection, both local and remote.
+pub struct QPackStreams {
+    local_encoder: LocalQPackStream,
+    local_decoder: LocalQPackStream,
+    remote_encoder: RemoteQPackEncStream,
+    remote_decoder: RemoteQPackDecStream,
+}
+
+impl QPackStreams {
+    pub fn empty(codec: QPackCodec) -> Self {
+        Self {
+            local_encoder: LocalQPackStream::empty(codec.clone()),
+            local_decoder: LocalQPackStream::empty(codec.clone()),
+            remote_encoder: RemoteQPackEncStream::empty(codec.clone()),
+            remote_decoder: RemoteQPackDecStream::empty(codec),
+        }
+    }
+
+    pub fn set_local_streams(&mut self, encoder: StreamUniLocalH3, decoder: StreamUniLocalH3) {
+        assert!(matches!(encoder.kind(), StreamKind::QPackEncoder));
+        assert!(matches!(decoder.kind(), StreamKind::QPackDecoder));
+
+        self.local_encoder.set_stream(encoder);
+        self.local_decoder.set_stream(decoder);
+    }
+
+    /// Sets the remote encoder or decoder stream.
+    ///
+    /// Fails with [`ErrorCode::StreamCreation`] if a stream of the same kind was already set.
+    pub fn set_remote_stream(&mut self, stream: StreamUniRemoteH3) -> Result<(), ErrorCode> {
+        match stream.kind() {
+            StreamKind::QPackEncoder => {
+                if !self.remote_encoder.is_empty() {
+                    return Err(ErrorCode::StreamCreation);
+                }
+
+                self.remote_encoder.set_stream(stream);
+            }
+            StreamKind::QPackDecoder => {
+                if !self.remote_decoder.is_empty() {
+                    return Err(ErrorCode::StreamCreation);
+                }
+
+                self.remote_decoder.set_stream(stream);
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Runs all the QPACK streams.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    pub async fn run(&mut self) -> DriverError {
+        tokio::select! {
+            error = self.local_encoder.run() => error,
+            error = self.local_decoder.run() => error,
+            error = self.remote_encoder.run() => error,
+            error = self.remote_decoder.run() => error,
+        }
+    }
+}
+
+/// Local QPACK encoder or decoder stream.
+pub struct LocalQPackStream {
+    stream: Option<StreamUniLocalH3>,
+    codec: QPackCodec,
+    pending: Vec<u8>,
+}
+
+impl LocalQPackStream {
+    pub fn empty(codec: QPackCodec) -> Self {
+        Self {
+            stream: None,
+            codec,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn set_stream(&mut self, stream: StreamUniLocalH3) {
+        assert!(matches!(
+            stream.kind(),
+            StreamKind::QPackEncoder | StreamKind::QPackDecoder
+        ));
+        self.stream = Some(stream);
+    }
+
+    /// Sends instructions produced by the codec.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe.
+    pub async fn run(&mut self) -> DriverError {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => pending().await,
+        };
+
+        let kind = stream.kind();
+
+        loop {
+            if self.pending.is_empty() {
+                tokio::select! {
+                    error = stream.stopped() => return Self::map_write_error(error),
+                    () = self.codec.instructions_ready(kind) => {}
+                }
+
+                self.pending = self.codec.take_instructions(kind);
+                continue;
+            }
+
+            match stream.stream_mut().write(&self.pending).await {
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(error) => return Self::map_write_error(error),
+            }
+        }
+    }
+
+    fn map_write_error(error: quinn::WriteError) -> DriverError {
+        match error {
+            quinn::WriteError::ConnectionLost(_) => DriverError::NotConnected,
+            quinn::WriteError::Stopped(_)
+            | quinn::WriteError::UnknownStream
+            | quinn::WriteError::ZeroRttRejected => {
+                DriverError::Proto(ErrorCode::ClosedCriticalStream)
+            }
+        }
+    }
+}
+
 pub struct RemoteQPackEncStream {
     stream: Option<StreamUniRemoteH3>,
+    codec: QPackCodec,
     buffer: Box<[u8]>,
 }
 
 impl RemoteQPackEncStream {
-    pub fn empty() -> Self {
-        let buffer = vec![0; 64].into_boxed_slice();
+    pub fn empty(codec: QPackCodec) -> Self {
+        let buffer = vec![0; 512].into_boxed_slice();
 
         Self {
             stream: None,
+            codec,
             buffer,
         }
     }
@@ -37,20 +336,13 @@ impl RemoteQPackEncStream {
         };
 
         loop {
-            match stream.stream_mut().read_exact(&mut self.buffer).await {
-                Ok(()) => {}
-                Err(StreamReadExactError::FinishedEarly) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
-                Err(StreamReadExactError::Read(StreamReadError::NotConnected)) => {
-                    return DriverError::NotConnected;
-                }
-                Err(StreamReadExactError::Read(StreamReadError::Reset(_))) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
-                Err(StreamReadExactError::Read(StreamReadError::QuicProto)) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
+            let read = match read_instructions(stream, &mut self.buffer).await {
+                Ok(read) => read,
+                Err(driver_error) => return driver_error,
+            };
+
+            if let Err(error_code) = self.codec.feed_encoder_stream(&self.buffer[..read]) {
+                return DriverError::Proto(error_code);
             }
         }
     }
@@ -58,14 +350,17 @@ impl RemoteQPackEncStream {
 
 pub struct RemoteQPackDecStream {
     stream: Option<StreamUniRemoteH3>,
+    codec: QPackCodec,
     buffer: Box<[u8]>,
 }
 
 impl RemoteQPackDecStream {
-    pub fn empty() -> Self {
+    pub fn empty(codec: QPackCodec) -> Self {
         let buffer = vec![0; 64].into_boxed_slice();
+
         Self {
             stream: None,
+            codec,
             buffer,
         }
     }
@@ -86,21 +381,26 @@ impl RemoteQPackDecStream {
         };
 
         loop {
-            match stream.stream_mut().read_exact(&mut self.buffer).await {
-                Ok(()) => {}
-                Err(StreamReadExactError::FinishedEarly) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
-                Err(StreamReadExactError::Read(StreamReadError::NotConnected)) => {
-                    return DriverError::NotConnected;
-                }
-                Err(StreamReadExactError::Read(StreamReadError::Reset(_))) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
-                Err(StreamReadExactError::Read(StreamReadError::QuicProto)) => {
-                    return DriverError::Proto(ErrorCode::ClosedCriticalStream);
-                }
+            let read = match read_instructions(stream, &mut self.buffer).await {
+                Ok(read) => read,
+                Err(driver_error) => return driver_error,
+            };
+
+            if let Err(error_code) = self.codec.feed_decoder_stream(&self.buffer[..read]) {
+                return DriverError::Proto(error_code);
             }
         }
     }
 }
+
+async fn read_instructions(
+    stream: &mut StreamUniRemoteH3,
+    buffer: &mut [u8],
+) -> Result<usize, DriverError> {
+    match stream.stream_mut().read(buffer).await {
+        Ok(Some(read)) => Ok(read),
+        Ok(None) => Err(DriverError::Proto(ErrorCode::ClosedCriticalStream)),
+        Err(quinn::ReadError::ConnectionLost(_)) => Err(DriverError::NotConnected),
+        Err(_) => Err(DriverError::Proto(ErrorCode::ClosedCriticalStream)),
+    }
+}
@@ -1,15 +1,20 @@
+use crate::config::WebTransportConfig;
 use crate::driver::streams::unilocal::StreamUniLocalH3;
 use crate::driver::streams::uniremote::StreamUniRemoteH3;
 use crate::driver::streams::ProtoReadError;
 use crate::driver::streams::ProtoWriteError;
 use crate::driver::DriverError;
-use crate::error::StreamWriteError;
 use std::future::pending;
 use tokio::sync::watch;
 use wtransport_proto::bytes;
 use wtransport_proto::error::ErrorCode;
 use wtransport_proto::frame::Frame;
 use wtransport_proto::frame::FrameKind;
+use wtransport_proto::goaway::GoAway;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::push::CancelPush;
+use wtransport_proto::push::MaxPushId;
+use wtransport_proto::session::WebTransportDraft;
 use wtransport_proto::settings::Settings;
 use wtransport_proto::stream_header::StreamKind;
 use wtransport_proto::varint::VarInt;
@@ -20,15 +25,39 @@ pub struct LocalSettingsStream {
 }
 
 impl LocalSettingsStream {
-    pub fn empty() -> Self {
-        let settings = Settings::builder()
-            .qpack_max_table_capacity(VarInt::from_u32(0))
-            .qpack_blocked_streams(VarInt::from_u32(0))
+    pub fn empty(webtransport_config: &WebTransportConfig) -> Self {
+        let max_sessions = VarInt::from_u32(webtransport_config.max_sessions);
+
+        let mut settings = Settings::builder()
+            .qpack_max_table_capacity(VarInt::from_u32(
+                webtransport_config.qpack_max_table_capacity,
+            ))
+            .qpack_blocked_streams(VarInt::from_u32(webtransport_config.qpack_blocked_streams))
             .enable_connect_protocol() // TODO(biagio): it would be nice to have this only for server
-            .enable_webtransport()
-            .enable_h3_datagrams()
-            .webtransport_max_sessions(VarInt::from_u32(1))
-            .build();
+            .enable_h3_datagrams();
+
+        for draft in &webtransport_config.drafts {
+            settings = match draft {
+                WebTransportDraft::Draft02 => settings.enable_webtransport(),
+                WebTransportDraft::Draft07 => settings.webtransport_max_sessions(max_sessions),
+                WebTransportDraft::Draft09 => settings.wt_max_sessions(max_sessions),
+            };
+        }
+
+        if let Some(max_data) = webtransport_config.session_max_data {
+            settings = settings.webtransport_initial_max_data(VarInt::from_u32(max_data));
+        }
+
+        if let Some(max_streams) = webtransport_config.session_max_streams_uni {
+            settings = settings.webtransport_initial_max_streams_uni(VarInt::from_u32(max_streams));
+        }
+
+        if let Some(max_streams) = webtransport_config.session_max_streams_bidi {
+            settings =
+                settings.webtransport_initial_max_streams_bidi(VarInt::from_u32(max_streams));
+        }
+
+        let settings = settings.build();
 
         Self {
             stream: None,
@@ -46,11 +75,35 @@ impl LocalSettingsStream {
     }
 
     pub async fn send_settings(&mut self) -> Result<(), DriverError> {
-        match self
-            .stream
+        Self::write_frame(&mut self.stream, self.settings.generate_frame()).await
+    }
+
+    pub async fn send_goaway(&mut self, goaway: GoAway) -> Result<(), DriverError> {
+        Self::write_frame(&mut self.stream, goaway.generate_frame()).await
+    }
+
+    pub async fn run(&mut self) -> DriverError {
+        match self.stream.as_mut() {
+            Some(stream) => match stream.stopped().await {
+                quinn::WriteError::ConnectionLost(_) => DriverError::NotConnected,
+                quinn::WriteError::Stopped(_)
+                | quinn::WriteError::UnknownStream
+                | quinn::WriteError::ZeroRttRejected => {
+                    DriverError::Proto(ErrorCode::ClosedCriticalStream)
+                }
+            },
+            None => pending().await,
+        }
+    }
+
+    async fn write_frame(
+        stream: &mut Option<StreamUniLocalH3>,
+        frame: Frame<'_>,
+    ) -> Result<(), DriverError> {
+        match stream
             .as_mut()
-            .expect("Cannot send settings on empty stream")
-            .write_frame(self.settings.generate_frame())
+            .expect("Cannot write frame on empty stream")
+            .write_frame(frame)
             .await
         {
             Ok(()) => Ok(()),
@@ -60,22 +113,13 @@ impl LocalSettingsStream {
             }
         }
     }
-
-    pub async fn run(&mut self) -> DriverError {
-        match self.stream.as_mut() {
-            Some(stream) => match stream.stopped().await {
-                StreamWriteError::NotConnected => DriverError::NotConnected,
-                StreamWriteError::Stopped(_) => DriverError::Proto(ErrorCode::ClosedCriticalStream),
-                StreamWriteError::QuicProto => DriverError::Proto(ErrorCode::ClosedCriticalStream),
-            },
-            None => pending().await,
-        }
-    }
 }
 
 pub struct RemoteSettingsStream {
     stream: Option<StreamUniRemoteH3>,
     settings: watch::Sender<Option<Settings>>,
+    goaway: watch::Sender<Option<GoAway>>,
+    max_push_id: Option<VarInt>,
 }
 
 impl RemoteSettingsStream {
@@ -83,6 +127,8 @@ impl RemoteSettingsStream {
         Self {
             stream: None,
             settings: watch::channel(None).0,
+            goaway: watch::channel(None).0,
+            max_push_id: None,
         }
     }
 
@@ -99,6 +145,10 @@ impl RemoteSettingsStream {
         RemoteSettingsWatcher(self.settings.subscribe())
     }
 
+    pub fn subscribe_goaway(&self) -> RemoteGoAwayWatcher {
+        RemoteGoAwayWatcher(self.goaway.subscribe())
+    }
+
     pub async fn run(&mut self) -> DriverError {
         loop {
             let frame = match self.read_frame().await {
@@ -117,10 +167,61 @@ impl RemoteSettingsStream {
                 };
 
                 self.settings.send_replace(Some(settings));
-            } else if !matches!(frame.kind(), FrameKind::Exercise(_)) {
-                return DriverError::Proto(ErrorCode::FrameUnexpected);
+            } else if let Err(error_code) = self.handle_frame(&frame) {
+                return DriverError::Proto(error_code);
+            }
+        }
+    }
+
+    fn handle_frame(&mut self, frame: &Frame) -> Result<(), ErrorCode> {
+        match frame.kind() {
+            FrameKind::GoAway => {
+                let goaway = GoAway::with_frame(frame)?;
+                check_goaway(goaway, *self.goaway.borrow(), self.peer_is_client())?;
+                self.goaway.send_replace(Some(goaway));
             }
+            FrameKind::MaxPushId => {
+                // Only a client can send MAX_PUSH_ID
+                if !self.peer_is_client() {
+                    return Err(ErrorCode::FrameUnexpected);
+                }
+
+                let max_push_id = MaxPushId::with_frame(frame)?;
+
+                // A client MUST NOT reduce the maximum push ID
+                if matches!(self.max_push_id, Some(previous) if max_push_id.push_id() < previous) {
+                    return Err(ErrorCode::Id);
+                }
+
+                self.max_push_id = Some(max_push_id.push_id());
+            }
+            FrameKind::CancelPush => {
+                CancelPush::with_frame(frame)?;
+
+                // Server push is never used: no push ID has been promised (by the server)
+                // nor allowed with MAX_PUSH_ID (by the client)
+                return Err(ErrorCode::Id);
+            }
+            FrameKind::PriorityUpdateRequest | FrameKind::PriorityUpdatePush => {
+                // Only a client can send PRIORITY_UPDATE. Prioritization is not supported
+                if !self.peer_is_client() {
+                    return Err(ErrorCode::FrameUnexpected);
+                }
+            }
+            FrameKind::Exercise(_) | FrameKind::Unknown(_) => {}
+            FrameKind::Data
+            | FrameKind::Headers
+            | FrameKind::Settings
+            | FrameKind::WebTransport => return Err(ErrorCode::FrameUnexpected),
         }
+
+        Ok(())
+    }
+
+    fn peer_is_client(&self) -> bool {
+        self.stream
+            .as_ref()
+            .is_some_and(|stream| stream.id().is_client_initiated())
     }
 
     async fn read_frame<'a>(&mut self) -> Result<Frame<'a>, DriverError> {
@@ -144,6 +245,29 @@ impl RemoteSettingsStream {
     }
 }
 
+/// Validates a GOAWAY received from the peer, following the `previous` one (if any).
+fn check_goaway(
+    goaway: GoAway,
+    previous: Option<GoAway>,
+    peer_is_client: bool,
+) -> Result<(), ErrorCode> {
+    // A server sends the ID of a client-initiated bidirectional stream (RFC 9114 section 5.2)
+    if !peer_is_client {
+        let stream_id = StreamId::new(goaway.id());
+
+        if !stream_id.is_client_initiated() || !stream_id.is_bidirectional() {
+            return Err(ErrorCode::Id);
+        }
+    }
+
+    // An endpoint MUST NOT increase the value it sends in a GOAWAY frame
+    if matches!(previous, Some(previous) if goaway.id() > previous.id()) {
+        return Err(ErrorCode::Id);
+    }
+
+    Ok(())
+}
+
 pub struct RemoteSettingsWatcher(watch::Receiver<Option<Settings>>);
 
 impl RemoteSettingsWatcher {
@@ -158,3 +282,54 @@ impl RemoteSettingsWatcher {
         )
     }
 }
+
+pub struct RemoteGoAwayWatcher(watch::Receiver<Option<GoAway>>);
+
+impl RemoteGoAwayWatcher {
+    pub async fn accept_goaway(&mut self) -> Option<GoAway> {
+        self.0.changed().await.ok()?;
+
+        Some(self.0.borrow().expect("On change goaway must be set"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goaway_from_server() {
+        let goaway = |id| GoAway::new(VarInt::from_u32(id));
+
+        assert!(check_goaway(goaway(0), None, false).is_ok());
+        assert!(check_goaway(goaway(8), None, false).is_ok());
+        assert!(check_goaway(goaway(4), Some(goaway(8)), false).is_ok());
+
+        // Not client-initiated bidirectional stream IDs
+        for id in [1, 2, 3, 5, 6, 7] {
+            assert!(matches!(
+                check_goaway(goaway(id), None, false),
+                Err(ErrorCode::Id)
+            ));
+        }
+
+        assert!(matches!(
+            check_goaway(goaway(12), Some(goaway(8)), false),
+            Err(ErrorCode::Id)
+        ));
+    }
+
+    #[test]
+    fn goaway_from_client() {
+        let goaway = |id| GoAway::new(VarInt::from_u32(id));
+
+        // Push IDs carry no stream type
+        assert!(check_goaway(goaway(3), None, true).is_ok());
+        assert!(check_goaway(goaway(2), Some(goaway(3)), true).is_ok());
+
+        assert!(matches!(
+            check_goaway(goaway(4), Some(goaway(3)), true),
+            Err(ErrorCode::Id)
+        ));
+    }
+}
@@ -1,7 +1,5 @@
 use std::sync::Arc;
-use tokio::sync::mpsc;
 use tokio::sync::watch;
-use tokio::sync::Mutex;
 use wtransport_proto::ids::StreamId;
 use wtransport_proto::varint::VarInt;
 
@@ -35,7 +33,7 @@ pub fn streamid_q2w(stream_id: quinn::StreamId) -> StreamId {
 
 pub fn shared_result<T>() -> (SharedResultSet<T>, SharedResultGet<T>)
 where
-    T: Copy,
+    T: Clone,
 {
     let set = SharedResultSet::new();
     let get = set.subscribe();
@@ -47,7 +45,7 @@ pub struct SharedResultSet<T>(Arc<watch::Sender<Option<T>>>);
 
 impl<T> SharedResultSet<T>
 where
-    T: Copy,
+    T: Clone,
 {
     #[inline(always)]
     pub fn new() -> Self {
@@ -83,16 +81,16 @@ where
     /// will be set.
     #[inline(always)]
     pub fn subscribe(&self) -> SharedResultGet<T> {
-        SharedResultGet(Mutex::new(self.0.subscribe()))
+        SharedResultGet(self.0.subscribe())
     }
 }
 
 #[derive(Debug)]
-pub struct SharedResultGet<T>(Mutex<watch::Receiver<Option<T>>>);
+pub struct SharedResultGet<T>(watch::Receiver<Option<T>>);
 
 impl<T> SharedResultGet<T>
 where
-    T: Copy,
+    T: Clone,
 {
     /// Awaits the shared result is set by any setter.
     ///
@@ -102,68 +100,26 @@ where
     /// If all setters are dead before setting any result, this will
     /// return `None`. And all successive calls will return `None`.
     pub async fn result(&self) -> Option<T> {
-        let mut lock = self.0.lock().await;
+        let mut receiver = self.0.clone();
 
         loop {
-            if let Some(result) = *lock.borrow() {
+            if let Some(result) = receiver.borrow().clone() {
                 return Some(result);
             }
 
-            if lock.changed().await.is_err() {
+            if receiver.changed().await.is_err() {
                 return None;
             }
         }
     }
-}
-
-pub struct SendError;
-
-pub enum TrySendError<T> {
-    Full(T),
-    Closed(T),
-}
 
-#[derive(Debug)]
-pub struct BiChannelEndpoint<T> {
-    sender: mpsc::Sender<T>,
-    receiver: Mutex<mpsc::Receiver<T>>,
-}
-
-impl<T> BiChannelEndpoint<T> {
+    /// Returns `true` if the shared result has been already set.
     #[inline(always)]
-    pub async fn send(&self, value: T) -> Result<(), SendError> {
-        self.sender.send(value).await.map_err(|_| SendError)
-    }
-
-    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
-        self.sender.try_send(value).map_err(|error| match error {
-            mpsc::error::TrySendError::Full(value) => TrySendError::Full(value),
-            mpsc::error::TrySendError::Closed(value) => TrySendError::Closed(value),
-        })
-    }
-
-    #[inline(always)]
-    pub async fn recv(&self) -> Option<T> {
-        self.receiver.lock().await.recv().await
+    pub fn is_set(&self) -> bool {
+        self.0.borrow().is_some()
     }
 }
 
-pub fn bichannel<T>(capacity: usize) -> (BiChannelEndpoint<T>, BiChannelEndpoint<T>) {
-    let c1 = mpsc::channel(capacity);
-    let c2 = mpsc::channel(capacity);
-
-    (
-        BiChannelEndpoint {
-            sender: c1.0,
-            receiver: Mutex::new(c2.1),
-        },
-        BiChannelEndpoint {
-            sender: c2.0,
-            receiver: Mutex::new(c1.1),
-        },
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,6 +158,16 @@ mod tests {
         assert!(matches!(poll_once(get.result()).await.unwrap(), Some(1)));
     }
 
+    #[test]
+    fn shared_result_is_set() {
+        let set = SharedResultSet::new();
+        let get = set.subscribe();
+        assert!(!get.is_set());
+
+        set.set(1);
+        assert!(get.is_set());
+    }
+
     mod utils {
         use std::future::Future;
         use std::pin::Pin;
@@ -2,38 +2,61 @@ use crate::config::ClientConfig;
 use crate::config::DnsResolver;
 use crate::config::Ipv6DualStackConfig;
 use crate::config::ServerConfig;
+use crate::config::WebTransportConfig;
+use crate::connect_udp::UdpTunnel;
+use crate::connect_udp::UriTemplate;
 use crate::connection::Connection;
+use crate::connection::WebTransportDraft;
 use crate::driver::streams::session::StreamSession;
 use crate::driver::streams::ProtoReadError;
 use crate::driver::streams::ProtoWriteError;
 use crate::driver::utils::varint_w2q;
 use crate::driver::Driver;
+use crate::driver::SessionAcceptor;
+use crate::driver::SessionQueues;
 use crate::error::ConnectingError;
 use crate::error::ConnectionError;
+use crate::error::HttpError;
+use crate::error::MissingCapability;
+use crate::http::IncomingResponse;
+use crate::http::Request;
 use quinn::TokioRuntime;
 use socket2::Domain as SocketDomain;
 use socket2::Protocol as SocketProtocol;
 use socket2::Socket;
 use socket2::Type as SocketType;
 use std::collections::HashMap;
+use std::fmt::Debug;
 use std::future::Future;
-use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::net::SocketAddrV4;
 use std::net::SocketAddrV6;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Weak;
 use std::task::Context;
 use std::task::Poll;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
 use tracing::debug;
 use url::Host;
 use url::Url;
+use wtransport_proto::bytes::IoReadError;
+use wtransport_proto::connect_udp::ConnectUdpRequest;
 use wtransport_proto::error::ErrorCode;
 use wtransport_proto::frame::FrameKind;
-use wtransport_proto::headers::Headers;
+use wtransport_proto::session::InvalidProtocol;
 use wtransport_proto::session::ReservedHeader;
 use wtransport_proto::session::SessionRequest as SessionRequestProto;
 use wtransport_proto::session::SessionResponse as SessionResponseProto;
+use wtransport_proto::settings::SettingId;
+use wtransport_proto::settings::Settings;
+
+#[doc(inline)]
+pub use wtransport_proto::headers::Headers;
+
+#[doc(inline)]
+pub use wtransport_proto::ids::StatusCode;
 
 /// Helper structure for Endpoint types.
 pub mod endpoint_side {
@@ -43,7 +66,11 @@ pub mod endpoint_side {
     ///
     /// Use [`Endpoint::server`] to create and server-endpoint.
     pub struct Server {
-        pub(super) _marker: PhantomData<()>,
+        pub(super) webtransport_config: std::sync::Mutex<WebTransportConfig>,
+        pub(super) pooled_sessions: mpsc::Sender<SessionRequest>,
+        pub(super) ready_pooled_sessions: Mutex<mpsc::Receiver<SessionRequest>>,
+        pub(super) drivers: ConnectionRegistry,
+        pub(super) handshakes: HandshakeRegistry,
     }
 
     /// Type of endpoint opening a WebTransport connection.
@@ -51,9 +78,21 @@ pub mod endpoint_side {
     /// Use [`Endpoint::client`] to create and client-endpoint.
     pub struct Client {
         pub(super) dns_resolver: Box<dyn DnsResolver + Send + Sync>,
+        pub(super) webtransport_config: WebTransportConfig,
     }
 }
 
+/// Drivers of the connections accepted by a server endpoint.
+///
+/// It does not keep connections alive. It is taken once the endpoint is shutting down,
+/// refusing connections completing their handshake afterwards.
+type ConnectionRegistry = Arc<std::sync::Mutex<Option<Vec<Weak<Driver>>>>>;
+
+/// Remote addresses of the connections completing their handshake on a server endpoint.
+///
+/// An entry is alive as long as its [`IncomingSession`] is in handshake.
+type HandshakeRegistry = Arc<std::sync::Mutex<Vec<Weak<SocketAddr>>>>;
+
 /// Entrypoint for creating client or server connections.
 ///
 /// A single endpoint can be used to accept or connect multiple connections.
@@ -154,25 +193,121 @@ impl Endpoint<endpoint_side::Server> {
             runtime,
         )?;
 
+        let pooled_sessions = mpsc::channel(4);
+
         Ok(Self {
             endpoint,
             side: endpoint_side::Server {
-                _marker: PhantomData,
+                webtransport_config: std::sync::Mutex::new(server_config.webtransport_config),
+                pooled_sessions: pooled_sessions.0,
+                ready_pooled_sessions: Mutex::new(pooled_sessions.1),
+                drivers: Arc::new(std::sync::Mutex::new(Some(Vec::new()))),
+                handshakes: Default::default(),
             },
         })
     }
 
     /// Get the next incoming connection attempt from a client.
+    ///
+    /// When [`max_sessions`](crate::config::ServerConfigBuilder::max_sessions) allows it,
+    /// clients can request additional sessions over an already established QUIC connection.
+    /// Those requests are yielded by this method as well.
+    ///
+    /// Connection attempts refused by [admission control](crate::admission) are not yielded.
     pub async fn accept(&self) -> IncomingSession {
-        let quic_connecting = self
-            .endpoint
-            .accept()
-            .await
-            .expect("Endpoint cannot be closed");
+        // The queue is only locked while waiting on it, so that concurrent callers
+        // keep accepting QUIC connections
+        let ready_pooled_session =
+            async { self.side.ready_pooled_sessions.lock().await.recv().await };
+
+        tokio::select! {
+            // `None` only after the endpoint has been shut down
+            Some((quic_connecting, webtransport_config, handshake)) = self.accept_connection() => {
+                debug!("New incoming QUIC connection");
+
+                IncomingSession::new(
+                    quic_connecting,
+                    webtransport_config,
+                    self.side.pooled_sessions.clone(),
+                    self.side.drivers.clone(),
+                    handshake,
+                )
+            }
+            session_request = ready_pooled_session => {
+                let session_request = session_request.expect("Endpoint holds a sender");
+
+                debug!("New incoming session on established QUIC connection");
+
+                IncomingSession::ready(session_request)
+            }
+        }
+    }
+
+    /// Awaits the next QUIC connection attempt passing admission control.
+    ///
+    /// The returned handshake entry is registered until dropped.
+    async fn accept_connection(
+        &self,
+    ) -> Option<(quinn::Connecting, WebTransportConfig, Arc<SocketAddr>)> {
+        loop {
+            let quic_connecting = self.endpoint.accept().await?;
+            let remote_address = quic_connecting.remote_address();
+
+            let webtransport_config = self
+                .side
+                .webtransport_config
+                .lock()
+                .expect("Config lock not poisoned")
+                .clone();
+
+            let mut handshakes = self
+                .side
+                .handshakes
+                .lock()
+                .expect("Registry lock not poisoned");
+
+            handshakes.retain(|handshake| handshake.strong_count() > 0);
+
+            let limits = &webtransport_config.connection_limits;
+
+            let admission = if limits.is_unbounded() {
+                limits.check(remote_address, [], [])
+            } else {
+                let connections = self
+                    .side
+                    .drivers
+                    .lock()
+                    .expect("Registry lock not poisoned")
+                    .iter()
+                    .flatten()
+                    .filter_map(|driver| driver.upgrade())
+                    .map(|driver| driver.remote_address())
+                    .collect::<Vec<_>>();
+
+                limits.check(
+                    remote_address,
+                    handshakes
+                        .iter()
+                        .filter_map(|handshake| handshake.upgrade())
+                        .map(|handshake| *handshake),
+                    connections,
+                )
+            };
 
-        debug!("New incoming QUIC connection");
+            match admission {
+                Ok(()) => {
+                    let handshake = Arc::new(remote_address);
+                    handshakes.push(Arc::downgrade(&handshake));
+                    return Some((quic_connecting, webtransport_config, handshake));
+                }
+                Err(refusal) => {
+                    debug!("Connection from {remote_address} refused: {refusal}");
 
-        IncomingSession::new(quic_connecting)
+                    // Abandoned without completing the handshake
+                    drop(quic_connecting);
+                }
+            }
+        }
     }
 
     /// Reloads the server configuration.
@@ -195,8 +330,128 @@ impl Endpoint<endpoint_side::Server> {
         let quic_config = server_config.quic_config;
         self.endpoint.set_server_config(Some(quic_config));
 
+        *self
+            .side
+            .webtransport_config
+            .lock()
+            .expect("Config lock not poisoned") = server_config.webtransport_config;
+
         Ok(())
     }
+
+    /// Gracefully shuts down the server.
+    ///
+    /// It stops accepting new QUIC connections and sends an HTTP3 GOAWAY frame on every
+    /// established connection, so that further session requests are rejected and clients
+    /// are notified (see [`Connection::draining`]).
+    /// Then it waits for all sessions to be closed, up to `deadline`.
+    /// Finally, the QUIC endpoint is closed with `H3_NO_ERROR` code, terminating any
+    /// connection still open.
+    ///
+    /// After this call, [`accept`](Self::accept) never yields QUIC connection attempts, and
+    /// connections still completing their handshake are closed.
+    ///
+    /// Returns how many sessions were closed before `deadline`, and how many were
+    /// terminated with the endpoint.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use wtransport::endpoint::endpoint_side::Server;
+    /// # use std::time::Duration;
+    /// # use std::time::Instant;
+    /// # async fn run(server: wtransport::Endpoint<Server>) {
+    /// let summary = server
+    ///     .shutdown(Instant::now() + Duration::from_secs(10))
+    ///     .await;
+    ///
+    /// println!(
+    ///     "{} sessions closed, {} terminated",
+    ///     summary.closed_cleanly(),
+    ///     summary.closed_forcibly()
+    /// );
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, deadline: std::time::Instant) -> ShutdownSummary {
+        self.endpoint.reject_new_connections();
+
+        let drivers = self
+            .side
+            .drivers
+            .lock()
+            .expect("Registry lock not poisoned")
+            .take()
+            .into_iter()
+            .flatten()
+            .filter_map(|driver| driver.upgrade())
+            .collect::<Vec<_>>();
+
+        debug!("Shutting down ({} connections)", drivers.len());
+
+        // Sessions closed before the shutdown are not accounted
+        let closed_sessions = drivers
+            .iter()
+            .map(|driver| driver.session_counts().closed)
+            .collect::<Vec<_>>();
+
+        let sessions_closed = async {
+            for driver in &drivers {
+                let _ = driver.go_away().await;
+            }
+
+            for driver in &drivers {
+                driver.sessions_closed().await;
+            }
+        };
+
+        if tokio::time::timeout_at(deadline.into(), sessions_closed)
+            .await
+            .is_err()
+        {
+            debug!("Shutdown deadline expired: closing remaining sessions");
+        }
+
+        let mut summary = ShutdownSummary::default();
+
+        for (driver, closed_sessions) in drivers.iter().zip(closed_sessions) {
+            let session_counts = driver.session_counts();
+            summary.closed_cleanly += session_counts.closed - closed_sessions;
+            summary.closed_forcibly += session_counts.active;
+        }
+
+        drop(drivers);
+
+        self.endpoint
+            .close(varint_w2q(ErrorCode::NoError.to_code()), b"");
+        self.endpoint.wait_idle().await;
+
+        debug!(
+            "Shutdown completed ({} sessions closed, {} terminated)",
+            summary.closed_cleanly, summary.closed_forcibly
+        );
+
+        summary
+    }
+}
+
+/// Outcome of [`Endpoint::shutdown`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ShutdownSummary {
+    closed_cleanly: usize,
+    closed_forcibly: usize,
+}
+
+impl ShutdownSummary {
+    /// Returns the number of sessions closed before the deadline.
+    pub fn closed_cleanly(&self) -> usize {
+        self.closed_cleanly
+    }
+
+    /// Returns the number of sessions still open at the deadline, terminated along
+    /// with their connection.
+    pub fn closed_forcibly(&self) -> usize {
+        self.closed_forcibly
+    }
 }
 
 impl Endpoint<endpoint_side::Client> {
@@ -220,6 +475,7 @@ impl Endpoint<endpoint_side::Client> {
             endpoint,
             side: endpoint_side::Client {
                 dns_resolver: client_config.dns_resolver,
+                webtransport_config: client_config.webtransport_config,
             },
         })
     }
@@ -283,17 +539,96 @@ impl Endpoint<endpoint_side::Client> {
     where
         O: IntoConnectOptions,
     {
-        let options = options.into_options();
+        let mut options = options.into_options();
+        let initial_url = Self::parse_url(&options.url)?;
+        let drafts = &self.side.webtransport_config.drafts;
+
+        let mut url = initial_url.clone();
+        let (mut quic_connection, mut driver, mut draft) = self.establish(&url).await?;
+        let mut hops = 0;
+        let mut reused = false;
+
+        loop {
+            let error = match Self::request_session(
+                quic_connection.clone(),
+                driver.clone(),
+                &url,
+                &options,
+                drafts,
+                draft,
+            )
+            .await
+            {
+                Ok(connection) => return Ok(connection),
+                Err(error) => error,
+            };
 
-        let url = Url::parse(&options.url)
-            .map_err(|parse_error| ConnectingError::InvalidUrl(parse_error.to_string()))?;
+            if std::mem::take(&mut reused) && matches!(error, ConnectingError::ConnectionError(_)) {
+                // The server might close the connection after redirecting
+                debug!("Connection lost on redirection, reconnecting to {}", url);
+                (quic_connection, driver, draft) = self.establish(&url).await?;
+                continue;
+            }
 
-        if url.scheme() != "https" {
-            return Err(ConnectingError::InvalidUrl(
-                "WebTransport URL scheme must be 'https'".to_string(),
-            ));
+            let target =
+                match &error {
+                    ConnectingError::SessionRejected {
+                        status: Some(status),
+                        headers,
+                    } if hops < options.redirect_policy.max_hops => options
+                        .redirect_policy
+                        .redirect(&initial_url, &url, *status, headers),
+                    _ => None,
+                };
+
+            let Some(target) = target else {
+                return Err(error);
+            };
+
+            debug!("Following redirection to {}", target);
+            hops += 1;
+
+            if same_authority(&url, &target) {
+                reused = true;
+            } else {
+                if !options.redirect_policy.forward_sensitive_headers {
+                    options.strip_sensitive_headers();
+                }
+
+                quic_connection.close(varint_w2q(ErrorCode::NoError.to_code()), b"");
+                (quic_connection, driver, draft) = self.establish(&target).await?;
+            }
+
+            url = target;
         }
+    }
+
+    /// Connects to the server of `url` and awaits its SETTINGS.
+    ///
+    /// On success, returns the newest draft supported by both endpoints.
+    async fn establish(
+        &self,
+        url: &Url,
+    ) -> Result<(quinn::Connection, Arc<Driver>, WebTransportDraft), ConnectingError> {
+        let (quic_connection, driver, settings) = self.establish_h3(url).await?;
+
+        let draft = validate_settings(
+            &settings,
+            &self.side.webtransport_config.drafts,
+            &quic_connection,
+            true,
+        )
+        .map_err(ConnectingError::ConnectionError)?;
+
+        Ok((quic_connection, driver, draft))
+    }
 
+    /// Connects to the server of `url` and awaits its SETTINGS, without requiring
+    /// WebTransport support.
+    async fn establish_h3(
+        &self,
+        url: &Url,
+    ) -> Result<(quinn::Connection, Arc<Driver>, Settings), ConnectingError> {
         let host = url.host().expect("https scheme must have an host");
         let port = url.port().unwrap_or(443);
 
@@ -328,28 +663,226 @@ impl Endpoint<endpoint_side::Client> {
                 ConnectingError::ConnectionError(connection_error.into())
             })?;
 
-        let driver = Driver::init(quic_connection.clone());
+        let driver = Arc::new(Driver::init(
+            quic_connection.clone(),
+            self.side.webtransport_config.clone(),
+        ));
 
-        let _settings = driver.accept_settings().await.map_err(|driver_error| {
+        let settings = driver.accept_settings().await.map_err(|driver_error| {
             ConnectingError::ConnectionError(ConnectionError::with_driver_error(
                 driver_error,
                 &quic_connection,
             ))
         })?;
 
-        // TODO(biagio): validate settings
+        Ok((quic_connection, driver, settings))
+    }
+
+    /// Opens an additional WebTransport session over the QUIC connection of an
+    /// established [`Connection`].
+    ///
+    /// This avoids a new QUIC and TLS handshake for each session. The server must
+    /// allow more than one session per connection
+    /// (see [`ServerConfigBuilder::max_sessions`](crate::config::ServerConfigBuilder::max_sessions)),
+    /// otherwise the request is rejected with [`ConnectingError::SessionRejected`].
+    ///
+    /// The URL in `options` is expected to refer to the same server of `connection`; only its
+    /// authority and path are used for the session request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use anyhow::Result;
+    /// # use wtransport::endpoint::endpoint_side::Client;
+    /// # async fn example(endpoint: wtransport::Endpoint<Client>) -> Result<()> {
+    /// let connection = endpoint.connect("https://example.com:4433/chat").await?;
+    /// let other_connection = endpoint
+    ///     .open_session(&connection, "https://example.com:4433/game")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn open_session<O>(
+        &self,
+        connection: &Connection,
+        options: O,
+    ) -> Result<Connection, ConnectingError>
+    where
+        O: IntoConnectOptions,
+    {
+        let options = options.into_options();
+        let url = Self::parse_url(&options.url)?;
+
+        Self::request_session(
+            connection.quic_connection().clone(),
+            connection.driver().clone(),
+            &url,
+            &options,
+            &self.side.webtransport_config.drafts,
+            connection.draft(),
+        )
+        .await
+    }
+
+    /// Sends a plain HTTP/3 request over a new QUIC connection.
+    ///
+    /// The connection is closed once the returned response is dropped. The server is not
+    /// required to support WebTransport.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use anyhow::Result;
+    /// # use wtransport::endpoint::endpoint_side::Client;
+    /// use wtransport::http::Request;
+    ///
+    /// # async fn example(endpoint: wtransport::Endpoint<Client>) -> Result<()> {
+    /// let mut response = endpoint
+    ///     .request(Request::get("https://example.com:4433/bootstrap.json"))
+    ///     .await?;
+    ///
+    /// if response.status() == 200 {
+    ///     let bootstrap = response.read_to_end().await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request(&self, request: Request) -> Result<IncomingResponse, HttpError> {
+        let url = Self::parse_url(request.url()).map_err(HttpError::Connecting)?;
+
+        let (quic_connection, driver, _settings) = self
+            .establish_h3(&url)
+            .await
+            .map_err(HttpError::Connecting)?;
+
+        request.send(&url, quic_connection, driver).await
+    }
+
+    /// Sends a plain HTTP/3 request over the QUIC connection of an established
+    /// [`Connection`].
+    ///
+    /// The URL of `request` is expected to refer to the same server of `connection`; only its
+    /// authority and path are used.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use anyhow::Result;
+    /// # use wtransport::endpoint::endpoint_side::Client;
+    /// use wtransport::http::Request;
+    ///
+    /// # async fn example(endpoint: wtransport::Endpoint<Client>) -> Result<()> {
+    /// let connection = endpoint.connect("https://example.com:4433/chat").await?;
+    /// let response = endpoint
+    ///     .request_on(&connection, Request::get("https://example.com:4433/status"))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_on(
+        &self,
+        connection: &Connection,
+        request: Request,
+    ) -> Result<IncomingResponse, HttpError> {
+        let url = Self::parse_url(request.url()).map_err(HttpError::Connecting)?;
+
+        request
+            .send(
+                &url,
+                connection.quic_connection().clone(),
+                connection.driver().clone(),
+            )
+            .await
+    }
+
+    /// Opens a CONNECT-UDP tunnel to `target_host`:`target_port` over a new QUIC connection
+    /// with the proxy identified by `template`.
+    ///
+    /// The connection is closed once the returned tunnel is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use anyhow::Result;
+    /// # use wtransport::endpoint::endpoint_side::Client;
+    /// use wtransport::connect_udp::UriTemplate;
+    ///
+    /// # async fn example(endpoint: wtransport::Endpoint<Client>) -> Result<()> {
+    /// let template = UriTemplate::with_default_path("proxy.example.org:4433")?;
+    /// let tunnel = endpoint.connect_udp(&template, "192.0.2.6", 443).await?;
+    ///
+    /// tunnel.send(b"ping")?;
+    /// let payload = tunnel.receive().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_udp(
+        &self,
+        template: &UriTemplate,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<UdpTunnel, ConnectingError> {
+        let url = Self::parse_url(&template.expand(target_host, target_port))?;
+
+        let (quic_connection, driver, settings) = self.establish_h3(&url).await?;
+
+        let quic_datagrams = quic_connection.max_datagram_size().is_some();
+
+        check_datagram_settings(&settings, quic_datagrams, true).map_err(|missing| {
+            ConnectingError::ConnectionError(refuse_peer(missing, &quic_connection))
+        })?;
+
+        let request = ConnectUdpRequest::new(template, target_host, target_port);
+
+        UdpTunnel::open(quic_connection, driver, request).await
+    }
+
+    fn parse_url(url: &str) -> Result<Url, ConnectingError> {
+        let url = Url::parse(url)
+            .map_err(|parse_error| ConnectingError::InvalidUrl(parse_error.to_string()))?;
+
+        if url.scheme() != "https" {
+            return Err(ConnectingError::InvalidUrl(
+                "WebTransport URL scheme must be 'https'".to_string(),
+            ));
+        }
+
+        Ok(url)
+    }
 
+    async fn request_session(
+        quic_connection: quinn::Connection,
+        driver: Arc<Driver>,
+        url: &Url,
+        options: &ConnectOptions,
+        drafts: &[WebTransportDraft],
+        draft: WebTransportDraft,
+    ) -> Result<Connection, ConnectingError> {
         let mut session_request_proto =
             SessionRequestProto::new(url.as_ref()).expect("Url has been already validate");
 
-        for (k, v) in options.additional_headers {
+        match draft {
+            WebTransportDraft::Draft02 => session_request_proto
+                .insert(WebTransportDraft::HEADER_DRAFT02, "1")
+                .expect("Draft header is not reserved"),
+            WebTransportDraft::Draft07 => session_request_proto
+                .insert(WebTransportDraft::HEADER, draft.header_value())
+                .expect("Draft header is not reserved"),
+            WebTransportDraft::Draft09 => {}
+        }
+
+        for (k, v) in &options.additional_headers {
             session_request_proto
-                .insert(k.clone(), v)
-                .map_err(|ReservedHeader| ConnectingError::ReservedHeader(k))?;
+                .insert(k, v)
+                .map_err(|ReservedHeader| ConnectingError::ReservedHeader(k.clone()))?;
         }
 
-        let mut stream_session = match driver.open_session(session_request_proto).await {
-            Ok(stream_session) => stream_session,
+        session_request_proto
+            .set_offered_protocols(&options.protocols)
+            .map_err(|InvalidProtocol| ConnectingError::InvalidProtocol)?;
+
+        let (mut stream_session, session) = match driver.open_session(session_request_proto).await {
+            Ok(ready_session) => ready_session,
             Err(driver_error) => {
                 return Err(ConnectingError::ConnectionError(
                     ConnectionError::with_driver_error(driver_error, &quic_connection),
@@ -357,15 +890,12 @@ impl Endpoint<endpoint_side::Client> {
             }
         };
 
-        let session_id = stream_session.session_id();
+        let frame = driver.encode_headers(stream_session.id(), stream_session.request().headers());
 
-        match stream_session
-            .write_frame(stream_session.request().headers().generate_frame())
-            .await
-        {
+        match stream_session.write_frame(frame).await {
             Ok(()) => {}
             Err(ProtoWriteError::Stopped) => {
-                return Err(ConnectingError::SessionRejected);
+                return Err(ConnectingError::rejected_without_response());
             }
             Err(ProtoWriteError::NotConnected) => {
                 return Err(ConnectingError::with_no_connection(&quic_connection));
@@ -381,9 +911,12 @@ impl Endpoint<endpoint_side::Client> {
                         ConnectionError::local_h3_error(error_code),
                     ));
                 }
-                Err(ProtoReadError::IO(_io_error)) => {
+                Err(ProtoReadError::IO(IoReadError::NotConnected)) => {
                     return Err(ConnectingError::with_no_connection(&quic_connection));
                 }
+                Err(ProtoReadError::IO(_io_error)) => {
+                    return Err(ConnectingError::rejected_without_response());
+                }
             };
 
             if let FrameKind::Exercise(_) = frame.kind() {
@@ -399,7 +932,7 @@ impl Endpoint<endpoint_side::Client> {
             ));
         }
 
-        let headers = match Headers::with_frame(&frame) {
+        let headers = match driver.decode_headers(stream_session.id(), &frame).await {
             Ok(headers) => headers,
             Err(error_code) => {
                 quic_connection.close(varint_w2q(error_code.to_code()), b"");
@@ -419,7 +952,16 @@ impl Endpoint<endpoint_side::Client> {
             }
         };
 
+        let protocol = session_response.protocol();
+
         if session_response.code().is_successful() {
+            if let Some(protocol) = protocol.as_ref() {
+                if !options.protocols.contains(protocol) {
+                    stream_session.finish().await;
+                    return Err(ConnectingError::ProtocolNotOffered(protocol.clone()));
+                }
+            }
+
             match driver.register_session(stream_session).await {
                 Ok(()) => {}
                 Err(driver_error) => {
@@ -429,10 +971,21 @@ impl Endpoint<endpoint_side::Client> {
                 }
             }
         } else {
-            return Err(ConnectingError::SessionRejected);
+            return Err(ConnectingError::SessionRejected {
+                status: Some(session_response.code()),
+                headers: session_response.headers().clone(),
+            });
         }
 
-        Ok(Connection::new(quic_connection, driver, session_id))
+        let draft = session_draft(session_response.draft(), drafts, draft);
+
+        Ok(Connection::new(
+            quic_connection,
+            driver,
+            session,
+            draft,
+            session_response,
+        ))
     }
 }
 
@@ -457,6 +1010,8 @@ impl Endpoint<endpoint_side::Client> {
 pub struct ConnectOptions {
     url: String,
     additional_headers: HashMap<String, String>,
+    protocols: Vec<String>,
+    redirect_policy: RedirectPolicy,
 }
 
 impl ConnectOptions {
@@ -477,10 +1032,26 @@ impl ConnectOptions {
         ConnectRequestBuilder {
             url: url.to_string(),
             additional_headers: Default::default(),
+            protocols: Default::default(),
+            redirect_policy: Default::default(),
         }
     }
+
+    /// Removes the additional headers carrying credentials.
+    fn strip_sensitive_headers(&mut self) {
+        self.additional_headers.retain(|key, _| {
+            !SENSITIVE_HEADERS
+                .iter()
+                .any(|sensitive| key.eq_ignore_ascii_case(sensitive))
+        });
+    }
 }
 
+/// Header fields carrying credentials, not forwarded to another authority by default.
+///
+/// See [`RedirectPolicy::forward_sensitive_headers`].
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
 /// A trait for converting types into `ConnectOptions`.
 pub trait IntoConnectOptions {
     /// Perform value-to-value conversion into [`ConnectOptions`].
@@ -493,6 +1064,8 @@ pub trait IntoConnectOptions {
 pub struct ConnectRequestBuilder {
     url: String,
     additional_headers: HashMap<String, String>,
+    protocols: Vec<String>,
+    redirect_policy: RedirectPolicy,
 }
 
 impl ConnectRequestBuilder {
@@ -517,15 +1090,163 @@ impl ConnectRequestBuilder {
         self
     }
 
+    /// Offers an application protocol to the server.
+    ///
+    /// Protocols are offered in the order they are added, the first being the preferred one.
+    /// The server might select one of them (see [`Connection::protocol`]); selecting a protocol
+    /// not offered makes the connection fail with [`ConnectingError::ProtocolNotOffered`].
+    ///
+    /// Protocols can only contain printable ASCII characters, otherwise the connection fails
+    /// with [`ConnectingError::InvalidProtocol`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wtransport::endpoint::ConnectOptions;
+    ///
+    /// let options = ConnectOptions::builder("https://example.com:4433/webtransport")
+    ///     .add_protocol("chat-v2")
+    ///     .add_protocol("chat-v1")
+    ///     .build();
+    /// ```
+    pub fn add_protocol<P>(mut self, protocol: P) -> Self
+    where
+        P: ToString,
+    {
+        self.protocols.push(protocol.to_string());
+        self
+    }
+
+    /// Sets the policy for following redirections of the session request.
+    ///
+    /// By default, redirections are not followed and they fail the connection with
+    /// [`ConnectingError::SessionRejected`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wtransport::endpoint::ConnectOptions;
+    /// use wtransport::endpoint::RedirectPolicy;
+    ///
+    /// let options = ConnectOptions::builder("https://example.com:4433/webtransport")
+    ///     .redirect_policy(RedirectPolicy::limited(3).allow_cross_authority(true))
+    ///     .build();
+    /// ```
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = policy;
+        self
+    }
+
     /// Constructs the [`ConnectOptions`] from the builder configuration.
     pub fn build(self) -> ConnectOptions {
         ConnectOptions {
             url: self.url,
             additional_headers: self.additional_headers,
+            protocols: self.protocols,
+            redirect_policy: self.redirect_policy,
+        }
+    }
+}
+
+/// Policy for following redirections of session requests.
+///
+/// A redirection is a response with a `3xx` status code and a `location` header.
+/// It is followed by re-issuing the session request to the new URL: on the current
+/// QUIC connection if the authority (host and port) is the same, otherwise on a new
+/// connection.
+///
+/// Only [`Endpoint::connect`] follows redirections.
+#[derive(Debug, Clone, Default)]
+pub struct RedirectPolicy {
+    max_hops: usize,
+    same_origin_only: bool,
+    allow_cross_authority: bool,
+    forward_sensitive_headers: bool,
+}
+
+impl RedirectPolicy {
+    /// Redirections are never followed.
+    ///
+    /// This is the default policy.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Follows at most `max_hops` redirections.
+    ///
+    /// By default, only redirections to the same authority are followed
+    /// (see [`allow_cross_authority`](Self::allow_cross_authority)).
+    pub fn limited(max_hops: usize) -> Self {
+        Self {
+            max_hops,
+            ..Default::default()
+        }
+    }
+
+    /// Only follows redirections to URLs with the same origin of the initial request.
+    ///
+    /// Default value is `false`.
+    pub fn same_origin_only(mut self, value: bool) -> Self {
+        self.same_origin_only = value;
+        self
+    }
+
+    /// Follows redirections to another authority, establishing a new QUIC connection.
+    ///
+    /// The new server is resolved with
+    /// [`ClientConfigBuilder::dns_resolver`](crate::config::ClientConfigBuilder::dns_resolver).
+    /// Default value is `false`.
+    pub fn allow_cross_authority(mut self, value: bool) -> Self {
+        self.allow_cross_authority = value;
+        self
+    }
+
+    /// Keeps sending the `authorization`, `cookie` and `proxy-authorization` additional
+    /// headers after a redirection to another authority.
+    ///
+    /// Otherwise, they are dropped from the first redirection to another authority on,
+    /// so that credentials are not disclosed to a different server.
+    /// Default value is `false`.
+    pub fn forward_sensitive_headers(mut self, value: bool) -> Self {
+        self.forward_sensitive_headers = value;
+        self
+    }
+
+    /// Returns the URL to follow for a response to the request to `url`, if allowed.
+    fn redirect(
+        &self,
+        initial_url: &Url,
+        url: &Url,
+        status: StatusCode,
+        headers: &Headers,
+    ) -> Option<Url> {
+        if !(300..400).contains(&status.into_inner()) {
+            return None;
+        }
+
+        let target = url.join(headers.get("location")?).ok()?;
+
+        if target.scheme() != "https" {
+            return None;
+        }
+
+        if self.same_origin_only && target.origin() != initial_url.origin() {
+            return None;
         }
+
+        if !self.allow_cross_authority && !same_authority(url, &target) {
+            return None;
+        }
+
+        Some(target)
     }
 }
 
+/// Returns `true` if `a` and `b` refer to the same host and port.
+fn same_authority(a: &Url, b: &Url) -> bool {
+    a.host() == b.host() && a.port_or_known_default() == b.port_or_known_default()
+}
+
 impl IntoConnectOptions for ConnectRequestBuilder {
     fn into_options(self) -> ConnectOptions {
         self.build()
@@ -547,35 +1268,488 @@ where
     }
 }
 
-type DynFutureIncomingSession =
-    dyn Future<Output = Result<SessionRequest, ConnectionError>> + Send + Sync;
-
+/// Policy for admitting session requests on a server.
+///
+/// Requests not satisfying the policy are rejected by the endpoint before being yielded by
+/// [`Endpoint::accept`]: with `404` status code if the path is not allowed, `403` otherwise.
+/// The reason is logged.
+///
+/// By default, all requests are admitted. Each option with an empty list imposes no restriction.
+///
+/// #### Examples:
+/// ```
+/// use wtransport::endpoint::SessionPolicy;
+///
+/// let policy = SessionPolicy::new()
+///     .allowed_origins(["https://example.com", "https://*.example.com"])
+///     .allowed_paths(["/chat"])
+///     .required_headers(["authorization"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SessionPolicy {
+    origins: Vec<String>,
+    paths: Vec<String>,
+    headers: Vec<String>,
+    authorities: Vec<String>,
+    match_server_name: bool,
+}
+
+impl SessionPolicy {
+    /// Creates a policy admitting all requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only admits requests whose `origin` header is among `origins`.
+    ///
+    /// An origin is in the form `scheme://host[:port]`. The host can start with a `*.`
+    /// wildcard, matching any of its subdomains (but not the domain itself).
+    /// Requests without an `origin` header are rejected.
+    pub fn allowed_origins<I, S>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.origins = lowercase(origins);
+        self
+    }
+
+    /// Only admits requests whose `:path` (query excluded) is among `paths`.
+    pub fn allowed_paths<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.paths = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Only admits requests carrying all header fields named in `headers`.
+    pub fn required_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.headers = lowercase(headers);
+        self
+    }
+
+    /// Only admits requests whose `:authority` is among `authorities`.
+    pub fn allowed_authorities<I, S>(mut self, authorities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.authorities = lowercase(authorities);
+        self
+    }
+
+    /// Only admits requests whose `:authority` host matches the server name (SNI) the client
+    /// presented in the TLS handshake.
+    ///
+    /// If the client did not present a server name, the host must be an IP address.
+    /// Other requests are rejected with `421` status code.
+    /// By default, it is `false`.
+    pub fn match_server_name(mut self, value: bool) -> Self {
+        self.match_server_name = value;
+        self
+    }
+
+    fn check(
+        &self,
+        request: &SessionRequestProto,
+        server_name: Option<&str>,
+    ) -> Result<(), PolicyViolation> {
+        let authority = request.authority().to_ascii_lowercase();
+
+        if self.match_server_name && !server_name_matches(&authority, server_name) {
+            return Err(PolicyViolation::Misdirected {
+                authority,
+                server_name: server_name.map(str::to_string),
+            });
+        }
+        if !self.authorities.is_empty() && !self.authorities.contains(&authority) {
+            return Err(PolicyViolation::Authority(authority));
+        }
+
+        let path = request.path().split('?').next().unwrap_or_default();
+        if !self.paths.is_empty() && !self.paths.iter().any(|allowed| allowed == path) {
+            return Err(PolicyViolation::Path(path.to_string()));
+        }
+
+        if !self.origins.is_empty() {
+            let origin = request.origin().map(str::to_ascii_lowercase);
+
+            if !origin.as_deref().is_some_and(|origin| {
+                self.origins
+                    .iter()
+                    .any(|allowed| origin_matches(allowed, origin))
+            }) {
+                return Err(PolicyViolation::Origin(origin));
+            }
+        }
+
+        if let Some(header) = self
+            .headers
+            .iter()
+            .find(|header| request.get(header.as_str()).is_none())
+        {
+            return Err(PolicyViolation::MissingHeader(header.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Reason a session request is not admitted by a [`SessionPolicy`].
+#[derive(Debug, thiserror::Error)]
+enum PolicyViolation {
+    #[error("authority '{authority}' not matching server name {server_name:?}")]
+    Misdirected {
+        authority: String,
+        server_name: Option<String>,
+    },
+
+    #[error("authority '{0}' not allowed")]
+    Authority(String),
+
+    #[error("path '{0}' not allowed")]
+    Path(String),
+
+    #[error("origin {0:?} not allowed")]
+    Origin(Option<String>),
+
+    #[error("missing required header '{0}'")]
+    MissingHeader(String),
+}
+
+impl PolicyViolation {
+    fn response(&self) -> SessionResponseProto {
+        match self {
+            PolicyViolation::Misdirected { .. } => SessionResponseProto::misdirected_request(),
+            PolicyViolation::Path(_) => SessionResponseProto::not_found(),
+            PolicyViolation::Authority(_)
+            | PolicyViolation::Origin(_)
+            | PolicyViolation::MissingHeader(_) => SessionResponseProto::forbidden(),
+        }
+    }
+}
+
+fn lowercase<I, S>(values: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    values
+        .into_iter()
+        .map(|value| value.as_ref().to_ascii_lowercase())
+        .collect()
+}
+
+/// Returns the host of `authority`, if valid.
+fn authority_host(authority: &str) -> Option<Host> {
+    Url::parse(&format!("https://{authority}"))
+        .ok()?
+        .host()
+        .map(|host| host.to_owned())
+}
+
+/// Returns `true` if the host of `authority` is `server_name`.
+///
+/// Without server name, only IP addresses are expected.
+fn server_name_matches(authority: &str, server_name: Option<&str>) -> bool {
+    match (authority_host(authority), server_name) {
+        (Some(Host::Domain(host)), Some(server_name)) => host.eq_ignore_ascii_case(server_name),
+        (Some(Host::Ipv4(_) | Host::Ipv6(_)), None) => true,
+        _ => false,
+    }
+}
+
+/// Returns `true` if `origin` matches the (lowercase) `allowed` origin pattern.
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+    let (Some((allowed_scheme, allowed_host)), Some((scheme, host))) =
+        (allowed.split_once("://"), origin.split_once("://"))
+    else {
+        return false;
+    };
+
+    if allowed_scheme != scheme {
+        return false;
+    }
+
+    match allowed_host.strip_prefix('*') {
+        Some(domain) if domain.starts_with('.') => {
+            host.len() > domain.len() && host.ends_with(domain)
+        }
+        _ => allowed_host == host,
+    }
+}
+
+/// A type alias representing the dynamic future returned by a [`SessionHandler`].
+pub type DynFutureSessionHandler = dyn Future<Output = ()> + Send;
+
+/// A trait for asynchronously handling session requests routed to a virtual host.
+///
+/// It is implemented for closures taking a [`SessionRequest`] and returning a future.
+/// See [`ServerConfigBuilder::virtual_host`](crate::config::ServerConfigBuilder::virtual_host).
+pub trait SessionHandler {
+    /// Handles a request, accepting or rejecting the session.
+    ///
+    /// The returned future is spawned on the *Tokio* runtime.
+    fn handle(&self, request: SessionRequest) -> Pin<Box<DynFutureSessionHandler>>;
+}
+
+impl<F, R> SessionHandler for F
+where
+    F: Fn(SessionRequest) -> R,
+    R: Future<Output = ()> + Send + 'static,
+{
+    fn handle(&self, request: SessionRequest) -> Pin<Box<DynFutureSessionHandler>> {
+        Box::pin(self(request))
+    }
+}
+
+/// A [`SessionHandler`] shared among connections.
+#[derive(Clone)]
+pub(crate) struct SharedSessionHandler(Arc<dyn SessionHandler + Send + Sync>);
+
+impl SharedSessionHandler {
+    pub(crate) fn new<H>(handler: H) -> Self
+    where
+        H: SessionHandler + Send + Sync + 'static,
+    {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn handle(&self, request: SessionRequest) -> Pin<Box<DynFutureSessionHandler>> {
+        self.0.handle(request)
+    }
+}
+
+impl Debug for SharedSessionHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionHandler").finish_non_exhaustive()
+    }
+}
+
+/// Dispatches session requests of a connection, before they reach [`Endpoint::accept`].
+#[derive(Clone)]
+struct SessionRouter {
+    policy: SessionPolicy,
+    virtual_hosts: HashMap<String, SharedSessionHandler>,
+}
+
+impl SessionRouter {
+    fn new(webtransport_config: &WebTransportConfig) -> Self {
+        Self {
+            policy: webtransport_config.session_policy.clone(),
+            virtual_hosts: webtransport_config.virtual_hosts.clone(),
+        }
+    }
+
+    /// Returns the request back if it is for the endpoint.
+    ///
+    /// Requests violating the policy are rejected, the ones for a virtual host are
+    /// passed to its handler.
+    fn route(&self, session_request: SessionRequest) -> Option<SessionRequest> {
+        let server_name = session_request.server_name();
+
+        if let Err(violation) = self.policy.check(
+            session_request.stream_session.request(),
+            server_name.as_deref(),
+        ) {
+            debug!("Session request rejected by policy: {violation}");
+            let response = SessionResponse(violation.response());
+            tokio::spawn(session_request.reject_with_response(response));
+            return None;
+        }
+
+        match self.virtual_host(session_request.authority()) {
+            Some(handler) => {
+                tokio::spawn(handler.handle(session_request));
+                None
+            }
+            None => Some(session_request),
+        }
+    }
+
+    /// Looks up the handler for `authority`, first with its port then without.
+    fn virtual_host(&self, authority: &str) -> Option<&SharedSessionHandler> {
+        if self.virtual_hosts.is_empty() {
+            return None;
+        }
+
+        self.virtual_hosts
+            .get(&authority.to_ascii_lowercase())
+            .or_else(|| {
+                self.virtual_hosts
+                    .get(&authority_host(authority)?.to_string())
+            })
+    }
+}
+
+type DynFutureIncomingSession =
+    dyn Future<Output = Result<SessionRequest, ConnectionError>> + Send + Sync;
+
 /// [`Future`] for an in-progress incoming connection attempt.
 ///
 /// Created by [`Endpoint::accept`].
+///
+/// Session requests rejected by the [`SessionPolicy`] or routed to a
+/// [virtual host](crate::config::ServerConfigBuilder::virtual_host) are not yielded: the future
+/// waits for a further request on the same connection, failing once the connection is closed.
+///
+/// Connections of peers without WebTransport support fail right away with
+/// [`ConnectionError::HttpOnly`] if they are kept open to serve plain HTTP/3 requests.
 pub struct IncomingSession(Pin<Box<DynFutureIncomingSession>>);
 
 impl IncomingSession {
-    fn new(quic_connecting: quinn::Connecting) -> Self {
-        Self(Box::pin(Self::accept(quic_connecting)))
+    fn new(
+        quic_connecting: quinn::Connecting,
+        webtransport_config: WebTransportConfig,
+        pooled_sessions: mpsc::Sender<SessionRequest>,
+        drivers: ConnectionRegistry,
+        handshake: Arc<SocketAddr>,
+    ) -> Self {
+        Self(Box::pin(Self::accept(
+            quic_connecting,
+            webtransport_config,
+            pooled_sessions,
+            drivers,
+            handshake,
+        )))
+    }
+
+    fn ready(session_request: SessionRequest) -> Self {
+        Self(Box::pin(std::future::ready(Ok(session_request))))
     }
 
-    async fn accept(quic_connecting: quinn::Connecting) -> Result<SessionRequest, ConnectionError> {
+    async fn accept(
+        quic_connecting: quinn::Connecting,
+        webtransport_config: WebTransportConfig,
+        pooled_sessions: mpsc::Sender<SessionRequest>,
+        drivers: ConnectionRegistry,
+        handshake: Arc<SocketAddr>,
+    ) -> Result<SessionRequest, ConnectionError> {
         let quic_connection = quic_connecting.await?;
+        let drafts = webtransport_config.drafts.clone();
+        let router = SessionRouter::new(&webtransport_config);
+        let serves_http = webtransport_config.http_handler.is_some()
+            || webtransport_config.connect_udp_handler.is_some()
+            || webtransport_config.websocket_handler.is_some();
+
+        let driver = {
+            let mut drivers = drivers.lock().expect("Registry lock not poisoned");
+
+            let Some(drivers) = drivers.as_mut() else {
+                debug!("Endpoint shutting down: closing connection");
+                quic_connection.close(varint_w2q(ErrorCode::NoError.to_code()), b"");
+                return Err(ConnectionError::LocallyClosed);
+            };
 
-        let driver = Driver::init(quic_connection.clone());
+            let driver = Arc::new(Driver::init(quic_connection.clone(), webtransport_config));
+            drivers.retain(|driver| driver.strong_count() > 0);
+            drivers.push(Arc::downgrade(&driver));
+            driver
+        };
+
+        // The connection is now accounted in the registry
+        drop(handshake);
 
-        let _settings = driver.accept_settings().await.map_err(|driver_error| {
+        let settings = driver.accept_settings().await.map_err(|driver_error| {
             ConnectionError::with_driver_error(driver_error, &quic_connection)
         })?;
 
-        // TODO(biagio): validate settings
+        let quic_datagrams = quic_connection.max_datagram_size().is_some();
 
-        let stream_session = driver.accept_session().await.map_err(|driver_error| {
-            ConnectionError::with_driver_error(driver_error, &quic_connection)
-        })?;
+        let draft = match check_settings(&settings, &drafts, quic_datagrams, false) {
+            Ok(draft) => draft,
+            Err(missing) if serves_http => {
+                debug!("Peer without WebTransport support ({missing}): serving HTTP/3 only");
+
+                // The driver keeps serving requests until the connection is closed
+                tokio::spawn(async move {
+                    driver.closed().await;
+                });
+
+                return Err(ConnectionError::HttpOnly(missing));
+            }
+            Err(missing) => return Err(refuse_peer(missing, &quic_connection)),
+        };
+
+        let session_request = loop {
+            let (stream_session, session) =
+                driver.accept_session().await.map_err(|driver_error| {
+                    ConnectionError::with_driver_error(driver_error, &quic_connection)
+                })?;
 
-        Ok(SessionRequest::new(quic_connection, driver, stream_session))
+            let session_draft = session_draft(stream_session.request().draft(), &drafts, draft);
+
+            let session_request = SessionRequest::new(
+                quic_connection.clone(),
+                driver.clone(),
+                stream_session,
+                session,
+                session_draft,
+            );
+
+            if let Some(session_request) = router.route(session_request) {
+                break session_request;
+            }
+        };
+
+        tokio::spawn(Self::pool_sessions(
+            quic_connection,
+            Arc::downgrade(&driver),
+            driver.session_acceptor(),
+            pooled_sessions,
+            drafts,
+            draft,
+            router,
+        ));
+
+        Ok(session_request)
+    }
+
+    /// Forwards further session requests on the same QUIC connection to the endpoint.
+    ///
+    /// It does not keep the driver alive: once all sessions are dropped, the connection
+    /// is terminated and this task ends.
+    async fn pool_sessions(
+        quic_connection: quinn::Connection,
+        driver: Weak<Driver>,
+        session_acceptor: SessionAcceptor,
+        pooled_sessions: mpsc::Sender<SessionRequest>,
+        drafts: Vec<WebTransportDraft>,
+        draft: WebTransportDraft,
+        router: SessionRouter,
+    ) {
+        while let Some((stream_session, session)) = session_acceptor.accept().await {
+            let Some(driver) = driver.upgrade() else {
+                break;
+            };
+
+            let session_draft = session_draft(stream_session.request().draft(), &drafts, draft);
+
+            let session_request = SessionRequest::new(
+                quic_connection.clone(),
+                driver,
+                stream_session,
+                session,
+                session_draft,
+            );
+
+            let Some(session_request) = router.route(session_request) else {
+                continue;
+            };
+
+            if pooled_sessions.send(session_request).await.is_err() {
+                break;
+            }
+        }
     }
 }
 
@@ -591,22 +1765,30 @@ impl Future for IncomingSession {
 ///
 /// Server should use methods [`accept`](Self::accept), [`forbidden`](Self::forbidden),
 /// or [`not_found`](Self::not_found) in order to validate or reject the client request.
+/// Custom responses can be sent with [`accept_with_response`](Self::accept_with_response)
+/// and [`reject_with_response`](Self::reject_with_response).
 pub struct SessionRequest {
     quic_connection: quinn::Connection,
-    driver: Driver,
+    driver: Arc<Driver>,
     stream_session: StreamSession,
+    session: SessionQueues,
+    draft: WebTransportDraft,
 }
 
 impl SessionRequest {
     pub(crate) fn new(
         quic_connection: quinn::Connection,
-        driver: Driver,
+        driver: Arc<Driver>,
         stream_session: StreamSession,
+        session: SessionQueues,
+        draft: WebTransportDraft,
     ) -> Self {
         Self {
             quic_connection,
             driver,
             stream_session,
+            session,
+            draft,
         }
     }
 
@@ -631,24 +1813,81 @@ impl SessionRequest {
     }
 
     /// Returns all header fields associated with the request.
-    pub fn headers(&self) -> &HashMap<String, String> {
-        self.stream_session.request().headers().as_ref()
+    pub fn headers(&self) -> &Headers {
+        self.stream_session.request().headers()
+    }
+
+    /// Returns the server name (SNI) the client presented in the TLS handshake, if any.
+    ///
+    /// It can differ from the [`authority`](Self::authority) of the request.
+    pub fn server_name(&self) -> Option<String> {
+        self.quic_connection
+            .handshake_data()?
+            .downcast::<quinn::crypto::rustls::HandshakeData>()
+            .ok()?
+            .server_name
+    }
+
+    /// Returns the draft of WebTransport over HTTP/3 the session uses once accepted.
+    ///
+    /// It is the draft declared by the client in the request if accepted by the server
+    /// (see [`ServerConfigBuilder::webtransport_drafts`](crate::config::ServerConfigBuilder::webtransport_drafts)),
+    /// otherwise the one negotiated with SETTINGS.
+    pub fn draft(&self) -> WebTransportDraft {
+        self.draft
+    }
+
+    /// Returns the application protocols offered by the client, in order of preference.
+    ///
+    /// It is empty if the client did not offer any protocol.
+    pub fn offered_protocols(&self) -> Vec<String> {
+        self.stream_session.request().offered_protocols()
     }
 
     /// Accepts the client request and it establishes the WebTransport session.
-    pub async fn accept(mut self) -> Result<Connection, ConnectionError> {
-        let user_agent = self.user_agent().unwrap_or_default();
+    pub async fn accept(self) -> Result<Connection, ConnectionError> {
+        self.accept_with_response(SessionResponse::ok()).await
+    }
 
-        let mut response = SessionResponseProto::ok();
+    /// Accepts the client request selecting one of the
+    /// [offered protocols](Self::offered_protocols), and it establishes the
+    /// WebTransport session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocol` has not been offered by the client.
+    pub async fn accept_with_protocol(self, protocol: &str) -> Result<Connection, ConnectionError> {
+        self.accept_with_response(SessionResponse::ok().protocol(protocol))
+            .await
+    }
 
-        // Chrome support
-        if !user_agent.contains("firefox") {
-            response.add("sec-webtransport-http3-draft", "draft02");
+    /// Accepts the client request replying with `response`, and it establishes the
+    /// WebTransport session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `response` does not have a `2xx` status code, or if it selects a
+    /// protocol not offered by the client.
+    pub async fn accept_with_response(
+        mut self,
+        response: SessionResponse,
+    ) -> Result<Connection, ConnectionError> {
+        let mut response = response.0;
+
+        assert!(
+            response.code().is_successful(),
+            "Accepting response must have a 2xx status code"
+        );
+
+        if let Some(protocol) = response.protocol() {
+            assert!(
+                self.offered_protocols().contains(&protocol),
+                "Protocol must be offered by the client"
+            );
         }
 
-        self.send_response(response).await?;
-
-        let session_id = self.stream_session.session_id();
+        self.add_draft_header(&mut response);
+        self.send_response(&response).await?;
 
         self.driver
             .register_session(self.stream_session)
@@ -660,37 +1899,60 @@ impl SessionRequest {
         Ok(Connection::new(
             self.quic_connection,
             self.driver,
-            session_id,
+            self.session,
+            self.draft,
+            response,
         ))
     }
 
     /// Rejects the client request by replying with `403` status code.
     pub async fn forbidden(self) {
-        self.reject(SessionResponseProto::forbidden()).await;
+        self.reject_with_response(SessionResponse::forbidden())
+            .await;
     }
 
     /// Rejects the client request by replying with `404` status code.
     pub async fn not_found(self) {
-        self.reject(SessionResponseProto::not_found()).await;
+        self.reject_with_response(SessionResponse::not_found())
+            .await;
     }
 
-    async fn reject(mut self, mut response: SessionResponseProto) {
+    /// Rejects the client request by replying with `response`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `response` has a `2xx` status code.
+    pub async fn reject_with_response(mut self, response: SessionResponse) {
+        let mut response = response.0;
+
+        assert!(
+            !response.code().is_successful(),
+            "Rejecting response must not have a 2xx status code"
+        );
+
+        self.add_draft_header(&mut response);
+
+        let _ = self.send_response(&response).await;
+        self.stream_session.finish().await;
+    }
+
+    /// Older drafts echo the draft in the response.
+    fn add_draft_header(&self, response: &mut SessionResponseProto) {
         let user_agent = self.user_agent().unwrap_or_default();
 
         // Chrome support
-        if !user_agent.contains("firefox") {
-            response.add("sec-webtransport-http3-draft", "draft02");
+        if self.draft < WebTransportDraft::Draft09 && !user_agent.contains("firefox") {
+            response.add(WebTransportDraft::HEADER, self.draft.header_value());
         }
-
-        let _ = self.send_response(response).await;
-        self.stream_session.finish().await;
     }
 
     async fn send_response(
         &mut self,
-        response: SessionResponseProto,
+        response: &SessionResponseProto,
     ) -> Result<(), ConnectionError> {
-        let frame = response.headers().generate_frame();
+        let frame = self
+            .driver
+            .encode_headers(self.stream_session.id(), response.headers());
 
         match self.stream_session.write_frame(frame).await {
             Ok(()) => Ok(()),
@@ -708,3 +1970,453 @@ impl SessionRequest {
         }
     }
 }
+
+/// A response to a [`SessionRequest`].
+///
+/// Responses with a `2xx` status code establish the session (see
+/// [`SessionRequest::accept_with_response`]), any other rejects it (see
+/// [`SessionRequest::reject_with_response`]).
+///
+/// #### Examples:
+/// ```
+/// use wtransport::endpoint::SessionResponse;
+///
+/// let response = SessionResponse::with_status(401).header("www-authenticate", "Bearer");
+///
+/// assert_eq!(response.status(), 401);
+/// ```
+#[derive(Debug)]
+pub struct SessionResponse(SessionResponseProto);
+
+impl SessionResponse {
+    /// Creates a response with `200` status code.
+    pub fn ok() -> Self {
+        Self(SessionResponseProto::ok())
+    }
+
+    /// Creates a response with `403` status code.
+    pub fn forbidden() -> Self {
+        Self(SessionResponseProto::forbidden())
+    }
+
+    /// Creates a response with `404` status code.
+    pub fn not_found() -> Self {
+        Self(SessionResponseProto::not_found())
+    }
+
+    /// Creates a response with `421` status code.
+    pub fn misdirected_request() -> Self {
+        Self(SessionResponseProto::misdirected_request())
+    }
+
+    /// Creates a response with `status` code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a final status code (in range `200..=599`).
+    pub fn with_status(status: u16) -> Self {
+        assert!(
+            (200..=599).contains(&status),
+            "Status code must be in range 200..=599"
+        );
+
+        let status_code = StatusCode::try_from(status).expect("Status code within bounds");
+        Self(SessionResponseProto::with_status_code(status_code))
+    }
+
+    /// Appends a header field to the response.
+    ///
+    /// The field name is converted to lowercase. Fields already present with the same
+    /// name are kept, e.g., to send multiple `set-cookie` fields.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is a pseudo-header field (starting with `:`).
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        let key = key.as_ref().to_ascii_lowercase();
+        assert!(!key.starts_with(':'), "Pseudo-header fields cannot be set");
+
+        self.0.append(key, value);
+        self
+    }
+
+    /// Selects the application protocol among the ones offered by the client
+    /// (see [`SessionRequest::offered_protocols`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocol` cannot be encoded as a structured field string.
+    pub fn protocol(mut self, protocol: &str) -> Self {
+        self.0
+            .set_protocol(protocol)
+            .expect("Protocol must be a valid string");
+        self
+    }
+
+    /// Returns the status code of the response.
+    pub fn status(&self) -> u16 {
+        self.0.code().into_inner()
+    }
+
+    /// Returns all header fields of the response.
+    pub fn headers(&self) -> &Headers {
+        self.0.headers()
+    }
+}
+
+/// Returns the draft `declared` in the session headers if accepted, otherwise `negotiated`.
+fn session_draft(
+    declared: Option<WebTransportDraft>,
+    drafts: &[WebTransportDraft],
+    negotiated: WebTransportDraft,
+) -> WebTransportDraft {
+    declared
+        .filter(|draft| drafts.contains(draft))
+        .unwrap_or(negotiated)
+}
+
+/// Checks the peer's SETTINGS allow WebTransport sessions.
+///
+/// `peer_is_server` additionally requires the extended CONNECT method to be enabled.
+/// In case of failure, the QUIC connection is closed.
+/// On success, returns the newest draft among `drafts` supported by the peer.
+fn validate_settings(
+    settings: &Settings,
+    drafts: &[WebTransportDraft],
+    quic_connection: &quinn::Connection,
+    peer_is_server: bool,
+) -> Result<WebTransportDraft, ConnectionError> {
+    let quic_datagrams = quic_connection.max_datagram_size().is_some();
+
+    check_settings(settings, drafts, quic_datagrams, peer_is_server)
+        .map_err(|missing| refuse_peer(missing, quic_connection))
+}
+
+/// Negotiates the WebTransport draft, checking the peer supports all required capabilities.
+///
+/// `quic_datagrams` tells whether the peer supports QUIC datagrams.
+fn check_settings(
+    settings: &Settings,
+    drafts: &[WebTransportDraft],
+    quic_datagrams: bool,
+    peer_is_server: bool,
+) -> Result<WebTransportDraft, MissingCapability> {
+    let Some(draft) = WebTransportDraft::negotiate(drafts, settings) else {
+        return Err(MissingCapability::WebTransport);
+    };
+
+    check_datagram_settings(settings, quic_datagrams, peer_is_server)?;
+
+    Ok(draft)
+}
+
+/// Checks the peer supports HTTP datagrams (and the extended CONNECT method, if it is the server).
+fn check_datagram_settings(
+    settings: &Settings,
+    quic_datagrams: bool,
+    peer_is_server: bool,
+) -> Result<(), MissingCapability> {
+    let enabled = |id| {
+        settings
+            .get(id)
+            .is_some_and(|value| value.into_inner() == 1)
+    };
+
+    if !enabled(SettingId::H3Datagram) {
+        Err(MissingCapability::H3Datagram)
+    } else if !quic_datagrams {
+        Err(MissingCapability::QuicDatagram)
+    } else if peer_is_server && !enabled(SettingId::EnableConnectProtocol) {
+        Err(MissingCapability::ConnectProtocol)
+    } else {
+        Ok(())
+    }
+}
+
+fn refuse_peer(missing: MissingCapability, quic_connection: &quinn::Connection) -> ConnectionError {
+    debug!("Incompatible peer settings: {missing}");
+    quic_connection.close(varint_w2q(ErrorCode::Settings.to_code()), b"");
+    ConnectionError::IncompatiblePeer(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wtransport_proto::varint::VarInt;
+
+    fn redirect(policy: &RedirectPolicy, status: u16, location: &str) -> Option<String> {
+        let initial_url = Url::parse("https://example.com/a").unwrap();
+        let url = Url::parse("https://example.com/b").unwrap();
+        let headers = [("location", location)].into_iter().collect();
+        let status = StatusCode::try_from(status).unwrap();
+
+        policy
+            .redirect(&initial_url, &url, status, &headers)
+            .map(String::from)
+    }
+
+    #[test]
+    fn redirect_same_authority() {
+        let policy = RedirectPolicy::limited(1);
+
+        assert_eq!(
+            redirect(&policy, 302, "/c").as_deref(),
+            Some("https://example.com/c")
+        );
+        assert_eq!(
+            redirect(&policy, 307, "https://example.com:443/c").as_deref(),
+            Some("https://example.com/c")
+        );
+        assert_eq!(redirect(&policy, 404, "/c"), None);
+        assert_eq!(redirect(&policy, 302, "http://example.com/c"), None);
+        assert_eq!(redirect(&policy, 302, "https://example.org/c"), None);
+    }
+
+    #[test]
+    fn redirect_cross_authority() {
+        let policy = RedirectPolicy::limited(1).allow_cross_authority(true);
+
+        assert_eq!(
+            redirect(&policy, 308, "https://example.org:4433/c").as_deref(),
+            Some("https://example.org:4433/c")
+        );
+
+        let policy = policy.same_origin_only(true);
+
+        assert_eq!(redirect(&policy, 308, "https://example.org:4433/c"), None);
+        assert_eq!(
+            redirect(&policy, 308, "/c").as_deref(),
+            Some("https://example.com/c")
+        );
+    }
+
+    fn check(policy: &SessionPolicy, path: &str, origin: Option<&str>) -> Option<u16> {
+        check_with_server_name(policy, path, origin, Some("example.com"))
+    }
+
+    fn check_with_server_name(
+        policy: &SessionPolicy,
+        path: &str,
+        origin: Option<&str>,
+        server_name: Option<&str>,
+    ) -> Option<u16> {
+        let mut headers = vec![
+            (":method", "CONNECT"),
+            (":scheme", "https"),
+            (":protocol", "webtransport"),
+            (":authority", "Example.com:4433"),
+            (":path", path),
+            ("authorization", "token"),
+        ];
+        headers.extend(origin.map(|origin| ("origin", origin)));
+
+        let request = SessionRequestProto::try_from(headers.into_iter().collect::<Headers>())
+            .expect("Valid request");
+
+        policy
+            .check(&request, server_name)
+            .err()
+            .map(|violation| violation.response().code().into_inner())
+    }
+
+    #[test]
+    fn session_policy_default() {
+        let policy = SessionPolicy::new();
+
+        assert_eq!(check(&policy, "/", None), None);
+        assert_eq!(check(&policy, "/any", Some("https://example.org")), None);
+    }
+
+    #[test]
+    fn session_policy_origins() {
+        let policy = SessionPolicy::new()
+            .allowed_origins(["https://*.example.com", "https://example.org:4433"]);
+
+        assert_eq!(check(&policy, "/", Some("https://a.example.com")), None);
+        assert_eq!(check(&policy, "/", Some("https://A.B.Example.com")), None);
+        assert_eq!(check(&policy, "/", Some("https://example.org:4433")), None);
+        assert_eq!(check(&policy, "/", Some("https://example.com")), Some(403));
+        assert_eq!(
+            check(&policy, "/", Some("https://badexample.com")),
+            Some(403)
+        );
+        assert_eq!(check(&policy, "/", Some("http://a.example.com")), Some(403));
+        assert_eq!(check(&policy, "/", Some("https://example.org")), Some(403));
+        assert_eq!(check(&policy, "/", None), Some(403));
+    }
+
+    #[test]
+    fn session_policy_request() {
+        let policy = SessionPolicy::new()
+            .allowed_paths(["/chat"])
+            .allowed_authorities(["example.com:4433"])
+            .required_headers(["Authorization"]);
+
+        assert_eq!(check(&policy, "/chat", None), None);
+        assert_eq!(check(&policy, "/chat?room=1", None), None);
+        assert_eq!(check(&policy, "/other", None), Some(404));
+
+        let policy = policy.required_headers(["cookie"]);
+        assert_eq!(check(&policy, "/chat", None), Some(403));
+
+        let policy = SessionPolicy::new().allowed_authorities(["example.org"]);
+        assert_eq!(check(&policy, "/chat", None), Some(403));
+    }
+
+    #[test]
+    fn session_policy_server_name() {
+        let policy = SessionPolicy::new().match_server_name(true);
+
+        assert_eq!(check(&policy, "/", None), None);
+        assert_eq!(
+            check_with_server_name(&policy, "/", None, Some("EXAMPLE.com")),
+            None
+        );
+        assert_eq!(
+            check_with_server_name(&policy, "/", None, Some("example.org")),
+            Some(421)
+        );
+        assert_eq!(check_with_server_name(&policy, "/", None, None), Some(421));
+        assert!(server_name_matches("127.0.0.1:4433", None));
+        assert!(server_name_matches("[::1]:4433", None));
+        assert!(!server_name_matches("127.0.0.1:4433", Some("example.com")));
+    }
+
+    #[test]
+    fn virtual_host_lookup() {
+        let mut webtransport_config = WebTransportConfig::default();
+
+        for authority in ["example.com", "example.org:4433"] {
+            webtransport_config.virtual_hosts.insert(
+                authority.to_string(),
+                SharedSessionHandler::new(|_request| async {}),
+            );
+        }
+
+        let router = SessionRouter::new(&webtransport_config);
+
+        assert!(router.virtual_host("example.com").is_some());
+        assert!(router.virtual_host("Example.COM:4433").is_some());
+        assert!(router.virtual_host("example.org:4433").is_some());
+        assert!(router.virtual_host("example.org").is_none());
+        assert!(router.virtual_host("example.org:443").is_none());
+        assert!(router.virtual_host("example.net").is_none());
+    }
+
+    #[test]
+    fn session_response() {
+        let response = SessionResponse::ok();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers().get(":status"), Some("200"));
+
+        assert_eq!(SessionResponse::forbidden().status(), 403);
+        assert_eq!(SessionResponse::not_found().status(), 404);
+        assert_eq!(SessionResponse::misdirected_request().status(), 421);
+
+        let response = SessionResponse::with_status(401)
+            .header("WWW-Authenticate", "Basic")
+            .header("www-authenticate", "Bearer")
+            .header("retry-after", 5)
+            .protocol("chat");
+
+        assert_eq!(response.status(), 401);
+        assert_eq!(
+            response
+                .headers()
+                .get_all("www-authenticate")
+                .collect::<Vec<_>>(),
+            ["Basic", "Bearer"]
+        );
+        assert_eq!(response.headers().get("retry-after"), Some("5"));
+        assert_eq!(
+            response
+                .headers()
+                .get(SessionResponseProto::PROTOCOL_HEADER),
+            Some("\"chat\"")
+        );
+
+        assert_eq!(SessionResponse::with_status(599).status(), 599);
+    }
+
+    #[test]
+    #[should_panic(expected = "Status code must be in range 200..=599")]
+    fn session_response_informational_status() {
+        SessionResponse::with_status(101);
+    }
+
+    #[test]
+    #[should_panic(expected = "Status code must be in range 200..=599")]
+    fn session_response_invalid_status() {
+        SessionResponse::with_status(600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Pseudo-header fields cannot be set")]
+    fn session_response_pseudo_header() {
+        SessionResponse::ok().header(":status", 404);
+    }
+
+    #[test]
+    #[should_panic(expected = "Protocol must be a valid string")]
+    fn session_response_invalid_protocol() {
+        SessionResponse::ok().protocol("caf\u{e9}");
+    }
+
+    #[test]
+    fn settings_missing_capability() {
+        let drafts = WebTransportDraft::ALL;
+        let max_sessions = VarInt::from_u32(1);
+        let check = |settings: Settings, peer_is_server| {
+            check_settings(&settings, &drafts, true, peer_is_server)
+        };
+
+        let settings = Settings::builder()
+            .enable_h3_datagrams()
+            .enable_connect_protocol()
+            .webtransport_max_sessions(max_sessions)
+            .build();
+        assert!(matches!(
+            check(settings, true),
+            Ok(WebTransportDraft::Draft07)
+        ));
+
+        let settings = Settings::builder()
+            .enable_h3_datagrams()
+            .enable_connect_protocol()
+            .build();
+        assert!(matches!(
+            check(settings, true),
+            Err(MissingCapability::WebTransport)
+        ));
+
+        let settings = Settings::builder()
+            .enable_connect_protocol()
+            .webtransport_max_sessions(max_sessions)
+            .build();
+        assert!(matches!(
+            check(settings, true),
+            Err(MissingCapability::H3Datagram)
+        ));
+
+        let settings = Settings::builder()
+            .enable_h3_datagrams()
+            .webtransport_max_sessions(max_sessions)
+            .build();
+        assert!(matches!(
+            check(settings.clone(), true),
+            Err(MissingCapability::ConnectProtocol)
+        ));
+
+        // Extended CONNECT is only required from the server
+        assert!(check(settings.clone(), false).is_ok());
+
+        assert!(matches!(
+            check_settings(&settings, &drafts, false, false),
+            Err(MissingCapability::QuicDatagram)
+        ));
+    }
+}
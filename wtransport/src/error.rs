@@ -1,8 +1,11 @@
 use crate::driver::utils::varint_q2w;
 use crate::driver::DriverError;
+use crate::endpoint::Headers;
+use crate::endpoint::StatusCode;
 use std::fmt::Display;
 use wtransport_proto::error::ErrorCode;
 use wtransport_proto::varint::VarInt;
+use wtransport_proto::websocket::CloseFrame;
 
 /// An enumeration representing various errors that can occur during a WebTransport connection.
 #[derive(thiserror::Error, Debug)]
@@ -30,6 +33,26 @@ pub enum ConnectionError {
     /// The connection was closed because a QUIC protocol error.
     #[error("QUIC protocol error: {0}")]
     QuicProto(QuicProtoError),
+
+    /// The peer does not support WebTransport.
+    ///
+    /// Its settings lack a capability required to establish WebTransport sessions.
+    #[error("incompatible peer: {0}")]
+    IncompatiblePeer(MissingCapability),
+
+    /// The peer does not support WebTransport, but its connection is kept open for plain
+    /// HTTP/3 requests, as an HTTP handler is set on the server.
+    ///
+    /// Requests are served in the background until the connection is closed.
+    #[error("peer serving HTTP/3 only: {0}")]
+    HttpOnly(MissingCapability),
+
+    /// The WebTransport session has been terminated.
+    ///
+    /// The underlying QUIC connection might still be alive.
+    /// See [`Connection::closed`](crate::Connection::closed) for the termination reason.
+    #[error("session gone")]
+    SessionGone,
 }
 
 impl ConnectionError {
@@ -40,6 +63,7 @@ impl ConnectionError {
         match driver_error {
             DriverError::Proto(error_code) => Self::local_h3_error(error_code),
             DriverError::NotConnected => Self::no_connect(quic_connection),
+            DriverError::SessionGone => ConnectionError::SessionGone,
         }
     }
 
@@ -51,10 +75,54 @@ impl ConnectionError {
     }
 
     pub(crate) fn local_h3_error(error_code: ErrorCode) -> Self {
-        ConnectionError::LocalH3Error(H3Error { code: error_code })
+        ConnectionError::LocalH3Error(H3Error::new(error_code))
     }
 }
 
+/// A capability required by WebTransport the peer did not advertise.
+#[derive(thiserror::Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MissingCapability {
+    /// The peer does not support any of the local WebTransport drafts.
+    #[error("WebTransport not enabled")]
+    WebTransport,
+
+    /// The peer did not send `SETTINGS_H3_DATAGRAM`.
+    #[error("HTTP3 datagrams not enabled")]
+    H3Datagram,
+
+    /// The peer did not send the QUIC `max_datagram_frame_size` transport parameter.
+    #[error("QUIC datagrams not supported")]
+    QuicDatagram,
+
+    /// The server did not send `SETTINGS_ENABLE_CONNECT_PROTOCOL`.
+    #[error("extended CONNECT not enabled")]
+    ConnectProtocol,
+}
+
+/// An enumeration representing the reasons a WebTransport session can terminate.
+#[derive(thiserror::Error, Debug)]
+pub enum SessionCloseReason {
+    /// The session was closed by the peer (`CLOSE_WEBTRANSPORT_SESSION` or session stream FIN).
+    #[error("session closed by peer: {0}")]
+    Closed(SessionClose),
+
+    /// The session was locally closed.
+    #[error("session locally closed")]
+    LocallyClosed,
+
+    /// The peer reset the session stream.
+    #[error("session stream reset (code: {0})")]
+    StreamReset(VarInt),
+
+    /// The session was locally aborted because an HTTP3 protocol violation on the session stream.
+    #[error("session locally aborted: {0}")]
+    LocalH3Error(H3Error),
+
+    /// The underlying connection has been closed.
+    #[error(transparent)]
+    ConnectionError(ConnectionError),
+}
+
 /// An enumeration representing various errors that can occur during a WebTransport client connecting.
 #[derive(thiserror::Error, Debug)]
 pub enum ConnectingError {
@@ -76,14 +144,35 @@ pub enum ConnectingError {
 
     /// Request rejected.
     #[error("server rejected WebTransport session request")]
-    SessionRejected,
+    SessionRejected {
+        /// Status code of the response, if the server replied before rejecting the request.
+        status: Option<StatusCode>,
+
+        /// Header fields of the response (empty if the server did not reply).
+        headers: Headers,
+    },
 
     /// Cannot use reserved key for additional headers.
     #[error("additional header '{0}' is reserved")]
     ReservedHeader(String),
+
+    /// An offered protocol contains characters other than printable ASCII.
+    #[error("invalid offered protocol")]
+    InvalidProtocol,
+
+    /// The server selected a protocol which has not been offered.
+    #[error("server selected protocol '{0}' which was not offered")]
+    ProtocolNotOffered(String),
 }
 
 impl ConnectingError {
+    pub(crate) fn rejected_without_response() -> Self {
+        ConnectingError::SessionRejected {
+            status: None,
+            headers: Headers::default(),
+        }
+    }
+
     pub(crate) fn with_no_connection(quic_connection: &quinn::Connection) -> Self {
         ConnectingError::ConnectionError(
             quic_connection
@@ -102,8 +191,21 @@ pub enum StreamWriteError {
     NotConnected,
 
     /// The peer is no longer accepting data on this stream.
+    ///
+    /// It carries the WebTransport application error code.
     #[error("stream stopped (code: {0})")]
-    Stopped(VarInt),
+    Stopped(u32),
+
+    /// The peer stopped the stream with a code outside the range of WebTransport
+    /// application error codes (e.g., a reserved codepoint).
+    ///
+    /// It carries the HTTP3 error code.
+    #[error("stream stopped (HTTP3 code: {0})")]
+    StoppedHttp3(VarInt),
+
+    /// The peer stopped the stream as the WebTransport session is gone.
+    #[error("session gone")]
+    SessionGone,
 
     /// QUIC protocol error.
     #[error("QUIC protocol error")]
@@ -118,8 +220,21 @@ pub enum StreamReadError {
     NotConnected,
 
     /// The peer abandoned transmitting data on this stream
+    ///
+    /// It carries the WebTransport application error code.
     #[error("stream reset (code: {0})")]
-    Reset(VarInt),
+    Reset(u32),
+
+    /// The peer reset the stream with a code outside the range of WebTransport
+    /// application error codes (e.g., a reserved codepoint).
+    ///
+    /// It carries the HTTP3 error code.
+    #[error("stream reset (HTTP3 code: {0})")]
+    ResetHttp3(VarInt),
+
+    /// The peer reset the stream as the WebTransport session is gone.
+    #[error("session gone")]
+    SessionGone,
 
     /// QUIC protocol error.
     #[error("QUIC protocol error")]
@@ -166,6 +281,71 @@ pub enum StreamOpeningError {
     Refused,
 }
 
+/// An error that arise from exchanging a plain HTTP3 request.
+#[derive(thiserror::Error, Debug)]
+pub enum HttpError {
+    /// Connection has been dropped.
+    #[error("not connected")]
+    NotConnected,
+
+    /// The peer abandoned transmitting the message.
+    #[error("stream reset (code: {0})")]
+    Reset(VarInt),
+
+    /// The peer is no longer accepting the message.
+    #[error("stream stopped (code: {0})")]
+    Stopped(VarInt),
+
+    /// The peer violated the HTTP3 protocol.
+    #[error("local HTTP3 error: {0}")]
+    LocalH3Error(H3Error),
+
+    /// QUIC protocol error.
+    #[error("QUIC protocol error")]
+    QuicProto,
+
+    /// Failure connecting to the server.
+    #[error(transparent)]
+    Connecting(ConnectingError),
+}
+
+/// An error that arise from receiving through a CONNECT-UDP tunnel.
+#[derive(thiserror::Error, Debug)]
+pub enum UdpTunnelError {
+    /// The tunnel has been closed by the peer.
+    #[error("tunnel closed")]
+    Closed,
+
+    /// Connection has been dropped.
+    #[error("not connected")]
+    NotConnected,
+}
+
+/// An error that arise from exchanging messages on a WebSocket.
+#[derive(thiserror::Error, Debug)]
+pub enum WebSocketError {
+    /// The WebSocket has been closed, with the given close frame.
+    #[error("closed (code: {})", .0.code())]
+    Closed(CloseFrame),
+
+    /// The close frame has already been sent, so no more messages can be sent.
+    #[error("closed locally")]
+    LocallyClosed,
+
+    /// The peer violated the WebSocket protocol (or a limit); the WebSocket has been
+    /// closed with the given status code.
+    #[error("protocol violation (close code: {0})")]
+    ProtocolViolation(u16),
+
+    /// The peer terminated the stream without a close frame.
+    #[error("abnormal closure")]
+    AbnormalClosure,
+
+    /// Error on the underlying HTTP/3 request stream.
+    #[error(transparent)]
+    Http(HttpError),
+}
+
 /// Reason given by an application for closing the connection
 #[derive(Debug)]
 pub struct ApplicationClose {
@@ -187,6 +367,45 @@ impl Display for ApplicationClose {
     }
 }
 
+/// Reason given by the peer for closing a WebTransport session.
+#[derive(Clone, Debug, Default)]
+pub struct SessionClose {
+    code: u32,
+    reason: String,
+}
+
+impl SessionClose {
+    pub(crate) fn new(code: u32, reason: String) -> Self {
+        Self { code, reason }
+    }
+
+    /// Returns the application error code.
+    #[inline(always)]
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    /// Returns the close message.
+    #[inline(always)]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Display for SessionClose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.reason.is_empty() {
+            self.code.fmt(f)?;
+        } else {
+            f.write_str(&self.reason)?;
+            f.write_str(" (code ")?;
+            self.code.fmt(f)?;
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
 /// Reason given by the transport for closing the connection.
 #[derive(Debug)]
 pub struct ConnectionClose(quinn::ConnectionClose);
@@ -203,6 +422,12 @@ pub struct H3Error {
     code: ErrorCode,
 }
 
+impl H3Error {
+    pub(crate) fn new(code: ErrorCode) -> Self {
+        Self { code }
+    }
+}
+
 impl Display for H3Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.code.fmt(f)
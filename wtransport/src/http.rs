@@ -0,0 +1,821 @@
+//! Besides WebTransport sessions, a server endpoint can serve ordinary HTTP/3 requests
+//! (e.g., the page loading a WebTransport client) on the same UDP port.
+//!
+//! Requests are handled by the [`HttpHandler`] set with
+//! [`ServerConfigBuilder::http_handler`](crate::config::ServerConfigBuilder::http_handler).
+//! Clients send them with [`Endpoint::request`](crate::Endpoint::request).
+//!
+//! #### Examples:
+//! ```no_run
+//! use wtransport::http::IncomingRequest;
+//! use wtransport::http::Response;
+//! use wtransport::Certificate;
+//! use wtransport::ServerConfig;
+//!
+//! let config = ServerConfig::builder()
+//!     .with_bind_default(4433)
+//!     .with_certificate(Certificate::self_signed(["localhost"]))
+//!     .http_handler(|request: IncomingRequest| async move {
+//!         let response = Response::ok()
+//!             .header("content-type", "text/plain")
+//!             .body("Hello, HTTP/3!");
+//!
+//!         let _ = request.respond(response).await;
+//!     })
+//!     .build();
+//! ```
+
+use crate::driver::streams::bilocal::StreamBiLocalQuic;
+use crate::driver::streams::qpack::QPackCodec;
+use crate::driver::streams::QuicRecvStream;
+use crate::driver::streams::QuicSendStream;
+use crate::driver::utils::varint_w2q;
+use crate::driver::Driver;
+use crate::endpoint::Headers;
+use crate::error::H3Error;
+use crate::error::HttpError;
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use url::Url;
+use wtransport_proto::bytes::BytesReader;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::varint::VarInt;
+
+/// Maximum size (in bytes) of an encoded field section (headers or trailers).
+const MAX_FIELD_SECTION_SIZE: usize = 16384;
+
+/// A type alias representing the dynamic future returned by an [`HttpHandler`].
+pub type DynFutureHttpHandler = dyn Future<Output = ()> + Send;
+
+/// A trait for asynchronously handling plain HTTP/3 requests.
+///
+/// It is implemented for closures taking an [`IncomingRequest`] and returning a future.
+pub trait HttpHandler {
+    /// Handles a request, replying with [`IncomingRequest::respond`].
+    ///
+    /// The returned future is spawned on the *Tokio* runtime.
+    fn handle(&self, request: IncomingRequest) -> Pin<Box<DynFutureHttpHandler>>;
+}
+
+impl<F, R> HttpHandler for F
+where
+    F: Fn(IncomingRequest) -> R,
+    R: Future<Output = ()> + Send + 'static,
+{
+    fn handle(&self, request: IncomingRequest) -> Pin<Box<DynFutureHttpHandler>> {
+        Box::pin(self(request))
+    }
+}
+
+/// An [`HttpHandler`] shared among connections.
+#[derive(Clone)]
+pub(crate) struct SharedHttpHandler(Arc<dyn HttpHandler + Send + Sync>);
+
+impl SharedHttpHandler {
+    pub(crate) fn new<H>(handler: H) -> Self
+    where
+        H: HttpHandler + Send + Sync + 'static,
+    {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn handle(&self, request: IncomingRequest) -> Pin<Box<DynFutureHttpHandler>> {
+        self.0.handle(request)
+    }
+}
+
+impl Debug for SharedHttpHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpHandler").finish_non_exhaustive()
+    }
+}
+
+/// A plain HTTP/3 request received by a server endpoint.
+///
+/// The request body is read with [`read`](Self::read) or
+/// [`read_to_end`](Self::read_to_end). Dropping the request without calling
+/// [`respond`](Self::respond) cancels it.
+pub struct IncomingRequest {
+    quic_connection: quinn::Connection,
+    qpack: QPackCodec,
+    headers: Headers,
+    body: Body,
+    send_stream: Option<QuicSendStream>,
+}
+
+impl IncomingRequest {
+    pub(crate) fn new(
+        quic_connection: quinn::Connection,
+        qpack: QPackCodec,
+        stream: (QuicSendStream, QuicRecvStream),
+        headers: Headers,
+    ) -> Self {
+        let body = Body::new(quic_connection.clone(), qpack.clone(), stream.1);
+
+        Self {
+            quic_connection,
+            qpack,
+            headers,
+            body,
+            send_stream: Some(stream.0),
+        }
+    }
+
+    /// Checks `headers` carry the pseudo-header fields required by a request.
+    pub(crate) fn validate(headers: &Headers) -> bool {
+        [":method", ":scheme", ":path"]
+            .into_iter()
+            .all(|key| headers.get(key).is_some())
+    }
+
+    /// Returns the `:method` field of the request.
+    pub fn method(&self) -> &str {
+        self.headers.get(":method").expect("Validated request")
+    }
+
+    /// Returns the `:scheme` field of the request.
+    pub fn scheme(&self) -> &str {
+        self.headers.get(":scheme").expect("Validated request")
+    }
+
+    /// Returns the `:authority` field of the request, or the `host` field if absent.
+    pub fn authority(&self) -> Option<&str> {
+        self.headers
+            .get(":authority")
+            .or_else(|| self.headers.get("host"))
+    }
+
+    /// Returns the `:path` field of the request.
+    pub fn path(&self) -> &str {
+        self.headers.get(":path").expect("Validated request")
+    }
+
+    /// Returns all header fields associated with the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the client's UDP address.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.quic_connection.remote_address()
+    }
+
+    /// Reads the next chunk of the request body into `buf`.
+    ///
+    /// Returns [`None`] once the whole body has been read.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, HttpError> {
+        self.body.read(buf).await
+    }
+
+    /// Reads the whole request body.
+    pub async fn read_to_end(&mut self) -> Result<Vec<u8>, HttpError> {
+        self.body.read_to_end().await
+    }
+
+    /// Returns the trailer fields of the request, if any.
+    ///
+    /// They are available once the whole body has been read.
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.body.trailers()
+    }
+
+    /// Sends `response` to the client.
+    ///
+    /// If the request body has not been completely read, the client is asked to stop
+    /// sending it.
+    pub async fn respond(mut self, response: Response) -> Result<(), HttpError> {
+        self.body.stop(ErrorCode::NoError);
+
+        let mut send_stream = self.send_stream.take().expect("Response not sent yet");
+        response.send(&mut send_stream, &self.qpack).await
+    }
+}
+
+impl Drop for IncomingRequest {
+    fn drop(&mut self) {
+        if let Some(send_stream) = self.send_stream.take() {
+            self.body.stop(ErrorCode::RequestCancelled);
+            send_stream.reset(ErrorCode::RequestCancelled.to_code());
+        }
+    }
+}
+
+/// A plain HTTP/3 response.
+///
+/// See [`IncomingRequest::respond`].
+///
+/// #### Examples:
+/// ```
+/// use wtransport::http::Response;
+///
+/// let response = Response::new(404).body("Not found");
+///
+/// assert_eq!(response.status(), 404);
+/// ```
+#[derive(Debug)]
+pub struct Response {
+    headers: Headers,
+    body: Vec<u8>,
+    trailers: Headers,
+}
+
+impl Response {
+    /// Creates a response with `status` code and empty body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a final status code (in range `200..=599`).
+    pub fn new(status: u16) -> Self {
+        assert!(
+            (200..=599).contains(&status),
+            "Status code must be in range 200..=599"
+        );
+
+        Self {
+            headers: [(":status", status.to_string())].into_iter().collect(),
+            body: Vec::new(),
+            trailers: Headers::default(),
+        }
+    }
+
+    /// Creates a response with `200` status code and empty body.
+    pub fn ok() -> Self {
+        Self::new(200)
+    }
+
+    /// Appends a header field to the response.
+    ///
+    /// The field name is converted to lowercase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is a pseudo-header field (starting with `:`).
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        self.headers.append(field_name(key.as_ref()), value);
+        self
+    }
+
+    /// Sets the response body.
+    pub fn body<B>(mut self, body: B) -> Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.body = body.into();
+        self
+    }
+
+    /// Appends a trailer field, sent after the response body.
+    ///
+    /// The field name is converted to lowercase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is a pseudo-header field (starting with `:`).
+    pub fn trailer<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        self.trailers.append(field_name(key.as_ref()), value);
+        self
+    }
+
+    /// Returns the status code of the response.
+    pub fn status(&self) -> u16 {
+        self.headers
+            .get(":status")
+            .expect("Status code is always present")
+            .parse()
+            .expect("Status code value must be valid")
+    }
+
+    /// Returns the header fields of the response.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Writes the whole response on `send_stream`, finishing it.
+    pub(crate) async fn send(
+        &self,
+        send_stream: &mut QuicSendStream,
+        qpack: &QPackCodec,
+    ) -> Result<(), HttpError> {
+        let stream_id = send_stream.id();
+
+        write_message(
+            send_stream,
+            qpack,
+            stream_id,
+            &self.headers,
+            &self.body,
+            &self.trailers,
+        )
+        .await?;
+
+        send_stream.finish().await?;
+
+        Ok(())
+    }
+}
+
+/// A plain HTTP/3 request to be sent by a client endpoint.
+///
+/// See [`Endpoint::request`](crate::Endpoint::request) and
+/// [`Endpoint::request_on`](crate::Endpoint::request_on).
+///
+/// #### Examples:
+/// ```
+/// use wtransport::http::Request;
+///
+/// let request = Request::post("https://example.com/login")
+///     .header("content-type", "application/json")
+///     .body(r#"{"user":"alice"}"#);
+///
+/// assert_eq!(request.method(), "POST");
+/// ```
+#[derive(Debug)]
+pub struct Request {
+    method: String,
+    url: String,
+    headers: Headers,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Creates a request with `method` for `url`.
+    ///
+    /// The URL must have an `https` scheme.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `method` is `CONNECT`, reserved for WebTransport sessions.
+    pub fn new<M, S>(method: M, url: S) -> Self
+    where
+        M: ToString,
+        S: ToString,
+    {
+        let method = method.to_string();
+
+        assert!(
+            !method.eq_ignore_ascii_case("CONNECT"),
+            "CONNECT method is reserved"
+        );
+
+        Self {
+            method,
+            url: url.to_string(),
+            headers: Headers::default(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a `GET` request for `url`.
+    pub fn get<S>(url: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::new("GET", url)
+    }
+
+    /// Creates a `POST` request for `url`.
+    pub fn post<S>(url: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::new("POST", url)
+    }
+
+    /// Appends a header field to the request.
+    ///
+    /// The field name is converted to lowercase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is a pseudo-header field (starting with `:`).
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        self.headers.append(field_name(key.as_ref()), value);
+        self
+    }
+
+    /// Sets the request body.
+    pub fn body<B>(mut self, body: B) -> Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.body = body.into();
+        self
+    }
+
+    /// Returns the method of the request.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the URL of the request.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Sends the request over `quic_connection`, awaiting the response headers.
+    pub(crate) async fn send(
+        self,
+        url: &Url,
+        quic_connection: quinn::Connection,
+        driver: Arc<Driver>,
+    ) -> Result<IncomingResponse, HttpError> {
+        let (mut send_stream, recv_stream) = StreamBiLocalQuic::open_bi(&quic_connection)
+            .await
+            .ok_or(HttpError::NotConnected)?
+            .upgrade()
+            .into_stream();
+
+        let stream_id = send_stream.id();
+        let qpack = driver.qpack().clone();
+
+        let path = format!(
+            "{}{}",
+            url.path(),
+            url.query().map(|s| format!("?{}", s)).unwrap_or_default()
+        );
+
+        let mut headers: Headers = [
+            (":method", self.method.as_str()),
+            (":scheme", "https"),
+            (":authority", url.authority()),
+            (":path", &path),
+        ]
+        .into_iter()
+        .collect();
+
+        for (key, value) in self.headers.iter() {
+            headers.append(key, value);
+        }
+
+        let sent = match write_message(
+            &mut send_stream,
+            &qpack,
+            stream_id,
+            &headers,
+            &self.body,
+            &Headers::default(),
+        )
+        .await
+        {
+            Ok(()) => send_stream.finish().await.map_err(HttpError::from),
+            Err(error) => Err(error),
+        };
+
+        match sent {
+            Ok(()) => {}
+            // The server might respond before reading the whole request
+            Err(HttpError::Stopped(code)) if code == ErrorCode::NoError.to_code() => {}
+            Err(error) => return Err(error),
+        }
+
+        let mut body = Body::new(quic_connection, qpack, recv_stream);
+        let (status, headers) = body.read_response_headers().await?;
+
+        Ok(IncomingResponse {
+            _driver: driver,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// A plain HTTP/3 response received by a client endpoint.
+///
+/// The response body is read with [`read`](Self::read) or
+/// [`read_to_end`](Self::read_to_end). Dropping the response before the whole
+/// body has been read cancels the request.
+pub struct IncomingResponse {
+    _driver: Arc<Driver>,
+    status: u16,
+    headers: Headers,
+    body: Body,
+}
+
+impl IncomingResponse {
+    /// Returns the status code of the response.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Returns all header fields associated with the response.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Reads the next chunk of the response body into `buf`.
+    ///
+    /// Returns [`None`] once the whole body has been read.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, HttpError> {
+        self.body.read(buf).await
+    }
+
+    /// Reads the whole response body.
+    pub async fn read_to_end(&mut self) -> Result<Vec<u8>, HttpError> {
+        self.body.read_to_end().await
+    }
+
+    /// Returns the trailer fields of the response, if any.
+    ///
+    /// They are available once the whole body has been read.
+    pub fn trailers(&self) -> Option<&Headers> {
+        self.body.trailers()
+    }
+}
+
+impl Drop for IncomingResponse {
+    fn drop(&mut self) {
+        self.body.stop(ErrorCode::RequestCancelled);
+    }
+}
+
+/// Returns the lowercase name of a (non pseudo-header) field.
+fn field_name(key: &str) -> String {
+    assert!(!key.starts_with(':'), "Pseudo-header fields cannot be set");
+    key.to_ascii_lowercase()
+}
+
+/// Writes a HTTP3 message: headers, body and trailers.
+async fn write_message(
+    send_stream: &mut QuicSendStream,
+    qpack: &QPackCodec,
+    stream_id: StreamId,
+    headers: &Headers,
+    body: &[u8],
+    trailers: &Headers,
+) -> Result<(), HttpError> {
+    write_frame(send_stream, qpack.encode_headers(stream_id, headers)).await?;
+
+    if !body.is_empty() {
+        write_frame(send_stream, Frame::new_data(Cow::Borrowed(body))).await?;
+    }
+
+    if !trailers.is_empty() {
+        write_frame(send_stream, qpack.encode_headers(stream_id, trailers)).await?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn write_frame(
+    send_stream: &mut QuicSendStream,
+    frame: Frame<'_>,
+) -> Result<(), HttpError> {
+    let mut buffer = Vec::with_capacity(frame.write_size());
+    frame.write(&mut buffer).expect("Vec does not have EOF");
+
+    let mut buffer = buffer.as_slice();
+
+    while !buffer.is_empty() {
+        let written = send_stream.write(buffer).await?;
+        buffer = &buffer[written..];
+    }
+
+    Ok(())
+}
+
+/// Receiving side of a HTTP3 message: DATA frames followed by optional trailers.
+pub(crate) struct Body {
+    quic_connection: quinn::Connection,
+    qpack: QPackCodec,
+    stream: QuicRecvStream,
+    remaining: u64,
+    trailers: Option<Headers>,
+    finished: bool,
+}
+
+impl Body {
+    pub(crate) fn new(
+        quic_connection: quinn::Connection,
+        qpack: QPackCodec,
+        stream: QuicRecvStream,
+    ) -> Self {
+        Self {
+            quic_connection,
+            qpack,
+            stream,
+            remaining: 0,
+            trailers: None,
+            finished: false,
+        }
+    }
+
+    pub(crate) async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, HttpError> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+
+            if self.remaining > 0 {
+                let len = usize::try_from(self.remaining)
+                    .map_or(buf.len(), |remaining| remaining.min(buf.len()));
+
+                if len == 0 {
+                    return Ok(Some(0));
+                }
+
+                return match self.stream.read(&mut buf[..len]).await? {
+                    Some(read) => {
+                        self.remaining -= read as u64;
+                        Ok(Some(read))
+                    }
+                    None => Err(self.abort(ErrorCode::Frame)),
+                };
+            }
+
+            let Some((kind, length)) = self.read_frame_header().await? else {
+                self.finished = true;
+                continue;
+            };
+
+            match kind {
+                Some(FrameKind::Data) if self.trailers.is_none() => {
+                    self.remaining = length;
+                }
+                Some(FrameKind::Headers) if self.trailers.is_none() => {
+                    self.trailers = Some(self.read_field_section(length).await?);
+                }
+                Some(FrameKind::Exercise(_) | FrameKind::Unknown(_)) => {
+                    self.skip(length).await?;
+                }
+                _ => return Err(self.abort(ErrorCode::FrameUnexpected)),
+            }
+        }
+    }
+
+    /// Reads the header fields of a response, skipping interim (`1xx`) responses.
+    ///
+    /// Returns the final status code along with the headers.
+    pub(crate) async fn read_response_headers(&mut self) -> Result<(u16, Headers), HttpError> {
+        loop {
+            let Some((kind, length)) = self.read_frame_header().await? else {
+                return Err(self.abort(ErrorCode::Message));
+            };
+
+            match kind {
+                Some(FrameKind::Headers) => {
+                    let headers = self.read_field_section(length).await?;
+
+                    let Some(status) = headers
+                        .get(":status")
+                        .and_then(|status| status.parse::<u16>().ok())
+                        .filter(|status| (100..=599).contains(status))
+                    else {
+                        return Err(self.abort(ErrorCode::Message));
+                    };
+
+                    if status >= 200 {
+                        return Ok((status, headers));
+                    }
+                }
+                Some(FrameKind::Exercise(_) | FrameKind::Unknown(_)) => {
+                    self.skip(length).await?;
+                }
+                _ => return Err(self.abort(ErrorCode::FrameUnexpected)),
+            }
+        }
+    }
+
+    async fn read_to_end(&mut self) -> Result<Vec<u8>, HttpError> {
+        let mut body = Vec::new();
+        let mut buffer = [0; 4096];
+
+        while let Some(read) = self.read(&mut buffer).await? {
+            body.extend_from_slice(&buffer[..read]);
+        }
+
+        Ok(body)
+    }
+
+    fn trailers(&self) -> Option<&Headers> {
+        self.trailers.as_ref()
+    }
+
+    pub(crate) fn stop(&mut self, error_code: ErrorCode) {
+        if !self.finished {
+            let _ = self.stream.stop(error_code.to_code());
+        }
+    }
+
+    /// Reads type and length of the next frame.
+    ///
+    /// Returns [`None`] if the stream is finished at frame boundary.
+    /// The frame kind is [`None`] for frame types reserved to HTTP/2.
+    async fn read_frame_header(&mut self) -> Result<Option<(Option<FrameKind>, u64)>, HttpError> {
+        let Some(kind) = self.read_varint().await? else {
+            return Ok(None);
+        };
+
+        let length = self
+            .read_varint()
+            .await?
+            .ok_or_else(|| self.abort(ErrorCode::Frame))?
+            .into_inner();
+
+        Ok(Some((FrameKind::parse(kind), length)))
+    }
+
+    /// Reads a varint at frame boundary.
+    ///
+    /// Returns [`None`] if the stream is finished before the first byte.
+    async fn read_varint(&mut self) -> Result<Option<VarInt>, HttpError> {
+        let mut buffer = [0; VarInt::MAX_SIZE];
+
+        if !self.read_exact(&mut buffer[..1]).await? {
+            return Ok(None);
+        }
+
+        let size = VarInt::parse_size(buffer[0]);
+
+        if !self.read_exact(&mut buffer[1..size]).await? {
+            return Err(self.abort(ErrorCode::Frame));
+        }
+
+        Ok(Some(
+            (&buffer[..size])
+                .get_varint()
+                .expect("Buffer contains the whole varint"),
+        ))
+    }
+
+    async fn read_field_section(&mut self, length: u64) -> Result<Headers, HttpError> {
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= MAX_FIELD_SECTION_SIZE)
+            .ok_or_else(|| self.abort(ErrorCode::ExcessiveLoad))?;
+
+        let mut payload = vec![0; length];
+
+        if !self.read_exact(&mut payload).await? {
+            return Err(self.abort(ErrorCode::Frame));
+        }
+
+        let frame = Frame::new_headers(Cow::Owned(payload));
+
+        match self.qpack.decode_headers(self.stream.id(), &frame).await {
+            Ok(headers) => Ok(headers),
+            Err(error_code) => Err(self.abort(error_code)),
+        }
+    }
+
+    async fn skip(&mut self, mut length: u64) -> Result<(), HttpError> {
+        let mut buffer = [0; 1024];
+
+        while length > 0 {
+            let len = usize::try_from(length).map_or(buffer.len(), |l| l.min(buffer.len()));
+
+            if !self.read_exact(&mut buffer[..len]).await? {
+                return Err(self.abort(ErrorCode::Frame));
+            }
+
+            length -= len as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Fills `buffer`.
+    ///
+    /// Returns `false` if the stream is finished before any byte is read.
+    /// Finishing after the first byte is a frame error.
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> Result<bool, HttpError> {
+        let mut offset = 0;
+
+        while offset < buffer.len() {
+            match self.stream.read(&mut buffer[offset..]).await? {
+                Some(read) => offset += read,
+                None if offset == 0 => return Ok(false),
+                None => return Err(self.abort(ErrorCode::Frame)),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Closes the connection because of a HTTP3 protocol violation.
+    fn abort(&self, error_code: ErrorCode) -> HttpError {
+        self.quic_connection
+            .close(varint_w2q(error_code.to_code()), b"");
+
+        HttpError::LocalH3Error(H3Error::new(error_code))
+    }
+}
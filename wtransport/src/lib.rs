@@ -91,6 +91,9 @@
 /// Client and server configurations.
 pub mod config;
 
+/// Admission control of incoming connections.
+pub mod admission;
+
 /// WebTransport connection.
 pub mod connection;
 
@@ -109,6 +112,15 @@ pub mod tls;
 /// Datagrams module.
 pub mod datagram;
 
+/// Plain HTTP/3 requests.
+pub mod http;
+
+/// CONNECT-UDP proxying (RFC 9298).
+pub mod connect_udp;
+
+/// WebSockets over HTTP/3 (RFC 9220).
+pub mod websocket;
+
 #[doc(inline)]
 pub use config::ClientConfig;
 
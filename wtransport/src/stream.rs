@@ -1,55 +1,73 @@
+use crate::driver::flow_control::SessionFlowControl;
 use crate::driver::streams::bilocal::StreamBiLocalQuic;
+use crate::driver::streams::inbound::InboundStream;
 use crate::driver::streams::unilocal::StreamUniLocalQuic;
 use crate::driver::streams::ProtoWriteError;
-use crate::driver::streams::QuicRecvStream;
 use crate::driver::streams::QuicSendStream;
+use crate::driver::streams::SessionStreams;
+use crate::driver::streams::SessionWatch;
 use crate::error::StreamOpeningError;
 use crate::error::StreamReadError;
 use crate::error::StreamReadExactError;
 use crate::error::StreamWriteError;
 use std::future::Future;
 use std::pin::Pin;
+use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
 use tokio::io::ReadBuf;
+use wtransport_proto::error::webtransport_to_http3_code;
+use wtransport_proto::error::ErrorCode;
 use wtransport_proto::ids::SessionId;
 use wtransport_proto::ids::StreamId;
 use wtransport_proto::stream_header::StreamHeader;
-use wtransport_proto::varint::VarInt;
 
 /// A stream that can only be used to send data.
+///
+/// Once its session is gone, the stream is reset with `WEBTRANSPORT_SESSION_GONE` as soon
+/// as it is used or dropped, and operations fail with [`StreamWriteError::SessionGone`].
 #[derive(Debug)]
-pub struct SendStream(QuicSendStream);
+pub struct SendStream(QuicSendStream, DataCredit, SessionWatch);
 
 impl SendStream {
     #[inline(always)]
-    pub(crate) fn new(stream: QuicSendStream) -> Self {
-        Self(stream)
+    pub(crate) fn new(
+        stream: QuicSendStream,
+        flow_control: SessionFlowControl,
+        session: &SessionStreams,
+    ) -> Self {
+        Self(stream, DataCredit::new(flow_control), session.watch())
     }
 
     /// Writes bytes to the stream.
     ///
     /// On success, returns the number of bytes written.
-    /// Congestion and flow control may cause this to be shorter than `buf.len()`,
-    /// indicating that only a prefix of `buf` was written.
-    #[inline(always)]
+    /// Congestion and flow control (including the session limit on data) may cause this
+    /// to be shorter than `buf.len()`, indicating that only a prefix of `buf` was written.
     pub async fn write(&mut self, buf: &[u8]) -> Result<usize, StreamWriteError> {
-        self.0.write(buf).await
+        std::future::poll_fn(|cx| self.poll_write(cx, buf)).await
     }
 
     /// Convenience method to write an entire buffer to the stream.
-    #[inline(always)]
-    pub async fn write_all(&mut self, buf: &[u8]) -> Result<(), StreamWriteError> {
-        self.0.write_all(buf).await
+    pub async fn write_all(&mut self, mut buf: &[u8]) -> Result<(), StreamWriteError> {
+        while !buf.is_empty() {
+            let written = self.write(buf).await?;
+            buf = &buf[written..];
+        }
+
+        Ok(())
     }
 
     /// Shut down the stream gracefully.
     ///
     /// No new data may be written after calling this method. Completes when the peer has
     /// acknowledged all sent data, retransmitting data as needed.
-    #[inline(always)]
     pub async fn finish(&mut self) -> Result<(), StreamWriteError> {
-        self.0.finish().await
+        std::future::poll_fn(|cx| {
+            ready!(self.poll_session(cx))?;
+            Poll::Ready(Ok(ready!(self.0.poll_finish(cx))?))
+        })
+        .await
     }
 
     /// Returns the [`StreamId`] associated.
@@ -81,17 +99,26 @@ impl SendStream {
     /// No new data can be written after calling this method. Locally buffered data is dropped, and
     /// previously transmitted data will no longer be retransmitted if lost. If an attempt has
     /// already been made to finish the stream, the peer may still receive all written data.
+    ///
+    /// `error_code` is a WebTransport application error code, mapped into the
+    /// HTTP3 error space on the wire.
     #[inline(always)]
-    pub fn reset(self, error_code: VarInt) {
-        self.0.reset(error_code);
+    pub fn reset(mut self, error_code: u32) {
+        self.0.reset_mut(webtransport_to_http3_code(error_code));
     }
 
     /// Awaits for the stream to be stopped by the peer.
     ///
     /// If the stream is stopped the error code will be stored in [`StreamWriteError::Stopped`].
-    #[inline(always)]
     pub async fn stopped(mut self) -> StreamWriteError {
-        self.0.stopped().await
+        std::future::poll_fn(|cx| {
+            if let Err(error) = ready!(self.poll_session(cx)) {
+                return Poll::Ready(error);
+            }
+
+            Poll::Ready(ready!(self.0.poll_stopped(cx)).into())
+        })
+        .await
     }
 
     /// Returns a reference to the underlying QUIC stream.
@@ -109,39 +136,97 @@ impl SendStream {
     pub fn quic_stream_mut(&mut self) -> &mut quinn::SendStream {
         self.0.quic_stream_mut()
     }
+
+    fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, StreamWriteError>> {
+        ready!(self.poll_session(cx))?;
+        let credit = ready!(self.1.poll_reserve(cx, buf.len()))?;
+        let written = ready!(self.0.poll_write(cx, &buf[..credit]))?;
+        self.1.consume(written);
+        Poll::Ready(Ok(written))
+    }
+
+    /// Resets the stream if its session is gone.
+    ///
+    /// It is always ready: the waker is only registered to be notified of the termination.
+    fn poll_session(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), StreamWriteError>> {
+        if self.2.poll_terminated(cx).is_ready() {
+            self.0.reset_mut(ErrorCode::SessionGone.to_code());
+            return Poll::Ready(Err(StreamWriteError::SessionGone));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SendStream {
+    fn drop(&mut self) {
+        if self.2.is_terminated() {
+            self.0.reset_mut(ErrorCode::SessionGone.to_code());
+        }
+    }
 }
 
 /// A stream that can only be used to receive data.
+///
+/// Data is received in the background as it arrives, so that it counts towards the
+/// session limit on data even if it is not read yet. For this reason, the underlying
+/// QUIC stream is not exposed.
+///
+/// Once its session is gone, the stream is stopped with `WEBTRANSPORT_SESSION_GONE`,
+/// and reads fail with [`StreamReadError::SessionGone`].
 #[derive(Debug)]
-pub struct RecvStream(QuicRecvStream);
+pub struct RecvStream(InboundStream);
 
 impl RecvStream {
     #[inline(always)]
-    pub(crate) fn new(stream: QuicRecvStream) -> Self {
+    pub(crate) fn new(stream: InboundStream) -> Self {
         Self(stream)
     }
 
     /// Read data contiguously from the stream.
     ///
     /// On success, returns the number of bytes read into `buf`.
-    #[inline(always)]
     pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, StreamReadError> {
-        self.0.read(buf).await
+        if buf.is_empty() {
+            return Ok(Some(0));
+        }
+
+        let mut buffer = ReadBuf::new(buf);
+        std::future::poll_fn(|cx| self.poll_read(cx, &mut buffer)).await?;
+
+        match buffer.filled().len() {
+            0 => Ok(None),
+            read => Ok(Some(read)),
+        }
     }
 
     /// Reads an exact number of bytes contiguously from the stream.
     ///
     /// If the stream terminates before the entire length has been read, it
     /// returns [`StreamReadExactError::FinishedEarly`].
-    pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), StreamReadExactError> {
-        self.0.read_exact(buf).await
+    pub async fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), StreamReadExactError> {
+        while !buf.is_empty() {
+            match self.read(buf).await.map_err(StreamReadExactError::Read)? {
+                Some(read) => buf = &mut buf[read..],
+                None => return Err(StreamReadExactError::FinishedEarly),
+            }
+        }
+
+        Ok(())
     }
 
     /// Stops accepting data on the stream.
     ///
     /// Discards unread data and notifies the peer to stop transmitting.
-    pub fn stop(mut self, error_code: VarInt) {
-        let _ = self.0.stop(error_code);
+    ///
+    /// `error_code` is a WebTransport application error code, mapped into the
+    /// HTTP3 error space on the wire.
+    pub fn stop(self, error_code: u32) {
+        self.0.stop(webtransport_to_http3_code(error_code));
     }
 
     /// Returns the [`StreamId`] associated.
@@ -150,56 +235,36 @@ impl RecvStream {
         self.0.id()
     }
 
-    /// Returns a reference to the underlying QUIC stream.
-    #[cfg(feature = "quinn")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "quinn")))]
-    #[inline(always)]
-    pub fn quic_stream(&self) -> &quinn::RecvStream {
-        self.0.quic_stream()
-    }
-
-    /// Returns a mutable reference to the underlying QUIC stream.
-    #[cfg(feature = "quinn")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "quinn")))]
     #[inline(always)]
-    pub fn quic_stream_mut(&mut self) -> &mut quinn::RecvStream {
-        self.0.quic_stream_mut()
+    fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<(), StreamReadError>> {
+        self.0.poll_read(cx, buf)
     }
 }
 
 impl tokio::io::AsyncWrite for SendStream {
-    #[inline(always)]
     fn poll_write(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<std::io::Result<usize>> {
-        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        SendStream::poll_write(&mut self, cx, buf).map_err(stream_io_error)
     }
 
     #[inline(always)]
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        ready!(self.poll_session(cx)).map_err(stream_io_error)?;
         tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
     }
 
     #[inline(always)]
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        ready!(self.poll_session(cx)).map_err(stream_io_error)?;
         tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
     }
-
-    #[inline(always)]
-    fn poll_write_vectored(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-        bufs: &[std::io::IoSlice<'_>],
-    ) -> Poll<Result<usize, std::io::Error>> {
-        tokio::io::AsyncWrite::poll_write_vectored(Pin::new(&mut self.0), cx, bufs)
-    }
-
-    #[inline(always)]
-    fn is_write_vectored(&self) -> bool {
-        tokio::io::AsyncWrite::is_write_vectored(&self.0)
-    }
 }
 
 impl tokio::io::AsyncRead for RecvStream {
@@ -209,7 +274,64 @@ impl tokio::io::AsyncRead for RecvStream {
         cx: &mut Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<std::io::Result<()>> {
-        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+        RecvStream::poll_read(&mut self, cx, buf).map_err(stream_io_error)
+    }
+}
+
+/// Wraps a stream error into an I/O error, as reported by the tokio traits.
+fn stream_io_error<E>(error: E) -> std::io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    std::io::Error::new(std::io::ErrorKind::Other, error)
+}
+
+/// Session credit reserved by a [`SendStream`] for sending data.
+///
+/// Credit not used for sending is given back to the session when dropped.
+#[derive(Debug)]
+struct DataCredit {
+    flow_control: SessionFlowControl,
+    reserved: usize,
+}
+
+impl DataCredit {
+    fn new(flow_control: SessionFlowControl) -> Self {
+        Self {
+            flow_control,
+            reserved: 0,
+        }
+    }
+
+    /// Reserves credit for up to `len` bytes.
+    ///
+    /// Returns the number of bytes which can be sent; it is pending only if no credit
+    /// at all is available.
+    fn poll_reserve(
+        &mut self,
+        cx: &mut Context<'_>,
+        len: usize,
+    ) -> Poll<Result<usize, StreamWriteError>> {
+        if self.reserved < len {
+            match self.flow_control.poll_reserve_data(cx, len - self.reserved) {
+                Poll::Ready(Ok(reserved)) => self.reserved += reserved,
+                Poll::Ready(Err(_)) => return Poll::Ready(Err(StreamWriteError::NotConnected)),
+                Poll::Pending if self.reserved == 0 => return Poll::Pending,
+                Poll::Pending => {}
+            }
+        }
+
+        Poll::Ready(Ok(self.reserved.min(len)))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reserved -= amount;
+    }
+}
+
+impl Drop for DataCredit {
+    fn drop(&mut self) {
+        self.flow_control.release_data(self.reserved);
     }
 }
 
@@ -221,13 +343,22 @@ type DynFutureUniStream = dyn Future<Output = Result<SendStream, StreamOpeningEr
 pub struct OpeningUniStream(Pin<Box<DynFutureUniStream>>);
 
 impl OpeningUniStream {
-    pub(crate) fn new(session_id: SessionId, quic_stream: StreamUniLocalQuic) -> Self {
+    pub(crate) fn new(
+        session_id: SessionId,
+        quic_stream: StreamUniLocalQuic,
+        flow_control: SessionFlowControl,
+        session: SessionStreams,
+    ) -> Self {
         Self(Box::pin(async move {
             match quic_stream
                 .upgrade(StreamHeader::new_webtransport(session_id))
                 .await
             {
-                Ok(stream) => Ok(SendStream(stream.upgrade().into_stream())),
+                Ok(stream) => Ok(SendStream::new(
+                    stream.upgrade().into_stream(),
+                    flow_control,
+                    &session,
+                )),
                 Err(ProtoWriteError::NotConnected) => Err(StreamOpeningError::NotConnected),
                 Err(ProtoWriteError::Stopped) => Err(StreamOpeningError::Refused),
             }
@@ -252,12 +383,20 @@ type DynFutureBiStream =
 pub struct OpeningBiStream(Pin<Box<DynFutureBiStream>>);
 
 impl OpeningBiStream {
-    pub(crate) fn new(session_id: SessionId, quic_stream: StreamBiLocalQuic) -> Self {
+    pub(crate) fn new(
+        session_id: SessionId,
+        quic_stream: StreamBiLocalQuic,
+        flow_control: SessionFlowControl,
+        session: SessionStreams,
+    ) -> Self {
         Self(Box::pin(async move {
             match quic_stream.upgrade().upgrade(session_id).await {
                 Ok(stream) => {
                     let stream = stream.into_stream();
-                    Ok((SendStream::new(stream.0), RecvStream::new(stream.1)))
+                    Ok((
+                        SendStream::new(stream.0, flow_control.clone(), &session),
+                        RecvStream::new(InboundStream::spawn(stream.1, flow_control, &session)),
+                    ))
                 }
                 Err(ProtoWriteError::NotConnected) => Err(StreamOpeningError::NotConnected),
                 Err(ProtoWriteError::Stopped) => Err(StreamOpeningError::Refused),
@@ -0,0 +1,588 @@
+//! WebSockets over HTTP/3 (RFC 9220).
+//!
+//! A server endpoint accepts WebSocket connections, along with WebTransport sessions,
+//! once a [`WebSocketHandler`] is set with
+//! [`ServerConfigBuilder::websocket_handler`](crate::config::ServerConfigBuilder::websocket_handler).
+//!
+//! Each [`WebSocket`] runs on its own request stream. Pings are answered automatically
+//! and the close handshake is carried out on [`WebSocket::close`] or when the client
+//! closes.
+//!
+//! #### Examples:
+//! ```no_run
+//! use wtransport::websocket::WebSocketRequest;
+//! use wtransport::Certificate;
+//! use wtransport::ServerConfig;
+//!
+//! let config = ServerConfig::builder()
+//!     .with_bind_default(4433)
+//!     .with_certificate(Certificate::self_signed(["localhost"]))
+//!     .websocket_handler(|request: WebSocketRequest| async move {
+//!         let Ok(websocket) = request.accept().await else {
+//!             return;
+//!         };
+//!
+//!         // Echo server
+//!         while let Ok(message) = websocket.receive().await {
+//!             if websocket.send(message).await.is_err() {
+//!                 break;
+//!             }
+//!         }
+//!     })
+//!     .build();
+//! ```
+
+use crate::driver::streams::qpack::QPackCodec;
+use crate::driver::streams::QuicRecvStream;
+use crate::driver::streams::QuicSendStream;
+use crate::endpoint::Headers;
+use crate::error::HttpError;
+use crate::error::WebSocketError;
+use crate::http::write_frame;
+use crate::http::Body;
+use crate::http::Response;
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+use wtransport_proto::bytes::BufferReader;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::websocket::Opcode;
+use wtransport_proto::websocket::ParseError;
+use wtransport_proto::websocket::WsFrame;
+use wtransport_proto::websocket::VERSION;
+
+#[doc(inline)]
+pub use wtransport_proto::websocket::CloseFrame;
+
+/// Maximum size (in bytes) of a received message, after reassembling its fragments.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// A type alias representing the dynamic future returned by a [`WebSocketHandler`].
+pub type DynFutureWebSocketHandler = dyn Future<Output = ()> + Send;
+
+/// A trait for asynchronously handling WebSocket requests.
+///
+/// It is implemented for closures taking a [`WebSocketRequest`] and returning a future.
+pub trait WebSocketHandler {
+    /// Handles a request, accepting or rejecting the WebSocket.
+    ///
+    /// The returned future is spawned on the *Tokio* runtime.
+    fn handle(&self, request: WebSocketRequest) -> Pin<Box<DynFutureWebSocketHandler>>;
+}
+
+impl<F, R> WebSocketHandler for F
+where
+    F: Fn(WebSocketRequest) -> R,
+    R: Future<Output = ()> + Send + 'static,
+{
+    fn handle(&self, request: WebSocketRequest) -> Pin<Box<DynFutureWebSocketHandler>> {
+        Box::pin(self(request))
+    }
+}
+
+/// A [`WebSocketHandler`] shared among connections.
+#[derive(Clone)]
+pub(crate) struct SharedWebSocketHandler(Arc<dyn WebSocketHandler + Send + Sync>);
+
+impl SharedWebSocketHandler {
+    pub(crate) fn new<H>(handler: H) -> Self
+    where
+        H: WebSocketHandler + Send + Sync + 'static,
+    {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn handle(&self, request: WebSocketRequest) -> Pin<Box<DynFutureWebSocketHandler>> {
+        self.0.handle(request)
+    }
+}
+
+impl Debug for SharedWebSocketHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocketHandler").finish_non_exhaustive()
+    }
+}
+
+/// A WebSocket message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Message {
+    /// A text message.
+    Text(String),
+
+    /// A binary message.
+    Binary(Vec<u8>),
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Message::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Message::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(data: Vec<u8>) -> Self {
+        Message::Binary(data)
+    }
+}
+
+impl From<&[u8]> for Message {
+    fn from(data: &[u8]) -> Self {
+        Message::Binary(data.to_vec())
+    }
+}
+
+/// A WebSocket request received by a server endpoint.
+///
+/// Use [`accept`](Self::accept) or [`reject`](Self::reject). Dropping the request
+/// cancels it.
+pub struct WebSocketRequest {
+    quic_connection: quinn::Connection,
+    qpack: QPackCodec,
+    headers: Headers,
+    stream: Option<(QuicSendStream, QuicRecvStream)>,
+}
+
+impl WebSocketRequest {
+    pub(crate) fn new(
+        quic_connection: quinn::Connection,
+        qpack: QPackCodec,
+        stream: (QuicSendStream, QuicRecvStream),
+        headers: Headers,
+    ) -> Self {
+        Self {
+            quic_connection,
+            qpack,
+            headers,
+            stream: Some(stream),
+        }
+    }
+
+    /// Checks `headers` carry the fields required by a request.
+    ///
+    /// In case of failure, returns the response rejecting the request, or [`None`] if the
+    /// request is malformed.
+    pub(crate) fn validate(headers: &Headers) -> Result<(), Option<Response>> {
+        if headers.get(":scheme") != Some("https")
+            || headers.get(":authority").is_none()
+            || headers.get(":path").is_none()
+        {
+            return Err(None);
+        }
+
+        if headers.get("sec-websocket-version") != Some(VERSION) {
+            debug!("Unsupported WebSocket version");
+            return Err(Some(
+                Response::new(400).header("sec-websocket-version", VERSION),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `:authority` field of the request.
+    pub fn authority(&self) -> &str {
+        self.headers.get(":authority").expect("Validated request")
+    }
+
+    /// Returns the `:path` field of the request.
+    pub fn path(&self) -> &str {
+        self.headers.get(":path").expect("Validated request")
+    }
+
+    /// Returns the `origin` field of the request, if present.
+    pub fn origin(&self) -> Option<&str> {
+        self.headers.get("origin")
+    }
+
+    /// Returns the subprotocols requested by the client (`sec-websocket-protocol`),
+    /// in order of preference.
+    pub fn protocols(&self) -> impl Iterator<Item = &str> {
+        self.headers
+            .get("sec-websocket-protocol")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|protocol| !protocol.is_empty())
+    }
+
+    /// Returns all header fields associated with the request.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the client's UDP address.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.quic_connection.remote_address()
+    }
+
+    /// Accepts the request, without selecting any subprotocol.
+    pub async fn accept(self) -> Result<WebSocket, HttpError> {
+        self.accept_inner(None).await
+    }
+
+    /// Accepts the request, selecting `protocol` among the ones requested by
+    /// the client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `protocol` has not been requested (see [`Self::protocols`]).
+    pub async fn accept_with_protocol(self, protocol: &str) -> Result<WebSocket, HttpError> {
+        assert!(
+            self.protocols().any(|requested| requested == protocol),
+            "Subprotocol not requested by the client"
+        );
+
+        self.accept_inner(Some(protocol.to_string())).await
+    }
+
+    /// Rejects the request with `response`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response status is successful (`2xx`).
+    pub async fn reject(mut self, response: Response) -> Result<(), HttpError> {
+        assert!(
+            !(200..=299).contains(&response.status()),
+            "Rejection response cannot be successful"
+        );
+
+        let (mut send_stream, mut recv_stream) =
+            self.stream.take().expect("Request not handled yet");
+        let _ = recv_stream.stop(ErrorCode::NoError.to_code());
+
+        response.send(&mut send_stream, &self.qpack).await
+    }
+
+    async fn accept_inner(mut self, protocol: Option<String>) -> Result<WebSocket, HttpError> {
+        let (mut send_stream, recv_stream) = self.stream.take().expect("Request not handled yet");
+        let stream_id = send_stream.id();
+
+        let mut headers: Headers = [(":status", "200")].into_iter().collect();
+
+        if let Some(protocol) = &protocol {
+            headers.insert("sec-websocket-protocol", protocol);
+        }
+
+        write_frame(
+            &mut send_stream,
+            self.qpack.encode_headers(stream_id, &headers),
+        )
+        .await?;
+
+        Ok(WebSocket {
+            quic_connection: self.quic_connection.clone(),
+            headers: std::mem::take(&mut self.headers),
+            protocol,
+            reader: Mutex::new(Reader {
+                body: Body::new(
+                    self.quic_connection.clone(),
+                    self.qpack.clone(),
+                    recv_stream,
+                ),
+                buffer: Vec::new(),
+                fragments: None,
+                closed: None,
+            }),
+            writer: Mutex::new(Writer {
+                stream: Some(send_stream),
+            }),
+        })
+    }
+}
+
+impl Drop for WebSocketRequest {
+    fn drop(&mut self) {
+        if let Some((send_stream, mut recv_stream)) = self.stream.take() {
+            let _ = recv_stream.stop(ErrorCode::RequestCancelled.to_code());
+            send_stream.reset(ErrorCode::RequestCancelled.to_code());
+        }
+    }
+}
+
+/// An established WebSocket.
+///
+/// Dropping it without [`close`](Self::close) abruptly terminates the
+/// underlying stream.
+pub struct WebSocket {
+    quic_connection: quinn::Connection,
+    headers: Headers,
+    protocol: Option<String>,
+    reader: Mutex<Reader>,
+    writer: Mutex<Writer>,
+}
+
+impl WebSocket {
+    /// Sends a message.
+    pub async fn send<M>(&self, message: M) -> Result<(), WebSocketError>
+    where
+        M: Into<Message>,
+    {
+        let frame = match message.into() {
+            Message::Text(text) => WsFrame::new(true, Opcode::Text, Cow::Owned(text.into_bytes())),
+            Message::Binary(data) => WsFrame::new(true, Opcode::Binary, Cow::Owned(data)),
+        };
+
+        self.writer.lock().await.send(&frame).await
+    }
+
+    /// Sends a ping control frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `payload` is greater than 125 bytes.
+    pub async fn ping(&self, payload: &[u8]) -> Result<(), WebSocketError> {
+        let frame = WsFrame::new(true, Opcode::Ping, Cow::Borrowed(payload));
+        self.writer.lock().await.send(&frame).await
+    }
+
+    /// Receives the next message.
+    ///
+    /// Fragmented messages are reassembled, and control frames are handled
+    /// internally. Once the client closes the WebSocket, its close frame is echoed
+    /// (unless already closed locally) and [`WebSocketError::Closed`] is returned.
+    pub async fn receive(&self) -> Result<Message, WebSocketError> {
+        let mut reader = self.reader.lock().await;
+
+        loop {
+            if let Some(close_frame) = &reader.closed {
+                return Err(WebSocketError::Closed(close_frame.clone()));
+            }
+
+            let (fin, opcode, payload) = match reader.next_frame().await {
+                Ok(frame) => frame,
+                Err(ReadError::Parse(ParseError::PayloadTooBig)) => {
+                    return Err(self.fail(&mut reader, CloseFrame::MESSAGE_TOO_BIG).await);
+                }
+                Err(ReadError::Parse(parse_error)) => {
+                    debug!("Invalid WebSocket frame: {}", parse_error);
+                    return Err(self.fail(&mut reader, CloseFrame::PROTOCOL_ERROR).await);
+                }
+                Err(ReadError::Unmasked) => {
+                    debug!("Unmasked WebSocket frame from client");
+                    return Err(self.fail(&mut reader, CloseFrame::PROTOCOL_ERROR).await);
+                }
+                Err(ReadError::Http(http_error)) => return Err(WebSocketError::Http(http_error)),
+                Err(ReadError::Finished) => {
+                    reader.body.stop(ErrorCode::NoError);
+                    return Err(WebSocketError::AbnormalClosure);
+                }
+            };
+
+            let message = match (opcode, reader.fragments.as_mut()) {
+                (Opcode::Ping, _) => {
+                    let pong = WsFrame::new(true, Opcode::Pong, Cow::Owned(payload));
+
+                    match self.writer.lock().await.send(&pong).await {
+                        Ok(()) | Err(WebSocketError::LocallyClosed) => continue,
+                        Err(error) => return Err(error),
+                    }
+                }
+                (Opcode::Pong, _) => continue,
+                (Opcode::Close, _) => {
+                    let frame = WsFrame::new(true, Opcode::Close, Cow::Owned(payload));
+
+                    let Ok(close_frame) = CloseFrame::with_frame(&frame) else {
+                        return Err(self.fail(&mut reader, CloseFrame::PROTOCOL_ERROR).await);
+                    };
+
+                    let mut writer = self.writer.lock().await;
+
+                    if writer.stream.is_some() {
+                        let _ = writer.close(&close_frame).await;
+                    }
+
+                    reader.closed = Some(close_frame.clone());
+                    return Err(WebSocketError::Closed(close_frame));
+                }
+                (Opcode::Text | Opcode::Binary, None) if fin => into_message(opcode, payload),
+                (Opcode::Text | Opcode::Binary, None) => {
+                    reader.fragments = Some((opcode, payload));
+                    continue;
+                }
+                (Opcode::Continuation, Some((_, fragments))) => {
+                    if fragments.len() + payload.len() > MAX_MESSAGE_SIZE {
+                        return Err(self.fail(&mut reader, CloseFrame::MESSAGE_TOO_BIG).await);
+                    }
+
+                    fragments.extend_from_slice(&payload);
+
+                    if !fin {
+                        continue;
+                    }
+
+                    let (opcode, data) = reader.fragments.take().expect("Fragmented message");
+                    into_message(opcode, data)
+                }
+                (Opcode::Continuation, None) | (Opcode::Text | Opcode::Binary, Some(_)) => {
+                    debug!("Unexpected WebSocket fragment");
+                    return Err(self.fail(&mut reader, CloseFrame::PROTOCOL_ERROR).await);
+                }
+            };
+
+            match message {
+                Some(message) => return Ok(message),
+                None => return Err(self.fail(&mut reader, CloseFrame::INVALID_PAYLOAD).await),
+            }
+        }
+    }
+
+    /// Closes the WebSocket, sending a close frame with `code` and `reason`.
+    ///
+    /// Messages can still be received until the client's close frame, which is
+    /// reported by [`receive`](Self::receive) as [`WebSocketError::Closed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `code` cannot be sent (see [`CloseFrame::is_valid_code`]), or
+    /// the `reason` is longer than 123 bytes.
+    pub async fn close(&self, code: u16, reason: &str) -> Result<(), WebSocketError> {
+        let close_frame = CloseFrame::new(code, reason);
+        let mut writer = self.writer.lock().await;
+
+        if writer.stream.is_none() {
+            return Ok(());
+        }
+
+        writer.close(&close_frame).await
+    }
+
+    /// Returns the subprotocol selected on [`WebSocketRequest::accept_with_protocol`].
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Returns all header fields of the request.
+    pub fn request_headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the client's UDP address.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.quic_connection.remote_address()
+    }
+
+    /// Fails the WebSocket connection, closing it with `code`.
+    async fn fail(&self, reader: &mut Reader, code: u16) -> WebSocketError {
+        let close_frame = CloseFrame::new(code, "");
+
+        let mut writer = self.writer.lock().await;
+
+        if writer.stream.is_some() {
+            let _ = writer.close(&close_frame).await;
+        }
+
+        reader.body.stop(ErrorCode::NoError);
+        reader.closed = Some(close_frame);
+
+        WebSocketError::ProtocolViolation(code)
+    }
+}
+
+impl Drop for WebSocket {
+    fn drop(&mut self) {
+        if let Some(send_stream) = self.writer.get_mut().stream.take() {
+            self.reader.get_mut().body.stop(ErrorCode::RequestCancelled);
+            send_stream.reset(ErrorCode::RequestCancelled.to_code());
+        }
+    }
+}
+
+enum ReadError {
+    Parse(ParseError),
+    Unmasked,
+    Finished,
+    Http(HttpError),
+}
+
+struct Reader {
+    body: Body,
+    buffer: Vec<u8>,
+    fragments: Option<(Opcode, Vec<u8>)>,
+    closed: Option<CloseFrame>,
+}
+
+impl Reader {
+    /// Reads the next frame, returning its fin bit, opcode and unmasked payload.
+    async fn next_frame(&mut self) -> Result<(bool, Opcode, Vec<u8>), ReadError> {
+        loop {
+            let mut buffer_reader = BufferReader::new(&self.buffer);
+
+            if let Some(frame) =
+                WsFrame::read_from_buffer(&mut buffer_reader).map_err(ReadError::Parse)?
+            {
+                if !frame.is_masked() {
+                    return Err(ReadError::Unmasked);
+                }
+
+                let offset = buffer_reader.offset();
+                let frame = (
+                    frame.fin(),
+                    frame.opcode(),
+                    frame.into_payload().into_owned(),
+                );
+
+                self.buffer.drain(..offset);
+
+                return Ok(frame);
+            }
+
+            let mut chunk = [0; 4096];
+
+            match self.body.read(&mut chunk).await {
+                Ok(Some(read)) => self.buffer.extend_from_slice(&chunk[..read]),
+                Ok(None) => return Err(ReadError::Finished),
+                Err(http_error) => return Err(ReadError::Http(http_error)),
+            }
+        }
+    }
+}
+
+/// Builds a complete message. Returns [`None`] if text is not valid UTF-8.
+fn into_message(opcode: Opcode, data: Vec<u8>) -> Option<Message> {
+    match opcode {
+        Opcode::Text => String::from_utf8(data).ok().map(Message::Text),
+        _ => Some(Message::Binary(data)),
+    }
+}
+
+struct Writer {
+    /// [`None`] once the close frame has been sent.
+    stream: Option<QuicSendStream>,
+}
+
+impl Writer {
+    async fn send(&mut self, frame: &WsFrame<'_>) -> Result<(), WebSocketError> {
+        let stream = self.stream.as_mut().ok_or(WebSocketError::LocallyClosed)?;
+
+        let mut payload = Vec::with_capacity(frame.write_size());
+        frame.write(&mut payload).expect("Vec does not have EOF");
+
+        write_frame(stream, Frame::new_data(Cow::Owned(payload)))
+            .await
+            .map_err(WebSocketError::Http)
+    }
+
+    /// Sends the close frame and finishes the stream.
+    async fn close(&mut self, close_frame: &CloseFrame) -> Result<(), WebSocketError> {
+        self.send(&close_frame.generate_frame()).await?;
+
+        let mut stream = self.stream.take().expect("Close frame not sent yet");
+
+        stream
+            .finish()
+            .await
+            .map_err(|error| WebSocketError::Http(error.into()))
+    }
+}
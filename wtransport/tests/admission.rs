@@ -0,0 +1,48 @@
+mod common;
+
+use common::client;
+use common::server_config;
+use common::url;
+use std::time::Duration;
+use wtransport::Endpoint;
+
+#[tokio::test]
+async fn refuse_over_max_handshakes() {
+    let server = Endpoint::server(server_config().max_handshakes(1).build()).unwrap();
+    let client = client();
+
+    let first = client.connect(url(&server, "/"));
+    tokio::pin!(first);
+
+    // Accounted as in handshake until polled
+    let incoming_session = tokio::select! {
+        incoming_session = server.accept() => incoming_session,
+        _ = &mut first => panic!("Connection established without being accepted"),
+    };
+
+    let refused = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::select! {
+            _ = server.accept() => panic!("Connection over the limit yielded"),
+            result = client.connect(url(&server, "/")) => result,
+        }
+    })
+    .await
+    .expect("Refused connection abandoned without waiting for the handshake");
+
+    assert!(refused.is_err());
+
+    let (_first_connection, client_connection) = tokio::join!(
+        async { incoming_session.await.unwrap().accept().await.unwrap() },
+        first,
+    );
+
+    client_connection.unwrap();
+
+    // The handshake no longer counts once completed
+    let (_second_connection, client_connection) = tokio::join!(
+        async { server.accept().await.await.unwrap().accept().await.unwrap() },
+        client.connect(url(&server, "/")),
+    );
+
+    client_connection.unwrap();
+}
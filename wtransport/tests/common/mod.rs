@@ -0,0 +1,273 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::sync::OnceLock;
+use wtransport::config::ServerConfigBuilder;
+use wtransport::endpoint::endpoint_side::Client;
+use wtransport::endpoint::endpoint_side::Server;
+use wtransport::endpoint::Headers;
+use wtransport::Certificate;
+use wtransport::ClientConfig;
+use wtransport::Endpoint;
+use wtransport::ServerConfig;
+use wtransport_proto::bytes::BytesWriter;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::varint::VarInt;
+use wtransport_proto::WEBTRANSPORT_ALPN;
+
+pub use wtransport::config::states::WantsTransportConfigServer;
+
+/// Certificate of all test servers, trusted by all test clients.
+pub fn certificate() -> Certificate {
+    static CERTIFICATE: OnceLock<Certificate> = OnceLock::new();
+
+    CERTIFICATE
+        .get_or_init(|| Certificate::self_signed(["localhost"]))
+        .clone()
+}
+
+/// Server configuration bound to an ephemeral port.
+pub fn server_config() -> ServerConfigBuilder<WantsTransportConfigServer> {
+    ServerConfig::builder()
+        .with_bind_default(0)
+        .with_certificate(certificate())
+}
+
+/// Returns the URL of `path` on `server`.
+pub fn url(server: &Endpoint<Server>, path: &str) -> String {
+    let port = server.local_addr().expect("Bound socket").port();
+    format!("https://localhost:{port}{path}")
+}
+
+/// TLS configuration trusting [`certificate`].
+pub fn tls_config() -> rustls::ClientConfig {
+    let mut root_store = rustls::RootCertStore::empty();
+
+    for certificate in certificate().certificates() {
+        root_store
+            .add(&rustls::Certificate(certificate.clone()))
+            .expect("Valid certificate");
+    }
+
+    let mut tls_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+
+    tls_config.alpn_protocols = vec![WEBTRANSPORT_ALPN.to_vec()];
+    tls_config
+}
+
+/// Client endpoint trusting test servers.
+pub fn client() -> Endpoint<Client> {
+    let config = ClientConfig::builder()
+        .with_bind_default()
+        .with_custom_tls(tls_config())
+        .build();
+
+    Endpoint::client(config).expect("Client endpoint")
+}
+
+/// Spawns a task accepting all sessions on `server` with `handler`.
+pub fn serve<H, F>(server: Arc<Endpoint<Server>>, handler: H)
+where
+    H: Fn(wtransport::Connection) -> F + Send + Sync + 'static,
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let incoming_session = server.accept().await;
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let Ok(session_request) = incoming_session.await else {
+                    return;
+                };
+
+                if let Ok(connection) = session_request.accept().await {
+                    handler(connection).await;
+                }
+            });
+        }
+    });
+}
+
+/// Encodes `frame`.
+pub fn encode(frame: &Frame) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    frame.write(&mut buffer).expect("Infallible write");
+    buffer
+}
+
+/// An HTTP/3 client over a raw QUIC connection, writing frames by hand.
+pub struct RawClient {
+    pub connection: quinn::Connection,
+    pub control: quinn::SendStream,
+}
+
+impl RawClient {
+    /// Connects to `server`, sending `settings` on the control stream.
+    pub async fn connect(server: &Endpoint<Server>, settings: Settings) -> Self {
+        let mut endpoint =
+            quinn::Endpoint::client("[::]:0".parse().unwrap()).expect("Client endpoint");
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(tls_config())));
+
+        let port = server.local_addr().expect("Bound socket").port();
+
+        let connection = endpoint
+            .connect(([127, 0, 0, 1], port).into(), "localhost")
+            .expect("Valid connection attempt")
+            .await
+            .expect("Connection established");
+
+        let mut control = connection.open_uni().await.expect("Control stream");
+        control.write_all(&[0x00]).await.unwrap();
+        control
+            .write_all(&encode(&settings.generate_frame()))
+            .await
+            .unwrap();
+
+        Self {
+            connection,
+            control,
+        }
+    }
+
+    /// Connects to `server` advertising WebTransport support.
+    pub async fn connect_webtransport(server: &Endpoint<Server>) -> Self {
+        let settings = Settings::builder()
+            .enable_webtransport()
+            .enable_h3_datagrams()
+            .webtransport_max_sessions(VarInt::from_u32(16))
+            .build();
+
+        Self::connect(server, settings).await
+    }
+
+    /// Opens a WebTransport bidirectional stream in the session of `session_stream`.
+    pub async fn open_wt_bi(
+        &self,
+        session_stream: &quinn::SendStream,
+    ) -> (quinn::SendStream, quinn::RecvStream) {
+        let mut stream = self.connection.open_bi().await.expect("WT stream");
+        stream
+            .0
+            .write_all(&wt_stream_header(0x41, session_stream))
+            .await
+            .unwrap();
+        stream
+    }
+
+    /// Opens a WebTransport unidirectional stream in the session of `session_stream`.
+    pub async fn open_wt_uni(&self, session_stream: &quinn::SendStream) -> quinn::SendStream {
+        let mut stream = self.connection.open_uni().await.expect("WT stream");
+        stream
+            .write_all(&wt_stream_header(0x54, session_stream))
+            .await
+            .unwrap();
+        stream
+    }
+
+    /// Sends a datagram in the session of `session_stream`.
+    pub fn send_wt_datagram(&self, session_stream: &quinn::SendStream, payload: &[u8]) {
+        let quarter_stream_id = session_id(session_stream).into_inner() / 4;
+
+        let mut datagram = Vec::new();
+        datagram
+            .put_varint(VarInt::try_from_u64(quarter_stream_id).unwrap())
+            .unwrap();
+        datagram.extend_from_slice(payload);
+
+        self.connection
+            .send_datagram(datagram.into())
+            .expect("Datagram sent");
+    }
+
+    /// Sends a WebTransport CONNECT request for `path` on a new request stream.
+    pub async fn request_session(
+        &self,
+        server: &Endpoint<Server>,
+        path: &str,
+    ) -> (quinn::SendStream, quinn::RecvStream) {
+        let port = server.local_addr().expect("Bound socket").port();
+        let authority = format!("localhost:{port}");
+
+        let headers: Headers = [
+            (":method", "CONNECT"),
+            (":scheme", "https"),
+            (":protocol", "webtransport"),
+            (":authority", authority.as_str()),
+            (":path", path),
+        ]
+        .into_iter()
+        .collect();
+
+        self.send_headers(&headers).await
+    }
+
+    /// Opens a request stream, sending a HEADERS frame with `headers`.
+    pub async fn send_headers(&self, headers: &Headers) -> (quinn::SendStream, quinn::RecvStream) {
+        let (mut send, recv) = self.connection.open_bi().await.expect("Request stream");
+        send.write_all(&encode(&headers.generate_frame()))
+            .await
+            .unwrap();
+
+        (send, recv)
+    }
+}
+
+/// Returns the ID of the session established on `session_stream`.
+pub fn session_id(session_stream: &quinn::SendStream) -> VarInt {
+    VarInt::try_from_u64(u64::from(quinn::VarInt::from(session_stream.id())))
+        .expect("Valid session ID")
+}
+
+/// Encodes the header of a WebTransport stream of `stream_type` (`0x41` bidirectional
+/// frame or `0x54` unidirectional stream) in the session of `session_stream`.
+fn wt_stream_header(stream_type: u32, session_stream: &quinn::SendStream) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.put_varint(VarInt::from_u32(stream_type)).unwrap();
+    header.put_varint(session_id(session_stream)).unwrap();
+    header
+}
+
+/// Reads the status code of the response on a request stream.
+pub async fn read_status(recv: &mut quinn::RecvStream) -> Option<u16> {
+    let mut buffer = Vec::new();
+
+    loop {
+        if let Some(frame) = Frame::read(&mut buffer.as_slice()).ok()? {
+            let headers = Headers::with_frame(&frame).ok()?;
+            return headers.get(":status")?.parse().ok();
+        }
+
+        let chunk = recv.read_chunk(usize::MAX, true).await.ok()??;
+        buffer.extend_from_slice(&chunk.bytes);
+    }
+}
+
+/// Reads a whole response on a request stream, returning its headers and body.
+pub async fn read_response(recv: &mut quinn::RecvStream) -> (Headers, Vec<u8>) {
+    let buffer = recv
+        .read_to_end(usize::MAX)
+        .await
+        .expect("Response received");
+    let mut buffer = buffer.as_slice();
+
+    let frame = Frame::read(&mut buffer)
+        .expect("Valid frame")
+        .expect("HEADERS frame");
+    let headers = Headers::with_frame(&frame).expect("Valid headers");
+
+    let mut body = Vec::new();
+    while let Some(frame) = Frame::read(&mut buffer).expect("Valid frame") {
+        body.extend_from_slice(frame.payload());
+    }
+
+    (headers, body)
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::client;
+use common::server_config;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use wtransport::connect_udp::UdpTunnelRequest;
+use wtransport::connect_udp::UriTemplate;
+use wtransport::endpoint::endpoint_side::Server;
+use wtransport::endpoint::StatusCode;
+use wtransport::error::ConnectingError;
+use wtransport::http::Response;
+use wtransport::Endpoint;
+
+/// Proxy relaying to loopback targets only.
+fn proxy_server() -> (Endpoint<Server>, UriTemplate) {
+    let template = UriTemplate::with_default_path("localhost").unwrap();
+
+    let config = server_config()
+        .connect_udp_handler(template, |request: UdpTunnelRequest| async move {
+            if request.target_host() == "127.0.0.1" {
+                let _ = request.proxy().await;
+            } else {
+                let _ = request.reject(Response::new(403)).await;
+            }
+        })
+        .build();
+
+    let server = Endpoint::server(config).unwrap();
+    let port = server.local_addr().unwrap().port();
+    let template = UriTemplate::with_default_path(&format!("localhost:{port}")).unwrap();
+
+    (server, template)
+}
+
+#[tokio::test]
+async fn proxy_round_trip() {
+    let (server, template) = proxy_server();
+    let client = client();
+
+    let target = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target_port = target.local_addr().unwrap().port();
+
+    let accepting = async {
+        loop {
+            tokio::spawn(server.accept().await);
+        }
+    };
+
+    let exchange = async {
+        let tunnel = client
+            .connect_udp(&template, "127.0.0.1", target_port)
+            .await
+            .unwrap();
+
+        let mut buffer = [0; 64];
+
+        for payload in [&b"ping"[..], b"", b"another ping"] {
+            tunnel.send(payload).unwrap();
+
+            // The context ID is stripped by the proxy
+            let (len, proxy_address) = target.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(&buffer[..len], payload);
+
+            target.send_to(b"pong", proxy_address).await.unwrap();
+
+            // And it is stripped by the client
+            assert_eq!(&tunnel.receive().await.unwrap()[..], b"pong");
+        }
+    };
+
+    tokio::select! {
+        () = accepting => {}
+        result = tokio::time::timeout(Duration::from_secs(10), exchange) => result.unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn refuse_unvetted_target() {
+    let (server, template) = proxy_server();
+    let client = client();
+
+    let accepting = async {
+        loop {
+            tokio::spawn(server.accept().await);
+        }
+    };
+
+    let result = tokio::select! {
+        () = accepting => unreachable!(),
+        result = client.connect_udp(&template, "192.0.2.6", 443) => result,
+    };
+
+    match result {
+        Err(ConnectingError::SessionRejected { status, .. }) => {
+            assert_eq!(status.map(StatusCode::into_inner), Some(403));
+        }
+        result => panic!("Unexpected result: {:?}", result.map(|_| ())),
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::read_status;
+use common::server_config;
+use common::RawClient;
+use std::time::Duration;
+use wtransport::Endpoint;
+use wtransport_proto::error::ErrorCode;
+
+/// Time for streams and datagrams sent by the client to reach the server.
+const IN_FLIGHT: Duration = Duration::from_millis(100);
+
+#[tokio::test]
+async fn early_incoming_delivered_after_accept() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let raw_client = RawClient::connect_webtransport(&server).await;
+
+    let mut session_stream = raw_client.request_session(&server, "/").await;
+    let mut bi_stream = raw_client.open_wt_bi(&session_stream.0).await;
+    let mut uni_stream = raw_client.open_wt_uni(&session_stream.0).await;
+    raw_client.send_wt_datagram(&session_stream.0, b"datagram");
+
+    bi_stream.0.write_all(b"bi").await.unwrap();
+    uni_stream.write_all(b"uni").await.unwrap();
+
+    let session_request = server.accept().await.await.unwrap();
+    tokio::time::sleep(IN_FLIGHT).await;
+
+    let connection = session_request.accept().await.unwrap();
+    assert_eq!(read_status(&mut session_stream.1).await, Some(200));
+
+    let mut buffer = [0; 3];
+
+    let (_send, mut recv) = connection.accept_bi().await.unwrap();
+    recv.read_exact(&mut buffer[..2]).await.unwrap();
+    assert_eq!(&buffer[..2], b"bi");
+
+    let mut recv = connection.accept_uni().await.unwrap();
+    recv.read_exact(&mut buffer).await.unwrap();
+    assert_eq!(&buffer, b"uni");
+
+    let datagram = connection.receive_datagram().await.unwrap();
+    assert_eq!(&datagram.payload()[..], b"datagram");
+}
+
+#[tokio::test]
+async fn early_incoming_over_limit_rejected() {
+    let server = Endpoint::server(
+        server_config()
+            .session_buffer_streams(1)
+            .session_buffer_bytes(8)
+            .build(),
+    )
+    .unwrap();
+    let raw_client = RawClient::connect_webtransport(&server).await;
+
+    let session_stream = raw_client.request_session(&server, "/").await;
+
+    let mut buffered = raw_client.open_wt_bi(&session_stream.0).await;
+    buffered.0.write_all(b"buffered").await.unwrap();
+    raw_client.send_wt_datagram(&session_stream.0, b"buffered");
+
+    let session_request = server.accept().await.await.unwrap();
+    tokio::time::sleep(IN_FLIGHT).await;
+
+    let mut rejected = raw_client.open_wt_bi(&session_stream.0).await;
+    rejected.0.write_all(b"rejected").await.unwrap();
+    raw_client.send_wt_datagram(&session_stream.0, b"dropped");
+    tokio::time::sleep(IN_FLIGHT).await;
+
+    let connection = session_request.accept().await.unwrap();
+
+    let buffered_rejected =
+        quinn::VarInt::from_u64(ErrorCode::BufferedStreamRejected.to_code().into_inner()).unwrap();
+    assert_eq!(rejected.0.stopped().await, Ok(buffered_rejected));
+
+    let (_send, mut recv) = connection.accept_bi().await.unwrap();
+    let mut buffer = [0; 8];
+    recv.read_exact(&mut buffer).await.unwrap();
+    assert_eq!(&buffer, b"buffered");
+
+    let datagram = connection.receive_datagram().await.unwrap();
+    assert_eq!(&datagram.payload()[..], b"buffered");
+
+    assert!(
+        tokio::time::timeout(IN_FLIGHT, connection.receive_datagram())
+            .await
+            .is_err(),
+        "Datagram over the limit delivered"
+    );
+
+    // Streams are no longer buffered once the session is established
+    let mut stream = raw_client.open_wt_bi(&session_stream.0).await;
+    stream.0.write_all(b"accepted").await.unwrap();
+    assert!(connection.accept_bi().await.is_ok());
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::client;
+use common::read_response;
+use common::server_config;
+use common::url;
+use common::RawClient;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use wtransport::endpoint::endpoint_side::Server;
+use wtransport::endpoint::Headers;
+use wtransport::error::ConnectionError;
+use wtransport::error::MissingCapability;
+use wtransport::http::IncomingRequest;
+use wtransport::http::Request;
+use wtransport::http::Response;
+use wtransport::Endpoint;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::settings::Settings;
+
+/// Server replying to `GET /hello` with a greeting and echoing the body of `POST /echo`,
+/// `404` otherwise.
+///
+/// Returns the number of requests handled along with the server.
+fn http_server() -> (Endpoint<Server>, Arc<AtomicUsize>) {
+    let handled = Arc::new(AtomicUsize::new(0));
+
+    let config = server_config()
+        .http_handler({
+            let handled = handled.clone();
+
+            move |mut request: IncomingRequest| {
+                handled.fetch_add(1, Ordering::Relaxed);
+
+                async move {
+                    let response = match (request.method(), request.path()) {
+                        ("GET", "/hello") => Response::ok()
+                            .header("content-type", "text/plain")
+                            .body("Hello, HTTP/3!"),
+                        ("POST", "/echo") => match request.read_to_end().await {
+                            Ok(body) => Response::ok().body(body),
+                            Err(_) => return,
+                        },
+                        _ => Response::new(404),
+                    };
+
+                    let _ = request.respond(response).await;
+                }
+            }
+        })
+        .build();
+
+    (Endpoint::server(config).unwrap(), handled)
+}
+
+fn request_headers(server: &Endpoint<Server>, path: &str) -> Headers {
+    let port = server.local_addr().unwrap().port();
+    let authority = format!("localhost:{port}");
+
+    [
+        (":method", "GET"),
+        (":scheme", "https"),
+        (":authority", authority.as_str()),
+        (":path", path),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Runs `client` while `server` accepts and drives connections.
+async fn with_server<F>(server: &Endpoint<Server>, client: F)
+where
+    F: std::future::Future<Output = ()>,
+{
+    let accepting = async {
+        loop {
+            tokio::spawn(server.accept().await);
+        }
+    };
+
+    tokio::select! {
+        () = accepting => {}
+        () = client => {}
+    }
+}
+
+#[tokio::test]
+async fn http_only_peer() {
+    let (server, handled) = http_server();
+
+    let (raw_client, result) = tokio::join!(
+        RawClient::connect(&server, Settings::builder().build()),
+        async { server.accept().await.await },
+    );
+
+    // Resolved without waiting for the connection to be closed
+    assert!(matches!(
+        result,
+        Err(ConnectionError::HttpOnly(MissingCapability::WebTransport))
+    ));
+
+    // Requests are still served in the background
+    let (mut send, mut recv) = raw_client
+        .send_headers(&request_headers(&server, "/hello"))
+        .await;
+    send.finish().await.unwrap();
+
+    let (headers, body) = read_response(&mut recv).await;
+    assert_eq!(headers.get(":status"), Some("200"));
+    assert_eq!(body, b"Hello, HTTP/3!");
+    assert_eq!(handled.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn serve_get() {
+    let (server, handled) = http_server();
+    let raw_client = RawClient::connect(&server, Settings::builder().build()).await;
+
+    with_server(&server, async {
+        let (mut send, mut recv) = raw_client
+            .send_headers(&request_headers(&server, "/hello"))
+            .await;
+        send.finish().await.unwrap();
+
+        let (headers, body) = read_response(&mut recv).await;
+        assert_eq!(headers.get(":status"), Some("200"));
+        assert_eq!(headers.get("content-type"), Some("text/plain"));
+        assert_eq!(body, b"Hello, HTTP/3!");
+
+        let (mut send, mut recv) = raw_client
+            .send_headers(&request_headers(&server, "/missing"))
+            .await;
+        send.finish().await.unwrap();
+
+        let (headers, body) = read_response(&mut recv).await;
+        assert_eq!(headers.get(":status"), Some("404"));
+        assert!(body.is_empty());
+    })
+    .await;
+
+    assert_eq!(handled.load(Ordering::Relaxed), 2);
+}
+
+#[tokio::test]
+async fn reject_malformed_request() {
+    let (server, handled) = http_server();
+    let raw_client = RawClient::connect(&server, Settings::builder().build()).await;
+
+    with_server(&server, async {
+        // Missing `:path` pseudo-header field
+        let mut headers = request_headers(&server, "/hello");
+        headers.remove(":path");
+
+        let (mut send, _recv) = raw_client.send_headers(&headers).await;
+
+        let message_error =
+            quinn::VarInt::from_u64(ErrorCode::Message.to_code().into_inner()).unwrap();
+        assert_eq!(send.stopped().await, Ok(message_error));
+    })
+    .await;
+
+    assert_eq!(handled.load(Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn client_request() {
+    let (server, handled) = http_server();
+    let client = client();
+
+    with_server(&server, async {
+        let mut response = client
+            .request(Request::get(url(&server, "/hello")))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers().get("content-type"), Some("text/plain"));
+        assert_eq!(response.read_to_end().await.unwrap(), b"Hello, HTTP/3!");
+
+        let mut response = client
+            .request(Request::post(url(&server, "/echo")).body("ping"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.read_to_end().await.unwrap(), b"ping");
+
+        let response = client
+            .request(Request::get(url(&server, "/missing")))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 404);
+    })
+    .await;
+
+    assert_eq!(handled.load(Ordering::Relaxed), 3);
+}
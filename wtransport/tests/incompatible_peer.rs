@@ -0,0 +1,59 @@
+mod common;
+
+use common::server_config;
+use common::RawClient;
+use wtransport::error::ConnectionError;
+use wtransport::error::MissingCapability;
+use wtransport::Endpoint;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::varint::VarInt;
+
+/// Connects a client sending `settings` and returns how the server refused it.
+async fn refused_with(settings: Settings) -> MissingCapability {
+    let server = Endpoint::server(server_config().build()).unwrap();
+
+    let (raw_client, result) = tokio::join!(RawClient::connect(&server, settings), async {
+        server.accept().await.await
+    });
+
+    let missing = match result {
+        Err(ConnectionError::IncompatiblePeer(missing)) => missing,
+        result => panic!("Unexpected result: {:?}", result.map(|_| ())),
+    };
+
+    let settings_error =
+        quinn::VarInt::from_u64(ErrorCode::Settings.to_code().into_inner()).unwrap();
+
+    match raw_client.connection.closed().await {
+        quinn::ConnectionError::ApplicationClosed(close) => {
+            assert_eq!(close.error_code, settings_error)
+        }
+        error => panic!("Unexpected connection error: {error:?}"),
+    }
+
+    missing
+}
+
+#[tokio::test]
+async fn refuse_client_without_webtransport() {
+    let settings = Settings::builder().enable_h3_datagrams().build();
+
+    assert!(matches!(
+        refused_with(settings).await,
+        MissingCapability::WebTransport
+    ));
+}
+
+#[tokio::test]
+async fn refuse_client_without_h3_datagrams() {
+    let settings = Settings::builder()
+        .enable_webtransport()
+        .webtransport_max_sessions(VarInt::from_u32(1))
+        .build();
+
+    assert!(matches!(
+        refused_with(settings).await,
+        MissingCapability::H3Datagram
+    ));
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::client;
+use common::server_config;
+use common::url;
+use wtransport::endpoint::ConnectOptions;
+use wtransport::endpoint::Headers;
+use wtransport::endpoint::RedirectPolicy;
+use wtransport::endpoint::SessionResponse;
+use wtransport::Endpoint;
+
+/// Redirects the session request to `/next` on the same server, then to `target`.
+///
+/// Returns the headers of the requests on `/next` and on `target`.
+async fn follow_redirects(policy: RedirectPolicy) -> (Headers, Headers) {
+    let origin = Endpoint::server(server_config().build()).unwrap();
+    let target = Endpoint::server(server_config().build()).unwrap();
+
+    let options = ConnectOptions::builder(url(&origin, "/"))
+        .add_header("Authorization", "Bearer token")
+        .add_header("cookie", "session=1")
+        .add_header("x-custom", "value")
+        .redirect_policy(policy)
+        .build();
+
+    let client = client();
+
+    let (next_headers, (target_headers, _target_connection), connection) = tokio::join!(
+        async {
+            let redirect =
+                |location: String| SessionResponse::with_status(307).header("location", location);
+
+            let session_request = origin.accept().await.await.unwrap();
+            session_request
+                .reject_with_response(redirect(url(&origin, "/next")))
+                .await;
+
+            let session_request = origin.accept().await.await.unwrap();
+            assert_eq!(session_request.path(), "/next");
+            let headers = session_request.headers().clone();
+            session_request
+                .reject_with_response(redirect(url(&target, "/")))
+                .await;
+
+            headers
+        },
+        async {
+            let session_request = target.accept().await.await.unwrap();
+            let headers = session_request.headers().clone();
+            let connection = session_request.accept().await.unwrap();
+            (headers, connection)
+        },
+        client.connect(options),
+    );
+
+    connection.unwrap();
+    (next_headers, target_headers)
+}
+
+#[tokio::test]
+async fn sensitive_headers_stripped_cross_authority() {
+    let policy = RedirectPolicy::limited(2).allow_cross_authority(true);
+    let (next_headers, target_headers) = follow_redirects(policy).await;
+
+    // Same authority
+    assert_eq!(next_headers.get("authorization"), Some("Bearer token"));
+    assert_eq!(next_headers.get("cookie"), Some("session=1"));
+    assert_eq!(next_headers.get("x-custom"), Some("value"));
+
+    // Another authority
+    assert_eq!(target_headers.get("authorization"), None);
+    assert_eq!(target_headers.get("cookie"), None);
+    assert_eq!(target_headers.get("x-custom"), Some("value"));
+}
+
+#[tokio::test]
+async fn sensitive_headers_forwarded_on_opt_in() {
+    let policy = RedirectPolicy::limited(2)
+        .allow_cross_authority(true)
+        .forward_sensitive_headers(true);
+    let (_, target_headers) = follow_redirects(policy).await;
+
+    assert_eq!(target_headers.get("authorization"), Some("Bearer token"));
+    assert_eq!(target_headers.get("cookie"), Some("session=1"));
+    assert_eq!(target_headers.get("x-custom"), Some("value"));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::client;
+use common::encode;
+use common::read_status;
+use common::server_config;
+use common::url;
+use common::RawClient;
+use std::borrow::Cow;
+use wtransport::error::SessionCloseReason;
+use wtransport::Endpoint;
+use wtransport_proto::bytes::BytesWriter;
+use wtransport_proto::capsule::capsules::CloseWebTransportSession;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::varint::VarInt;
+
+#[tokio::test]
+async fn close_with_code_and_reason() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let client = client();
+
+    let (server_connection, client_connection) = tokio::join!(
+        async { server.accept().await.await.unwrap().accept().await.unwrap() },
+        async { client.connect(url(&server, "/")).await.unwrap() },
+    );
+
+    server_connection.close_session(42, "game over");
+
+    match client_connection.closed().await {
+        SessionCloseReason::Closed(close) => {
+            assert_eq!(close.code(), 42);
+            assert_eq!(close.reason(), "game over");
+        }
+        reason => panic!("Unexpected close reason: {reason:?}"),
+    }
+
+    assert!(matches!(
+        server_connection.closed().await,
+        SessionCloseReason::LocallyClosed
+    ));
+
+    // Other operations on a closed session fail
+    assert!(client_connection.open_bi().await.is_err());
+}
+
+#[tokio::test]
+async fn skip_oversized_unknown_capsule() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let raw_client = RawClient::connect_webtransport(&server).await;
+
+    let ((mut send, _recv), server_connection) = tokio::join!(
+        async {
+            let mut stream = raw_client.request_session(&server, "/").await;
+            assert_eq!(read_status(&mut stream.1).await, Some(200));
+            stream
+        },
+        async { server.accept().await.await.unwrap().accept().await.unwrap() },
+    );
+
+    // Capsule of reserved type whose payload is far larger than a parsed capsule
+    const CHUNK_LEN: u32 = 4000;
+    const CHUNKS: u32 = 256;
+
+    let mut capsule = Vec::new();
+    capsule.put_varint(VarInt::from_u32(0x17)).unwrap();
+    capsule
+        .put_varint(VarInt::from_u32(CHUNK_LEN * CHUNKS))
+        .unwrap();
+    send.write_all(&encode(&Frame::new_data(Cow::Owned(capsule))))
+        .await
+        .unwrap();
+
+    let chunk = encode(&Frame::new_data(Cow::Owned(vec![0; CHUNK_LEN as usize])));
+    for _ in 0..CHUNKS {
+        send.write_all(&chunk).await.unwrap();
+    }
+
+    // The session survives and the following capsules are processed
+    let mut close = Vec::new();
+    CloseWebTransportSession::new(7, "done")
+        .generate_capsule()
+        .write(&mut close)
+        .unwrap();
+    send.write_all(&encode(&Frame::new_data(Cow::Owned(close))))
+        .await
+        .unwrap();
+
+    match server_connection.closed().await {
+        SessionCloseReason::Closed(close) => {
+            assert_eq!(close.code(), 7);
+            assert_eq!(close.reason(), "done");
+        }
+        reason => panic!("Unexpected close reason: {reason:?}"),
+    }
+}
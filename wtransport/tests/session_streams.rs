@@ -0,0 +1,166 @@
+mod common;
+
+use common::encode;
+use common::read_status;
+use common::server_config;
+use common::RawClient;
+use wtransport::endpoint::Headers;
+use wtransport::error::SessionCloseReason;
+use wtransport::error::StreamReadError;
+use wtransport::error::StreamWriteError;
+use wtransport::Connection;
+use wtransport::Endpoint;
+use wtransport_proto::error::ErrorCode;
+
+/// Streams of a session, as seen by the raw client.
+struct PeerStreams {
+    /// Bidirectional stream opened by the client and accepted by the application.
+    accepted: (quinn::SendStream, quinn::RecvStream),
+
+    /// Unidirectional stream opened by the application.
+    opened: quinn::RecvStream,
+
+    /// HTTP/3 unidirectional streams opened by the server.
+    _h3_streams: Vec<quinn::RecvStream>,
+}
+
+/// How the client terminates the session.
+enum Termination {
+    Finish,
+    Reset,
+    UnexpectedFrame,
+}
+
+fn session_gone() -> quinn::VarInt {
+    quinn::VarInt::from_u64(ErrorCode::SessionGone.to_code().into_inner()).unwrap()
+}
+
+/// Establishes a session, with an accepted and an opened stream, then terminates the
+/// session from the client.
+///
+/// Asserts the streams fail on the server, once used, and that they are reset and stopped
+/// with `WEBTRANSPORT_SESSION_GONE`.
+async fn streams_reset_on_termination(termination: Termination) {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let raw_client = RawClient::connect_webtransport(&server).await;
+
+    let ((mut session_stream, peer_streams), (connection, accepted, mut opened)) = tokio::join!(
+        async {
+            let (send, mut recv) = raw_client.request_session(&server, "/").await;
+            assert_eq!(read_status(&mut recv).await, Some(200));
+
+            let mut accepted = raw_client.open_wt_bi(&send).await;
+            accepted.0.write_all(b"ping").await.unwrap();
+
+            let mut h3_streams = Vec::new();
+
+            let opened = loop {
+                let mut stream = raw_client.connection.accept_uni().await.unwrap();
+                let mut stream_type = [0; 1];
+                stream.read_exact(&mut stream_type).await.unwrap();
+
+                // Only the WebTransport stream type (0x54) is encoded on 2 bytes
+                if stream_type == [0x40] {
+                    break stream;
+                }
+
+                // Dropping HTTP/3 critical streams closes the connection
+                h3_streams.push(stream);
+            };
+
+            (
+                (send, recv),
+                PeerStreams {
+                    accepted,
+                    opened,
+                    _h3_streams: h3_streams,
+                },
+            )
+        },
+        async {
+            let connection: Connection =
+                server.accept().await.await.unwrap().accept().await.unwrap();
+
+            let accepted = connection.accept_bi().await.unwrap();
+            let mut opened = connection.open_uni().await.unwrap().await.unwrap();
+            opened.write_all(b"pong").await.unwrap();
+
+            (connection, accepted, opened)
+        },
+    );
+
+    let (mut accepted_send, mut accepted_recv) = accepted;
+
+    // Pending when the session terminates
+    let pending_read = tokio::spawn(async move {
+        let mut buffer = [0; 4];
+        accepted_recv.read_exact(&mut buffer).await.unwrap();
+        accepted_recv.read(&mut buffer).await
+    });
+
+    let trailers: Headers = [("x-trailer", "1")].into_iter().collect();
+
+    match termination {
+        Termination::Finish => session_stream.0.finish().await.unwrap(),
+        Termination::Reset => session_stream
+            .0
+            .reset(quinn::VarInt::from_u32(0x42))
+            .unwrap(),
+        Termination::UnexpectedFrame => session_stream
+            .0
+            .write_all(&encode(&trailers.generate_frame()))
+            .await
+            .unwrap(),
+    }
+
+    assert!(!matches!(
+        connection.closed().await,
+        SessionCloseReason::ConnectionError(_)
+    ));
+
+    assert!(matches!(
+        pending_read.await.unwrap(),
+        Err(StreamReadError::SessionGone)
+    ));
+    assert!(matches!(
+        accepted_send.write_all(b"late").await,
+        Err(StreamWriteError::SessionGone)
+    ));
+    assert!(matches!(
+        opened.write_all(b"late").await,
+        Err(StreamWriteError::SessionGone)
+    ));
+
+    let PeerStreams {
+        accepted: (mut accepted_send, mut accepted_recv),
+        mut opened,
+        ..
+    } = peer_streams;
+
+    assert_eq!(accepted_send.stopped().await, Ok(session_gone()));
+
+    assert_eq!(
+        accepted_recv.read_to_end(usize::MAX).await.unwrap_err(),
+        quinn::ReadToEndError::Read(quinn::ReadError::Reset(session_gone()))
+    );
+
+    assert_eq!(
+        opened.read_to_end(usize::MAX).await.unwrap_err(),
+        quinn::ReadToEndError::Read(quinn::ReadError::Reset(session_gone()))
+    );
+}
+
+#[tokio::test]
+async fn streams_reset_on_session_stream_finish() {
+    streams_reset_on_termination(Termination::Finish).await;
+}
+
+#[tokio::test]
+async fn streams_reset_on_session_stream_reset() {
+    streams_reset_on_termination(Termination::Reset).await;
+}
+
+#[tokio::test]
+async fn streams_reset_on_unexpected_frame() {
+    streams_reset_on_termination(Termination::UnexpectedFrame).await;
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::client;
+use common::serve;
+use common::server_config;
+use common::url;
+use std::sync::Arc;
+use std::time::Duration;
+use wtransport::endpoint::SessionResponse;
+use wtransport::error::ConnectingError;
+use wtransport::Endpoint;
+
+#[tokio::test]
+async fn sessions_pooled_over_connection() {
+    let server = Arc::new(Endpoint::server(server_config().max_sessions(2).build()).unwrap());
+
+    serve(server.clone(), |connection| async move {
+        connection.closed().await;
+    });
+
+    let client = client();
+    let first = client.connect(url(&server, "/first")).await.unwrap();
+    let second = client
+        .open_session(&first, url(&server, "/second"))
+        .await
+        .unwrap();
+
+    assert_eq!(first.stable_id(), second.stable_id());
+    assert_ne!(first.session_id(), second.session_id());
+
+    let third = client.open_session(&first, url(&server, "/third")).await;
+
+    assert!(matches!(
+        third,
+        Err(ConnectingError::SessionRejected { status: None, .. })
+    ));
+}
+
+#[tokio::test]
+async fn accept_not_serialized() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+
+    // A pending accept must not prevent others from yielding new connections
+    let pending = server.accept();
+    tokio::pin!(pending);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), &mut pending)
+            .await
+            .is_err()
+    );
+
+    let client = client();
+    let connecting = tokio::spawn({
+        let url = url(&server, "/");
+        async move { client.connect(url).await.map(|_| ()) }
+    });
+
+    let session_request = tokio::time::timeout(Duration::from_secs(5), server.accept())
+        .await
+        .expect("Accept not blocked by a pending one")
+        .await
+        .unwrap();
+
+    let _connection = session_request.accept().await.unwrap();
+    connecting.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn rejection_response_reaches_client() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let client = client();
+
+    let (_, result) = tokio::join!(
+        async {
+            let session_request = server.accept().await.await.unwrap();
+            let response = SessionResponse::with_status(401)
+                .header("WWW-Authenticate", "Bearer")
+                .header("retry-after", 30);
+
+            session_request.reject_with_response(response).await;
+        },
+        client.connect(url(&server, "/")),
+    );
+
+    match result {
+        Err(ConnectingError::SessionRejected {
+            status: Some(status),
+            headers,
+        }) => {
+            assert_eq!(status.into_inner(), 401);
+            assert_eq!(headers.get("www-authenticate"), Some("Bearer"));
+            assert_eq!(headers.get("retry-after"), Some("30"));
+        }
+        result => panic!("Unexpected result: {:?}", result.map(|_| ())),
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::client;
+use common::read_status;
+use common::server_config;
+use common::url;
+use common::RawClient;
+use std::time::Duration;
+use std::time::Instant;
+use wtransport::Endpoint;
+
+#[tokio::test]
+async fn shutdown_counts_sessions() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let client = client();
+
+    let mut connections = Vec::new();
+
+    for _ in 0..2 {
+        connections.push(tokio::join!(
+            async { server.accept().await.await.unwrap().accept().await.unwrap() },
+            async { client.connect(url(&server, "/")).await.unwrap() },
+        ));
+    }
+
+    let (_lingering_server, _lingering_client) = connections.pop().unwrap();
+    let (_closing_server, closing_client) = connections.pop().unwrap();
+
+    let (summary, ()) = tokio::join!(
+        server.shutdown(Instant::now() + Duration::from_millis(500)),
+        async {
+            closing_client.draining().await;
+            closing_client.close_session(0, "");
+        },
+    );
+
+    assert_eq!(summary.closed_cleanly(), 1);
+    assert_eq!(summary.closed_forcibly(), 1);
+}
+
+#[tokio::test]
+async fn shutdown_counts_sessions_of_closed_connections() {
+    let server = Endpoint::server(server_config().build()).unwrap();
+    let raw_client = RawClient::connect_webtransport(&server).await;
+
+    let (_stream, _server_connection) = tokio::join!(
+        async {
+            let mut stream = raw_client.request_session(&server, "/").await;
+            assert_eq!(read_status(&mut stream.1).await, Some(200));
+            stream
+        },
+        async { server.accept().await.await.unwrap().accept().await.unwrap() },
+    );
+
+    // The session is never closed: it goes away along with the connection
+    let (summary, ()) = tokio::join!(
+        server.shutdown(Instant::now() + Duration::from_secs(5)),
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            raw_client.connection.close(quinn::VarInt::from_u32(0), b"");
+        },
+    );
+
+    assert_eq!(summary.closed_cleanly(), 0);
+    assert_eq!(summary.closed_forcibly(), 1);
+}
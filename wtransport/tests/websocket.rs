@@ -0,0 +1,250 @@
+mod common;
+
+use common::encode;
+use common::server_config;
+use common::RawClient;
+use std::borrow::Cow;
+use std::time::Duration;
+use wtransport::endpoint::endpoint_side::Server;
+use wtransport::endpoint::Headers;
+use wtransport::websocket::CloseFrame;
+use wtransport::websocket::WebSocketRequest;
+use wtransport::Endpoint;
+use wtransport_proto::bytes::BufferReader;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::websocket::Opcode;
+use wtransport_proto::websocket::WsFrame;
+
+const MASKING_KEY: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+
+/// Server echoing messages on every WebSocket.
+fn echo_server() -> Endpoint<Server> {
+    let config = server_config()
+        .websocket_handler(|request: WebSocketRequest| async move {
+            let Ok(websocket) = request.accept().await else {
+                return;
+            };
+
+            while let Ok(message) = websocket.receive().await {
+                if websocket.send(message).await.is_err() {
+                    break;
+                }
+            }
+        })
+        .build();
+
+    Endpoint::server(config).unwrap()
+}
+
+/// Runs `client` while `server` accepts and drives connections.
+async fn with_server<F>(server: &Endpoint<Server>, client: F)
+where
+    F: std::future::Future<Output = ()>,
+{
+    let accepting = async {
+        loop {
+            tokio::spawn(server.accept().await);
+        }
+    };
+
+    tokio::select! {
+        () = accepting => {}
+        result = tokio::time::timeout(Duration::from_secs(10), client) => result.unwrap(),
+    }
+}
+
+/// A WebSocket client writing frames by hand on a request stream.
+struct WsClient {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+    frames: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+impl WsClient {
+    /// Sends the extended CONNECT request with `version`, returning the response headers.
+    async fn connect(
+        raw_client: &RawClient,
+        server: &Endpoint<Server>,
+        version: &str,
+    ) -> (Self, Headers) {
+        let port = server.local_addr().unwrap().port();
+        let authority = format!("localhost:{port}");
+
+        let headers: Headers = [
+            (":method", "CONNECT"),
+            (":scheme", "https"),
+            (":protocol", "websocket"),
+            (":authority", authority.as_str()),
+            (":path", "/chat"),
+            ("sec-websocket-version", version),
+        ]
+        .into_iter()
+        .collect();
+
+        let (send, recv) = raw_client.send_headers(&headers).await;
+
+        let mut client = Self {
+            send,
+            recv,
+            frames: Vec::new(),
+            payload: Vec::new(),
+        };
+
+        let headers = loop {
+            let mut buffer_reader = BufferReader::new(&client.frames);
+
+            if let Some(frame) = Frame::read_from_buffer(&mut buffer_reader).unwrap() {
+                assert!(matches!(frame.kind(), FrameKind::Headers));
+                let headers = Headers::with_frame(&frame).unwrap();
+                let offset = buffer_reader.offset();
+                client.frames.drain(..offset);
+                break headers;
+            }
+
+            assert!(client.read_chunk().await, "Response received");
+        };
+
+        (client, headers)
+    }
+
+    async fn send_frame(&mut self, frame: WsFrame<'_>) {
+        let mut payload = Vec::new();
+        frame.write(&mut payload).unwrap();
+
+        self.send
+            .write_all(&encode(&Frame::new_data(Cow::Owned(payload))))
+            .await
+            .unwrap();
+    }
+
+    /// Reads the next frame sent by the server, returning its opcode and payload.
+    ///
+    /// Returns [`None`] once the stream is finished.
+    async fn receive_frame(&mut self) -> Option<(Opcode, Vec<u8>)> {
+        loop {
+            let mut buffer_reader = BufferReader::new(&self.frames);
+
+            while let Some(frame) = Frame::read_from_buffer(&mut buffer_reader).unwrap() {
+                assert!(matches!(frame.kind(), FrameKind::Data));
+                self.payload.extend_from_slice(frame.payload());
+            }
+
+            let offset = buffer_reader.offset();
+            self.frames.drain(..offset);
+
+            let mut buffer_reader = BufferReader::new(&self.payload);
+
+            if let Some(frame) = WsFrame::read_from_buffer(&mut buffer_reader).unwrap() {
+                // Server frames are never masked
+                assert!(!frame.is_masked());
+
+                let frame = (frame.opcode(), frame.payload().to_vec());
+                let offset = buffer_reader.offset();
+                self.payload.drain(..offset);
+                return Some(frame);
+            }
+
+            if !self.read_chunk().await {
+                assert!(self.payload.is_empty() && self.frames.is_empty());
+                return None;
+            }
+        }
+    }
+
+    /// Returns `false` once the stream is finished.
+    async fn read_chunk(&mut self) -> bool {
+        match self.recv.read_chunk(usize::MAX, true).await.unwrap() {
+            Some(chunk) => {
+                self.frames.extend_from_slice(&chunk.bytes);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn masked(opcode: Opcode, payload: &[u8]) -> WsFrame<'_> {
+    WsFrame::new(true, opcode, Cow::Borrowed(payload)).with_mask(MASKING_KEY)
+}
+
+fn close_code(payload: &[u8]) -> u16 {
+    let frame = WsFrame::new(true, Opcode::Close, Cow::Borrowed(payload));
+    CloseFrame::with_frame(&frame).unwrap().code()
+}
+
+#[tokio::test]
+async fn echo_and_close() {
+    let server = echo_server();
+    let raw_client = RawClient::connect(&server, Settings::builder().build()).await;
+
+    with_server(&server, async {
+        let (mut client, headers) = WsClient::connect(&raw_client, &server, "13").await;
+        assert_eq!(headers.get(":status"), Some("200"));
+
+        client.send_frame(masked(Opcode::Text, b"hello")).await;
+        assert_eq!(
+            client.receive_frame().await,
+            Some((Opcode::Text, b"hello".to_vec()))
+        );
+
+        // Pings are answered without reaching the application
+        client
+            .send_frame(masked(Opcode::Ping, b"are you there?"))
+            .await;
+        assert_eq!(
+            client.receive_frame().await,
+            Some((Opcode::Pong, b"are you there?".to_vec()))
+        );
+
+        // Close handshake: the close frame is echoed and the stream finished
+        let close = CloseFrame::new(CloseFrame::NORMAL, "bye").generate_frame();
+        client
+            .send_frame(masked(Opcode::Close, close.payload()))
+            .await;
+
+        let (opcode, payload) = client.receive_frame().await.unwrap();
+        assert_eq!(opcode, Opcode::Close);
+        assert_eq!(close_code(&payload), CloseFrame::NORMAL);
+
+        assert_eq!(client.receive_frame().await, None);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn close_on_unmasked_frame() {
+    let server = echo_server();
+    let raw_client = RawClient::connect(&server, Settings::builder().build()).await;
+
+    with_server(&server, async {
+        let (mut client, headers) = WsClient::connect(&raw_client, &server, "13").await;
+        assert_eq!(headers.get(":status"), Some("200"));
+
+        client
+            .send_frame(WsFrame::new(true, Opcode::Text, Cow::Borrowed(b"hello")))
+            .await;
+
+        let (opcode, payload) = client.receive_frame().await.unwrap();
+        assert_eq!(opcode, Opcode::Close);
+        assert_eq!(close_code(&payload), CloseFrame::PROTOCOL_ERROR);
+
+        assert_eq!(client.receive_frame().await, None);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn reject_unsupported_version() {
+    let server = echo_server();
+    let raw_client = RawClient::connect(&server, Settings::builder().build()).await;
+
+    with_server(&server, async {
+        let (_client, headers) = WsClient::connect(&raw_client, &server, "8").await;
+        assert_eq!(headers.get(":status"), Some("400"));
+        assert_eq!(headers.get("sec-websocket-version"), Some("13"));
+    })
+    .await;
+}